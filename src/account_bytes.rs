@@ -0,0 +1,232 @@
+//! Writing parsed pools back to account data.
+//!
+//! `AmmPool::to_account_bytes` is the inverse of `parse_from_bytes`: the bytes it returns
+//! parse back into an equal pool with the same `PoolType`, which makes it suitable for
+//! building fixtures and for patching accounts in a local validator. Anchor accounts are
+//! written with their discriminator and the other layouts exactly as the program stores
+//! them.
+//!
+//! Only the layout is written. Programs that allocate accounts larger than their layout,
+//! such as the SPL stake pool, leave the rest of the account zeroed, which the bytes
+//! don't include.
+
+use anchor_lang::{AccountSerialize, AnchorSerialize};
+
+use crate::parsing::{AldrinPool, AmmPool, BonkswapPool, PoolError, StabblePool};
+
+impl AmmPool {
+    /// Serializes the pool back into the data of its account.
+    ///
+    /// Pools parsed together with other accounts, such as `RaydiumCpmmAmmWithConfig` and
+    /// `SanctumInfinity`, write only the account at the pool's address. Fails with
+    /// `PoolError::NotSerializable` for pools whose parsed form drops part of the account:
+    /// Phoenix markets, whose order trees are flattened to resting orders, pools decoded
+    /// with a runtime IDL, and EVM pools.
+    pub fn to_account_bytes(&self) -> Result<Vec<u8>, PoolError> {
+        let mut data = Vec::new();
+        match self {
+            AmmPool::PumpFun(pool) => pool.try_serialize(&mut data)?,
+            AmmPool::PumpFunWithConfig(pool) => pool.pool.try_serialize(&mut data)?,
+            AmmPool::PumpFunGlobalConfig(config) => config.try_serialize(&mut data)?,
+            AmmPool::PumpFunBondingCurve(curve) => curve.try_serialize(&mut data)?,
+            AmmPool::RaydiumCpmmAmm(pool) => pool.try_serialize(&mut data)?,
+            AmmPool::RaydiumCpmmAmmWithConfig(pool) => pool.pool.try_serialize(&mut data)?,
+            AmmPool::RaydiumCpmmConfig(config) => config.try_serialize(&mut data)?,
+            AmmPool::RaydiumCamm(pool) => pool.try_serialize(&mut data)?,
+            AmmPool::RaydiumCammConfig(config) => config.try_serialize(&mut data)?,
+            AmmPool::RaydiumCammObservation(observation) => observation.try_serialize(&mut data)?,
+            AmmPool::RaydiumLegacyAmm(amm) => write_layout(amm, &mut data)?,
+            AmmPool::RaydiumLaunchLab(pool) => pool.try_serialize(&mut data)?,
+            AmmPool::LifinityV2(amm) => amm.try_serialize(&mut data)?,
+            AmmPool::SaberStableSwap(swap) => write_layout(swap, &mut data)?,
+            AmmPool::MercurialStableSwap(swap) => write_layout(swap, &mut data)?,
+            AmmPool::Invariant(pool) => pool.try_serialize(&mut data)?,
+            AmmPool::CremaClmm(pool) => pool.try_serialize(&mut data)?,
+            AmmPool::Aldrin(AldrinPool::V1(pool)) => pool.try_serialize(&mut data)?,
+            AmmPool::Aldrin(AldrinPool::V2(pool)) => pool.try_serialize(&mut data)?,
+            AmmPool::FluxBeam(swap)
+            | AmmPool::OrcaTokenSwap(swap)
+            | AmmPool::SplTokenSwap(swap) => write_layout(swap, &mut data)?,
+            AmmPool::SanctumInfinity(pool) => write_layout(&pool.state, &mut data)?,
+            AmmPool::Stabble(StabblePool::Stable(pool)) => pool.try_serialize(&mut data)?,
+            AmmPool::Stabble(StabblePool::Weighted(pool)) => pool.try_serialize(&mut data)?,
+            AmmPool::Moonshot(curve) => curve.try_serialize(&mut data)?,
+            AmmPool::MeteoraDbc(pool) => pool.try_serialize(&mut data)?,
+            AmmPool::MeteoraVault(vault) => vault.vault.try_serialize(&mut data)?,
+            AmmPool::MeteoraDlmm(pair) => pair.try_serialize(&mut data)?,
+            AmmPool::Bonkswap(BonkswapPool::V1(pool)) => pool.try_serialize(&mut data)?,
+            AmmPool::Bonkswap(BonkswapPool::V2(pool)) => pool.try_serialize(&mut data)?,
+            AmmPool::BonkswapFarm(farm) => farm.try_serialize(&mut data)?,
+            AmmPool::OrcaWhirlpool(whirlpool) => whirlpool.try_serialize(&mut data)?,
+            AmmPool::OrcaWhirlpoolPosition(position) => position.try_serialize(&mut data)?,
+            AmmPool::SplStakePool(pool) => write_layout(pool, &mut data)?,
+            AmmPool::Marinade(state) => state.try_serialize(&mut data)?,
+            AmmPool::Phoenix(_) | AmmPool::Dynamic(_) => {
+                return Err(PoolError::NotSerializable(self.protocol_tag()));
+            }
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV2(_) | AmmPool::UniswapV3(_) => {
+                return Err(PoolError::NotSerializable(self.protocol_tag()));
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// Writes one of the layouts in `layouts`, which have no discriminator besides their
+/// leading fields.
+fn write_layout<T: AnchorSerialize>(layout: &T, data: &mut Vec<u8>) -> anchor_lang::Result<()> {
+    layout
+        .serialize(data)
+        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AccountDeserialize;
+    use anchor_lang::prelude::Pubkey;
+
+    use super::*;
+    use crate::layouts::phoenix::{FifoMarketState, MARKET_HEADER_DISCRIMINANT, MarketHeader};
+    use crate::layouts::raydium_amm_v4::AmmInfo;
+    use crate::layouts::sanctum::{InfinityPool, LstStateList, PoolState};
+    use crate::layouts::spl_stake_pool::{AccountType, Fee, FutureEpoch, Lockup, StakePool};
+    use crate::layouts::{mercurial, saber, spl_token_swap};
+    use crate::parsing::{PoolType, parse_from_bytes};
+
+    /// Returns `len` bytes that differ from their neighbours, so that a field written at
+    /// the wrong offset shows up in the comparison.
+    fn patterned(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 + 7) as u8).collect()
+    }
+
+    /// Parses `data` as `pool_type` and checks that the pool writes the same bytes back.
+    fn assert_round_trip(pool_type: &PoolType, data: &[u8]) {
+        let pool = parse_from_bytes(pool_type, data).unwrap();
+        assert_eq!(pool.to_account_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn raydium_amm_v4_round_trips() {
+        let pool_type = PoolType::RaydiumLegacyAmm {
+            pool_address: Pubkey::new_unique(),
+        };
+        assert_round_trip(&pool_type, &patterned(AmmInfo::LEN));
+    }
+
+    #[test]
+    fn saber_round_trips() {
+        let mut data = patterned(saber::SwapInfo::LEN);
+        data[0] = 1; // is_initialized
+        data[1] = 0; // is_paused
+        let pool_type = PoolType::SaberStableSwap {
+            pool_address: Pubkey::new_unique(),
+        };
+        assert_round_trip(&pool_type, &data);
+    }
+
+    #[test]
+    fn mercurial_round_trips() {
+        let mut data = patterned(mercurial::SwapInfo::LEN);
+        data[1] = 1; // is_initialized
+        let pool_type = PoolType::MercurialStableSwap {
+            pool_address: Pubkey::new_unique(),
+        };
+        assert_round_trip(&pool_type, &data);
+    }
+
+    #[test]
+    fn token_swap_round_trips() {
+        let mut data = patterned(spl_token_swap::TokenSwap::LEN);
+        data[0] = spl_token_swap::SWAP_V1;
+        data[1] = 1; // is_initialized
+        let pool_address = Pubkey::new_unique();
+        for pool_type in [
+            PoolType::FluxBeam { pool_address },
+            PoolType::OrcaTokenSwap { pool_address },
+            PoolType::SplTokenSwap { pool_address },
+        ] {
+            assert_round_trip(&pool_type, &data);
+        }
+    }
+
+    #[test]
+    fn sanctum_writes_the_pool_state() {
+        let data = patterned(PoolState::LEN);
+        let pool = AmmPool::SanctumInfinity(InfinityPool {
+            state: PoolState::try_deserialize(&mut data.as_slice()).unwrap(),
+            lst_states: LstStateList(Vec::new()),
+        });
+        assert_eq!(pool.to_account_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn stake_pool_round_trips() {
+        let fee = |numerator| Fee {
+            denominator: 10_000,
+            numerator,
+        };
+        let stake_pool = StakePool {
+            account_type: AccountType::StakePool,
+            manager: Pubkey::new_unique(),
+            staker: Pubkey::new_unique(),
+            stake_deposit_authority: Pubkey::new_unique(),
+            stake_withdraw_bump_seed: 255,
+            validator_list: Pubkey::new_unique(),
+            reserve_stake: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            manager_fee_account: Pubkey::new_unique(),
+            token_program_id: Pubkey::new_unique(),
+            total_lamports: 1_234_567_890_123,
+            pool_token_supply: 1_100_000_000_000,
+            last_update_epoch: 700,
+            lockup: Lockup {
+                unix_timestamp: 0,
+                epoch: 0,
+                custodian: Pubkey::new_unique(),
+            },
+            epoch_fee: fee(400),
+            next_epoch_fee: FutureEpoch::Two(fee(500)),
+            preferred_deposit_validator_vote_address: Some(Pubkey::new_unique()),
+            preferred_withdraw_validator_vote_address: None,
+            stake_deposit_fee: fee(0),
+            stake_withdrawal_fee: fee(10),
+            next_stake_withdrawal_fee: FutureEpoch::None,
+            stake_referral_fee: 50,
+            sol_deposit_authority: None,
+            sol_deposit_fee: fee(8),
+            sol_referral_fee: 50,
+            sol_withdraw_authority: Some(Pubkey::new_unique()),
+            sol_withdrawal_fee: fee(30),
+            next_sol_withdrawal_fee: FutureEpoch::One(fee(20)),
+            last_epoch_pool_token_supply: 1_099_000_000_000,
+            last_epoch_total_lamports: 1_230_000_000_000,
+        };
+        let data = AmmPool::SplStakePool(stake_pool.clone())
+            .to_account_bytes()
+            .unwrap();
+        let pool_type = PoolType::SplStakePool {
+            pool_address: Pubkey::new_unique(),
+        };
+        match parse_from_bytes(&pool_type, &data).unwrap() {
+            AmmPool::SplStakePool(parsed) => assert_eq!(parsed, stake_pool),
+            other => panic!("parsed as {}", other.protocol_tag()),
+        }
+    }
+
+    #[test]
+    fn phoenix_markets_are_not_serializable() {
+        // A market with empty order trees: the header, the market state and two tree
+        // headers whose roots are the sentinel.
+        let mut data = vec![0; MarketHeader::LEN + FifoMarketState::LEN + 64];
+        data[..8].copy_from_slice(&MARKET_HEADER_DISCRIMINANT.to_le_bytes());
+        let pool_type = PoolType::Phoenix {
+            pool_address: Pubkey::new_unique(),
+        };
+        let pool = parse_from_bytes(&pool_type, &data).unwrap();
+        assert!(matches!(
+            pool.to_account_bytes(),
+            Err(PoolError::NotSerializable(_))
+        ));
+    }
+}
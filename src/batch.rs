@@ -0,0 +1,79 @@
+//! Fetching many pools at once.
+//!
+//! `get_info_structs` fetches and parses a batch of pools concurrently, keeping at most a
+//! given number of requests in flight so that large batches don't overrun the rate limit
+//! of the RPC node. A pool that fails doesn't abort the batch: each result is returned
+//! together with the `PoolType` it was fetched for, in the order the pools complete.
+
+use futures::stream::{self, StreamExt};
+
+use crate::fetcher::AccountFetcher;
+use crate::parsing::{self, ParseMode, ParsedPool, PoolError, PoolType};
+use crate::program_ids::Cluster;
+
+/// Number of pools `UniversalParser::parse_many` fetches at once unless configured
+/// otherwise.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 16;
+
+/// Fetches and parses each of `pool_types` like `get_info_struct_on`, with at most
+/// `max_in_flight` pools fetched at once.
+///
+/// A `max_in_flight` of zero is treated as one.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+/// # use anchor_lang::prelude::Pubkey;
+/// # use universal_liquidity_pool_parser::batch::get_info_structs;
+/// # use universal_liquidity_pool_parser::parsing::PoolType;
+/// # use universal_liquidity_pool_parser::program_ids::Cluster;
+/// # async fn example(addresses: Vec<Pubkey>) {
+/// let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let pool_types = addresses
+///     .into_iter()
+///     .map(|pool_address| PoolType::OrcaWhirlpool { pool_address });
+/// for (pool_type, result) in get_info_structs(pool_types, &rpc_client, &Cluster::Mainnet, 8).await {
+///     match result {
+///         Ok(parsed) => println!("{}", parsed.pool),
+///         Err(e) => eprintln!("{}: {e}", pool_type.pool_address()),
+///     }
+/// }
+/// # }
+/// ```
+pub async fn get_info_structs<F, I>(
+    pool_types: I,
+    rpc_client: &F,
+    cluster: &Cluster,
+    max_in_flight: usize,
+) -> Vec<(PoolType, Result<ParsedPool, PoolError>)>
+where
+    F: AccountFetcher,
+    I: IntoIterator<Item = PoolType>,
+{
+    fetch_many(pool_types, max_in_flight, |pool_type| {
+        parsing::get_info_struct_with(pool_type, rpc_client, cluster, ParseMode::Lenient)
+    })
+    .await
+}
+
+/// Runs `fetch` on each of `pool_types` with at most `max_in_flight` running at once.
+pub(crate) async fn fetch_many<I, Fetch, Fut>(
+    pool_types: I,
+    max_in_flight: usize,
+    fetch: Fetch,
+) -> Vec<(PoolType, Result<ParsedPool, PoolError>)>
+where
+    I: IntoIterator<Item = PoolType>,
+    Fetch: Fn(PoolType) -> Fut,
+    Fut: Future<Output = Result<ParsedPool, PoolError>>,
+{
+    stream::iter(pool_types)
+        .map(|pool_type| {
+            let parsed = fetch(pool_type.clone());
+            async move { (pool_type, parsed.await) }
+        })
+        .buffer_unordered(max_in_flight.max(1))
+        .collect()
+        .await
+}
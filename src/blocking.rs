@@ -0,0 +1,188 @@
+//! Blocking versions of the fetching functions, for scripts and CLIs without a Tokio
+//! runtime of their own.
+//!
+//! The functions and methods here run their async counterparts to completion on a
+//! runtime shared by the whole module, started the first time it's needed, so they
+//! behave exactly like them: fetchers, retries, failover and caches are those of the
+//! async core.
+//!
+//! ```no_run
+//! # use anchor_client::solana_client::rpc_client::RpcClient;
+//! # use anchor_lang::prelude::Pubkey;
+//! # use universal_liquidity_pool_parser::blocking;
+//! # use universal_liquidity_pool_parser::parsing::{PoolError, PoolType};
+//! # fn example(pool_address: Pubkey, address: Pubkey) -> Result<(), PoolError> {
+//! let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+//! let pool_type = PoolType::RaydiumCpmmAmm { pool_address };
+//! let parsed = blocking::get_info_struct(pool_type, &rpc_client)?;
+//!
+//! let fetcher = blocking::PoolFetcher::from_env()?;
+//! let pool = fetcher.fetch(address)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Like the blocking clients of `reqwest`, these panic when they're called from async
+//! code, inside a Tokio runtime; async code calls the functions they wrap instead.
+
+use std::future::Future;
+use std::sync::OnceLock;
+
+use anchor_lang::prelude::Pubkey;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::detect;
+use crate::fetcher::AccountFetcher;
+use crate::hot_fields::HotFields;
+use crate::parsing::{self, ParsedPool, PoolError, PoolType};
+use crate::program_ids::Cluster;
+use crate::universal;
+
+/// Results of a batch or a search, each with the pool it's the result for.
+type Pools<K> = Vec<(K, Result<ParsedPool, PoolError>)>;
+
+/// Runs `future` to completion on the runtime of the module.
+fn block_on<F: Future>(future: F) -> F::Output {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    let runtime = RUNTIME.get_or_init(|| {
+        // A worker of its own drives the connections and timers of the clients between
+        // calls, and lets blocking fetchers such as `rpc_client::RpcClient` run theirs.
+        Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("ulp-blocking")
+            .enable_all()
+            .build()
+            .expect("can't start the runtime of blocking calls")
+    });
+    runtime.block_on(future)
+}
+
+/// Like `parsing::get_info_struct`, but blocks until the pool is parsed.
+pub fn get_info_struct<F: AccountFetcher>(
+    pool_type: PoolType,
+    fetcher: &F,
+) -> Result<ParsedPool, PoolError> {
+    block_on(parsing::get_info_struct(pool_type, fetcher))
+}
+
+/// Like `parsing::get_info_struct_on`, but blocks until the pool is parsed.
+pub fn get_info_struct_on<F: AccountFetcher>(
+    pool_type: PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+) -> Result<ParsedPool, PoolError> {
+    block_on(parsing::get_info_struct_on(pool_type, fetcher, cluster))
+}
+
+/// Like `detect::detect_and_parse_with`, but blocks until the pool is parsed.
+pub fn detect_and_parse<F: AccountFetcher>(
+    address: &Pubkey,
+    fetcher: &F,
+) -> Result<ParsedPool, PoolError> {
+    block_on(detect::detect_and_parse_with(address, fetcher))
+}
+
+/// A `universal::UniversalParser` whose methods block until they're done.
+pub struct UniversalParser {
+    inner: universal::UniversalParser,
+}
+
+/// `UniversalParser` under the name of what it does, as `universal::PoolFetcher`.
+pub type PoolFetcher = UniversalParser;
+
+impl UniversalParser {
+    pub fn new(inner: universal::UniversalParser) -> Self {
+        UniversalParser { inner }
+    }
+
+    /// Like `universal::UniversalParser::from_env`.
+    pub fn from_env() -> Result<Self, PoolError> {
+        universal::UniversalParser::from_env().map(UniversalParser::new)
+    }
+
+    /// Returns the async parser the methods run, for its configuration and the methods
+    /// that don't fetch, such as `parse_bytes`.
+    pub fn as_async(&self) -> &universal::UniversalParser {
+        &self.inner
+    }
+
+    pub fn into_async(self) -> universal::UniversalParser {
+        self.inner
+    }
+
+    /// Like `universal::UniversalParser::parse`.
+    pub fn parse(&self, pool_type: PoolType) -> Result<ParsedPool, PoolError> {
+        block_on(self.inner.parse(pool_type))
+    }
+
+    /// Like `universal::UniversalParser::parse_at_min_slot`.
+    pub fn parse_at_min_slot(
+        &self,
+        pool_type: PoolType,
+        min_context_slot: u64,
+    ) -> Result<ParsedPool, PoolError> {
+        block_on(self.inner.parse_at_min_slot(pool_type, min_context_slot))
+    }
+
+    /// Like `universal::UniversalParser::parse_many`, still fetching the configured
+    /// number of pools at once.
+    pub fn parse_many<I>(&self, pool_types: I) -> Pools<PoolType>
+    where
+        I: IntoIterator<Item = PoolType>,
+    {
+        block_on(self.inner.parse_many(pool_types))
+    }
+
+    /// Like `universal::UniversalParser::fetch`.
+    pub fn fetch(&self, address: Pubkey) -> Result<ParsedPool, PoolError> {
+        block_on(self.inner.fetch(address))
+    }
+
+    /// Like `universal::UniversalParser::fetch_typed`.
+    pub fn fetch_typed(&self, name: &str, address: Pubkey) -> Result<ParsedPool, PoolError> {
+        block_on(self.inner.fetch_typed(name, address))
+    }
+
+    /// Like `universal::UniversalParser::fetch_many`.
+    pub fn fetch_many(&self, addresses: &[Pubkey]) -> Result<Pools<Pubkey>, PoolError> {
+        block_on(self.inner.fetch_many(addresses))
+    }
+
+    /// Like `universal::UniversalParser::discover_pools`.
+    pub fn discover_pools(&self, pool_type: &PoolType) -> Result<Pools<Pubkey>, PoolError> {
+        block_on(self.inner.discover_pools(pool_type))
+    }
+
+    /// Like `universal::UniversalParser::discover_pool_addresses`.
+    pub fn discover_pool_addresses(&self, pool_type: &PoolType) -> Result<Vec<Pubkey>, PoolError> {
+        block_on(self.inner.discover_pool_addresses(pool_type))
+    }
+
+    /// Like `universal::UniversalParser::find_pools_for_pair`.
+    pub fn find_pools_for_pair(
+        &self,
+        mint_a: &Pubkey,
+        mint_b: &Pubkey,
+    ) -> Result<Pools<PoolType>, PoolError> {
+        block_on(self.inner.find_pools_for_pair(mint_a, mint_b))
+    }
+
+    /// Like `universal::UniversalParser::find_pools_for_mint`.
+    pub fn find_pools_for_mint(&self, mint: &Pubkey) -> Result<Pools<PoolType>, PoolError> {
+        block_on(self.inner.find_pools_for_mint(mint))
+    }
+
+    /// Like `universal::UniversalParser::fetch_hot_fields`.
+    pub fn fetch_hot_fields(
+        &self,
+        pool_types: &[PoolType],
+    ) -> Result<Vec<Option<HotFields>>, PoolError> {
+        block_on(self.inner.fetch_hot_fields(pool_types))
+    }
+}
+
+impl From<universal::UniversalParser> for UniversalParser {
+    fn from(inner: universal::UniversalParser) -> Self {
+        UniversalParser::new(inner)
+    }
+}
@@ -0,0 +1,491 @@
+//! Keeping fetched accounts for a while, so repeated fetches skip the RPC node.
+//!
+//! An `AccountCache` holds the accounts of recent requests by address for a fixed time to
+//! live. `Cached` answers a request from it when every account asked for is there and
+//! fresh, and sends it on otherwise, caching what comes back. Pools are parsed again from
+//! the cached accounts, so a `UniversalParser` built with `cache_ttl` returns the same
+//! `ParsedPool` for repeated `fetch` and `parse` calls within the TTL without a round
+//! trip:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use anchor_lang::prelude::Pubkey;
+//! # use universal_liquidity_pool_parser::parsing::PoolError;
+//! # use universal_liquidity_pool_parser::universal::UniversalParser;
+//! # async fn example(address: Pubkey) -> Result<(), PoolError> {
+//! let parser = UniversalParser::builder()
+//!     .rpc_url("https://api.mainnet-beta.solana.com")
+//!     .cache_ttl(Duration::from_secs(2))
+//!     .build()?;
+//! let first = parser.fetch(address).await?;
+//! let again = parser.fetch(address).await?;
+//! println!("{:?}", parser.account_cache().map(|cache| cache.stats()));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Caches for services watching many pools can be bounded `with_capacity`, past which the
+//! least recently used accounts are evicted; `CacheStats::evictions` counts them and
+//! `on_evict` observes each one.
+//!
+//! A request is answered from the cache as a whole or not at all, and only if every account
+//! it asks for was read at the same slot, so the accounts of one response, such as a pool
+//! and its vaults, are still read together. Accounts cached by requests answered at
+//! different slots are fetched again. Requests for a slice of the account data and
+//! `getProgramAccounts` requests aren't cached.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_response::{Response, RpcResponseContext};
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::Pubkey;
+
+use crate::fetcher::AccountFetcher;
+use crate::parsing::PoolError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How many requests an `AccountCache` has answered, and how many accounts it dropped to
+/// stay within its capacity.
+pub struct CacheStats {
+    /// Requests answered from the cache.
+    pub hits: u64,
+    /// Requests that had to be sent on, because an account wasn't cached or had expired.
+    pub misses: u64,
+    /// Accounts dropped because the cache was full. Expired accounts aren't counted.
+    pub evictions: u64,
+}
+
+/// Called with the address of each account an `AccountCache` evicts.
+pub type EvictionCallback = Box<dyn Fn(&Pubkey) + Send + Sync>;
+
+/// Accounts of recent requests, kept for `ttl` after they were fetched.
+///
+/// A cache created `with_capacity` holds at most that many accounts, evicting the least
+/// recently used ones first; it's unbounded otherwise.
+///
+/// The cache ignores the commitment of requests: an account fetched at one commitment
+/// answers requests at any other until it expires.
+pub struct AccountCache {
+    ttl: Duration,
+    capacity: Option<usize>,
+    on_evict: Option<EvictionCallback>,
+    store: Mutex<Store>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// The cached accounts, with the order they were last used in.
+#[derive(Default)]
+struct Store {
+    entries: HashMap<Pubkey, Entry>,
+    /// Address of each entry by its `used` tick, least recently used first.
+    by_use: BTreeMap<u64, Pubkey>,
+    /// Tick of the latest use.
+    tick: u64,
+}
+
+struct Entry {
+    /// The account, or `None` if it didn't exist.
+    account: Option<Account>,
+    /// Slot the account was read at.
+    slot: u64,
+    cached_at: Instant,
+    /// Tick of the last request the entry was cached by or answered.
+    used: u64,
+}
+
+impl Store {
+    /// Marks the entry of `address` as used now.
+    fn touch(&mut self, address: &Pubkey) {
+        let Some(entry) = self.entries.get_mut(address) else {
+            return;
+        };
+        self.tick += 1;
+        self.by_use.remove(&entry.used);
+        entry.used = self.tick;
+        self.by_use.insert(self.tick, *address);
+    }
+
+    fn remove(&mut self, address: &Pubkey) {
+        if let Some(entry) = self.entries.remove(address) {
+            self.by_use.remove(&entry.used);
+        }
+    }
+}
+
+impl AccountCache {
+    pub fn new(ttl: Duration) -> Self {
+        AccountCache {
+            ttl,
+            capacity: None,
+            on_evict: None,
+            store: Mutex::new(Store::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Bounds the cache to `capacity` accounts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Calls `on_evict` with the address of each account evicted to stay within the
+    /// capacity, after the cache is unlocked again.
+    pub fn on_evict(mut self, on_evict: impl Fn(&Pubkey) + Send + Sync + 'static) -> Self {
+        self.on_evict = Some(Box::new(on_evict));
+        self
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the most accounts the cache holds, or `None` if it's unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the number of cached accounts, expired ones not yet dropped included.
+    pub fn len(&self) -> usize {
+        self.store().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the account at `address`, so the next request for it is sent on.
+    pub fn invalidate(&self, address: &Pubkey) {
+        self.store().remove(address);
+    }
+
+    pub fn clear(&self) {
+        *self.store() = Store::default();
+    }
+
+    /// Answers a request for the accounts at `addresses` with `config` from the cache, or
+    /// makes it with `request` and caches the response.
+    pub(crate) async fn fetch_with<Fut>(
+        &self,
+        addresses: &[Pubkey],
+        config: &RpcAccountInfoConfig,
+        request: impl FnOnce() -> Fut,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError>
+    where
+        Fut: Future<Output = Result<Response<Vec<Option<Account>>>, PoolError>>,
+    {
+        if config.data_slice.is_some() {
+            return request().await;
+        }
+        if let Some(response) = self.get(addresses, config.min_context_slot) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(response);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let response = request().await?;
+        self.insert(addresses, &response);
+        Ok(response)
+    }
+
+    /// Returns the cached accounts at `addresses` if every one of them is fresh and was
+    /// read at one slot, `min_context_slot` or later, in a response at that slot.
+    fn get(
+        &self,
+        addresses: &[Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> Option<Response<Vec<Option<Account>>>> {
+        let mut store = self.store();
+        let now = Instant::now();
+        let mut accounts = Vec::with_capacity(addresses.len());
+        let mut slot = None;
+        for address in addresses {
+            let entry = store.entries.get(address)?;
+            if now.duration_since(entry.cached_at) >= self.ttl {
+                store.remove(address);
+                return None;
+            }
+            if min_context_slot.is_some_and(|min_context_slot| entry.slot < min_context_slot) {
+                return None;
+            }
+            if slot.is_some_and(|slot| slot != entry.slot) {
+                return None;
+            }
+            accounts.push(entry.account.clone());
+            slot = Some(entry.slot);
+        }
+        for address in addresses {
+            store.touch(address);
+        }
+        Some(Response {
+            context: RpcResponseContext::new(slot.unwrap_or_default()),
+            value: accounts,
+        })
+    }
+
+    /// Caches the accounts of `response`, fetched from `addresses`, evicting the least
+    /// recently used accounts beyond the capacity.
+    fn insert(&self, addresses: &[Pubkey], response: &Response<Vec<Option<Account>>>) {
+        let mut evicted = Vec::new();
+        {
+            let mut store = self.store();
+            let cached_at = Instant::now();
+            for (address, account) in addresses.iter().zip(&response.value) {
+                store.remove(address);
+                store.entries.insert(
+                    *address,
+                    Entry {
+                        account: account.clone(),
+                        slot: response.context.slot,
+                        cached_at,
+                        used: 0,
+                    },
+                );
+                store.touch(address);
+            }
+            if let Some(capacity) = self.capacity {
+                while store.entries.len() > capacity {
+                    let Some((_, address)) = store.by_use.pop_first() else {
+                        break;
+                    };
+                    store.entries.remove(&address);
+                    evicted.push(address);
+                }
+            }
+        }
+        self.evictions
+            .fetch_add(evicted.len() as u64, Ordering::Relaxed);
+        if let Some(on_evict) = &self.on_evict {
+            for address in &evicted {
+                on_evict(address);
+            }
+        }
+    }
+
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for AccountCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccountCache")
+            .field("ttl", &self.ttl)
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An `AccountFetcher` that answers the requests of `inner` from `cache` when it can.
+pub struct Cached<F> {
+    pub inner: F,
+    pub cache: Arc<AccountCache>,
+}
+
+impl<F> Cached<F> {
+    pub fn new(inner: F, cache: Arc<AccountCache>) -> Self {
+        Cached { inner, cache }
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for Cached<F> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        let request = || {
+            self.inner
+                .fetch_accounts_with_config(addresses, config.clone())
+        };
+        self.cache.fetch_with(addresses, &config, request).await
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        self.inner.fetch_program_accounts(program_id, config).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockFetcher;
+
+    fn account(data: u8) -> Account {
+        Account {
+            data: vec![data],
+            ..Account::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn accounts_cached_at_different_slots_are_fetched_again() {
+        let [pool, vault_a, vault_b] = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let node = |slot| {
+            MockFetcher::new()
+                .with_slot(slot)
+                .with_account(pool, account(1))
+                .with_account(vault_a, account(2))
+                .with_account(vault_b, account(3))
+        };
+        let cache = Arc::new(AccountCache::new(Duration::from_secs(60)));
+        let (at_10, at_11, at_12) = (node(10), node(11), node(12));
+
+        // The pool is cached by one response and its vaults by a later one.
+        Cached::new(&at_10, cache.clone())
+            .fetch_accounts(&[pool])
+            .await
+            .unwrap();
+        Cached::new(&at_11, cache.clone())
+            .fetch_accounts(&[vault_a, vault_b])
+            .await
+            .unwrap();
+
+        let all = [pool, vault_a, vault_b];
+        let response = Cached::new(&at_12, cache.clone())
+            .fetch_accounts(&all)
+            .await
+            .unwrap();
+        assert_eq!(response.context.slot, 12);
+        assert_eq!(at_12.request_count(), 1);
+
+        // Now that they were read together, they're answered together.
+        let response = Cached::new(&at_12, cache.clone())
+            .fetch_accounts(&all)
+            .await
+            .unwrap();
+        assert_eq!(response.context.slot, 12);
+        assert_eq!(at_12.request_count(), 1);
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 3);
+    }
+
+    #[tokio::test]
+    async fn evicts_the_least_recently_used_accounts_beyond_the_capacity() {
+        let [a, b, c] = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let node = MockFetcher::new()
+            .with_account(a, account(1))
+            .with_account(b, account(2))
+            .with_account(c, account(3));
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let on_evict = {
+            let evicted = evicted.clone();
+            move |address: &Pubkey| evicted.lock().unwrap().push(*address)
+        };
+        let cache = AccountCache::new(Duration::from_secs(60))
+            .with_capacity(2)
+            .on_evict(on_evict);
+        let fetcher = Cached::new(&node, Arc::new(cache));
+
+        fetcher.fetch_accounts(&[a]).await.unwrap();
+        fetcher.fetch_accounts(&[b]).await.unwrap();
+        // Answering `a` makes `b` the least recently used.
+        fetcher.fetch_accounts(&[a]).await.unwrap();
+        fetcher.fetch_accounts(&[c]).await.unwrap();
+        assert_eq!(*evicted.lock().unwrap(), vec![b]);
+        assert_eq!(fetcher.cache.len(), 2);
+
+        fetcher.fetch_accounts(&[a, c]).await.unwrap();
+        assert_eq!(node.request_count(), 3);
+        fetcher.fetch_accounts(&[b]).await.unwrap();
+        assert_eq!(node.request_count(), 4);
+        assert_eq!(*evicted.lock().unwrap(), vec![b, a]);
+        assert_eq!(
+            fetcher.cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 4,
+                evictions: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_accounts_are_fetched_again() {
+        let address = Pubkey::new_unique();
+        let node = MockFetcher::new().with_account(address, account(1));
+        let cache = Arc::new(AccountCache::new(Duration::from_millis(50)));
+        let fetcher = Cached::new(&node, cache);
+
+        fetcher.fetch_accounts(&[address]).await.unwrap();
+        fetcher.fetch_accounts(&[address]).await.unwrap();
+        assert_eq!(node.request_count(), 1);
+        std::thread::sleep(Duration::from_millis(60));
+        fetcher.fetch_accounts(&[address]).await.unwrap();
+        assert_eq!(node.request_count(), 2);
+        assert_eq!(fetcher.cache.stats().hits, 1);
+        assert_eq!(fetcher.cache.stats().evictions, 0);
+    }
+
+    #[tokio::test]
+    async fn accounts_older_than_the_min_context_slot_are_fetched_again() {
+        let address = Pubkey::new_unique();
+        let node = |slot| {
+            MockFetcher::new()
+                .with_slot(slot)
+                .with_account(address, account(1))
+        };
+        let (at_10, at_12) = (node(10), node(12));
+        let cache = Arc::new(AccountCache::new(Duration::from_secs(60)));
+        Cached::new(&at_10, cache.clone())
+            .fetch_accounts(&[address])
+            .await
+            .unwrap();
+
+        let fetcher = Cached::new(&at_12, cache);
+        let at_least = |min_context_slot| RpcAccountInfoConfig {
+            min_context_slot: Some(min_context_slot),
+            ..RpcAccountInfoConfig::default()
+        };
+        let response = fetcher
+            .fetch_accounts_with_config(&[address], at_least(10))
+            .await
+            .unwrap();
+        assert_eq!(response.context.slot, 10);
+        assert_eq!(at_12.request_count(), 0);
+
+        let response = fetcher
+            .fetch_accounts_with_config(&[address], at_least(11))
+            .await
+            .unwrap();
+        assert_eq!(response.context.slot, 12);
+        assert_eq!(at_12.request_count(), 1);
+    }
+}
@@ -0,0 +1,154 @@
+//! Static metadata about every supported pool type.
+//!
+//! `PoolType::all` lists the pool types with what's known about them without fetching
+//! anything, for building selection menus and checking capabilities at runtime.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::normalized::PoolKind;
+use crate::parsing::{PoolType, StabblePoolKind};
+use crate::pool_name::NAMES;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What's known about a pool type before fetching one.
+pub struct PoolTypeInfo {
+    /// Name accepted by `PoolType::from_name`.
+    pub name: &'static str,
+    /// Name of the protocol, as returned by `PoolType::pool_name`.
+    pub display_name: &'static str,
+    /// Mainnet program owning accounts of the pool type, as returned by
+    /// `PoolType::expected_program_id`.
+    pub program_id: Option<Pubkey>,
+    /// Name of the account type in the program's IDL or source, or `None` for dynamic
+    /// pools, which can be any account of their IDL.
+    pub account_type: Option<&'static str>,
+    /// Pricing model of the pools, or `None` for pool types that aren't pools, such as
+    /// configs and positions.
+    ///
+    /// The token-swap programs also support stable curves, so token-swap pools are listed
+    /// as constant-product but may be stable-swap pools; `NormalizedPool::kind` has the
+    /// kind of a fetched pool.
+    pub kind: Option<PoolKind>,
+}
+
+impl PoolTypeInfo {
+    /// Returns whether the pools are concentrated-liquidity pools.
+    pub fn is_clmm(&self) -> bool {
+        self.kind == Some(PoolKind::ConcentratedLiquidity)
+    }
+
+    /// Returns whether the pools are constant-product pools.
+    pub fn is_cpmm(&self) -> bool {
+        self.kind == Some(PoolKind::ConstantProduct)
+    }
+}
+
+impl PoolType {
+    /// Returns the metadata of every pool type constructible from an address, in the order
+    /// of the variants.
+    ///
+    /// EVM and dynamic pool types are left out since they need more than an address; their
+    /// metadata is available from `info` on a constructed pool type.
+    pub fn all() -> impl Iterator<Item = PoolTypeInfo> {
+        NAMES.iter().map(|name| {
+            PoolType::from_name(name, Pubkey::default())
+                .expect("every name is constructible")
+                .info()
+        })
+    }
+
+    /// Returns the metadata of the pool type.
+    pub fn info(&self) -> PoolTypeInfo {
+        PoolTypeInfo {
+            name: self.name(),
+            display_name: self.pool_name(),
+            program_id: self.expected_program_id(),
+            account_type: account_type(self),
+            kind: self.kind(),
+        }
+    }
+
+    /// Returns the pricing model of pools of this type, as described on
+    /// `PoolTypeInfo::kind`.
+    pub fn kind(&self) -> Option<PoolKind> {
+        kind(self)
+    }
+}
+
+fn account_type(pool_type: &PoolType) -> Option<&'static str> {
+    Some(match pool_type {
+        PoolType::PumpFun { .. } | PoolType::PumpFunWithConfig { .. } => "Pool",
+        PoolType::RaydiumCpmmAmm { .. }
+        | PoolType::RaydiumCpmmAmmWithConfig { .. }
+        | PoolType::RaydiumCamm { .. }
+        | PoolType::RaydiumLaunchLab { .. }
+        | PoolType::SanctumInfinity { .. } => "PoolState",
+        PoolType::RaydiumLegacyAmm { .. } => "AmmInfo",
+        PoolType::LifinityV2 { .. } => "Amm",
+        PoolType::Phoenix { .. } => "Market",
+        PoolType::SaberStableSwap { .. } | PoolType::MercurialStableSwap { .. } => "SwapInfo",
+        PoolType::Invariant { .. } | PoolType::Aldrin { .. } | PoolType::Stabble { .. } => "Pool",
+        PoolType::CremaClmm { .. } => "ClmmPool",
+        PoolType::FluxBeam { .. }
+        | PoolType::OrcaTokenSwap { .. }
+        | PoolType::SplTokenSwap { .. } => "TokenSwap",
+        PoolType::PumpFunBondingCurve { .. } => "BondingCurve",
+        PoolType::Moonshot { .. } => "CurveAccount",
+        PoolType::MeteoraDbc { .. } => "VirtualPool",
+        PoolType::MeteoraVault { .. } => "Vault",
+        // Either `Pool` or `PoolV2`, whichever layout the pool has.
+        PoolType::Bonkswap { .. } => "Pool",
+        PoolType::BonkswapFarm { .. } => "Farm",
+        #[cfg(feature = "evm")]
+        PoolType::UniswapV2 { .. } => "UniswapV2Pair",
+        #[cfg(feature = "evm")]
+        PoolType::UniswapV3 { .. } => "UniswapV3Pool",
+        PoolType::Dynamic { .. } => return None,
+        PoolType::SplStakePool { .. } => "StakePool",
+        PoolType::Marinade { .. } => "State",
+        PoolType::RaydiumCammConfig { .. } | PoolType::RaydiumCpmmConfig { .. } => "AmmConfig",
+        PoolType::RaydiumCammObservation { .. } => "ObservationState",
+        PoolType::PumpFunGlobalConfig { .. } => "GlobalConfig",
+        PoolType::MeteoraDlmm { .. } => "LbPair",
+        PoolType::OrcaWhirlpool { .. } => "Whirlpool",
+        PoolType::OrcaWhirlpoolPosition { .. } => "Position",
+    })
+}
+
+fn kind(pool_type: &PoolType) -> Option<PoolKind> {
+    Some(match pool_type {
+        PoolType::PumpFun { .. }
+        | PoolType::PumpFunWithConfig { .. }
+        | PoolType::RaydiumCpmmAmm { .. }
+        | PoolType::RaydiumCpmmAmmWithConfig { .. }
+        | PoolType::RaydiumLegacyAmm { .. }
+        | PoolType::Aldrin { .. }
+        | PoolType::Bonkswap { .. }
+        | PoolType::FluxBeam { .. }
+        | PoolType::OrcaTokenSwap { .. }
+        | PoolType::SplTokenSwap { .. } => PoolKind::ConstantProduct,
+        #[cfg(feature = "evm")]
+        PoolType::UniswapV2 { .. } => PoolKind::ConstantProduct,
+        PoolType::RaydiumCamm { .. }
+        | PoolType::Invariant { .. }
+        | PoolType::CremaClmm { .. }
+        | PoolType::OrcaWhirlpool { .. } => PoolKind::ConcentratedLiquidity,
+        #[cfg(feature = "evm")]
+        PoolType::UniswapV3 { .. } => PoolKind::ConcentratedLiquidity,
+        PoolType::MeteoraDlmm { .. } => PoolKind::LiquidityBook,
+        PoolType::SaberStableSwap { .. } | PoolType::MercurialStableSwap { .. } => {
+            PoolKind::StableSwap
+        }
+        PoolType::Stabble { kind, .. } => match kind {
+            StabblePoolKind::Stable => PoolKind::StableSwap,
+            StabblePoolKind::Weighted => PoolKind::Weighted,
+        },
+        PoolType::LifinityV2 { .. } | PoolType::SanctumInfinity { .. } => PoolKind::Oracle,
+        PoolType::Phoenix { .. } => PoolKind::OrderBook,
+        PoolType::PumpFunBondingCurve { .. }
+        | PoolType::Moonshot { .. }
+        | PoolType::RaydiumLaunchLab { .. }
+        | PoolType::MeteoraDbc { .. } => PoolKind::BondingCurve,
+        _ => return None,
+    })
+}
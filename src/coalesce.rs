@@ -0,0 +1,301 @@
+//! Sharing one request between callers that make it at the same time.
+//!
+//! Services that look up the same hot pools from many tasks at once would otherwise send
+//! the same request once per task. `Coalescing` lets the first caller of a request send
+//! it and the others that ask for the same accounts with the same configuration while
+//! it's in flight wait for its response instead. Requests are only shared while they're
+//! in flight: one made after the last has completed is sent again, unless a cache in
+//! front of it answers it.
+//!
+//! Errors aren't shared. If the request fails, or the caller sending it gives up on it,
+//! one of the callers waiting for it sends it again and the rest wait for that one.
+//!
+//! `UniversalParser` coalesces its requests unless built with `coalesce(false)`.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_response::Response;
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::Pubkey;
+use tokio::sync::watch;
+
+use crate::fetcher::AccountFetcher;
+use crate::parsing::PoolError;
+
+/// A request, by what it asks for and its configuration as JSON.
+type Key<T> = (T, String);
+
+type Accounts = Response<Vec<Option<Account>>>;
+type ProgramAccounts = Response<Vec<(Pubkey, Account)>>;
+
+/// The requests in flight, and the responses their callers wait for.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    accounts: Flights<Vec<Pubkey>, Accounts>,
+    program_accounts: Flights<Pubkey, ProgramAccounts>,
+}
+
+impl InFlight {
+    /// Makes the request for the accounts at `addresses` with `config` with `request`, or
+    /// waits for the same request already in flight.
+    pub(crate) async fn fetch_accounts<Fut>(
+        &self,
+        addresses: &[Pubkey],
+        config: &RpcAccountInfoConfig,
+        request: impl Fn() -> Fut,
+    ) -> Result<Accounts, PoolError>
+    where
+        Fut: Future<Output = Result<Accounts, PoolError>>,
+    {
+        let key = (addresses.to_vec(), config_key(config));
+        self.accounts.run(key, request).await
+    }
+
+    /// Makes the request for the accounts of `program_id` with `config` with `request`,
+    /// or waits for the same request already in flight.
+    pub(crate) async fn fetch_program_accounts<Fut>(
+        &self,
+        program_id: &Pubkey,
+        config: &RpcProgramAccountsConfig,
+        request: impl Fn() -> Fut,
+    ) -> Result<ProgramAccounts, PoolError>
+    where
+        Fut: Future<Output = Result<ProgramAccounts, PoolError>>,
+    {
+        let key = (*program_id, config_key(config));
+        self.program_accounts.run(key, request).await
+    }
+}
+
+/// Returns the JSON `config` is sent as, which tells requests apart along with what they
+/// ask for.
+fn config_key(config: &impl serde::Serialize) -> String {
+    serde_json::to_string(config).expect("RPC configs serialize to JSON")
+}
+
+/// Requests of one kind in flight, each with the receiver of its response. The sender is
+/// held by the caller making the request, and dropped without a response if it fails.
+#[derive(Debug)]
+struct Flights<K, T> {
+    flights: Mutex<HashMap<Key<K>, watch::Receiver<Option<T>>>>,
+}
+
+impl<K, T> Default for Flights<K, T> {
+    fn default() -> Self {
+        Flights {
+            flights: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, T: Clone> Flights<K, T> {
+    async fn run<Fut>(&self, key: Key<K>, request: impl Fn() -> Fut) -> Result<T, PoolError>
+    where
+        Fut: Future<Output = Result<T, PoolError>>,
+    {
+        loop {
+            let joined = {
+                let mut flights = self.flights();
+                match flights.get(&key) {
+                    Some(receiver) => Ok(receiver.clone()),
+                    None => {
+                        let (sender, receiver) = watch::channel(None);
+                        flights.insert(key.clone(), receiver);
+                        Err(sender)
+                    }
+                }
+            };
+            let sender = match joined {
+                Ok(mut receiver) => {
+                    if let Some(response) = shared_response(&mut receiver).await {
+                        return Ok(response);
+                    }
+                    // The request failed or was given up on; make or join it again.
+                    continue;
+                }
+                Err(sender) => sender,
+            };
+
+            let _landing = Landing {
+                flights: self,
+                key: &key,
+            };
+            let response = request().await?;
+            // Waiting callers may all have given up.
+            let _ = sender.send(Some(response.clone()));
+            return Ok(response);
+        }
+    }
+
+    fn flights(&self) -> MutexGuard<'_, HashMap<Key<K>, watch::Receiver<Option<T>>>> {
+        self.flights.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Waits for the response sent through `receiver`, or `None` if its sender is dropped
+/// without one.
+async fn shared_response<T: Clone>(receiver: &mut watch::Receiver<Option<T>>) -> Option<T> {
+    match receiver.wait_for(Option::is_some).await {
+        Ok(response) => response.clone(),
+        Err(_) => None,
+    }
+}
+
+/// Removes a request from its flights once the caller making it is done with it, however
+/// that happens.
+struct Landing<'a, K: Hash + Eq, T> {
+    flights: &'a Flights<K, T>,
+    key: &'a Key<K>,
+}
+
+impl<K: Hash + Eq, T> Drop for Landing<'_, K, T> {
+    fn drop(&mut self) {
+        self.flights
+            .flights
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(self.key);
+    }
+}
+
+#[derive(Debug)]
+/// An `AccountFetcher` that shares each request of `inner` between the callers making it at
+/// the same time.
+pub struct Coalescing<F> {
+    pub inner: F,
+    in_flight: InFlight,
+}
+
+impl<F> Coalescing<F> {
+    pub fn new(inner: F) -> Self {
+        Coalescing {
+            inner,
+            in_flight: InFlight::default(),
+        }
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for Coalescing<F> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        let request = || {
+            self.inner
+                .fetch_accounts_with_config(addresses, config.clone())
+        };
+        self.in_flight
+            .fetch_accounts(addresses, &config, request)
+            .await
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        let request = || {
+            self.inner
+                .fetch_program_accounts(program_id, config.clone())
+        };
+        self.in_flight
+            .fetch_program_accounts(program_id, &config, request)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::sync::Notify;
+
+    use super::*;
+
+    fn key() -> Key<u32> {
+        (1, String::new())
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_are_sent_once() {
+        let flights = Flights::<u32, u64>::default();
+        let calls = AtomicUsize::new(0);
+        let landed = Notify::new();
+        let request = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            landed.notified().await;
+            Ok(7)
+        };
+
+        let (a, b, c, ()) = tokio::join!(
+            flights.run(key(), request),
+            flights.run(key(), request),
+            flights.run(key(), request),
+            async { landed.notify_one() },
+        );
+        assert_eq!((a.unwrap(), b.unwrap(), c.unwrap()), (7, 7, 7));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(flights.flights().is_empty());
+    }
+
+    #[tokio::test]
+    async fn one_waiter_sends_a_failed_request_again() {
+        let flights = Flights::<u32, u64>::default();
+        let calls = AtomicUsize::new(0);
+        let failed = Notify::new();
+        let request = || async {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                failed.notified().await;
+                return Err(PoolError::InvalidConfig("unavailable".to_string()));
+            }
+            // Stay in flight until the other waiter has joined.
+            tokio::task::yield_now().await;
+            Ok(7)
+        };
+
+        let (a, b, c, ()) = tokio::join!(
+            flights.run(key(), request),
+            flights.run(key(), request),
+            flights.run(key(), request),
+            async { failed.notify_one() },
+        );
+        assert!(a.is_err());
+        assert_eq!((b.unwrap(), c.unwrap()), (7, 7));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn one_waiter_sends_a_cancelled_request_again() {
+        let flights = Flights::<u32, u64>::default();
+        let calls = AtomicUsize::new(0);
+        let cancel = Notify::new();
+        let request = || async {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                std::future::pending::<()>().await;
+            }
+            tokio::task::yield_now().await;
+            Ok(7)
+        };
+        let cancelled = async {
+            tokio::select! {
+                response = flights.run(key(), request) => Some(response),
+                () = cancel.notified() => None,
+            }
+        };
+
+        let (a, b, c, ()) = tokio::join!(
+            cancelled,
+            flights.run(key(), request),
+            flights.run(key(), request),
+            async { cancel.notify_one() },
+        );
+        assert!(a.is_none());
+        assert_eq!((b.unwrap(), c.unwrap()), (7, 7));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
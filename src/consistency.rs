@@ -0,0 +1,348 @@
+//! Reading the accounts of a pool at one slot.
+//!
+//! Many pools are completed with accounts besides their own, such as configs and, with
+//! `reserves`, their vaults, in requests of their own. Each request is answered at the
+//! slot the node is at when it gets it, which may be a different node behind a load
+//! balancer or failover, so a pool may be parsed together with a config or vault balances
+//! from other slots: states the chain never had, such as reserves that don't match the
+//! price.
+//!
+//! A `Consistency` decides how far that goes:
+//!
+//! - `Consistency::Any` takes each answer as it comes, as every fetch does by default.
+//! - `Consistency::MinSlot` only accepts answers no earlier than the latest slot seen, as
+//!   `Monotonic` does, so no account is older than the pool.
+//! - `Consistency::SameSlot` reads every account at one slot. If the accounts were read at
+//!   different slots, all of them are fetched again in one request, which nodes answer at
+//!   a single slot, and the pool is parsed again from it, up to
+//!   `MAX_SAME_SLOT_ATTEMPTS` times in all.
+//!
+//! ```no_run
+//! # use universal_liquidity_pool_parser::consistency::Consistency;
+//! # use universal_liquidity_pool_parser::parsing::PoolError;
+//! # use universal_liquidity_pool_parser::universal::UniversalParser;
+//! # fn example() -> Result<(), PoolError> {
+//! let parser = UniversalParser::builder()
+//!     .rpc_url("https://api.mainnet-beta.solana.com")
+//!     .reserves(true)
+//!     .consistency(Consistency::SameSlot)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use anchor_client::solana_client::rpc_response::{Response, RpcResponseContext};
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::Pubkey;
+
+use crate::fetcher::{self, AccountFetcher};
+use crate::parsing::{self, ParseMode, ParsedPool, PoolError, PoolType};
+use crate::program_ids::Cluster;
+use crate::reserves;
+
+/// How many times `Consistency::SameSlot` parses a pool, the first time included, before
+/// failing with `PoolError::InconsistentSlots`.
+pub const MAX_SAME_SLOT_ATTEMPTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Which slots the accounts of a pool may be read at.
+pub enum Consistency {
+    /// Any slot.
+    #[default]
+    Any,
+    /// The slot of the pool account or a later one.
+    MinSlot,
+    /// The same slot for every account.
+    SameSlot,
+}
+
+/// Like `parsing::get_info_struct_on`, but reads the accounts of the pool as `consistency`
+/// requires.
+///
+/// Returns `PoolError::InconsistentSlots` if `Consistency::SameSlot` can't read them at
+/// one slot.
+pub async fn get_info_struct_with_consistency<F: AccountFetcher>(
+    pool_type: PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+    consistency: Consistency,
+) -> Result<ParsedPool, PoolError> {
+    get_info_struct_with(
+        pool_type,
+        fetcher,
+        cluster,
+        ParseMode::default(),
+        consistency,
+        None,
+    )
+    .await
+}
+
+/// Fetches and parses the pool as `consistency` requires, with the balances of its vaults
+/// if `vaults` is given, as `reserves::get_info_struct_with_vaults` does.
+pub(crate) async fn get_info_struct_with<F: AccountFetcher>(
+    pool_type: PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+    consistency: Consistency,
+    vaults: Option<&[Pubkey]>,
+) -> Result<ParsedPool, PoolError> {
+    match consistency {
+        Consistency::Any => parse(pool_type, fetcher, cluster, mode, vaults).await,
+        Consistency::MinSlot => {
+            let fetcher = Monotonic::new(fetcher);
+            parse(pool_type, &fetcher, cluster, mode, vaults).await
+        }
+        Consistency::SameSlot => {
+            let mut snapshot = Snapshot::default();
+            let mut slots = (0, 0);
+            for _ in 0..MAX_SAME_SLOT_ATTEMPTS {
+                let pass = Pass::new(fetcher, &snapshot);
+                let parsed = parse(pool_type.clone(), &pass, cluster, mode, vaults).await?;
+                let (addresses, read_at) = pass.into_parts();
+                let (Some(&earliest), Some(&latest)) = (read_at.first(), read_at.last()) else {
+                    return Ok(parsed);
+                };
+                if earliest == latest {
+                    return Ok(parsed);
+                }
+                slots = (earliest, latest);
+                snapshot = Snapshot::fetch(fetcher, &addresses, latest).await?;
+            }
+            Err(PoolError::InconsistentSlots(slots.0, slots.1))
+        }
+    }
+}
+
+async fn parse<F: AccountFetcher>(
+    pool_type: PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+    vaults: Option<&[Pubkey]>,
+) -> Result<ParsedPool, PoolError> {
+    match vaults {
+        Some(vaults) => {
+            reserves::get_info_struct_with_vaults(pool_type, fetcher, cluster, mode, vaults).await
+        }
+        None => parsing::get_info_struct_with(pool_type, fetcher, cluster, mode).await,
+    }
+}
+
+#[derive(Debug)]
+/// An `AccountFetcher` that only accepts accounts of `inner` read no earlier than the
+/// latest slot it has read accounts at, as `fetcher::WithMinContextSlot` does for a fixed
+/// slot.
+pub struct Monotonic<F> {
+    pub inner: F,
+    latest: AtomicU64,
+}
+
+impl<F> Monotonic<F> {
+    pub fn new(inner: F) -> Self {
+        Monotonic {
+            inner,
+            latest: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the latest slot accounts were read at, or zero before the first request.
+    pub fn latest_slot(&self) -> u64 {
+        self.latest.load(Ordering::Relaxed)
+    }
+
+    fn with_latest(&self, config: RpcAccountInfoConfig) -> RpcAccountInfoConfig {
+        with_min_context_slot(config, self.latest_slot())
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for Monotonic<F> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        let config = self.with_latest(config);
+        let response = self
+            .inner
+            .fetch_accounts_with_config(addresses, config)
+            .await?;
+        self.latest
+            .fetch_max(response.context.slot, Ordering::Relaxed);
+        Ok(response)
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        let config = RpcProgramAccountsConfig {
+            account_config: self.with_latest(config.account_config),
+            ..config
+        };
+        let response = self
+            .inner
+            .fetch_program_accounts(program_id, config)
+            .await?;
+        self.latest
+            .fetch_max(response.context.slot, Ordering::Relaxed);
+        Ok(response)
+    }
+}
+
+/// Returns `config` asking for accounts read at `slot` or later as well.
+fn with_min_context_slot(config: RpcAccountInfoConfig, slot: u64) -> RpcAccountInfoConfig {
+    if slot == 0 {
+        return config;
+    }
+    RpcAccountInfoConfig {
+        min_context_slot: Some(config.min_context_slot.map_or(slot, |min| min.max(slot))),
+        ..config
+    }
+}
+
+/// Accounts fetched together, each with the slot it was read at.
+#[derive(Default)]
+struct Snapshot {
+    accounts: HashMap<Pubkey, (Option<Account>, u64)>,
+    /// Slot the accounts were asked for at or after, zero for an empty snapshot.
+    min_context_slot: u64,
+}
+
+impl Snapshot {
+    /// Fetches the accounts at `addresses` in as few requests as possible, read at
+    /// `min_context_slot` or later.
+    async fn fetch<F: AccountFetcher>(
+        fetcher: &F,
+        addresses: &[Pubkey],
+        min_context_slot: u64,
+    ) -> Result<Self, PoolError> {
+        let mut accounts = HashMap::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let config = with_min_context_slot(RpcAccountInfoConfig::default(), min_context_slot);
+            let response = fetcher.fetch_accounts_with_config(chunk, config).await?;
+            let slot = response.context.slot;
+            for (address, account) in chunk.iter().zip(response.value) {
+                accounts.insert(*address, (account, slot));
+            }
+        }
+        Ok(Snapshot {
+            accounts,
+            min_context_slot,
+        })
+    }
+}
+
+/// One attempt at parsing a pool: answers requests from `snapshot` when it holds every
+/// account asked for and with `inner` otherwise, noting the accounts asked for and the
+/// slots they were read at.
+struct Pass<'a, F> {
+    inner: &'a F,
+    snapshot: &'a Snapshot,
+    /// Accounts asked for, in the order they were first asked for.
+    addresses: Mutex<Vec<Pubkey>>,
+    slots: Mutex<BTreeSet<u64>>,
+}
+
+impl<'a, F: AccountFetcher> Pass<'a, F> {
+    fn new(inner: &'a F, snapshot: &'a Snapshot) -> Self {
+        Pass {
+            inner,
+            snapshot,
+            addresses: Mutex::new(Vec::new()),
+            slots: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    fn asked(&self, addresses: &[Pubkey]) {
+        let mut asked = lock(&self.addresses);
+        for address in addresses {
+            if !asked.contains(address) {
+                asked.push(*address);
+            }
+        }
+    }
+
+    fn read_at(&self, slot: u64) {
+        lock(&self.slots).insert(slot);
+    }
+
+    /// Returns the accounts asked for and the slots they were read at.
+    fn into_parts(self) -> (Vec<Pubkey>, BTreeSet<u64>) {
+        let addresses = self
+            .addresses
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner());
+        let slots = self.slots.into_inner().unwrap_or_else(|e| e.into_inner());
+        (addresses, slots)
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for Pass<'_, F> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        self.asked(addresses);
+        let held: Option<Vec<_>> = addresses
+            .iter()
+            .map(|address| self.snapshot.accounts.get(address))
+            .collect();
+        if let Some(held) = held {
+            let mut accounts = Vec::with_capacity(held.len());
+            for (account, read_at) in &held {
+                self.read_at(*read_at);
+                accounts.push(
+                    account
+                        .as_ref()
+                        .map(|account| fetcher::sliced(account, &config)),
+                );
+            }
+            let slot = held.iter().map(|(_, read_at)| *read_at).min();
+            return Ok(Response {
+                context: RpcResponseContext::new(slot.unwrap_or(self.snapshot.min_context_slot)),
+                value: accounts,
+            });
+        }
+        let config = with_min_context_slot(config, self.snapshot.min_context_slot);
+        let response = self
+            .inner
+            .fetch_accounts_with_config(addresses, config)
+            .await?;
+        self.read_at(response.context.slot);
+        Ok(response)
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        let config = RpcProgramAccountsConfig {
+            account_config: with_min_context_slot(
+                config.account_config,
+                self.snapshot.min_context_slot,
+            ),
+            ..config
+        };
+        let response = self
+            .inner
+            .fetch_program_accounts(program_id, config)
+            .await?;
+        self.read_at(response.context.slot);
+        Ok(response)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
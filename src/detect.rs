@@ -0,0 +1,360 @@
+//! Parsing an account without knowing which protocol it belongs to.
+//!
+//! Every supported program owns its pools, so the owner of an account is enough to pick
+//! the protocol. Programs that own several kinds of accounts are told apart by their
+//! discriminators. Token-swap deployments share one layout, so the program id also picks
+//! the `AmmPool` variant.
+//!
+//! `AmmPool::try_parse_any` works on the data alone, for sources such as Geyser streams
+//! that don't carry the owner.
+
+use std::time::SystemTime;
+
+use anchor_lang::prelude::{AccountDeserialize, AnchorDeserialize, Discriminator, Pubkey};
+
+use crate::fetcher::AccountFetcher;
+use crate::layouts::mercurial::SwapInfo as MercurialSwapInfo;
+use crate::layouts::phoenix::{self, Market as PhoenixMarket};
+use crate::layouts::raydium_amm_v4::{self, AmmInfo};
+use crate::layouts::saber::SwapInfo as SaberSwapInfo;
+use crate::layouts::sanctum::{self, PoolState as SanctumPoolState};
+use crate::layouts::spl_stake_pool::{self, StakePool};
+use crate::layouts::spl_token_swap::{self, TokenSwap};
+use crate::layouts::{mercurial, saber};
+use crate::parsing::{
+    AldrinPool, AldrinVersion, AmmPool, BonkswapPool, MeteoraVaultState, ParsedPool, PoolError,
+    PoolType, StabblePool, StabblePoolKind, aldrin_v1, aldrin_v2, bonkswap, crema_clmm, invariant,
+    lifinity_v2, marinade, meteora_dbc, meteora_dlmm, meteora_vault, meteora_vault_with_strategies,
+    moonshot, orca_whirlpool, pamm, pump, raydium_amm_cpmm_new, raydium_camm, raydium_launchlab,
+    sanctum_infinity_with_lst_states, stabble_stable_swap, stabble_weighted_swap,
+};
+
+/// Fetches the account at `address`, detects its protocol from the owning program and
+/// parses it.
+///
+/// Only the account itself is fetched, except for Sanctum Infinity pools and Meteora
+/// vaults, which are completed with the accounts `get_info_struct` fetches for them.
+/// Returns `PoolError::UnsupportedProgram` if the owner isn't a program this crate
+/// parses.
+pub async fn detect_and_parse<F: AccountFetcher>(
+    address: &Pubkey,
+    fetcher: &F,
+) -> std::result::Result<AmmPool, PoolError> {
+    detect_and_parse_with(address, fetcher)
+        .await
+        .map(|parsed| parsed.pool)
+}
+
+/// Like `detect_and_parse`, but returning the pool together with the slot and data of its
+/// account like `get_info_struct`.
+pub async fn detect_and_parse_with<F: AccountFetcher>(
+    address: &Pubkey,
+    fetcher: &F,
+) -> std::result::Result<ParsedPool, PoolError> {
+    let response = fetcher.fetch_account(address).await?;
+    let fetched_at = SystemTime::now();
+    let Some(account) = response.value else {
+        return Err(PoolError::AccountNotFound { address: *address });
+    };
+    let pool = complete(detect(&account.owner, &account.data)?, fetcher).await?;
+    Ok(ParsedPool {
+        pool,
+        address: *address,
+        slot: Some(response.context.slot),
+        fetched_at,
+        raw: Some(account.data),
+        reserves: None,
+    })
+}
+
+/// An account parsed by its owner, which may still need other accounts to complete it.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Detected {
+    Pool(AmmPool),
+    SanctumInfinity(SanctumPoolState),
+    MeteoraVault(MeteoraVaultState),
+}
+
+impl Detected {
+    /// Returns the pool type of the account at `pool_address` as detected.
+    pub(crate) fn pool_type(&self, pool_address: Pubkey) -> Option<PoolType> {
+        match self {
+            Detected::Pool(pool) => pool.pool_type(pool_address),
+            Detected::SanctumInfinity(_) => Some(PoolType::SanctumInfinity { pool_address }),
+            Detected::MeteoraVault(_) => Some(PoolType::MeteoraVault { pool_address }),
+        }
+    }
+
+    /// Returns the detected pool, or `PoolError::IncompleteData` if it needs other
+    /// accounts to be complete.
+    pub(crate) fn into_pool(self) -> std::result::Result<AmmPool, PoolError> {
+        let pool_type = match self {
+            Detected::Pool(pool) => return Ok(pool),
+            Detected::SanctumInfinity(_) => PoolType::SanctumInfinity {
+                pool_address: Pubkey::default(),
+            },
+            Detected::MeteoraVault(_) => PoolType::MeteoraVault {
+                pool_address: Pubkey::default(),
+            },
+        };
+        Err(PoolError::IncompleteData(pool_type.pool_name()))
+    }
+}
+
+/// Fetches the accounts that complete `detected` with `fetcher`.
+pub(crate) async fn complete<F: AccountFetcher>(
+    detected: Detected,
+    fetcher: &F,
+) -> std::result::Result<AmmPool, PoolError> {
+    match detected {
+        Detected::Pool(pool) => Ok(pool),
+        Detected::SanctumInfinity(state) => sanctum_infinity_with_lst_states(state, fetcher)
+            .await
+            .map(AmmPool::SanctumInfinity),
+        Detected::MeteoraVault(vault) => meteora_vault_with_strategies(vault, fetcher)
+            .await
+            .map(AmmPool::MeteoraVault),
+    }
+}
+
+/// Parses the data of an account owned by `owner` with the layout of its program.
+pub(crate) fn detect(owner: &Pubkey, data: &[u8]) -> std::result::Result<Detected, PoolError> {
+    let owner = *owner;
+
+    let pool = if owner == pamm::ID {
+        pamm::accounts::Pool::try_deserialize(&mut &data[..])
+            .map(AmmPool::PumpFun)
+            .or_else(|_| {
+                pamm::accounts::GlobalConfig::try_deserialize(&mut &data[..])
+                    .map(AmmPool::PumpFunGlobalConfig)
+            })
+    } else if owner == pump::ID {
+        pump::accounts::BondingCurve::try_deserialize(&mut &data[..])
+            .map(AmmPool::PumpFunBondingCurve)
+    } else if owner == raydium_amm_cpmm_new::ID {
+        raydium_amm_cpmm_new::accounts::PoolState::try_deserialize(&mut &data[..])
+            .map(AmmPool::RaydiumCpmmAmm)
+            .or_else(|_| {
+                raydium_amm_cpmm_new::accounts::AmmConfig::try_deserialize(&mut &data[..])
+                    .map(AmmPool::RaydiumCpmmConfig)
+            })
+    } else if owner == raydium_camm::ID {
+        raydium_camm::accounts::PoolState::try_deserialize(&mut &data[..])
+            .map(AmmPool::RaydiumCamm)
+            .or_else(|_| {
+                raydium_camm::accounts::AmmConfig::try_deserialize(&mut &data[..])
+                    .map(AmmPool::RaydiumCammConfig)
+            })
+            .or_else(|_| {
+                raydium_camm::accounts::ObservationState::try_deserialize(&mut &data[..])
+                    .map(AmmPool::RaydiumCammObservation)
+            })
+    } else if owner == raydium_amm_v4::ID {
+        AmmInfo::try_deserialize(&mut &data[..]).map(AmmPool::RaydiumLegacyAmm)
+    } else if owner == raydium_launchlab::ID {
+        raydium_launchlab::accounts::PoolState::try_deserialize(&mut &data[..])
+            .map(AmmPool::RaydiumLaunchLab)
+    } else if owner == lifinity_v2::ID {
+        lifinity_v2::accounts::Amm::try_deserialize(&mut &data[..]).map(AmmPool::LifinityV2)
+    } else if owner == phoenix::ID {
+        PhoenixMarket::try_deserialize(&mut &data[..]).map(AmmPool::Phoenix)
+    } else if owner == saber::ID {
+        SaberSwapInfo::try_deserialize(&mut &data[..]).map(AmmPool::SaberStableSwap)
+    } else if owner == mercurial::ID {
+        MercurialSwapInfo::try_deserialize(&mut &data[..]).map(AmmPool::MercurialStableSwap)
+    } else if owner == invariant::ID {
+        invariant::accounts::Pool::try_deserialize(&mut &data[..]).map(AmmPool::Invariant)
+    } else if owner == crema_clmm::ID {
+        crema_clmm::accounts::ClmmPool::try_deserialize(&mut &data[..]).map(AmmPool::CremaClmm)
+    } else if owner == aldrin_v1::ID {
+        aldrin_v1::accounts::Pool::try_deserialize(&mut &data[..])
+            .map(|pool| AmmPool::Aldrin(AldrinPool::V1(pool)))
+    } else if owner == aldrin_v2::ID {
+        aldrin_v2::accounts::Pool::try_deserialize(&mut &data[..])
+            .map(|pool| AmmPool::Aldrin(AldrinPool::V2(pool)))
+    } else if owner == spl_token_swap::FLUXBEAM_ID {
+        TokenSwap::try_deserialize(&mut &data[..]).map(AmmPool::FluxBeam)
+    } else if owner == spl_token_swap::ORCA_V1_ID || owner == spl_token_swap::ORCA_V2_ID {
+        TokenSwap::try_deserialize(&mut &data[..]).map(AmmPool::OrcaTokenSwap)
+    } else if owner == spl_token_swap::ID {
+        TokenSwap::try_deserialize(&mut &data[..]).map(AmmPool::SplTokenSwap)
+    } else if owner == sanctum::ID {
+        let state = SanctumPoolState::try_deserialize(&mut &data[..])?;
+        return Ok(Detected::SanctumInfinity(state));
+    } else if owner == stabble_stable_swap::ID {
+        stabble_stable_swap::accounts::Pool::try_deserialize(&mut &data[..])
+            .map(|pool| AmmPool::Stabble(StabblePool::Stable(pool)))
+    } else if owner == stabble_weighted_swap::ID {
+        stabble_weighted_swap::accounts::Pool::try_deserialize(&mut &data[..])
+            .map(|pool| AmmPool::Stabble(StabblePool::Weighted(pool)))
+    } else if owner == moonshot::ID {
+        moonshot::accounts::CurveAccount::try_deserialize(&mut &data[..]).map(AmmPool::Moonshot)
+    } else if owner == meteora_dbc::ID {
+        meteora_dbc::accounts::VirtualPool::try_deserialize(&mut &data[..]).map(AmmPool::MeteoraDbc)
+    } else if owner == meteora_vault::ID {
+        let vault = meteora_vault::accounts::Vault::try_deserialize(&mut &data[..])?;
+        return Ok(Detected::MeteoraVault(vault));
+    } else if owner == meteora_dlmm::ID {
+        meteora_dlmm::accounts::LbPair::try_deserialize(&mut &data[..]).map(AmmPool::MeteoraDlmm)
+    } else if owner == bonkswap::ID {
+        bonkswap::accounts::PoolV2::try_deserialize(&mut &data[..])
+            .map(|pool| AmmPool::Bonkswap(BonkswapPool::V2(pool)))
+            .or_else(|_| {
+                bonkswap::accounts::Pool::try_deserialize(&mut &data[..])
+                    .map(|pool| AmmPool::Bonkswap(BonkswapPool::V1(pool)))
+            })
+            .or_else(|_| {
+                bonkswap::accounts::Farm::try_deserialize(&mut &data[..]).map(AmmPool::BonkswapFarm)
+            })
+    } else if owner == orca_whirlpool::ID {
+        orca_whirlpool::accounts::Whirlpool::try_deserialize(&mut &data[..])
+            .map(AmmPool::OrcaWhirlpool)
+            .or_else(|_| {
+                orca_whirlpool::accounts::Position::try_deserialize(&mut &data[..])
+                    .map(AmmPool::OrcaWhirlpoolPosition)
+            })
+    } else if owner == spl_stake_pool::ID {
+        StakePool::try_deserialize(&mut &data[..]).map(AmmPool::SplStakePool)
+    } else if owner == marinade::ID {
+        marinade::accounts::State::try_deserialize(&mut &data[..]).map(AmmPool::Marinade)
+    } else {
+        return Err(PoolError::UnsupportedProgram(owner));
+    };
+    match pool {
+        Ok(pool) => Ok(Detected::Pool(pool)),
+        Err(e) => Err(PoolError::DeserializeError(e)),
+    }
+}
+
+impl AmmPool {
+    /// Parses Anchor account data by its 8-byte discriminator, without knowing the owner.
+    ///
+    /// Several programs name their pool account `Pool`, `PoolState` or `AmmConfig`, which
+    /// gives them the same discriminator. Every layout with a matching discriminator is
+    /// tried and the one that leaves the fewest bytes unread wins, ties going to the
+    /// first in the order below. Accounts without an Anchor discriminator, and Meteora
+    /// vaults, which need their strategies, aren't recognized; use `detect_and_parse`
+    /// or `get_info_struct` for those. Returns `None` if no layout parses the data.
+    pub fn try_parse_any(data: &[u8]) -> Option<AmmPool> {
+        let candidates = [
+            parse::<pamm::accounts::Pool>(data).map(|(pool, n)| (AmmPool::PumpFun(pool), n)),
+            parse::<pamm::accounts::GlobalConfig>(data)
+                .map(|(config, n)| (AmmPool::PumpFunGlobalConfig(config), n)),
+            parse::<pump::accounts::BondingCurve>(data)
+                .map(|(curve, n)| (AmmPool::PumpFunBondingCurve(curve), n)),
+            parse::<raydium_amm_cpmm_new::accounts::PoolState>(data)
+                .map(|(pool, n)| (AmmPool::RaydiumCpmmAmm(pool), n)),
+            parse::<raydium_amm_cpmm_new::accounts::AmmConfig>(data)
+                .map(|(config, n)| (AmmPool::RaydiumCpmmConfig(config), n)),
+            parse::<raydium_camm::accounts::PoolState>(data)
+                .map(|(pool, n)| (AmmPool::RaydiumCamm(pool), n)),
+            parse::<raydium_camm::accounts::AmmConfig>(data)
+                .map(|(config, n)| (AmmPool::RaydiumCammConfig(config), n)),
+            parse::<raydium_camm::accounts::ObservationState>(data)
+                .map(|(state, n)| (AmmPool::RaydiumCammObservation(state), n)),
+            parse::<raydium_launchlab::accounts::PoolState>(data)
+                .map(|(pool, n)| (AmmPool::RaydiumLaunchLab(pool), n)),
+            parse::<lifinity_v2::accounts::Amm>(data).map(|(amm, n)| (AmmPool::LifinityV2(amm), n)),
+            parse::<invariant::accounts::Pool>(data).map(|(pool, n)| (AmmPool::Invariant(pool), n)),
+            parse::<crema_clmm::accounts::ClmmPool>(data)
+                .map(|(pool, n)| (AmmPool::CremaClmm(pool), n)),
+            parse::<aldrin_v1::accounts::Pool>(data)
+                .map(|(pool, n)| (AmmPool::Aldrin(AldrinPool::V1(pool)), n)),
+            parse::<aldrin_v2::accounts::Pool>(data)
+                .map(|(pool, n)| (AmmPool::Aldrin(AldrinPool::V2(pool)), n)),
+            parse::<stabble_stable_swap::accounts::Pool>(data)
+                .map(|(pool, n)| (AmmPool::Stabble(StabblePool::Stable(pool)), n)),
+            parse::<stabble_weighted_swap::accounts::Pool>(data)
+                .map(|(pool, n)| (AmmPool::Stabble(StabblePool::Weighted(pool)), n)),
+            parse::<moonshot::accounts::CurveAccount>(data)
+                .map(|(curve, n)| (AmmPool::Moonshot(curve), n)),
+            parse::<meteora_dbc::accounts::VirtualPool>(data)
+                .map(|(pool, n)| (AmmPool::MeteoraDbc(pool), n)),
+            parse::<meteora_dlmm::accounts::LbPair>(data)
+                .map(|(pair, n)| (AmmPool::MeteoraDlmm(pair), n)),
+            parse::<bonkswap::accounts::PoolV2>(data)
+                .map(|(pool, n)| (AmmPool::Bonkswap(BonkswapPool::V2(pool)), n)),
+            parse::<bonkswap::accounts::Pool>(data)
+                .map(|(pool, n)| (AmmPool::Bonkswap(BonkswapPool::V1(pool)), n)),
+            parse::<bonkswap::accounts::Farm>(data)
+                .map(|(farm, n)| (AmmPool::BonkswapFarm(farm), n)),
+            parse::<orca_whirlpool::accounts::Whirlpool>(data)
+                .map(|(pool, n)| (AmmPool::OrcaWhirlpool(pool), n)),
+            parse::<orca_whirlpool::accounts::Position>(data)
+                .map(|(position, n)| (AmmPool::OrcaWhirlpoolPosition(position), n)),
+            parse::<marinade::accounts::State>(data)
+                .map(|(state, n)| (AmmPool::Marinade(state), n)),
+        ];
+        candidates
+            .into_iter()
+            .flatten()
+            .min_by_key(|(_, unread)| *unread)
+            .map(|(pool, _)| pool)
+    }
+
+    /// Returns the `PoolType` that parses the account at `pool_address` into this pool,
+    /// or `None` for dynamic and EVM pools, whose pool types need more than an address.
+    pub fn pool_type(&self, pool_address: Pubkey) -> Option<PoolType> {
+        Some(match self {
+            AmmPool::PumpFun(_) => PoolType::PumpFun { pool_address },
+            AmmPool::RaydiumCpmmAmm(_) => PoolType::RaydiumCpmmAmm { pool_address },
+            AmmPool::RaydiumCamm(_) => PoolType::RaydiumCamm { pool_address },
+            AmmPool::RaydiumLegacyAmm(_) => PoolType::RaydiumLegacyAmm { pool_address },
+            AmmPool::LifinityV2(_) => PoolType::LifinityV2 { pool_address },
+            AmmPool::Phoenix(_) => PoolType::Phoenix { pool_address },
+            AmmPool::SaberStableSwap(_) => PoolType::SaberStableSwap { pool_address },
+            AmmPool::MercurialStableSwap(_) => PoolType::MercurialStableSwap { pool_address },
+            AmmPool::Invariant(_) => PoolType::Invariant { pool_address },
+            AmmPool::CremaClmm(_) => PoolType::CremaClmm { pool_address },
+            AmmPool::Aldrin(pool) => PoolType::Aldrin {
+                pool_address,
+                version: match pool {
+                    AldrinPool::V1(_) => AldrinVersion::V1,
+                    AldrinPool::V2(_) => AldrinVersion::V2,
+                },
+            },
+            AmmPool::FluxBeam(_) => PoolType::FluxBeam { pool_address },
+            AmmPool::SanctumInfinity(_) => PoolType::SanctumInfinity { pool_address },
+            AmmPool::Stabble(pool) => PoolType::Stabble {
+                pool_address,
+                kind: match pool {
+                    StabblePool::Stable(_) => StabblePoolKind::Stable,
+                    StabblePool::Weighted(_) => StabblePoolKind::Weighted,
+                },
+            },
+            AmmPool::PumpFunBondingCurve(_) => PoolType::PumpFunBondingCurve { pool_address },
+            AmmPool::Moonshot(_) => PoolType::Moonshot { pool_address },
+            AmmPool::RaydiumLaunchLab(_) => PoolType::RaydiumLaunchLab { pool_address },
+            AmmPool::MeteoraDbc(_) => PoolType::MeteoraDbc { pool_address },
+            AmmPool::OrcaTokenSwap(_) => PoolType::OrcaTokenSwap { pool_address },
+            AmmPool::SplTokenSwap(_) => PoolType::SplTokenSwap { pool_address },
+            AmmPool::MeteoraVault(_) => PoolType::MeteoraVault { pool_address },
+            AmmPool::Bonkswap(_) => PoolType::Bonkswap { pool_address },
+            AmmPool::BonkswapFarm(_) => PoolType::BonkswapFarm { pool_address },
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV2(_) | AmmPool::UniswapV3(_) => return None,
+            AmmPool::Dynamic(_) => return None,
+            AmmPool::SplStakePool(_) => PoolType::SplStakePool { pool_address },
+            AmmPool::Marinade(_) => PoolType::Marinade { pool_address },
+            AmmPool::RaydiumCammConfig(_) => PoolType::RaydiumCammConfig { pool_address },
+            AmmPool::RaydiumCammObservation(_) => PoolType::RaydiumCammObservation { pool_address },
+            AmmPool::RaydiumCpmmConfig(_) => PoolType::RaydiumCpmmConfig { pool_address },
+            AmmPool::RaydiumCpmmAmmWithConfig(_) => {
+                PoolType::RaydiumCpmmAmmWithConfig { pool_address }
+            }
+            AmmPool::PumpFunGlobalConfig(_) => PoolType::PumpFunGlobalConfig { pool_address },
+            AmmPool::PumpFunWithConfig(_) => PoolType::PumpFunWithConfig { pool_address },
+            AmmPool::MeteoraDlmm(_) => PoolType::MeteoraDlmm { pool_address },
+            AmmPool::OrcaWhirlpool(_) => PoolType::OrcaWhirlpool { pool_address },
+            AmmPool::OrcaWhirlpoolPosition(_) => PoolType::OrcaWhirlpoolPosition { pool_address },
+        })
+    }
+}
+
+/// Parses `data` as a `T` if it starts with the discriminator of `T`, returning the
+/// account and the number of bytes left unread.
+fn parse<T: AnchorDeserialize + Discriminator>(data: &[u8]) -> Option<(T, usize)> {
+    let mut body = data.strip_prefix(T::DISCRIMINATOR)?;
+    let account = T::deserialize(&mut body).ok()?;
+    Some((account, body.len()))
+}
@@ -0,0 +1,83 @@
+//! Field-level differences between two snapshots of a pool.
+//!
+//! `AmmPool::diff` compares the JSON representations of the pools, as written to `data`
+//! by `AmmPool::to_json`, so fields are named and formatted the same way there and here.
+//! Each change is reported at the deepest field that differs, with a path made of field
+//! names joined by `.` and array indices in brackets:
+//!
+//! ```text
+//! reward_infos[1].reward_growth_global_x64: "1843" -> "1851"
+//! ```
+//!
+//! Arrays whose length changed, such as the strategies of a Meteora vault, are reported
+//! as a whole.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::fields::{field_path, index_path};
+use crate::parsing::AmmPool;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A field whose value differs between two snapshots.
+pub struct FieldChange {
+    /// Path of the field within the pool, empty if the pools differ as a whole.
+    pub path: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.path, self.old, self.new)
+    }
+}
+
+impl AmmPool {
+    /// Returns the fields that changed from `self` to `other`, ordered by field name and
+    /// array index, or an empty list if the snapshots are equal.
+    ///
+    /// Snapshots of different account types, which have no fields in common, differ as a
+    /// whole and yield a single change with an empty path.
+    pub fn diff(&self, other: &AmmPool) -> Result<Vec<FieldChange>, serde_json::Error> {
+        let old = self.data_json()?;
+        let new = other.data_json()?;
+        let mut changes = Vec::new();
+        if self.protocol_tag() == other.protocol_tag() {
+            diff_values(String::new(), &old, &new, &mut changes);
+        } else {
+            changes.push(FieldChange {
+                path: String::new(),
+                old,
+                new,
+            });
+        }
+        Ok(changes)
+    }
+}
+
+fn diff_values(path: String, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(old_fields), Value::Object(new_fields))
+            if old_fields.keys().eq(new_fields.keys()) =>
+        {
+            for ((name, old), new) in old_fields.iter().zip(new_fields.values()) {
+                diff_values(field_path(&path, name), old, new, changes);
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items))
+            if old_items.len() == new_items.len() =>
+        {
+            for (i, (old, new)) in old_items.iter().zip(new_items).enumerate() {
+                diff_values(index_path(&path, i), old, new, changes);
+            }
+        }
+        _ if old != new => changes.push(FieldChange {
+            path,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
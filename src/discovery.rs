@@ -0,0 +1,568 @@
+//! Enumerating every pool of a protocol.
+//!
+//! `discover_pools` asks the program of a pool type for all of its accounts with
+//! `getProgramAccounts`, filtered down to those of the pool type: by the discriminator of
+//! Anchor accounts, and by the size or tag of the accounts of other programs. The
+//! accounts found are parsed like `get_info_struct` would, or left unparsed by
+//! `discover_pool_addresses`, which asks for none of their data.
+//!
+//! `find_pools_for_pair` and `find_pools_for_mint` narrow the search down to the pools
+//! trading two mints or one, with filters on the offsets each layout stores its mints at.
+//!
+//! Programs with many pools return large responses: Raydium AMM v4 alone has hundreds of
+//! thousands. Nodes that limit `getProgramAccounts` may refuse them, and the request
+//! timeout of a `UniversalParser` may have to be raised.
+
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+use anchor_client::solana_account_decoder::UiDataSliceConfig;
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use anchor_lang::prelude::{Discriminator, Pubkey};
+
+use crate::fetcher::AccountFetcher;
+use crate::layouts::phoenix::MARKET_HEADER_DISCRIMINANT;
+use crate::layouts::raydium_amm_v4::AmmInfo;
+use crate::layouts::saber::SwapInfo as SaberSwapInfo;
+use crate::layouts::sanctum::PoolState as SanctumPoolState;
+use crate::layouts::spl_stake_pool::AccountType as StakePoolAccountType;
+use crate::layouts::spl_token_swap::{SWAP_V1, TokenSwap};
+use crate::parsing::{
+    AldrinVersion, ParseMode, ParsedPool, PoolError, PoolType, StabblePoolKind, aldrin_v1,
+    aldrin_v2, bonkswap, crema_clmm, invariant, lifinity_v2, marinade, meteora_dbc, meteora_dlmm,
+    meteora_vault, moonshot, orca_whirlpool, pamm, parse_account_with, pump, raydium_amm_cpmm_new,
+    raydium_camm, raydium_launchlab, stabble_stable_swap, stabble_weighted_swap,
+};
+use crate::program_ids::{self, Cluster};
+use crate::pump_amm::BONDING_CURVE_SEED;
+
+/// Finds every account of `pool_type` on `cluster` and parses each like `get_info_struct`,
+/// paired with its address. The address of `pool_type` itself is ignored.
+///
+/// Fails as a whole only if a request does; accounts that don't parse fail on their own.
+/// Returns `PoolError::NotDiscoverable` for dynamic and EVM pool types, and for pool types
+/// fetched together with a config, whose pools are discovered as their plain pool type.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+/// # use anchor_lang::prelude::Pubkey;
+/// # use universal_liquidity_pool_parser::discovery::discover_pools;
+/// # use universal_liquidity_pool_parser::parsing::{PoolError, PoolType};
+/// # use universal_liquidity_pool_parser::program_ids::Cluster;
+/// # async fn example() -> Result<(), PoolError> {
+/// let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let pool_type = PoolType::OrcaWhirlpool {
+///     pool_address: Pubkey::default(),
+/// };
+/// for (address, pool) in discover_pools(&pool_type, &rpc_client, &Cluster::Mainnet).await? {
+///     match pool {
+///         Ok(pool) => println!("{address}: {:?}", pool.pool.summary()),
+///         Err(e) => println!("{address}: {e}"),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn discover_pools<F: AccountFetcher>(
+    pool_type: &PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+) -> Result<Vec<(Pubkey, Result<ParsedPool, PoolError>)>, PoolError> {
+    discover_pools_with(pool_type, fetcher, cluster, ParseMode::Lenient).await
+}
+
+/// Like `discover_pools`, but checks the account data as `mode` requires.
+pub(crate) async fn discover_pools_with<F: AccountFetcher>(
+    pool_type: &PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+) -> Result<Vec<(Pubkey, Result<ParsedPool, PoolError>)>, PoolError> {
+    let mut pools = Vec::new();
+    for (program_id, layout) in queries(pool_type, cluster)? {
+        let found = fetch_pools(
+            pool_type,
+            &program_id,
+            layout.filters,
+            fetcher,
+            cluster,
+            mode,
+        );
+        pools.extend(found.await?);
+    }
+    Ok(pools)
+}
+
+/// Finds the address of every account of `pool_type` on `cluster`, like `discover_pools`
+/// but without any of their data.
+pub async fn discover_pool_addresses<F: AccountFetcher>(
+    pool_type: &PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+) -> Result<Vec<Pubkey>, PoolError> {
+    let mut addresses = Vec::new();
+    for (program_id, layout) in queries(pool_type, cluster)? {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(layout.filters),
+            account_config: RpcAccountInfoConfig {
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 0,
+                    length: 0,
+                }),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let response = fetcher.fetch_program_accounts(&program_id, config).await?;
+        addresses.extend(response.value.into_iter().map(|(address, _)| address));
+    }
+    Ok(addresses)
+}
+
+/// Finds every pool on `cluster` that trades `mint_a` against `mint_b`, in either order,
+/// across all discoverable pool types, and parses each like `get_info_struct`.
+///
+/// Each pool type is asked for the pools that store the two mints at the offsets of its
+/// layout, in two requests, one per order. Stabble pools are matched on their first two
+/// tokens, and pool types that don't store both mints, such as Meteora DBC pools and
+/// Mercurial and pump.fun bonding curve pools, aren't searched.
+///
+/// Fails as a whole only if a request does; accounts that don't parse fail on their own.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+/// # use anchor_lang::prelude::Pubkey;
+/// # use universal_liquidity_pool_parser::discovery::find_pools_for_pair;
+/// # use universal_liquidity_pool_parser::parsing::PoolError;
+/// # use universal_liquidity_pool_parser::program_ids::Cluster;
+/// # async fn example(sol: Pubkey, usdc: Pubkey) -> Result<(), PoolError> {
+/// let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// for (pool_type, pool) in find_pools_for_pair(&sol, &usdc, &rpc_client, &Cluster::Mainnet).await? {
+///     if let Ok(pool) = pool {
+///         println!("{}: {}", pool_type.name(), pool.address);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn find_pools_for_pair<F: AccountFetcher>(
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    fetcher: &F,
+    cluster: &Cluster,
+) -> Result<Vec<(PoolType, Result<ParsedPool, PoolError>)>, PoolError> {
+    let pool_types = searchable_pool_types();
+    let mint_filters = pair_filters(*mint_a, *mint_b);
+    find_pools_with(
+        pool_types,
+        mint_filters,
+        fetcher,
+        cluster,
+        ParseMode::Lenient,
+    )
+    .await
+}
+
+/// Finds every pool on `cluster` that trades `mint`, against any other mint, across all
+/// discoverable pool types, and parses each like `get_info_struct`.
+///
+/// Each pool type is asked for the pools that store `mint` at each of the mint offsets
+/// of its layout, in one request per offset. The pump.fun bonding curve of `mint`, which
+/// doesn't store its mint, is fetched from the address derived from it. Stabble pools are
+/// matched on their first two tokens, and Mercurial pools, which don't store their mints,
+/// aren't searched.
+///
+/// Fails as a whole only if a request does; accounts that don't parse fail on their own.
+pub async fn find_pools_for_mint<F: AccountFetcher>(
+    mint: &Pubkey,
+    fetcher: &F,
+    cluster: &Cluster,
+) -> Result<Vec<(PoolType, Result<ParsedPool, PoolError>)>, PoolError> {
+    let pool_types = searchable_pool_types();
+    find_pools_for_mint_with(pool_types, mint, fetcher, cluster, ParseMode::Lenient).await
+}
+
+/// Like `find_pools_for_mint`, but searches only `pool_types` and checks the account data
+/// as `mode` requires.
+pub(crate) async fn find_pools_for_mint_with<F: AccountFetcher>(
+    pool_types: Vec<PoolType>,
+    mint: &Pubkey,
+    fetcher: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+) -> Result<Vec<(PoolType, Result<ParsedPool, PoolError>)>, PoolError> {
+    let searches_bonding_curves = pool_types
+        .iter()
+        .any(|pool_type| matches!(pool_type, PoolType::PumpFunBondingCurve { .. }));
+    let mint_filters = |mint_offsets: &[usize]| {
+        mint_offsets
+            .iter()
+            .map(|offset| vec![memcmp(*offset, mint.as_ref())])
+            .collect()
+    };
+    let mut pools = find_pools_with(pool_types, mint_filters, fetcher, cluster, mode).await?;
+
+    if searches_bonding_curves {
+        let program_id = cluster.program_id(pump::ID);
+        let address =
+            Pubkey::find_program_address(&[BONDING_CURVE_SEED, mint.as_ref()], &program_id).0;
+        let response = fetcher.fetch_account(&address).await?;
+        let fetched_at = SystemTime::now();
+        if let Some(account) = response.value {
+            let pool_type = PoolType::PumpFunBondingCurve {
+                pool_address: address,
+            };
+            let slot = response.context.slot;
+            let pool = parse_account_with(
+                pool_type.clone(),
+                account,
+                slot,
+                fetched_at,
+                fetcher,
+                cluster,
+                mode,
+            );
+            pools.push((pool_type, pool.await));
+        }
+    }
+    Ok(pools)
+}
+
+/// Returns every pool type constructible from an address, which `find_pools_with`
+/// searches unless told otherwise.
+pub(crate) fn searchable_pool_types() -> Vec<PoolType> {
+    PoolType::all()
+        .map(|info| {
+            PoolType::from_name(info.name, Pubkey::default()).expect("every name is constructible")
+        })
+        .collect()
+}
+
+/// Returns the filters selecting the pools of a layout with its mints at `mint_offsets`
+/// that trade `mint_a` against `mint_b`, one set per order of the mints.
+pub(crate) fn pair_filters(
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+) -> impl Fn(&[usize]) -> Vec<Vec<RpcFilterType>> {
+    move |mint_offsets| {
+        let [offset_a, offset_b, ..] = *mint_offsets else {
+            return Vec::new();
+        };
+        let mut filter_sets = vec![vec![
+            memcmp(offset_a, mint_a.as_ref()),
+            memcmp(offset_b, mint_b.as_ref()),
+        ]];
+        if mint_a != mint_b {
+            filter_sets.push(vec![
+                memcmp(offset_a, mint_b.as_ref()),
+                memcmp(offset_b, mint_a.as_ref()),
+            ]);
+        }
+        filter_sets
+    }
+}
+
+/// Finds the pools of each of `pool_types` that pass one of the sets of filters
+/// `mint_filters` returns for the mint offsets of their layouts, skipping the pool types
+/// that aren't discoverable. Each pool is returned once, paired with its `PoolType`.
+pub(crate) async fn find_pools_with<F, MintFilters>(
+    pool_types: Vec<PoolType>,
+    mint_filters: MintFilters,
+    fetcher: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+) -> Result<Vec<(PoolType, Result<ParsedPool, PoolError>)>, PoolError>
+where
+    F: AccountFetcher,
+    MintFilters: Fn(&[usize]) -> Vec<Vec<RpcFilterType>>,
+{
+    let mut found = HashSet::new();
+    let mut pools = Vec::new();
+    for pool_type in pool_types {
+        let Ok(queries) = queries(&pool_type, cluster) else {
+            continue;
+        };
+        for (program_id, layout) in queries {
+            for filters in mint_filters(layout.mint_offsets) {
+                let filters = layout.filters.iter().cloned().chain(filters).collect();
+                let matches = fetch_pools(&pool_type, &program_id, filters, fetcher, cluster, mode);
+                for (address, pool) in matches.await? {
+                    if found.insert(address) {
+                        pools.push((at_address(&pool_type, address), pool));
+                    }
+                }
+            }
+        }
+    }
+    Ok(pools)
+}
+
+/// Fetches the accounts `program_id` returns for `filters` and parses each as
+/// `pool_type`.
+async fn fetch_pools<F: AccountFetcher>(
+    pool_type: &PoolType,
+    program_id: &Pubkey,
+    filters: Vec<RpcFilterType>,
+    fetcher: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+) -> Result<Vec<(Pubkey, Result<ParsedPool, PoolError>)>, PoolError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(filters),
+        ..RpcProgramAccountsConfig::default()
+    };
+    let response = fetcher.fetch_program_accounts(program_id, config).await?;
+    let fetched_at = SystemTime::now();
+    let slot = response.context.slot;
+    let mut pools = Vec::with_capacity(response.value.len());
+    for (address, account) in response.value {
+        let pool_type = at_address(pool_type, address);
+        let pool = parse_account_with(pool_type, account, slot, fetched_at, fetcher, cluster, mode);
+        pools.push((address, pool.await));
+    }
+    Ok(pools)
+}
+
+/// Returns `pool_type` for the account at `address`.
+fn at_address(pool_type: &PoolType, address: Pubkey) -> PoolType {
+    PoolType::from_name(pool_type.name(), address)
+        .expect("discoverable pool types are constructible from an address")
+}
+
+/// Returns the `getProgramAccounts` requests that find every account of `pool_type`, as
+/// the program asked and the layout of the accounts it's asked for.
+fn queries(pool_type: &PoolType, cluster: &Cluster) -> Result<Vec<(Pubkey, Layout)>, PoolError> {
+    let not_discoverable = || PoolError::NotDiscoverable(pool_type.pool_name());
+    let program_ids = match pool_type {
+        PoolType::OrcaTokenSwap { .. } => vec![
+            cluster.program_id(program_ids::ORCA_TOKEN_SWAP_V1),
+            cluster.program_id(program_ids::ORCA_TOKEN_SWAP_V2),
+        ],
+        _ => vec![
+            pool_type
+                .expected_program_id_on(cluster)
+                .ok_or_else(not_discoverable)?,
+        ],
+    };
+    let layouts = layouts(pool_type).ok_or_else(not_discoverable)?;
+    Ok(program_ids
+        .into_iter()
+        .flat_map(|program_id| {
+            layouts
+                .iter()
+                .map(move |layout| (program_id, layout.clone()))
+        })
+        .collect())
+}
+
+#[derive(Clone)]
+/// How the accounts of a pool type are told apart from the other accounts of its program,
+/// and where they store their mints.
+struct Layout {
+    filters: Vec<RpcFilterType>,
+    /// Offsets of the mints in the account data, in the order of
+    /// `PoolAccess::token_mint_a` and `token_mint_b`.
+    mint_offsets: &'static [usize],
+}
+
+/// Returns the layouts that together cover every account of `pool_type`, or `None` if the
+/// pool type isn't discoverable.
+fn layouts(pool_type: &PoolType) -> Option<Vec<Layout>> {
+    let layout = match pool_type {
+        PoolType::PumpFun { .. } => anchor::<pamm::accounts::Pool>(&[43, 75]),
+        PoolType::PumpFunGlobalConfig { .. } => anchor::<pamm::accounts::GlobalConfig>(&[]),
+        // Bonding curves don't store their mint; they're at an address derived from it.
+        PoolType::PumpFunBondingCurve { .. } => anchor::<pump::accounts::BondingCurve>(&[]),
+        PoolType::RaydiumCpmmAmm { .. } => {
+            anchor::<raydium_amm_cpmm_new::accounts::PoolState>(&[168, 200])
+        }
+        PoolType::RaydiumCpmmConfig { .. } => {
+            anchor::<raydium_amm_cpmm_new::accounts::AmmConfig>(&[])
+        }
+        PoolType::RaydiumCamm { .. } => anchor::<raydium_camm::accounts::PoolState>(&[73, 105]),
+        PoolType::RaydiumCammConfig { .. } => anchor::<raydium_camm::accounts::AmmConfig>(&[]),
+        PoolType::RaydiumCammObservation { .. } => {
+            anchor::<raydium_camm::accounts::ObservationState>(&[])
+        }
+        PoolType::RaydiumLaunchLab { .. } => {
+            anchor::<raydium_launchlab::accounts::PoolState>(&[205, 237])
+        }
+        PoolType::LifinityV2 { .. } => anchor::<lifinity_v2::accounts::Amm>(&[254, 286]),
+        PoolType::Invariant { .. } => anchor::<invariant::accounts::Pool>(&[8, 40]),
+        PoolType::CremaClmm { .. } => anchor::<crema_clmm::accounts::ClmmPool>(&[40, 72]),
+        PoolType::Aldrin { version, .. } => match version {
+            AldrinVersion::V1 => anchor::<aldrin_v1::accounts::Pool>(&[297, 361]),
+            AldrinVersion::V2 => anchor::<aldrin_v2::accounts::Pool>(&[297, 361]),
+        },
+        // The mints of the first two tokens, which are all most pools have.
+        PoolType::Stabble { kind, .. } => match kind {
+            StabblePoolKind::Stable => anchor::<stabble_stable_swap::accounts::Pool>(&[138, 188]),
+            StabblePoolKind::Weighted => {
+                anchor::<stabble_weighted_swap::accounts::Pool>(&[126, 184])
+            }
+        },
+        PoolType::Moonshot { .. } => anchor::<moonshot::accounts::CurveAccount>(&[24]),
+        // The quote mint is stored in the config of the pool.
+        PoolType::MeteoraDbc { .. } => anchor::<meteora_dbc::accounts::VirtualPool>(&[136]),
+        PoolType::MeteoraVault { .. } => anchor::<meteora_vault::accounts::Vault>(&[]),
+        PoolType::MeteoraDlmm { .. } => anchor::<meteora_dlmm::accounts::LbPair>(&[88, 120]),
+        // Pools created before and after the LP mint upgrade have different accounts.
+        PoolType::Bonkswap { .. } => {
+            return Some(vec![
+                anchor::<bonkswap::accounts::Pool>(&[8, 40]),
+                anchor::<bonkswap::accounts::PoolV2>(&[8, 40]),
+            ]);
+        }
+        PoolType::BonkswapFarm { .. } => anchor::<bonkswap::accounts::Farm>(&[]),
+        PoolType::OrcaWhirlpool { .. } => {
+            anchor::<orca_whirlpool::accounts::Whirlpool>(&[101, 181])
+        }
+        PoolType::OrcaWhirlpoolPosition { .. } => anchor::<orca_whirlpool::accounts::Position>(&[]),
+        PoolType::Marinade { .. } => anchor::<marinade::accounts::State>(&[]),
+        PoolType::RaydiumLegacyAmm { .. } => Layout {
+            filters: vec![data_size(AmmInfo::LEN)],
+            mint_offsets: &[400, 432],
+        },
+        PoolType::SaberStableSwap { .. } => Layout {
+            filters: vec![data_size(SaberSwapInfo::LEN)],
+            mint_offsets: &[203, 235],
+        },
+        // Only the prefix of a Mercurial `SwapInfo` is parsed, so its size isn't known;
+        // pools are told apart by `is_initialized`, after the version. They don't store
+        // their mints.
+        PoolType::MercurialStableSwap { .. } => Layout {
+            filters: vec![memcmp(1, &[1])],
+            mint_offsets: &[],
+        },
+        PoolType::FluxBeam { .. }
+        | PoolType::OrcaTokenSwap { .. }
+        | PoolType::SplTokenSwap { .. } => Layout {
+            filters: vec![data_size(TokenSwap::LEN), memcmp(0, &[SWAP_V1])],
+            mint_offsets: &[131, 163],
+        },
+        PoolType::SanctumInfinity { .. } => Layout {
+            filters: vec![data_size(SanctumPoolState::LEN)],
+            mint_offsets: &[],
+        },
+        PoolType::Phoenix { .. } => Layout {
+            filters: vec![memcmp(0, &MARKET_HEADER_DISCRIMINANT.to_le_bytes())],
+            mint_offsets: &[48, 128],
+        },
+        PoolType::SplStakePool { .. } => Layout {
+            filters: vec![memcmp(0, &[StakePoolAccountType::StakePool as u8])],
+            mint_offsets: &[],
+        },
+        PoolType::RaydiumCpmmAmmWithConfig { .. }
+        | PoolType::PumpFunWithConfig { .. }
+        | PoolType::Dynamic { .. } => return None,
+        #[cfg(feature = "evm")]
+        PoolType::UniswapV2 { .. } | PoolType::UniswapV3 { .. } => return None,
+    };
+    Some(vec![layout])
+}
+
+/// Selects the Anchor accounts of type `T`, which store their mints at `mint_offsets`.
+fn anchor<T: Discriminator>(mint_offsets: &'static [usize]) -> Layout {
+    Layout {
+        filters: vec![memcmp(0, T::DISCRIMINATOR)],
+        mint_offsets,
+    }
+}
+
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, bytes))
+}
+
+fn data_size(len: usize) -> RpcFilterType {
+    RpcFilterType::DataSize(len as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parse_from_bytes;
+    use crate::pool_access::PoolAccess;
+
+    /// Returns zeroed account data that passes the filters of `layout`, with `mints`
+    /// written at its mint offsets.
+    fn account(layout: &Layout, mints: &[Pubkey]) -> Vec<u8> {
+        let mut data = vec![0; 4_000];
+        for filter in &layout.filters {
+            match filter {
+                RpcFilterType::DataSize(len) => data.resize(*len as usize, 0),
+                RpcFilterType::Memcmp(memcmp) => {
+                    let bytes = memcmp.bytes().unwrap();
+                    data[memcmp.offset()..memcmp.offset() + bytes.len()].copy_from_slice(&bytes);
+                }
+                _ => unreachable!("layouts only filter on size and bytes"),
+            }
+        }
+        for (offset, mint) in layout.mint_offsets.iter().zip(mints) {
+            data[*offset..*offset + 32].copy_from_slice(mint.as_ref());
+        }
+        data
+    }
+
+    /// Checks that the mints written at the mint offsets of each layout of the pool type
+    /// named `name` are the mints of the parsed pool, in order.
+    fn assert_mint_offsets(name: &str) {
+        let pool_type = PoolType::from_name(name, Pubkey::default()).unwrap();
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        for layout in layouts(&pool_type).unwrap() {
+            let mut data = account(&layout, &mints);
+            if let PoolType::Stabble { .. } = pool_type {
+                // The mints are those of the first two entries of the `tokens` vector,
+                // whose length precedes them.
+                let len_offset = layout.mint_offsets[0] - 4;
+                data[len_offset..len_offset + 4].copy_from_slice(&2u32.to_le_bytes());
+            }
+            let pool = parse_from_bytes(&pool_type, &data).unwrap();
+            let parsed = [pool.token_mint_a(), pool.token_mint_b()];
+            assert!(!layout.mint_offsets.is_empty());
+            for (index, mint) in mints.iter().take(layout.mint_offsets.len()).enumerate() {
+                assert_eq!(parsed[index], Some(*mint), "mint {index}");
+            }
+        }
+    }
+
+    macro_rules! mint_offset_tests {
+        ($($test:ident: $name:literal,)*) => {
+            $(
+                #[test]
+                fn $test() {
+                    assert_mint_offsets($name);
+                }
+            )*
+        };
+    }
+
+    mint_offset_tests! {
+        pump_amm_mint_offsets: "pumpfun-amm",
+        raydium_cpmm_mint_offsets: "raydium-cpmm",
+        raydium_clmm_mint_offsets: "raydium-clmm",
+        raydium_launchlab_mint_offsets: "raydium-launchlab",
+        raydium_amm_v4_mint_offsets: "raydium-amm-v4",
+        lifinity_v2_mint_offsets: "lifinity-v2",
+        invariant_mint_offsets: "invariant",
+        crema_clmm_mint_offsets: "crema-clmm",
+        aldrin_v1_mint_offsets: "aldrin-v1",
+        aldrin_v2_mint_offsets: "aldrin-v2",
+        stabble_stable_swap_mint_offsets: "stabble-stable-swap",
+        stabble_weighted_swap_mint_offsets: "stabble-weighted-swap",
+        moonshot_mint_offsets: "moonshot",
+        meteora_dbc_mint_offsets: "meteora-dbc",
+        meteora_dlmm_mint_offsets: "meteora-dlmm",
+        bonkswap_mint_offsets: "bonkswap",
+        orca_whirlpool_mint_offsets: "orca-whirlpool",
+        saber_mint_offsets: "saber",
+        fluxbeam_mint_offsets: "fluxbeam",
+        orca_token_swap_mint_offsets: "orca-token-swap",
+        spl_token_swap_mint_offsets: "spl-token-swap",
+        phoenix_mint_offsets: "phoenix",
+    }
+}
@@ -0,0 +1,230 @@
+//! Parsing pool accounts saved to disk, without an RPC node.
+//!
+//! Two formats written by the Solana CLI are read:
+//!
+//! - the JSON printed by `solana account <ADDRESS> --output json`, also accepted by
+//!   `solana-test-validator --account`, which holds the address and owner of the
+//!   account besides its data. `AccountDump::parse` detects the protocol from the owner
+//!   as `detect::detect_and_parse` does.
+//! - the raw data written by `solana account <ADDRESS> --output-file <FILE>`, which has
+//!   neither, so `read_pool_bin` parses it as a given `PoolType` like
+//!   `parse_from_bytes`.
+//!
+//! Dumps hold a single account, so pool types that `get_info_struct` completes with other
+//! accounts, such as Sanctum Infinity pools, return `PoolError::IncompleteData`.
+//!
+//! ```no_run
+//! # use universal_liquidity_pool_parser::dump::AccountDump;
+//! # use universal_liquidity_pool_parser::parsing::PoolError;
+//! # use universal_liquidity_pool_parser::program_ids::Cluster;
+//! # fn example() -> Result<(), PoolError> {
+//! let parsed = AccountDump::read_json("fixtures/pool.json")?.parse(&Cluster::Mainnet)?;
+//! println!("{} at {}", parsed.pool.protocol_tag(), parsed.address);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anchor_client::solana_client::rpc_response::RpcKeyedAccount;
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::Pubkey;
+
+use crate::detect;
+use crate::parsing::{self, AmmPool, ParsedPool, PoolError, PoolType};
+use crate::program_ids::Cluster;
+
+#[derive(Debug, Clone, PartialEq)]
+/// An account read from a `solana account --output json` dump.
+pub struct AccountDump {
+    pub address: Pubkey,
+    pub account: Account,
+    /// When the dump was written, as far as it's known: the modification time of the
+    /// file it was read from, or the time it was parsed at.
+    pub saved_at: SystemTime,
+}
+
+impl AccountDump {
+    /// Parses the JSON of a dump.
+    ///
+    /// Returns `PoolError::InvalidDump` if `json` isn't a dump or its data can't be
+    /// decoded.
+    pub fn from_json(json: &str) -> Result<Self, PoolError> {
+        let keyed_account: RpcKeyedAccount = match serde_json::from_str(json) {
+            Ok(keyed_account) => keyed_account,
+            Err(e) => return Err(PoolError::InvalidDump(e.to_string())),
+        };
+        let Ok(address) = keyed_account.pubkey.parse::<Pubkey>() else {
+            return Err(PoolError::InvalidDump(format!(
+                "invalid pubkey {:?}",
+                keyed_account.pubkey
+            )));
+        };
+        let Some(account) = keyed_account.account.decode::<Account>() else {
+            return Err(PoolError::InvalidDump(format!(
+                "undecodable data of account {address}"
+            )));
+        };
+        Ok(AccountDump {
+            address,
+            account,
+            saved_at: SystemTime::now(),
+        })
+    }
+
+    /// Reads the dump in the file at `path`.
+    ///
+    /// Returns `PoolError::IoError` if the file can't be read and `PoolError::InvalidDump`
+    /// as `from_json` does.
+    pub fn read_json(path: impl AsRef<Path>) -> Result<Self, PoolError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)?;
+        let mut dump = AccountDump::from_json(&json)?;
+        if let Ok(modified) = fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            dump.saved_at = modified;
+        }
+        Ok(dump)
+    }
+
+    /// Parses the account with the protocol of the program owning it, recognized by its id
+    /// on `cluster`.
+    ///
+    /// Returns `PoolError::UnsupportedProgram` if the owner isn't a program this crate
+    /// parses.
+    pub fn parse(self, cluster: &Cluster) -> Result<ParsedPool, PoolError> {
+        let owner = cluster.mainnet_id(self.account.owner);
+        let pool = detect::detect(&owner, &self.account.data)?.into_pool()?;
+        Ok(self.parsed(pool))
+    }
+
+    /// Parses the account as the account of `pool_type`, like `parse_from_bytes`.
+    ///
+    /// Returns `PoolError::UnexpectedOwner` if the account isn't owned by the program of
+    /// `pool_type` on `cluster`.
+    pub fn parse_as(
+        self,
+        pool_type: &PoolType,
+        cluster: &Cluster,
+    ) -> Result<ParsedPool, PoolError> {
+        if !pool_type.is_owned_by(&self.account.owner, cluster) {
+            return Err(PoolError::UnexpectedOwner(
+                pool_type.pool_name(),
+                self.account.owner,
+            ));
+        }
+        let pool = parsing::parse_from_bytes(pool_type, &self.account.data)?;
+        Ok(self.parsed(pool))
+    }
+
+    /// Returns `pool`, parsed from this dump. Dumps don't record the slot they were read
+    /// at.
+    fn parsed(self, pool: AmmPool) -> ParsedPool {
+        ParsedPool {
+            pool,
+            address: self.address,
+            slot: None,
+            fetched_at: self.saved_at,
+            raw: Some(self.account.data),
+            reserves: None,
+        }
+    }
+}
+
+/// Reads the raw account data in the file at `path` and parses it as the account of
+/// `pool_type`, like `parse_from_bytes`.
+///
+/// Returns `PoolError::IoError` if the file can't be read.
+pub fn read_pool_bin(pool_type: &PoolType, path: impl AsRef<Path>) -> Result<AmmPool, PoolError> {
+    let data = fs::read(path)?;
+    parsing::parse_from_bytes(pool_type, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::pubkey;
+
+    use super::*;
+    use crate::program_ids;
+
+    /// The dump of a Saber USDC/USDT pool as printed by `solana account --output json`.
+    const SABER_DUMP: &str = r#"{
+  "pubkey": "YAkoNb6HKmSxQN9L8hiBE5tPJRsniSSMzND1boHmZxe",
+  "account": {
+    "lamports": 3640080,
+    "data": [
+      "AQD+ZAAAAAAAAABkAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAGFsAZCAPHYB+SwrJhR1fOc2u2rbUr0YfaUnQB3+OZ7LjGl25bVBBBW96Qi9Te4V37Fnqchz/Eu4qB9vKrRIqRhmz4iSb4bUJB9UZ4BZKXan3USU6ORVzExyC4VWpicJHYKNriRHgvSI9jh3VjOD57TTEFUk7yWQMIT/nRmN+zuFnkwkw6ekGVR/IkKamNF7oh1ZAwHYppmQodY7Am5OffjG+nrzvtutOj1l82qryXQxsbvkwtL24OR8pgIDRS9dYc4BDmCv7bInF71jGS9UFFo/llozu4LSxwKess4eIIJktSoT5EoVxxrv0faJAnUEgf+bQn5pV/ARI9sP0u4Q9NJa5KjpS/eaw2kGyKbaWypKi3TLZBhKGZhSM0M3Z+1GlAAAAAAAAAAAECcAAAAAAAAAAAAAAAAAABAnAAAAAAAABAAAAAAAAAAQJwAAAAAAAAAAAAAAAAAAECcAAAAAAAA=",
+      "base64"
+    ],
+    "owner": "SSwpkEEcbUqx4vtoEByFjSkhKdCT862DNVb52nZg1UZ",
+    "executable": false,
+    "rentEpoch": 18446744073709551615,
+    "space": 395
+  }
+}"#;
+
+    const ADDRESS: Pubkey = pubkey!("YAkoNb6HKmSxQN9L8hiBE5tPJRsniSSMzND1boHmZxe");
+    const USDC: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+    const USDT: Pubkey = pubkey!("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB");
+
+    fn assert_saber_pool(parsed: &ParsedPool) {
+        assert_eq!(parsed.address, ADDRESS);
+        assert_eq!(parsed.slot, None);
+        assert_eq!(parsed.raw.as_ref().map(Vec::len), Some(395));
+        let AmmPool::SaberStableSwap(swap) = &parsed.pool else {
+            panic!("parsed as {}", parsed.pool.protocol_tag());
+        };
+        assert!(swap.is_initialized);
+        assert_eq!(swap.target_amp_factor, 100);
+        assert_eq!((swap.token_a_mint, swap.token_b_mint), (USDC, USDT));
+        assert_eq!(swap.fees.trade_fee_numerator, 4);
+    }
+
+    #[test]
+    fn from_json_reads_a_cli_dump() {
+        let dump = AccountDump::from_json(SABER_DUMP).unwrap();
+        assert_eq!(dump.address, ADDRESS);
+        assert_eq!(dump.account.owner, program_ids::SABER);
+        assert_eq!(dump.account.lamports, 3_640_080);
+        assert_eq!(dump.account.rent_epoch, u64::MAX);
+        assert!(!dump.account.executable);
+        assert_eq!(dump.account.data.len(), 395);
+    }
+
+    #[test]
+    fn dumps_parse_by_their_owner() {
+        let dump = AccountDump::from_json(SABER_DUMP).unwrap();
+        assert_saber_pool(&dump.parse(&Cluster::Mainnet).unwrap());
+    }
+
+    #[test]
+    fn dumps_parse_as_a_pool_type_of_their_owner() {
+        let dump = AccountDump::from_json(SABER_DUMP).unwrap();
+        let pool_type = PoolType::SaberStableSwap {
+            pool_address: ADDRESS,
+        };
+        assert_saber_pool(
+            &dump
+                .clone()
+                .parse_as(&pool_type, &Cluster::Mainnet)
+                .unwrap(),
+        );
+
+        let pool_type = PoolType::MercurialStableSwap {
+            pool_address: ADDRESS,
+        };
+        assert!(matches!(
+            dump.parse_as(&pool_type, &Cluster::Mainnet),
+            Err(PoolError::UnexpectedOwner(_, owner)) if owner == program_ids::SABER
+        ));
+    }
+
+    #[test]
+    fn from_json_rejects_other_json() {
+        assert!(matches!(
+            AccountDump::from_json(r#"{"jsonrpc": "2.0", "result": null, "id": 1}"#),
+            Err(PoolError::InvalidDump(_))
+        ));
+    }
+}
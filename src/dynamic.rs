@@ -0,0 +1,317 @@
+//! Parsing of accounts of arbitrary Anchor programs from an IDL loaded at runtime.
+//!
+//! Every other pool type in the crate is compiled in, either through `declare_program!`
+//! or a hand-written layout. `DynamicIdl` instead walks the type definitions of an IDL
+//! supplied by the caller and decodes an account into a generic `Value` tree, which lets
+//! new programs be read without waiting for a release of the crate.
+
+use std::sync::Arc;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang_idl::types::{
+    Idl, IdlArrayLen, IdlDefinedFields, IdlSerialization, IdlType, IdlTypeDefTy,
+};
+use serde::Serialize;
+use serde::ser::{SerializeMap, Serializer};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+/// Errors that can occur while loading an IDL or decoding an account with it.
+pub enum DynamicError {
+    /// The IDL is not valid JSON in either the legacy or the current Anchor format.
+    #[error("invalid IDL: {0}")]
+    InvalidIdl(String),
+
+    /// The account's discriminator doesn't match any account declared in the IDL.
+    #[error("no account in the IDL matches discriminator {0:?}")]
+    UnknownDiscriminator(Vec<u8>),
+
+    /// A type referenced by the IDL is not defined in it.
+    #[error("type `{0}` is not defined in the IDL")]
+    UnknownType(String),
+
+    /// The IDL uses a feature the decoder doesn't handle, such as generics.
+    #[error("unsupported IDL feature: {0}")]
+    Unsupported(String),
+
+    /// An enum tag in the account data doesn't match any variant of the enum.
+    #[error("invalid variant {index} of enum `{name}`")]
+    InvalidVariant { name: String, index: u8 },
+
+    /// The account data ended before every field was decoded.
+    #[error("account data ended unexpectedly")]
+    UnexpectedEof,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A decoded value, typed after the IDL type it was read as.
+pub enum Value {
+    Bool(bool),
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    U128(u128),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    Pubkey(Pubkey),
+    Option(Option<Box<Value>>),
+    /// Elements of a `Vec` or a fixed-size array.
+    Array(Vec<Value>),
+    /// Fields of a struct in declaration order. Tuple fields are named by their index.
+    Struct(Vec<(String, Value)>),
+    /// A variant of an enum, with its fields if it has any.
+    Enum {
+        variant: String,
+        fields: Option<Box<Value>>,
+    },
+}
+
+impl Value {
+    /// Returns the value of the field `name` if this is a struct that has one.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Struct(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// Serializes like the Rust types the IDL describes would, except that pubkeys are written
+/// as base58 strings and 128-bit integers as decimal strings, which JSON can't hold.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::U8(v) => serializer.serialize_u8(*v),
+            Value::I8(v) => serializer.serialize_i8(*v),
+            Value::U16(v) => serializer.serialize_u16(*v),
+            Value::I16(v) => serializer.serialize_i16(*v),
+            Value::U32(v) => serializer.serialize_u32(*v),
+            Value::I32(v) => serializer.serialize_i32(*v),
+            Value::U64(v) => serializer.serialize_u64(*v),
+            Value::I64(v) => serializer.serialize_i64(*v),
+            Value::U128(v) => serializer.collect_str(v),
+            Value::I128(v) => serializer.collect_str(v),
+            Value::F32(v) => serializer.serialize_f32(*v),
+            Value::F64(v) => serializer.serialize_f64(*v),
+            Value::Bytes(bytes) => serializer.collect_seq(bytes),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Pubkey(key) => serializer.collect_str(key),
+            Value::Option(None) => serializer.serialize_none(),
+            Value::Option(Some(value)) => serializer.serialize_some(value),
+            Value::Array(values) => serializer.collect_seq(values),
+            Value::Struct(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (name, value) in fields {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+            // Externally tagged, like serde's default for Rust enums.
+            Value::Enum {
+                variant,
+                fields: None,
+            } => serializer.serialize_str(variant),
+            Value::Enum {
+                variant,
+                fields: Some(fields),
+            } => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(variant, fields)?;
+                map.end()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An Anchor IDL loaded at runtime, shared cheaply between `PoolType`s.
+pub struct DynamicIdl(Arc<Idl>);
+
+impl DynamicIdl {
+    /// Loads an IDL from its JSON, in either the legacy or the current Anchor format.
+    pub fn from_json(json: &[u8]) -> Result<Self, DynamicError> {
+        let idl = anchor_lang_idl::convert::convert_idl(json)
+            .map_err(|e| DynamicError::InvalidIdl(e.to_string()))?;
+        Ok(DynamicIdl(Arc::new(idl)))
+    }
+
+    /// Returns the program address declared by the IDL.
+    pub fn address(&self) -> &str {
+        &self.0.address
+    }
+
+    #[cfg(feature = "schemars")]
+    pub(crate) fn idl(&self) -> &Idl {
+        &self.0
+    }
+
+    /// Decodes `data`, the full contents of an account including its discriminator, as
+    /// whichever account of the IDL the discriminator designates.
+    pub fn parse_account(&self, data: &[u8]) -> Result<DynamicPool, DynamicError> {
+        let account = self
+            .0
+            .accounts
+            .iter()
+            .find(|account| data.starts_with(&account.discriminator))
+            .ok_or_else(|| {
+                DynamicError::UnknownDiscriminator(data.iter().take(8).copied().collect())
+            })?;
+        let mut buf = &data[account.discriminator.len()..];
+        let value = self.decode_defined(&account.name, &mut buf)?;
+        Ok(DynamicPool {
+            account_name: account.name.clone(),
+            value,
+        })
+    }
+
+    fn decode_defined(&self, name: &str, buf: &mut &[u8]) -> Result<Value, DynamicError> {
+        let def = self
+            .0
+            .types
+            .iter()
+            .find(|def| def.name == name)
+            .ok_or_else(|| DynamicError::UnknownType(name.to_string()))?;
+        if !def.generics.is_empty() {
+            return Err(DynamicError::Unsupported(format!("generic type `{name}`")));
+        }
+        // Zero-copy accounts are `Pod`, which rules out padding, so they decode field by
+        // field exactly like borsh ones.
+        if let IdlSerialization::Custom(serialization) = &def.serialization {
+            return Err(DynamicError::Unsupported(format!(
+                "`{serialization}` serialization of `{name}`"
+            )));
+        }
+        match &def.ty {
+            IdlTypeDefTy::Struct { fields } => self.decode_fields(fields.as_ref(), buf),
+            IdlTypeDefTy::Enum { variants } => {
+                let index = take::<1>(buf)?[0];
+                let variant = variants.get(usize::from(index)).ok_or_else(|| {
+                    DynamicError::InvalidVariant {
+                        name: name.to_string(),
+                        index,
+                    }
+                })?;
+                let fields = match &variant.fields {
+                    Some(fields) => Some(Box::new(self.decode_fields(Some(fields), buf)?)),
+                    None => None,
+                };
+                Ok(Value::Enum {
+                    variant: variant.name.clone(),
+                    fields,
+                })
+            }
+            IdlTypeDefTy::Type { alias } => self.decode(alias, buf),
+        }
+    }
+
+    fn decode_fields(
+        &self,
+        fields: Option<&IdlDefinedFields>,
+        buf: &mut &[u8],
+    ) -> Result<Value, DynamicError> {
+        let mut values = Vec::new();
+        match fields {
+            Some(IdlDefinedFields::Named(fields)) => {
+                for field in fields {
+                    values.push((field.name.clone(), self.decode(&field.ty, buf)?));
+                }
+            }
+            Some(IdlDefinedFields::Tuple(types)) => {
+                for (index, ty) in types.iter().enumerate() {
+                    values.push((index.to_string(), self.decode(ty, buf)?));
+                }
+            }
+            None => {}
+        }
+        Ok(Value::Struct(values))
+    }
+
+    fn decode(&self, ty: &IdlType, buf: &mut &[u8]) -> Result<Value, DynamicError> {
+        let value = match ty {
+            IdlType::Bool => Value::Bool(take::<1>(buf)?[0] != 0),
+            IdlType::U8 => Value::U8(u8::from_le_bytes(take(buf)?)),
+            IdlType::I8 => Value::I8(i8::from_le_bytes(take(buf)?)),
+            IdlType::U16 => Value::U16(u16::from_le_bytes(take(buf)?)),
+            IdlType::I16 => Value::I16(i16::from_le_bytes(take(buf)?)),
+            IdlType::U32 => Value::U32(u32::from_le_bytes(take(buf)?)),
+            IdlType::I32 => Value::I32(i32::from_le_bytes(take(buf)?)),
+            IdlType::U64 => Value::U64(u64::from_le_bytes(take(buf)?)),
+            IdlType::I64 => Value::I64(i64::from_le_bytes(take(buf)?)),
+            IdlType::U128 => Value::U128(u128::from_le_bytes(take(buf)?)),
+            IdlType::I128 => Value::I128(i128::from_le_bytes(take(buf)?)),
+            IdlType::F32 => Value::F32(f32::from_le_bytes(take(buf)?)),
+            IdlType::F64 => Value::F64(f64::from_le_bytes(take(buf)?)),
+            IdlType::Pubkey => Value::Pubkey(Pubkey::new_from_array(take(buf)?)),
+            IdlType::Bytes => Value::Bytes(take_prefixed(buf)?.to_vec()),
+            IdlType::String => {
+                let bytes = take_prefixed(buf)?;
+                Value::String(String::from_utf8_lossy(bytes).into_owned())
+            }
+            IdlType::Option(inner) => match take::<1>(buf)?[0] {
+                0 => Value::Option(None),
+                _ => Value::Option(Some(Box::new(self.decode(inner, buf)?))),
+            },
+            IdlType::Vec(inner) => {
+                let len = u32::from_le_bytes(take(buf)?) as usize;
+                let mut values = Vec::new();
+                for _ in 0..len {
+                    values.push(self.decode(inner, buf)?);
+                }
+                Value::Array(values)
+            }
+            IdlType::Array(inner, IdlArrayLen::Value(len)) => {
+                let mut values = Vec::with_capacity(*len);
+                for _ in 0..*len {
+                    values.push(self.decode(inner, buf)?);
+                }
+                Value::Array(values)
+            }
+            IdlType::Defined { name, generics } if generics.is_empty() => {
+                self.decode_defined(name, buf)?
+            }
+            other => return Err(DynamicError::Unsupported(format!("type {other:?}"))),
+        };
+        Ok(value)
+    }
+}
+
+/// Splits the next `N` bytes off `buf`.
+fn take<const N: usize>(buf: &mut &[u8]) -> Result<[u8; N], DynamicError> {
+    let (head, rest) = buf
+        .split_first_chunk::<N>()
+        .ok_or(DynamicError::UnexpectedEof)?;
+    *buf = rest;
+    Ok(*head)
+}
+
+/// Splits a borsh `u32` length-prefixed byte string off `buf`.
+fn take_prefixed<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], DynamicError> {
+    let len = u32::from_le_bytes(take(buf)?) as usize;
+    if buf.len() < len {
+        return Err(DynamicError::UnexpectedEof);
+    }
+    let (head, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(head)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// An account decoded with a runtime IDL.
+pub struct DynamicPool {
+    /// Name of the IDL account the data matched.
+    pub account_name: String,
+    /// The decoded account, always a `Value::Struct` for accounts declared as structs.
+    pub value: Value,
+}
@@ -0,0 +1,265 @@
+//! Pools on EVM chains, fetched over Ethereum JSON-RPC.
+//!
+//! EVM pools are contracts rather than data accounts, so their state is read by issuing
+//! `eth_call`s against the contract's view functions and decoding the ABI-encoded return
+//! data. Everything here is behind the `evm` feature.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use serde::Serialize;
+use serde_json::{Value, json};
+use serde_with::{DisplayFromStr, serde_as};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+/// Errors that can occur while talking to an Ethereum JSON-RPC endpoint.
+pub enum EvmError {
+    /// The HTTP request to the endpoint failed.
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The endpoint answered with a JSON-RPC error object.
+    #[error("JSON-RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+
+    /// The endpoint answered with something that isn't valid return data for the call.
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+
+    /// A string could not be parsed as a 20 byte hex address.
+    #[error("invalid EVM address: {0}")]
+    InvalidAddress(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A 20 byte EVM address.
+pub struct EvmAddress(pub [u8; 20]);
+
+impl EvmAddress {
+    /// Returns the address left-padded to 32 bytes, the way the ABI encodes it, as a
+    /// `Pubkey` so it can stand in wherever the crate expects an account key.
+    pub fn to_pubkey(&self) -> Pubkey {
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(&self.0);
+        Pubkey::new_from_array(word)
+    }
+}
+
+impl FromStr for EvmAddress {
+    type Err = EvmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex(s).ok_or_else(|| EvmError::InvalidAddress(s.to_string()))?;
+        let address = bytes
+            .try_into()
+            .map_err(|_| EvmError::InvalidAddress(s.to_string()))?;
+        Ok(EvmAddress(address))
+    }
+}
+
+impl fmt::Display for EvmAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", encode_hex(&self.0))
+    }
+}
+
+/// Serialized as its checksum-less `0x` hex string.
+impl Serialize for EvmAddress {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Calls the view function with the given 4 byte selector and no arguments on `to`, and
+/// returns the raw return data.
+pub(crate) async fn eth_call(
+    rpc_url: &str,
+    to: &EvmAddress,
+    selector: [u8; 4],
+) -> Result<Vec<u8>, EvmError> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_call",
+        "params": [{ "to": to.to_string(), "data": format!("0x{}", encode_hex(&selector)) }, "latest"],
+    });
+    let response: Value = reqwest::Client::new()
+        .post(rpc_url)
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
+    if let Some(error) = response.get("error") {
+        return Err(EvmError::Rpc {
+            code: error
+                .get("code")
+                .and_then(Value::as_i64)
+                .unwrap_or_default(),
+            message: error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        });
+    }
+    response
+        .get("result")
+        .and_then(Value::as_str)
+        .and_then(decode_hex)
+        .ok_or_else(|| EvmError::InvalidResponse(response.to_string()))
+}
+
+/// Returns the `index`th 32 byte word of ABI-encoded return data.
+pub(crate) fn word(data: &[u8], index: usize) -> Result<&[u8; 32], EvmError> {
+    data.get(index * 32..(index + 1) * 32)
+        .and_then(|word| word.try_into().ok())
+        .ok_or_else(|| EvmError::InvalidResponse(format!("missing return word {index}")))
+}
+
+/// Decodes an ABI `address` word.
+pub(crate) fn word_to_address(word: &[u8; 32]) -> EvmAddress {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&word[12..]);
+    EvmAddress(address)
+}
+
+/// Decodes an ABI `uint` word that is known to fit in 128 bits.
+pub(crate) fn word_to_u128(word: &[u8; 32]) -> u128 {
+    let mut low = [0u8; 16];
+    low.copy_from_slice(&word[16..]);
+    u128::from_be_bytes(low)
+}
+
+/// Decodes an ABI `int` word that is known to fit in 32 bits, such as an `int24`.
+pub(crate) fn word_to_i32(word: &[u8; 32]) -> i32 {
+    // Negative values are sign-extended over the whole word, so the low bytes suffice.
+    let mut low = [0u8; 4];
+    low.copy_from_slice(&word[28..]);
+    i32::from_be_bytes(low)
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// State of a Uniswap V2 pair, or of any fork exposing the same interface.
+pub struct UniswapV2Pair {
+    pub address: EvmAddress,
+    pub token0: EvmAddress,
+    pub token1: EvmAddress,
+    /// Reserves of `token0`, in its smallest unit. Stored on-chain as a `uint112`.
+    #[serde_as(as = "DisplayFromStr")]
+    pub reserve0: u128,
+    /// Reserves of `token1`, in its smallest unit. Stored on-chain as a `uint112`.
+    #[serde_as(as = "DisplayFromStr")]
+    pub reserve1: u128,
+    /// Block timestamp of the last reserve update, modulo 2^32.
+    pub block_timestamp_last: u32,
+}
+
+/// `getReserves()`
+const GET_RESERVES: [u8; 4] = [0x09, 0x02, 0xf1, 0xac];
+/// `token0()`
+const TOKEN0: [u8; 4] = [0x0d, 0xfe, 0x16, 0x81];
+/// `token1()`
+const TOKEN1: [u8; 4] = [0xd2, 0x12, 0x20, 0xa7];
+
+impl UniswapV2Pair {
+    /// Fetches the pair at `address` from the JSON-RPC endpoint at `rpc_url`.
+    pub async fn fetch(rpc_url: &str, address: EvmAddress) -> Result<Self, EvmError> {
+        let token0 = word_to_address(word(&eth_call(rpc_url, &address, TOKEN0).await?, 0)?);
+        let token1 = word_to_address(word(&eth_call(rpc_url, &address, TOKEN1).await?, 0)?);
+        let reserves = eth_call(rpc_url, &address, GET_RESERVES).await?;
+        let block_timestamp_last = u32::try_from(word_to_u128(word(&reserves, 2)?))
+            .map_err(|_| EvmError::InvalidResponse("timestamp overflows u32".to_string()))?;
+        Ok(UniswapV2Pair {
+            address,
+            token0,
+            token1,
+            reserve0: word_to_u128(word(&reserves, 0)?),
+            reserve1: word_to_u128(word(&reserves, 1)?),
+            block_timestamp_last,
+        })
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// State of a Uniswap V3 pool, or of any fork exposing the same interface.
+pub struct UniswapV3Pool {
+    pub address: EvmAddress,
+    pub token0: EvmAddress,
+    pub token1: EvmAddress,
+    /// Fee tier in hundredths of a basis point, e.g. `3000` for 0.3%.
+    pub fee: u32,
+    pub tick_spacing: i32,
+    /// In-range liquidity.
+    #[serde_as(as = "DisplayFromStr")]
+    pub liquidity: u128,
+    /// Square root of the price of `token0` in `token1` as a big-endian Q64.96 `uint160`.
+    pub sqrt_price_x96: [u8; 20],
+    /// Current tick of the pool.
+    pub tick: i32,
+}
+
+/// `slot0()`
+const SLOT0: [u8; 4] = [0x38, 0x50, 0xc7, 0xbd];
+/// `fee()`
+const FEE: [u8; 4] = [0xdd, 0xca, 0x3f, 0x43];
+/// `liquidity()`
+const LIQUIDITY: [u8; 4] = [0x1a, 0x68, 0x65, 0x02];
+/// `tickSpacing()`
+const TICK_SPACING: [u8; 4] = [0xd0, 0xc9, 0x3a, 0x7c];
+
+impl UniswapV3Pool {
+    /// Fetches the pool at `address` from the JSON-RPC endpoint at `rpc_url`.
+    pub async fn fetch(rpc_url: &str, address: EvmAddress) -> Result<Self, EvmError> {
+        let token0 = word_to_address(word(&eth_call(rpc_url, &address, TOKEN0).await?, 0)?);
+        let token1 = word_to_address(word(&eth_call(rpc_url, &address, TOKEN1).await?, 0)?);
+        let fee = u32::try_from(word_to_u128(word(
+            &eth_call(rpc_url, &address, FEE).await?,
+            0,
+        )?))
+        .map_err(|_| EvmError::InvalidResponse("fee overflows u32".to_string()))?;
+        let tick_spacing = word_to_i32(word(&eth_call(rpc_url, &address, TICK_SPACING).await?, 0)?);
+        let liquidity = word_to_u128(word(&eth_call(rpc_url, &address, LIQUIDITY).await?, 0)?);
+        let slot0 = eth_call(rpc_url, &address, SLOT0).await?;
+        let mut sqrt_price_x96 = [0u8; 20];
+        sqrt_price_x96.copy_from_slice(&word(&slot0, 0)?[12..]);
+        Ok(UniswapV3Pool {
+            address,
+            token0,
+            token1,
+            fee,
+            tick_spacing,
+            liquidity,
+            sqrt_price_x96,
+            tick: word_to_i32(word(&slot0, 1)?),
+        })
+    }
+
+    /// Returns the square root price as a Q64.64, the format Raydium CLMM stores in
+    /// `sqrt_price_x64`, so prices from both venues can be compared directly. The 32
+    /// lowest fractional bits are dropped.
+    pub fn sqrt_price_x64(&self) -> u128 {
+        let mut high = [0u8; 16];
+        high.copy_from_slice(&self.sqrt_price_x96[..16]);
+        u128::from_be_bytes(high)
+    }
+}
@@ -0,0 +1,277 @@
+//! Fetching from a list of RPC endpoints that stand in for each other.
+//!
+//! `Failover` sends each request to the first endpoint in its list that is healthy. An
+//! endpoint that fails with a transient error, as told by `PoolError::is_transient`, is
+//! marked unhealthy for a cooldown and the request moves on to the next one. Once the
+//! cooldown is over the endpoint is tried again, so traffic returns to the primary as
+//! soon as it recovers. If every endpoint is cooling down, they're all tried in order
+//! regardless.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_response::Response;
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::Pubkey;
+
+use crate::fetcher::AccountFetcher;
+use crate::parsing::PoolError;
+
+/// How long an endpoint that failed is skipped unless configured otherwise.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+/// An `AccountFetcher` that fails over between `endpoints`, in order of preference.
+pub struct Failover<F> {
+    endpoints: Vec<F>,
+    cooldown: Duration,
+    /// When each endpoint may be tried again, or `None` for healthy endpoints.
+    unhealthy_until: Mutex<Vec<Option<Instant>>>,
+}
+
+impl<F> Failover<F> {
+    /// Creates a failover over `endpoints`, the first of which is the primary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `endpoints` is empty.
+    pub fn new(endpoints: Vec<F>) -> Self {
+        assert!(!endpoints.is_empty(), "at least one endpoint is required");
+        let unhealthy_until = Mutex::new(vec![None; endpoints.len()]);
+        Failover {
+            endpoints,
+            cooldown: DEFAULT_COOLDOWN,
+            unhealthy_until,
+        }
+    }
+
+    /// Sets how long an endpoint that failed is skipped.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn endpoints(&self) -> &[F] {
+        &self.endpoints
+    }
+
+    /// Returns the endpoint requests are sent to first.
+    pub fn primary(&self) -> &F {
+        &self.endpoints[0]
+    }
+
+    /// Returns whether the endpoint at `index` is healthy, which it is unless it failed
+    /// less than a cooldown ago.
+    pub fn is_healthy(&self, index: usize) -> bool {
+        let unhealthy_until = self
+            .unhealthy_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        unhealthy_until[index].is_none_or(|until| Instant::now() >= until)
+    }
+
+    /// Returns the indices of the endpoints in the order a request tries them.
+    fn order(&self) -> Vec<usize> {
+        let healthy: Vec<usize> = (0..self.endpoints.len())
+            .filter(|index| self.is_healthy(*index))
+            .collect();
+        if healthy.is_empty() {
+            (0..self.endpoints.len()).collect()
+        } else {
+            healthy
+        }
+    }
+
+    fn set_healthy(&self, index: usize, healthy: bool) {
+        let mut unhealthy_until = self
+            .unhealthy_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        unhealthy_until[index] = if healthy {
+            None
+        } else {
+            Some(Instant::now() + self.cooldown)
+        };
+    }
+
+    /// Runs `request` on each endpoint in turn until one succeeds or fails with an error
+    /// that isn't transient, returning the last error if none does.
+    async fn run<'a, T, Request, Fut>(&'a self, request: Request) -> Result<T, PoolError>
+    where
+        Request: Fn(&'a F) -> Fut,
+        Fut: Future<Output = Result<T, PoolError>>,
+    {
+        let mut last_error = None;
+        for index in self.order() {
+            match request(&self.endpoints[index]).await {
+                Err(e) if e.is_transient() => {
+                    self.set_healthy(index, false);
+                    last_error = Some(e);
+                }
+                result => {
+                    self.set_healthy(index, true);
+                    return result;
+                }
+            }
+        }
+        Err(last_error.expect("at least one endpoint is tried"))
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for Failover<F> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        self.run(|endpoint| endpoint.fetch_accounts_with_config(addresses, config.clone()))
+            .await
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        self.run(|endpoint| endpoint.fetch_program_accounts(program_id, config.clone()))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    use super::*;
+    use crate::mock::MockFetcher;
+
+    const HEALTHY: u8 = 0;
+    const TIMING_OUT: u8 = 1;
+    const REJECTING: u8 = 2;
+
+    /// An endpoint answering at its own slot, which fails with a timeout or an error that
+    /// isn't transient as told by `state`.
+    struct Endpoint {
+        inner: MockFetcher,
+        state: AtomicU8,
+    }
+
+    impl Endpoint {
+        fn new(slot: u64) -> Self {
+            Endpoint {
+                inner: MockFetcher::new().with_slot(slot),
+                state: AtomicU8::new(HEALTHY),
+            }
+        }
+
+        fn set_state(&self, state: u8) {
+            self.state.store(state, Ordering::Relaxed);
+        }
+
+        fn check(&self) -> Result<(), PoolError> {
+            match self.state.load(Ordering::Relaxed) {
+                TIMING_OUT => Err(PoolError::Timeout(Duration::from_secs(1))),
+                REJECTING => Err(PoolError::InvalidConfig("rejected".to_string())),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    impl AccountFetcher for Endpoint {
+        async fn fetch_accounts_with_config(
+            &self,
+            addresses: &[Pubkey],
+            config: RpcAccountInfoConfig,
+        ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+            let response = self
+                .inner
+                .fetch_accounts_with_config(addresses, config)
+                .await;
+            self.check()?;
+            response
+        }
+
+        async fn fetch_program_accounts(
+            &self,
+            program_id: &Pubkey,
+            config: RpcProgramAccountsConfig,
+        ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+            let response = self.inner.fetch_program_accounts(program_id, config).await;
+            self.check()?;
+            response
+        }
+    }
+
+    /// Returns the slot of the endpoint that answered a request of `failover`.
+    async fn answering_slot(failover: &Failover<Endpoint>) -> Result<u64, PoolError> {
+        let response = failover.fetch_accounts(&[Pubkey::new_unique()]).await?;
+        Ok(response.context.slot)
+    }
+
+    fn requests(failover: &Failover<Endpoint>) -> Vec<usize> {
+        failover
+            .endpoints()
+            .iter()
+            .map(|endpoint| endpoint.inner.request_count())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn transient_failures_move_on_to_the_next_endpoint() {
+        let failover = Failover::new(vec![Endpoint::new(1), Endpoint::new(2), Endpoint::new(3)]);
+        failover.primary().set_state(TIMING_OUT);
+        assert_eq!(answering_slot(&failover).await.unwrap(), 2);
+        assert_eq!(requests(&failover), [1, 1, 0]);
+        assert!(!failover.is_healthy(0));
+        assert!(failover.is_healthy(1));
+    }
+
+    #[tokio::test]
+    async fn failed_endpoints_are_skipped_during_the_cooldown() {
+        let failover = Failover::new(vec![Endpoint::new(1), Endpoint::new(2)]);
+        failover.primary().set_state(TIMING_OUT);
+        answering_slot(&failover).await.unwrap();
+        failover.primary().set_state(HEALTHY);
+        assert_eq!(answering_slot(&failover).await.unwrap(), 2);
+        assert_eq!(requests(&failover), [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn requests_return_to_the_primary_after_the_cooldown() {
+        let failover = Failover::new(vec![Endpoint::new(1), Endpoint::new(2)])
+            .with_cooldown(Duration::from_millis(50));
+        failover.primary().set_state(TIMING_OUT);
+        assert_eq!(answering_slot(&failover).await.unwrap(), 2);
+        failover.primary().set_state(HEALTHY);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(failover.is_healthy(0));
+        assert_eq!(answering_slot(&failover).await.unwrap(), 1);
+        assert_eq!(requests(&failover), [2, 1]);
+    }
+
+    #[tokio::test]
+    async fn errors_that_arent_transient_are_returned_at_once() {
+        let failover = Failover::new(vec![Endpoint::new(1), Endpoint::new(2)]);
+        failover.primary().set_state(REJECTING);
+        let result = answering_slot(&failover).await;
+        assert!(matches!(result, Err(PoolError::InvalidConfig(_))));
+        assert_eq!(requests(&failover), [1, 0]);
+        assert!(failover.is_healthy(0));
+    }
+
+    #[tokio::test]
+    async fn every_endpoint_is_tried_once_all_are_cooling_down() {
+        let failover = Failover::new(vec![Endpoint::new(1), Endpoint::new(2)]);
+        for endpoint in failover.endpoints() {
+            endpoint.set_state(TIMING_OUT);
+        }
+        let result = answering_slot(&failover).await;
+        assert!(matches!(result, Err(PoolError::Timeout(_))));
+        assert_eq!(requests(&failover), [1, 1]);
+
+        failover.endpoints()[1].set_state(HEALTHY);
+        assert_eq!(answering_slot(&failover).await.unwrap(), 2);
+        assert_eq!(requests(&failover), [2, 2]);
+    }
+}
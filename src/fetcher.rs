@@ -0,0 +1,483 @@
+//! The clients `get_info_struct` fetches accounts with.
+//!
+//! `AccountFetcher` is implemented for both Solana RPC clients. The nonblocking
+//! `nonblocking::rpc_client::RpcClient` is the one to use inside async services: its
+//! requests are awaited without holding up the runtime. The blocking
+//! `rpc_client::RpcClient` is still accepted, but makes each request on the calling
+//! thread before returning a future that is already complete. Scripts and CLIs without a
+//! runtime call the functions of `blocking`, which run these on a runtime of their own.
+//!
+//! Accounts are fetched at the commitment of the client unless it's wrapped in a
+//! `WithCommitment`, which fetches at a commitment of its own. `WithMinContextSlot` only
+//! accepts accounts read at a given slot or later. `WithTimeout` gives up on requests that
+//! take too long with `PoolError::Timeout`.
+//!
+//! Tests that shouldn't need an RPC node fetch from a `mock::MockFetcher` instead.
+
+use std::future::{self, Future};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anchor_client::solana_account_decoder::UiAccountEncoding;
+use anchor_client::solana_client::client_error::ClientError;
+use anchor_client::solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_request::{RpcError, RpcRequest};
+use anchor_client::solana_client::rpc_response::{OptionalContext, Response, RpcKeyedAccount};
+use anchor_client::solana_sdk::account::Account;
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_lang::prelude::Pubkey;
+use serde_json::json;
+
+use crate::parsing::PoolError;
+
+/// A client that fetches accounts, together with the slot they were read at.
+///
+/// Only `fetch_accounts_with_config` and `fetch_program_accounts` have to be implemented;
+/// the other methods fetch with the default configuration of the fetcher, such as the
+/// commitment of an `RpcClient`.
+pub trait AccountFetcher: Send + Sync {
+    /// Fetches the accounts at `addresses` in one request, in the same order, with `None`
+    /// for those that don't exist.
+    ///
+    /// Fields of `config` left as `None` keep the default of the fetcher.
+    fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> impl Future<Output = Result<Response<Vec<Option<Account>>>, PoolError>> + Send;
+
+    /// Fetches the accounts owned by `program_id` that pass the filters of `config`, with
+    /// `getProgramAccounts`.
+    ///
+    /// Fields of `config.account_config` left as `None` keep the default of the fetcher,
+    /// and the slot is always returned, whatever `config.with_context` says.
+    fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> impl Future<Output = Result<Response<Vec<(Pubkey, Account)>>, PoolError>> + Send;
+
+    /// Fetches the accounts at `addresses` in one request, in the same order, with `None`
+    /// for those that don't exist.
+    fn fetch_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> impl Future<Output = Result<Response<Vec<Option<Account>>>, PoolError>> + Send {
+        self.fetch_accounts_with_config(addresses, RpcAccountInfoConfig::default())
+    }
+
+    /// Fetches the account at `address`, or `None` if it doesn't exist.
+    fn fetch_account(
+        &self,
+        address: &Pubkey,
+    ) -> impl Future<Output = Result<Response<Option<Account>>, PoolError>> + Send {
+        async move {
+            let response = self.fetch_accounts(&[*address]).await?;
+            Ok(Response {
+                context: response.context,
+                value: response.value.into_iter().next().flatten(),
+            })
+        }
+    }
+}
+
+// Single accounts are fetched with `getMultipleAccounts` as well, because the client turns
+// every error of `getAccountInfo` into an `AccountNotFound`, hiding rate limits and
+// timeouts. `getProgramAccounts` is sent as is, because
+// `RpcClient::get_program_accounts_with_config` drops the slot of the response.
+
+impl AccountFetcher for NonblockingRpcClient {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        match self
+            .get_multiple_accounts_with_config(addresses, with_encoding(config))
+            .await
+        {
+            Ok(response) => Ok(response),
+            Err(e) => Err(PoolError::from(e)),
+        }
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        let config = program_accounts_config(config, self.commitment());
+        match self
+            .send(
+                RpcRequest::GetProgramAccounts,
+                json!([program_id.to_string(), config]),
+            )
+            .await
+        {
+            Ok(response) => keyed_accounts(response),
+            Err(e) => Err(PoolError::from(e)),
+        }
+    }
+}
+
+impl AccountFetcher for RpcClient {
+    fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> impl Future<Output = Result<Response<Vec<Option<Account>>>, PoolError>> + Send {
+        let response = self.get_multiple_accounts_with_config(addresses, with_encoding(config));
+        future::ready(response.map_err(PoolError::from))
+    }
+
+    fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> impl Future<Output = Result<Response<Vec<(Pubkey, Account)>>, PoolError>> + Send {
+        let config = program_accounts_config(config, self.commitment());
+        let response = match self.send(
+            RpcRequest::GetProgramAccounts,
+            json!([program_id.to_string(), config]),
+        ) {
+            Ok(response) => keyed_accounts(response),
+            Err(e) => Err(PoolError::from(e)),
+        };
+        future::ready(response)
+    }
+}
+
+/// Fills in the encoding `RpcClient::get_multiple_accounts` requests, since the node
+/// defaults to base58, which it refuses for accounts over 128 bytes.
+fn with_encoding(config: RpcAccountInfoConfig) -> RpcAccountInfoConfig {
+    RpcAccountInfoConfig {
+        encoding: config.encoding.or(Some(UiAccountEncoding::Base64Zstd)),
+        ..config
+    }
+}
+
+/// Fills in what `RpcClient::get_program_accounts_with_config` would, and asks for the
+/// slot of the response.
+fn program_accounts_config(
+    config: RpcProgramAccountsConfig,
+    commitment: CommitmentConfig,
+) -> RpcProgramAccountsConfig {
+    let account_config = with_encoding(config.account_config);
+    RpcProgramAccountsConfig {
+        account_config: RpcAccountInfoConfig {
+            commitment: account_config.commitment.or(Some(commitment)),
+            ..account_config
+        },
+        with_context: Some(true),
+        ..config
+    }
+}
+
+/// Decodes the accounts of a `getProgramAccounts` response.
+fn keyed_accounts(
+    response: OptionalContext<Vec<RpcKeyedAccount>>,
+) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+    let OptionalContext::Context(response) = response else {
+        return Err(parse_error("getProgramAccounts response without a context"));
+    };
+    let mut accounts = Vec::with_capacity(response.value.len());
+    for keyed_account in response.value {
+        let Ok(address) = keyed_account.pubkey.parse::<Pubkey>() else {
+            return Err(parse_error("invalid pubkey in getProgramAccounts response"));
+        };
+        let Some(account) = keyed_account.account.decode::<Account>() else {
+            return Err(parse_error(
+                "undecodable account in getProgramAccounts response",
+            ));
+        };
+        accounts.push((address, account));
+    }
+    Ok(Response {
+        context: response.context,
+        value: accounts,
+    })
+}
+
+fn parse_error(message: &str) -> PoolError {
+    PoolError::from(ClientError::from(RpcError::ParseError(message.to_string())))
+}
+
+impl<T: AccountFetcher> AccountFetcher for &T {
+    fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> impl Future<Output = Result<Response<Vec<Option<Account>>>, PoolError>> + Send {
+        (**self).fetch_accounts_with_config(addresses, config)
+    }
+
+    fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> impl Future<Output = Result<Response<Vec<(Pubkey, Account)>>, PoolError>> + Send {
+        (**self).fetch_program_accounts(program_id, config)
+    }
+}
+
+impl<T: AccountFetcher> AccountFetcher for Arc<T> {
+    fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> impl Future<Output = Result<Response<Vec<Option<Account>>>, PoolError>> + Send {
+        (**self).fetch_accounts_with_config(addresses, config)
+    }
+
+    fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> impl Future<Output = Result<Response<Vec<(Pubkey, Account)>>, PoolError>> + Send {
+        (**self).fetch_program_accounts(program_id, config)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An `AccountFetcher` that fetches with `inner` at `commitment`, whatever the commitment
+/// `inner` defaults to.
+///
+/// ```no_run
+/// # use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+/// # use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+/// # use universal_liquidity_pool_parser::fetcher::WithCommitment;
+/// let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let fetcher = WithCommitment::new(rpc_client, CommitmentConfig::processed());
+/// ```
+pub struct WithCommitment<F> {
+    pub inner: F,
+    pub commitment: CommitmentConfig,
+}
+
+impl<F> WithCommitment<F> {
+    pub fn new(inner: F, commitment: CommitmentConfig) -> Self {
+        WithCommitment { inner, commitment }
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for WithCommitment<F> {
+    fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> impl Future<Output = Result<Response<Vec<Option<Account>>>, PoolError>> + Send {
+        let config = RpcAccountInfoConfig {
+            commitment: config.commitment.or(Some(self.commitment)),
+            ..config
+        };
+        self.inner.fetch_accounts_with_config(addresses, config)
+    }
+
+    fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> impl Future<Output = Result<Response<Vec<(Pubkey, Account)>>, PoolError>> + Send {
+        let config = RpcProgramAccountsConfig {
+            account_config: RpcAccountInfoConfig {
+                commitment: config.account_config.commitment.or(Some(self.commitment)),
+                ..config.account_config
+            },
+            ..config
+        };
+        self.inner.fetch_program_accounts(program_id, config)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An `AccountFetcher` that makes the requests of `inner` fail unless the node has reached
+/// `min_context_slot`, so the accounts are read at that slot or later.
+///
+/// Solana RPC nodes only serve the current state of accounts; this bounds how old it may
+/// be, for instance to read a pool no earlier than the slot of a transaction that changed
+/// it. A node behind the slot answers with an error that `PoolError::is_transient`
+/// accepts, so `Retrying` waits for it to catch up. The later of `min_context_slot` and
+/// that of a request is used.
+///
+/// ```no_run
+/// # use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+/// # use universal_liquidity_pool_parser::fetcher::WithMinContextSlot;
+/// let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let fetcher = WithMinContextSlot::new(rpc_client, 330_000_000);
+/// ```
+pub struct WithMinContextSlot<F> {
+    pub inner: F,
+    pub min_context_slot: u64,
+}
+
+impl<F> WithMinContextSlot<F> {
+    pub fn new(inner: F, min_context_slot: u64) -> Self {
+        WithMinContextSlot {
+            inner,
+            min_context_slot,
+        }
+    }
+
+    /// Returns the later of `min_context_slot` and `requested`.
+    fn slot(&self, requested: Option<u64>) -> Option<u64> {
+        Some(requested.map_or(self.min_context_slot, |slot| {
+            slot.max(self.min_context_slot)
+        }))
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for WithMinContextSlot<F> {
+    fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> impl Future<Output = Result<Response<Vec<Option<Account>>>, PoolError>> + Send {
+        let config = RpcAccountInfoConfig {
+            min_context_slot: self.slot(config.min_context_slot),
+            ..config
+        };
+        self.inner.fetch_accounts_with_config(addresses, config)
+    }
+
+    fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> impl Future<Output = Result<Response<Vec<(Pubkey, Account)>>, PoolError>> + Send {
+        let config = RpcProgramAccountsConfig {
+            account_config: RpcAccountInfoConfig {
+                min_context_slot: self.slot(config.account_config.min_context_slot),
+                ..config.account_config
+            },
+            ..config
+        };
+        self.inner.fetch_program_accounts(program_id, config)
+    }
+}
+
+/// How long `UniversalParser` waits for a request unless configured otherwise, the same
+/// as the timeout of an `RpcClient`.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+/// An `AccountFetcher` that fails requests of `inner` that take longer than `timeout` with
+/// `PoolError::Timeout`.
+///
+/// Requests of the blocking `RpcClient` complete before their future is first polled, so
+/// they can't be timed out here; set the timeout of the client itself instead.
+pub struct WithTimeout<F> {
+    pub inner: F,
+    pub timeout: Duration,
+}
+
+impl<F> WithTimeout<F> {
+    pub fn new(inner: F, timeout: Duration) -> Self {
+        WithTimeout { inner, timeout }
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for WithTimeout<F> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        let request = self.inner.fetch_accounts_with_config(addresses, config);
+        match tokio::time::timeout(self.timeout, request).await {
+            Ok(result) => result,
+            Err(_) => Err(PoolError::Timeout(self.timeout)),
+        }
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        let request = self.inner.fetch_program_accounts(program_id, config);
+        match tokio::time::timeout(self.timeout, request).await {
+            Ok(result) => result,
+            Err(_) => Err(PoolError::Timeout(self.timeout)),
+        }
+    }
+}
+
+/// Fetches the data of the account at `address`, failing if it doesn't exist.
+pub(crate) async fn fetch_account_data<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> Result<Vec<u8>, PoolError> {
+    match fetcher.fetch_account(address).await?.value {
+        Some(account) => Ok(account.data),
+        None => Err(PoolError::AccountNotFound { address: *address }),
+    }
+}
+
+/// Fetches the accounts at `addresses` in one request, failing with the address of the
+/// first one that doesn't exist.
+pub(crate) async fn fetch_existing_accounts<F: AccountFetcher, const N: usize>(
+    fetcher: &F,
+    addresses: &[Pubkey; N],
+) -> Result<[Account; N], PoolError> {
+    let mut accounts = fetcher.fetch_accounts(addresses).await?.value.into_iter();
+    let mut existing = Vec::with_capacity(N);
+    for address in addresses {
+        match accounts.next().flatten() {
+            Some(account) => existing.push(account),
+            None => return Err(PoolError::AccountNotFound { address: *address }),
+        }
+    }
+    Ok(existing.try_into().expect("one account per address"))
+}
+
+/// Returns `account` with the slice of its data `config` asks for, as an RPC node would.
+pub(crate) fn sliced(account: &Account, config: &RpcAccountInfoConfig) -> Account {
+    let mut account = account.clone();
+    if let Some(slice) = config.data_slice {
+        let start = slice.offset.min(account.data.len());
+        let end = slice
+            .offset
+            .saturating_add(slice.length)
+            .min(account.data.len());
+        account.data = account.data[start..end].to_vec();
+    }
+    account
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockFetcher;
+
+    fn account(data: &[u8]) -> Account {
+        Account {
+            data: data.to_vec(),
+            ..Account::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_existing_accounts_in_order() {
+        let [a, b] = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let fetcher = MockFetcher::new()
+            .with_account(a, account(&[1]))
+            .with_account(b, account(&[2]));
+        let [first, second] = fetch_existing_accounts(&fetcher, &[b, a]).await.unwrap();
+        assert_eq!((first.data, second.data), (vec![2], vec![1]));
+        assert_eq!(fetcher.request_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_the_first_missing_account() {
+        let [a, b, c] = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let fetcher = MockFetcher::new().with_account(a, account(&[1]));
+        let result = fetch_existing_accounts(&fetcher, &[a, b, c]).await;
+        assert!(matches!(result, Err(PoolError::AccountNotFound { address }) if address == b));
+    }
+}
@@ -0,0 +1,63 @@
+//! Flat maps of the fields of parsed pools.
+//!
+//! `AmmPool::to_field_map` flattens the JSON representation of a pool, as written to
+//! `data` by `AmmPool::to_json`, into one entry per scalar field, for tooling that shows
+//! pools of any protocol as rows of a table. Keys are paths in the format `AmmPool::diff`
+//! reports changes in, field names joined by `.` and array indices in brackets:
+//!
+//! ```text
+//! amm_config             "<base58 address>"
+//! reward_infos[0].mint   "<base58 address>"
+//! sqrt_price_x64         "18435679900048796078"
+//! tick_current           -12
+//! ```
+//!
+//! Empty arrays and structs have no fields of their own and are kept as an entry holding
+//! the empty value.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::parsing::AmmPool;
+
+impl AmmPool {
+    /// Returns every scalar field of the pool, keyed by its path.
+    pub fn to_field_map(&self) -> Result<BTreeMap<String, Value>, serde_json::Error> {
+        let mut fields = BTreeMap::new();
+        flatten(String::new(), self.data_json()?, &mut fields);
+        Ok(fields)
+    }
+}
+
+/// Returns the path of the field `name` of the struct at `path`.
+pub(crate) fn field_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+/// Returns the path of element `index` of the array at `path`.
+pub(crate) fn index_path(path: &str, index: usize) -> String {
+    format!("{path}[{index}]")
+}
+
+fn flatten(path: String, value: Value, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (name, value) in object {
+                flatten(field_path(&path, &name), value, fields);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, value) in items.into_iter().enumerate() {
+                flatten(index_path(&path, i), value, fields);
+            }
+        }
+        value => {
+            fields.insert(path, value);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Fetching only the fields of a pool that change with every trade.
+//!
+//! Polling loops that track prices don't need the hundreds of bytes of configuration,
+//! vaults and reward state in a pool account. `fetch_hot_fields` requests just the range
+//! of the account holding the price and liquidity, with the `dataSlice` option of
+//! `getMultipleAccounts`, and decodes `HotFields` from it. Pools sharing a layout are
+//! fetched together, so polling hundreds of Raydium CLMM pools takes a handful of
+//! requests of 36 bytes per pool instead of about 1.5 KB.
+//!
+//! | Pool type | Range |
+//! | --- | --- |
+//! | `RaydiumCamm` | `liquidity`, `sqrt_price_x64`, `tick_current` |
+//! | `OrcaWhirlpool` | `liquidity`, `sqrt_price`, `tick_current_index` |
+//! | `MeteoraDlmm` | `active_id`, `bin_step` |
+//! | `PumpFunBondingCurve` | virtual and real reserves |
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use anchor_client::solana_account_decoder::UiDataSliceConfig;
+use anchor_client::solana_client::rpc_config::RpcAccountInfoConfig;
+use anchor_client::solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use anchor_lang::error::ErrorCode;
+
+use crate::fetcher::AccountFetcher;
+use crate::parsing::{PoolError, PoolType};
+use crate::program_ids::Cluster;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The fields of a pool that change with every trade.
+pub enum HotFields {
+    /// A Raydium CLMM pool or an Orca Whirlpool.
+    Clmm {
+        liquidity: u128,
+        /// Square root of the price of token 0 in token 1, as a Q64.64 number.
+        sqrt_price_x64: u128,
+        tick_current: i32,
+    },
+    /// A Meteora DLMM pair, whose price is set by the active bin.
+    Dlmm { active_id: i32, bin_step: u16 },
+    /// A pump.fun bonding curve.
+    BondingCurve {
+        virtual_token_reserves: u64,
+        virtual_sol_reserves: u64,
+        real_token_reserves: u64,
+        real_sol_reserves: u64,
+    },
+}
+
+impl PoolType {
+    /// Returns the range of the account data holding the `HotFields` of this pool type,
+    /// or `None` if it has none.
+    pub fn hot_fields_range(&self) -> Option<Range<usize>> {
+        match self {
+            PoolType::RaydiumCamm { .. } => Some(237..273),
+            PoolType::OrcaWhirlpool { .. } => Some(49..85),
+            PoolType::MeteoraDlmm { .. } => Some(76..82),
+            PoolType::PumpFunBondingCurve { .. } => Some(8..40),
+            _ => None,
+        }
+    }
+}
+
+impl HotFields {
+    /// Decodes the hot fields of `pool_type` from `data`, the range of its account given
+    /// by `PoolType::hot_fields_range`.
+    pub fn parse(pool_type: &PoolType, data: &[u8]) -> Result<Self, PoolError> {
+        let Some(range) = pool_type.hot_fields_range() else {
+            return Err(PoolError::NoHotFields(pool_type.pool_name()));
+        };
+        if data.len() < range.len() {
+            return Err(PoolError::DeserializeError(
+                ErrorCode::AccountDidNotDeserialize.into(),
+            ));
+        }
+        Ok(match pool_type {
+            PoolType::RaydiumCamm { .. } | PoolType::OrcaWhirlpool { .. } => HotFields::Clmm {
+                liquidity: u128::from_le_bytes(bytes(data, 0)),
+                sqrt_price_x64: u128::from_le_bytes(bytes(data, 16)),
+                tick_current: i32::from_le_bytes(bytes(data, 32)),
+            },
+            PoolType::MeteoraDlmm { .. } => HotFields::Dlmm {
+                active_id: i32::from_le_bytes(bytes(data, 0)),
+                bin_step: u16::from_le_bytes(bytes(data, 4)),
+            },
+            _ => HotFields::BondingCurve {
+                virtual_token_reserves: u64::from_le_bytes(bytes(data, 0)),
+                virtual_sol_reserves: u64::from_le_bytes(bytes(data, 8)),
+                real_token_reserves: u64::from_le_bytes(bytes(data, 16)),
+                real_sol_reserves: u64::from_le_bytes(bytes(data, 24)),
+            },
+        })
+    }
+}
+
+/// Returns the `N` bytes of `data` at `offset`, which the caller has checked are there.
+fn bytes<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    data[offset..offset + N]
+        .try_into()
+        .expect("range is in bounds")
+}
+
+/// Fetches the `HotFields` of each of `pool_types`, in the same order, with `None` for
+/// pools whose account doesn't exist.
+///
+/// Pools with the same range are fetched together, up to the 100 accounts a request may
+/// ask for.
+///
+/// Returns `PoolError::NoHotFields` if one of `pool_types` has no hot fields and
+/// `PoolError::UnexpectedOwner` if an account isn't owned by the program of its pool type
+/// on `cluster`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+/// # use anchor_lang::prelude::Pubkey;
+/// # use universal_liquidity_pool_parser::hot_fields::fetch_hot_fields;
+/// # use universal_liquidity_pool_parser::parsing::{PoolError, PoolType};
+/// # use universal_liquidity_pool_parser::program_ids::Cluster;
+/// # async fn example(addresses: Vec<Pubkey>) -> Result<(), PoolError> {
+/// let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// let pool_types: Vec<PoolType> = addresses
+///     .into_iter()
+///     .map(|pool_address| PoolType::RaydiumCamm { pool_address })
+///     .collect();
+/// for hot_fields in fetch_hot_fields(&pool_types, &rpc_client, &Cluster::Mainnet).await? {
+///     println!("{hot_fields:?}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn fetch_hot_fields<F: AccountFetcher>(
+    pool_types: &[PoolType],
+    fetcher: &F,
+    cluster: &Cluster,
+) -> Result<Vec<Option<HotFields>>, PoolError> {
+    // Indices of the pool types with each range.
+    let mut by_range: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for (index, pool_type) in pool_types.iter().enumerate() {
+        let Some(range) = pool_type.hot_fields_range() else {
+            return Err(PoolError::NoHotFields(pool_type.pool_name()));
+        };
+        by_range
+            .entry((range.start, range.end))
+            .or_default()
+            .push(index);
+    }
+
+    let mut hot_fields = vec![None; pool_types.len()];
+    for ((start, end), indices) in by_range {
+        for chunk in indices.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let addresses: Vec<_> = chunk
+                .iter()
+                .map(|index| pool_types[*index].pool_address())
+                .collect();
+            let config = RpcAccountInfoConfig {
+                data_slice: Some(UiDataSliceConfig {
+                    offset: start,
+                    length: end - start,
+                }),
+                ..RpcAccountInfoConfig::default()
+            };
+            let accounts = fetcher
+                .fetch_accounts_with_config(&addresses, config)
+                .await?
+                .value;
+            for (index, account) in chunk.iter().zip(accounts) {
+                let Some(account) = account else {
+                    continue;
+                };
+                let pool_type = &pool_types[*index];
+                if !pool_type.is_owned_by(&account.owner, cluster) {
+                    return Err(PoolError::UnexpectedOwner(
+                        pool_type.pool_name(),
+                        account.owner,
+                    ));
+                }
+                hot_fields[*index] = Some(HotFields::parse(pool_type, &account.data)?);
+            }
+        }
+    }
+    Ok(hot_fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::Discriminator;
+    use anchor_lang::prelude::Pubkey;
+
+    use super::*;
+    use crate::parsing::meteora_dlmm::accounts::LbPair;
+    use crate::parsing::orca_whirlpool::accounts::Whirlpool;
+    use crate::parsing::pump::accounts::BondingCurve;
+    use crate::parsing::raydium_camm::accounts::PoolState;
+    use crate::parsing::{AmmPool, parse_from_bytes};
+
+    /// Returns an account of `pool_type` that starts with `discriminator` and is filled
+    /// with bytes that differ from their neighbours, written back from its parsed form so
+    /// that it is exactly as long as the layout.
+    fn account(pool_type: &PoolType, discriminator: &[u8], fix: impl Fn(&mut [u8])) -> Vec<u8> {
+        let mut data: Vec<u8> = (0..2_000).map(|i| (i * 31 + 7) as u8).collect();
+        data[..discriminator.len()].copy_from_slice(discriminator);
+        fix(&mut data);
+        parse_from_bytes(pool_type, &data)
+            .unwrap()
+            .to_account_bytes()
+            .unwrap()
+    }
+
+    /// Checks that the hot fields sliced out of `data` match those of the full parse.
+    fn assert_slice_matches_parse(pool_type: &PoolType, data: &[u8]) {
+        let range = pool_type.hot_fields_range().unwrap();
+        let sliced = HotFields::parse(pool_type, &data[range]).unwrap();
+        let parsed = match parse_from_bytes(pool_type, data).unwrap() {
+            AmmPool::RaydiumCamm(pool) => HotFields::Clmm {
+                liquidity: pool.liquidity,
+                sqrt_price_x64: pool.sqrt_price_x64,
+                tick_current: pool.tick_current,
+            },
+            AmmPool::OrcaWhirlpool(whirlpool) => HotFields::Clmm {
+                liquidity: whirlpool.liquidity,
+                sqrt_price_x64: whirlpool.sqrt_price,
+                tick_current: whirlpool.tick_current_index,
+            },
+            AmmPool::MeteoraDlmm(pair) => HotFields::Dlmm {
+                active_id: pair.active_id,
+                bin_step: pair.bin_step,
+            },
+            AmmPool::PumpFunBondingCurve(curve) => HotFields::BondingCurve {
+                virtual_token_reserves: curve.virtual_token_reserves,
+                virtual_sol_reserves: curve.virtual_sol_reserves,
+                real_token_reserves: curve.real_token_reserves,
+                real_sol_reserves: curve.real_sol_reserves,
+            },
+            other => panic!("parsed as {}", other.protocol_tag()),
+        };
+        assert_eq!(sliced, parsed);
+    }
+
+    #[test]
+    fn raydium_clmm_range_holds_the_hot_fields() {
+        let pool_type = PoolType::RaydiumCamm {
+            pool_address: Pubkey::new_unique(),
+        };
+        let data = account(&pool_type, PoolState::DISCRIMINATOR, |_| {});
+        assert_slice_matches_parse(&pool_type, &data);
+    }
+
+    #[test]
+    fn orca_whirlpool_range_holds_the_hot_fields() {
+        let pool_type = PoolType::OrcaWhirlpool {
+            pool_address: Pubkey::new_unique(),
+        };
+        let data = account(&pool_type, Whirlpool::DISCRIMINATOR, |_| {});
+        assert_slice_matches_parse(&pool_type, &data);
+    }
+
+    #[test]
+    fn meteora_dlmm_range_holds_the_hot_fields() {
+        let pool_type = PoolType::MeteoraDlmm {
+            pool_address: Pubkey::new_unique(),
+        };
+        let data = account(&pool_type, LbPair::DISCRIMINATOR, |_| {});
+        assert_slice_matches_parse(&pool_type, &data);
+    }
+
+    #[test]
+    fn bonding_curve_range_holds_the_hot_fields() {
+        let pool_type = PoolType::PumpFunBondingCurve {
+            pool_address: Pubkey::new_unique(),
+        };
+        // `complete` follows the five reserve and supply fields and must be a valid bool.
+        let data = account(&pool_type, BondingCurve::DISCRIMINATOR, |data| data[48] = 0);
+        assert_slice_matches_parse(&pool_type, &data);
+    }
+}
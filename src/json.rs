@@ -0,0 +1,188 @@
+//! A stable JSON representation of parsed pools for consumers outside Rust.
+//!
+//! `AmmPool::to_json` wraps the serialized account in an envelope whose shape doesn't
+//! depend on the protocol:
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "protocol": "raydium_cpmm",
+//!   "address": "<base58 address of the pool account>",
+//!   "data": { "...": "fields of the account" }
+//! }
+//! ```
+//!
+//! `data` holds the fields of the account under their on-chain names in snake case, as
+//! written by the `Serialize` impls of the crate: pubkeys are base58 strings, 128-bit
+//! integers are decimal strings and enums are externally tagged. Pools made of several
+//! accounts, such as `raydium_cpmm_with_config`, nest each account under the name of
+//! the field that holds it. EVM pools use their `0x` hex address in `address`.
+//!
+//! `protocol` is one of the tags below. A tag is never renamed and a change to the
+//! layout of `data` beyond new fields bumps `schema_version`.
+//!
+//! | Tag | `AmmPool` variant |
+//! | --- | --- |
+//! | `pump_fun_amm` | `PumpFun` |
+//! | `pump_fun_amm_with_config` | `PumpFunWithConfig` |
+//! | `pump_fun_amm_global_config` | `PumpFunGlobalConfig` |
+//! | `pump_fun_bonding_curve` | `PumpFunBondingCurve` |
+//! | `raydium_cpmm` | `RaydiumCpmmAmm` |
+//! | `raydium_cpmm_with_config` | `RaydiumCpmmAmmWithConfig` |
+//! | `raydium_cpmm_config` | `RaydiumCpmmConfig` |
+//! | `raydium_camm` | `RaydiumCamm` |
+//! | `raydium_camm_config` | `RaydiumCammConfig` |
+//! | `raydium_camm_observation` | `RaydiumCammObservation` |
+//! | `raydium_amm_v4` | `RaydiumLegacyAmm` |
+//! | `raydium_launchlab` | `RaydiumLaunchLab` |
+//! | `lifinity_v2` | `LifinityV2` |
+//! | `phoenix` | `Phoenix` |
+//! | `saber` | `SaberStableSwap` |
+//! | `mercurial` | `MercurialStableSwap` |
+//! | `invariant` | `Invariant` |
+//! | `crema_clmm` | `CremaClmm` |
+//! | `aldrin_v1`, `aldrin_v2` | `Aldrin` |
+//! | `fluxbeam` | `FluxBeam` |
+//! | `orca_token_swap` | `OrcaTokenSwap` |
+//! | `spl_token_swap` | `SplTokenSwap` |
+//! | `sanctum_infinity` | `SanctumInfinity` |
+//! | `stabble_stable_swap`, `stabble_weighted_swap` | `Stabble` |
+//! | `moonshot` | `Moonshot` |
+//! | `meteora_dbc` | `MeteoraDbc` |
+//! | `meteora_vault` | `MeteoraVault` |
+//! | `meteora_dlmm` | `MeteoraDlmm` |
+//! | `bonkswap_v1`, `bonkswap_v2` | `Bonkswap` |
+//! | `bonkswap_farm` | `BonkswapFarm` |
+//! | `orca_whirlpool` | `OrcaWhirlpool` |
+//! | `orca_whirlpool_position` | `OrcaWhirlpoolPosition` |
+//! | `spl_stake_pool` | `SplStakePool` |
+//! | `marinade` | `Marinade` |
+//! | `dynamic` | `Dynamic` |
+//! | `uniswap_v2` | `UniswapV2` |
+//! | `uniswap_v3` | `UniswapV3` |
+
+use anchor_lang::prelude::Pubkey;
+use serde_json::{Value, json, to_value};
+
+use crate::parsing::{AldrinPool, AmmPool, BonkswapPool, StabblePool};
+
+/// Version of the envelope and `data` layout written by `AmmPool::to_json`.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+impl AmmPool {
+    /// Returns the stable tag identifying the protocol and account type of the pool, as
+    /// listed in the module documentation.
+    pub fn protocol_tag(&self) -> &'static str {
+        match self {
+            AmmPool::PumpFun(_) => "pump_fun_amm",
+            AmmPool::PumpFunWithConfig(_) => "pump_fun_amm_with_config",
+            AmmPool::PumpFunGlobalConfig(_) => "pump_fun_amm_global_config",
+            AmmPool::PumpFunBondingCurve(_) => "pump_fun_bonding_curve",
+            AmmPool::RaydiumCpmmAmm(_) => "raydium_cpmm",
+            AmmPool::RaydiumCpmmAmmWithConfig(_) => "raydium_cpmm_with_config",
+            AmmPool::RaydiumCpmmConfig(_) => "raydium_cpmm_config",
+            AmmPool::RaydiumCamm(_) => "raydium_camm",
+            AmmPool::RaydiumCammConfig(_) => "raydium_camm_config",
+            AmmPool::RaydiumCammObservation(_) => "raydium_camm_observation",
+            AmmPool::RaydiumLegacyAmm(_) => "raydium_amm_v4",
+            AmmPool::RaydiumLaunchLab(_) => "raydium_launchlab",
+            AmmPool::LifinityV2(_) => "lifinity_v2",
+            AmmPool::Phoenix(_) => "phoenix",
+            AmmPool::SaberStableSwap(_) => "saber",
+            AmmPool::MercurialStableSwap(_) => "mercurial",
+            AmmPool::Invariant(_) => "invariant",
+            AmmPool::CremaClmm(_) => "crema_clmm",
+            AmmPool::Aldrin(AldrinPool::V1(_)) => "aldrin_v1",
+            AmmPool::Aldrin(AldrinPool::V2(_)) => "aldrin_v2",
+            AmmPool::FluxBeam(_) => "fluxbeam",
+            AmmPool::OrcaTokenSwap(_) => "orca_token_swap",
+            AmmPool::SplTokenSwap(_) => "spl_token_swap",
+            AmmPool::SanctumInfinity(_) => "sanctum_infinity",
+            AmmPool::Stabble(StabblePool::Stable(_)) => "stabble_stable_swap",
+            AmmPool::Stabble(StabblePool::Weighted(_)) => "stabble_weighted_swap",
+            AmmPool::Moonshot(_) => "moonshot",
+            AmmPool::MeteoraDbc(_) => "meteora_dbc",
+            AmmPool::MeteoraVault(_) => "meteora_vault",
+            AmmPool::MeteoraDlmm(_) => "meteora_dlmm",
+            AmmPool::Bonkswap(BonkswapPool::V1(_)) => "bonkswap_v1",
+            AmmPool::Bonkswap(BonkswapPool::V2(_)) => "bonkswap_v2",
+            AmmPool::BonkswapFarm(_) => "bonkswap_farm",
+            AmmPool::OrcaWhirlpool(_) => "orca_whirlpool",
+            AmmPool::OrcaWhirlpoolPosition(_) => "orca_whirlpool_position",
+            AmmPool::SplStakePool(_) => "spl_stake_pool",
+            AmmPool::Marinade(_) => "marinade",
+            AmmPool::Dynamic(_) => "dynamic",
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV2(_) => "uniswap_v2",
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV3(_) => "uniswap_v3",
+        }
+    }
+
+    /// Serializes the pool at `address` into the envelope described in the module
+    /// documentation.
+    pub fn to_json(&self, address: &Pubkey) -> Result<Value, serde_json::Error> {
+        let data = self.data_json()?;
+        let address = match self {
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV2(pair) => pair.address.to_string(),
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV3(pool) => pool.address.to_string(),
+            _ => address.to_string(),
+        };
+        Ok(json!({
+            "schema_version": JSON_SCHEMA_VERSION,
+            "protocol": self.protocol_tag(),
+            "address": address,
+            "data": data,
+        }))
+    }
+
+    /// Serializes the account of the pool, which `to_json` writes to `data`.
+    pub(crate) fn data_json(&self) -> Result<Value, serde_json::Error> {
+        Ok(match self {
+            AmmPool::PumpFun(pool) => to_value(pool)?,
+            AmmPool::PumpFunWithConfig(pool) => to_value(pool)?,
+            AmmPool::PumpFunGlobalConfig(config) => to_value(config)?,
+            AmmPool::PumpFunBondingCurve(curve) => to_value(curve)?,
+            AmmPool::RaydiumCpmmAmm(pool) => to_value(pool)?,
+            AmmPool::RaydiumCpmmAmmWithConfig(pool) => to_value(pool)?,
+            AmmPool::RaydiumCpmmConfig(config) => to_value(config)?,
+            AmmPool::RaydiumCamm(pool) => to_value(pool)?,
+            AmmPool::RaydiumCammConfig(config) => to_value(config)?,
+            AmmPool::RaydiumCammObservation(observation) => to_value(observation)?,
+            AmmPool::RaydiumLegacyAmm(amm) => to_value(amm)?,
+            AmmPool::RaydiumLaunchLab(pool) => to_value(pool)?,
+            AmmPool::LifinityV2(amm) => to_value(amm)?,
+            AmmPool::Phoenix(market) => to_value(market)?,
+            AmmPool::SaberStableSwap(swap) => to_value(swap)?,
+            AmmPool::MercurialStableSwap(swap) => to_value(swap)?,
+            AmmPool::Invariant(pool) => to_value(pool)?,
+            AmmPool::CremaClmm(pool) => to_value(pool)?,
+            AmmPool::Aldrin(AldrinPool::V1(pool)) => to_value(pool)?,
+            AmmPool::Aldrin(AldrinPool::V2(pool)) => to_value(pool)?,
+            AmmPool::FluxBeam(swap)
+            | AmmPool::OrcaTokenSwap(swap)
+            | AmmPool::SplTokenSwap(swap) => to_value(swap)?,
+            AmmPool::SanctumInfinity(pool) => to_value(pool)?,
+            AmmPool::Stabble(StabblePool::Stable(pool)) => to_value(pool)?,
+            AmmPool::Stabble(StabblePool::Weighted(pool)) => to_value(pool)?,
+            AmmPool::Moonshot(curve) => to_value(curve)?,
+            AmmPool::MeteoraDbc(pool) => to_value(pool)?,
+            AmmPool::MeteoraVault(vault) => to_value(vault)?,
+            AmmPool::MeteoraDlmm(pair) => to_value(pair)?,
+            AmmPool::Bonkswap(BonkswapPool::V1(pool)) => to_value(pool)?,
+            AmmPool::Bonkswap(BonkswapPool::V2(pool)) => to_value(pool)?,
+            AmmPool::BonkswapFarm(farm) => to_value(farm)?,
+            AmmPool::OrcaWhirlpool(whirlpool) => to_value(whirlpool)?,
+            AmmPool::OrcaWhirlpoolPosition(position) => to_value(position)?,
+            AmmPool::SplStakePool(pool) => to_value(pool)?,
+            AmmPool::Marinade(state) => to_value(state)?,
+            AmmPool::Dynamic(pool) => to_value(pool)?,
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV2(pair) => to_value(pair)?,
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV3(pool) => to_value(pool)?,
+        })
+    }
+}
@@ -0,0 +1,48 @@
+//! Layout versions of parsed accounts, for protocols that have changed their layouts.
+//!
+//! Programs migrate accounts in one of three ways, and the parser handles each of them:
+//!
+//! - A new account type with its own discriminator, such as Aldrin's v2 pools and
+//!   Bonkswap's `PoolV2`. Parsing picks the right type by discriminator.
+//! - A `version` field at the start of the account, as in the token-swap programs,
+//!   Mercurial, Sanctum Infinity and Meteora DLMM. Every version shares one layout.
+//! - New fields carved out of trailing padding, as in Raydium CPMM. The account keeps
+//!   its length and parses with the original layout, and the revision is told apart by
+//!   which of the new fields are set; see `raydium_cpmm::PoolStateExtension`.
+//!
+//! `AmmPool::layout_version` reports the version in each case, so consumers can tell
+//! which fields of a pool are meaningful.
+
+use crate::parsing::raydium_amm_cpmm_new::accounts::PoolState;
+use crate::parsing::{AldrinPool, AmmPool, BonkswapPool};
+use crate::raydium_cpmm::{PoolStateExtension, PoolStateVersion};
+
+impl AmmPool {
+    /// Returns the version of the layout the pool was parsed with, starting at 1, or
+    /// `None` for protocols that have only ever had one layout.
+    pub fn layout_version(&self) -> Option<u8> {
+        match self {
+            AmmPool::RaydiumCpmmAmm(pool) => Some(cpmm_version(pool)),
+            AmmPool::RaydiumCpmmAmmWithConfig(pool) => Some(cpmm_version(&pool.pool)),
+            AmmPool::Aldrin(AldrinPool::V1(_)) => Some(1),
+            AmmPool::Aldrin(AldrinPool::V2(_)) => Some(2),
+            AmmPool::Bonkswap(BonkswapPool::V1(_)) => Some(1),
+            AmmPool::Bonkswap(BonkswapPool::V2(_)) => Some(2),
+            AmmPool::FluxBeam(swap)
+            | AmmPool::OrcaTokenSwap(swap)
+            | AmmPool::SplTokenSwap(swap) => Some(swap.version),
+            AmmPool::MercurialStableSwap(swap) => Some(swap.version),
+            AmmPool::SanctumInfinity(pool) => Some(pool.state.version),
+            AmmPool::MeteoraDlmm(pair) => Some(pair.version),
+            _ => None,
+        }
+    }
+}
+
+fn cpmm_version(pool: &PoolState) -> u8 {
+    match PoolStateExtension::from_pool(pool).version() {
+        PoolStateVersion::V1 => 1,
+        PoolStateVersion::V2 => 2,
+        PoolStateVersion::V3 => 3,
+    }
+}
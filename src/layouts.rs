@@ -0,0 +1,16 @@
+//! Hand-written account layouts for programs that aren't built with Anchor.
+//!
+//! `declare_program!` relies on an Anchor IDL and an 8-byte discriminator, neither of
+//! which exist for these programs, so their accounts are described manually with borsh.
+
+pub mod mercurial;
+pub mod metaplex;
+pub mod openbook;
+pub mod phoenix;
+pub mod raydium_amm_v4;
+pub mod saber;
+pub mod sanctum;
+pub mod spl_stake_pool;
+pub mod spl_token;
+pub mod spl_token_swap;
+pub mod token_2022;
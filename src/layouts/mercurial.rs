@@ -0,0 +1,162 @@
+//! Mercurial multi-token stable-swap pools (`MERLuDFBMmsHnsBPZw2sDQZHvXFMwp8EdjudcU2HKky`).
+//!
+//! A pool holds between two and four tokens. The account always reserves room for four
+//! token accounts; only the first `token_accounts_length` entries are in use.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+use crate::math::stable_swap;
+
+/// Program id of Mercurial's stable-swap program.
+pub const ID: Pubkey = pubkey!("MERLuDFBMmsHnsBPZw2sDQZHvXFMwp8EdjudcU2HKky");
+
+/// Denominator of `SwapInfo::fee_numerator` and `SwapInfo::admin_fee_numerator`.
+pub const FEE_DENOMINATOR: u64 = 10_000_000_000;
+
+/// Maximum number of tokens a pool can hold.
+pub const MAX_TOKENS: usize = 4;
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// State account of a Mercurial stable-swap pool.
+pub struct SwapInfo {
+    pub version: u8,
+    pub is_initialized: bool,
+    pub nonce: u8,
+    /// Amplification coefficient (A) of the invariant.
+    pub amplification_coefficient: u64,
+    /// Trade fee, over `FEE_DENOMINATOR`.
+    pub fee_numerator: u64,
+    /// Share of the trade fee kept by the admin, over `FEE_DENOMINATOR`.
+    pub admin_fee_numerator: u64,
+    /// Number of tokens in the pool.
+    pub token_accounts_length: u32,
+    /// Common precision all token amounts are scaled to before applying the invariant.
+    pub precision_factor: u64,
+    /// Per-token multipliers that scale raw amounts up to `precision_factor`.
+    pub precision_multipliers: [u64; MAX_TOKENS],
+    /// Token accounts holding the pool's reserves.
+    #[serde_as(as = "[DisplayFromStr; MAX_TOKENS]")]
+    pub token_accounts: [Pubkey; MAX_TOKENS],
+    #[serde_as(as = "DisplayFromStr")]
+    pub pool_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub admin_token_mint: Pubkey,
+}
+
+impl SwapInfo {
+    /// Size of the parsed prefix of a `SwapInfo` account in bytes.
+    pub const LEN: usize = 263;
+
+    /// Returns the token accounts that are actually in use by the pool.
+    pub fn active_token_accounts(&self) -> &[Pubkey] {
+        let len = (self.token_accounts_length as usize).min(MAX_TOKENS);
+        &self.token_accounts[..len]
+    }
+
+    /// Returns the output of swapping `amount_in` of token `i` into token `j`, net of the
+    /// trade fee, given the `reserves` of the active token accounts in order.
+    ///
+    /// Reserves are scaled by `precision_multipliers` before entering the invariant and
+    /// the output is scaled back down. Returns `None` if `reserves` doesn't match the
+    /// pool's token count or an index is out of range.
+    pub fn quote(&self, reserves: &[u64], i: usize, j: usize, amount_in: u64) -> Option<u64> {
+        let tokens = self.active_token_accounts().len();
+        if reserves.len() != tokens || i >= tokens || j >= tokens {
+            return None;
+        }
+        let multiplier = |k: usize| u128::from(self.precision_multipliers[k]);
+        let balances = reserves
+            .iter()
+            .enumerate()
+            .map(|(k, reserve)| u128::from(*reserve).checked_mul(multiplier(k)))
+            .collect::<Option<Vec<u128>>>()?;
+        let dx = u128::from(amount_in).checked_mul(multiplier(i))?;
+        let dy = stable_swap::get_dy(self.amplification_coefficient, &balances, i, j, dx)?;
+        let fee = dy.checked_mul(u128::from(self.fee_numerator))? / u128::from(FEE_DENOMINATOR);
+        u64::try_from(dy.checked_sub(fee)?.checked_div(multiplier(j))?).ok()
+    }
+}
+
+impl AccountDeserialize for SwapInfo {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < Self::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pool of `multipliers.len()` tokens with a 0.04% trade fee.
+    fn swap_info(amplification_coefficient: u64, multipliers: &[u64]) -> SwapInfo {
+        let mut precision_multipliers = [0; MAX_TOKENS];
+        precision_multipliers[..multipliers.len()].copy_from_slice(multipliers);
+        SwapInfo {
+            version: 1,
+            is_initialized: true,
+            nonce: 255,
+            amplification_coefficient,
+            fee_numerator: 4_000_000,
+            admin_fee_numerator: 0,
+            token_accounts_length: multipliers.len() as u32,
+            precision_factor: 9,
+            precision_multipliers,
+            token_accounts: [Pubkey::new_unique(); MAX_TOKENS],
+            pool_mint: Pubkey::new_unique(),
+            admin_token_mint: Pubkey::new_unique(),
+        }
+    }
+
+    // Expected outputs come from Curve's invariant on the upscaled reserves, with the fee
+    // taken from the upscaled output before it's scaled back down, the way Meteora's
+    // stable curve scales amounts by its token multipliers.
+
+    #[test]
+    fn quote_scales_tokens_of_fewer_decimals_up() {
+        // 6-decimal token into a 9-decimal one.
+        let pool = swap_info(100, &[1_000, 1]);
+        let reserves = [1_000_000_000_000, 1_200_000_000_000_000];
+        assert_eq!(
+            pool.quote(&reserves, 0, 1, 1_000_000_000),
+            Some(1_001_421_256_809)
+        );
+
+        // And the other way around.
+        let pool = swap_info(100, &[1, 1_000]);
+        let reserves = [1_200_000_000_000_000, 1_000_000_000_000];
+        assert_eq!(
+            pool.quote(&reserves, 0, 1, 1_000_000_000_000),
+            Some(997_763_202)
+        );
+    }
+
+    #[test]
+    fn quote_handles_three_tokens() {
+        let pool = swap_info(2_000, &[1_000, 1_000, 1]);
+        let reserves = [5_000_000_000_000, 4_000_000_000_000, 6_000_000_000_000_000];
+        assert_eq!(
+            pool.quote(&reserves, 2, 0, 250_000_000_000_000),
+            Some(249_872_614_616)
+        );
+    }
+
+    #[test]
+    fn quote_rejects_reserves_of_other_pools() {
+        let pool = swap_info(100, &[1, 1]);
+        assert_eq!(pool.quote(&[1_000, 1_000, 1_000], 0, 1, 10), None);
+        assert_eq!(pool.quote(&[1_000, 1_000], 0, 2, 10), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! Metaplex Token Metadata (`metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s`).
+//!
+//! Only the leading part of the `Metadata` account is described, up to the mutability
+//! flag; the fields added by later versions of the program follow it and are ignored.
+//! Strings are borsh strings that older versions of the program padded with NUL bytes.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+/// Program id of Metaplex Token Metadata.
+pub const ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Seed prefix of metadata PDAs.
+pub const METADATA_SEED: &[u8] = b"metadata";
+
+/// `Key` of a `Metadata` account.
+pub const KEY_METADATA_V1: u8 = 4;
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// A creator of the token and their share of royalties.
+pub struct Creator {
+    #[serde_as(as = "DisplayFromStr")]
+    pub address: Pubkey,
+    pub verified: bool,
+    /// Share of royalties, in percent.
+    pub share: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Name, symbol and off-chain metadata of a token.
+pub struct Data {
+    pub name: String,
+    pub symbol: String,
+    /// URI of the off-chain JSON metadata.
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Metadata account of a mint.
+pub struct Metadata {
+    pub key: u8,
+    #[serde_as(as = "DisplayFromStr")]
+    pub update_authority: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub mint: Pubkey,
+    pub data: Data,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+}
+
+impl Metadata {
+    /// Derives the address of the metadata account of `mint`.
+    pub fn address(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[METADATA_SEED, ID.as_ref(), mint.as_ref()], &ID).0
+    }
+
+    /// Returns the token name without NUL padding.
+    pub fn name(&self) -> &str {
+        self.data.name.trim_end_matches('\0')
+    }
+
+    /// Returns the token symbol without NUL padding.
+    pub fn symbol(&self) -> &str {
+        self.data.symbol.trim_end_matches('\0')
+    }
+
+    /// Returns the URI of the off-chain metadata without NUL padding.
+    pub fn uri(&self) -> &str {
+        self.data.uri.trim_end_matches('\0')
+    }
+}
+
+impl AccountDeserialize for Metadata {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.first() != Some(&KEY_METADATA_V1) {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
@@ -0,0 +1,151 @@
+//! OpenBook v1 (`srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX`), the fork of Serum DEX v3.
+//!
+//! Accounts are packed structs wrapped in a 5 byte `serum` head and a 7 byte `padding`
+//! tail. The first field of every account is a bit set of `ACCOUNT_FLAG_*` values that
+//! identifies its kind. Only the market state and open orders are described here, which
+//! is what Raydium AMM v4 pools reference.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+/// Program id of OpenBook v1.
+pub const ID: Pubkey = pubkey!("srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX");
+
+/// Program id of Serum DEX v3, which shares the layouts of OpenBook v1.
+pub const SERUM_V3_ID: Pubkey = pubkey!("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin");
+
+/// Bytes preceding every account.
+pub const ACCOUNT_HEAD: &[u8; 5] = b"serum";
+
+/// Bytes following every account.
+pub const ACCOUNT_TAIL: &[u8; 7] = b"padding";
+
+pub const ACCOUNT_FLAG_INITIALIZED: u64 = 1 << 0;
+pub const ACCOUNT_FLAG_MARKET: u64 = 1 << 1;
+pub const ACCOUNT_FLAG_OPEN_ORDERS: u64 = 1 << 2;
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// State account of an OpenBook market.
+///
+/// "Coin" is the base token and "pc" (price currency) the quote token of the market.
+pub struct MarketState {
+    pub account_flags: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub own_address: Pubkey,
+    pub vault_signer_nonce: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub coin_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pc_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub coin_vault: Pubkey,
+    pub coin_deposits_total: u64,
+    pub coin_fees_accrued: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pc_vault: Pubkey,
+    pub pc_deposits_total: u64,
+    pub pc_fees_accrued: u64,
+    pub pc_dust_threshold: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub req_q: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub event_q: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub bids: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub asks: Pubkey,
+    /// Coin atoms per base lot.
+    pub coin_lot_size: u64,
+    /// Pc atoms per quote lot.
+    pub pc_lot_size: u64,
+    pub fee_rate_bps: u64,
+    pub referrer_rebates_accrued: u64,
+}
+
+impl MarketState {
+    /// Size of a `MarketState` account in bytes, including head and tail.
+    pub const LEN: usize = 388;
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Balances and resting orders of one owner on one market.
+pub struct OpenOrders {
+    pub account_flags: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub market: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub owner: Pubkey,
+    /// Coin atoms settled and withdrawable.
+    pub native_coin_free: u64,
+    /// Coin atoms held by the market for the owner, free or locked in asks.
+    pub native_coin_total: u64,
+    /// Pc atoms settled and withdrawable.
+    pub native_pc_free: u64,
+    /// Pc atoms held by the market for the owner, free or locked in bids.
+    pub native_pc_total: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub free_slot_bits: u128,
+    #[serde_as(as = "DisplayFromStr")]
+    pub is_bid_bits: u128,
+    #[serde_as(as = "[DisplayFromStr; 128]")]
+    pub orders: [u128; 128],
+    #[serde_as(as = "[_; 128]")]
+    pub client_order_ids: [u64; 128],
+    pub referrer_rebates_accrued: u64,
+}
+
+impl OpenOrders {
+    /// Size of an `OpenOrders` account in bytes, including head and tail.
+    pub const LEN: usize = 3228;
+}
+
+/// Strips the head and tail off `buf` and checks that the account flags include
+/// `flags`.
+fn account_body(buf: &[u8], len: usize, flags: u64) -> anchor_lang::Result<&[u8]> {
+    if buf.len() < len {
+        return Err(ErrorCode::AccountDidNotDeserialize.into());
+    }
+    let body = &buf[ACCOUNT_HEAD.len()..len - ACCOUNT_TAIL.len()];
+    let account_flags = u64::from_le_bytes(body[..8].try_into().unwrap());
+    if !buf.starts_with(ACCOUNT_HEAD)
+        || !buf[..len].ends_with(ACCOUNT_TAIL)
+        || account_flags & (flags | ACCOUNT_FLAG_INITIALIZED) != flags | ACCOUNT_FLAG_INITIALIZED
+    {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    Ok(body)
+}
+
+impl AccountDeserialize for MarketState {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        let mut body = account_body(buf, Self::LEN, ACCOUNT_FLAG_MARKET)?;
+        Self::deserialize(&mut body).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        let mut body = buf
+            .get(ACCOUNT_HEAD.len()..)
+            .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+        Self::deserialize(&mut body).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl AccountDeserialize for OpenOrders {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        let mut body = account_body(buf, Self::LEN, ACCOUNT_FLAG_OPEN_ORDERS)?;
+        Self::deserialize(&mut body).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        let mut body = buf
+            .get(ACCOUNT_HEAD.len()..)
+            .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+        Self::deserialize(&mut body).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
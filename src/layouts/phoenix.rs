@@ -0,0 +1,238 @@
+//! Phoenix order-book markets (`PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY`).
+//!
+//! A market account starts with a fixed-size `MarketHeader`, followed by the `FIFOMarket`
+//! whose scalar fields are parsed here. The bid and ask trees that come after them are
+//! sokoban red-black trees sized by `MarketHeader::market_size_params`; their resting
+//! orders are read by walking the trees from their roots. The trader seats that follow
+//! are left unparsed.
+
+use std::collections::BTreeMap;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+use crate::order_book::{Depth, PriceLevel};
+
+/// Program id of Phoenix v1.
+pub const ID: Pubkey = pubkey!("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY");
+
+/// `keccak(ID || "phoenix::program::accounts::MarketHeader")[..8]` as a little-endian `u64`.
+pub const MARKET_HEADER_DISCRIMINANT: u64 = 8167313896524341111;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Capacities of the order trees and trader seats stored after the header.
+pub struct MarketSizeParams {
+    pub bids_size: u64,
+    pub asks_size: u64,
+    pub num_seats: u64,
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Mint and vault of one side of a market.
+pub struct TokenParams {
+    pub decimals: u32,
+    pub vault_bump: u32,
+    #[serde_as(as = "DisplayFromStr")]
+    pub mint_key: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub vault_key: Pubkey,
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Fixed-size header at the start of every Phoenix market account.
+pub struct MarketHeader {
+    pub discriminant: u64,
+    pub status: u64,
+    pub market_size_params: MarketSizeParams,
+    pub base_params: TokenParams,
+    /// Base atoms per base lot.
+    pub base_lot_size: u64,
+    pub quote_params: TokenParams,
+    /// Quote atoms per quote lot.
+    pub quote_lot_size: u64,
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub authority: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub fee_recipient: Pubkey,
+    pub market_sequence_number: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub successor: Pubkey,
+    pub raw_base_units_per_base_unit: u32,
+    pub padding1: u32,
+    pub padding2: [u64; 32],
+}
+
+impl MarketHeader {
+    /// Size of the header in bytes.
+    pub const LEN: usize = 576;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Scalar fields of the `FIFOMarket` that follows the header.
+pub struct FifoMarketState {
+    pub padding: [u64; 32],
+    pub base_lots_per_base_unit: u64,
+    pub tick_size_in_quote_lots_per_base_unit: u64,
+    pub order_sequence_number: u64,
+    /// Taker fee charged on the quote lots of each trade, in basis points.
+    pub taker_fee_bps: u64,
+    /// Fees collected over the market's lifetime, in quote lots.
+    pub collected_quote_lot_fees: u64,
+    /// Fees accrued to the market that haven't been claimed yet, in quote lots.
+    pub unclaimed_quote_lot_fees: u64,
+}
+
+impl FifoMarketState {
+    /// Size of the scalar prefix in bytes.
+    pub const LEN: usize = 304;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+/// An order resting on one side of the book.
+pub struct RestingOrder {
+    pub price_in_ticks: u64,
+    /// Sequence number of the order; bits are inverted for bids.
+    pub order_sequence_number: u64,
+    pub trader_index: u64,
+    pub num_base_lots: u64,
+    /// Slot after which the order expires, or 0 if it doesn't.
+    pub last_valid_slot: u64,
+    /// Unix timestamp after which the order expires, or 0 if it doesn't.
+    pub last_valid_unix_timestamp_in_seconds: u64,
+}
+
+/// Size in bytes of the root and allocator header that precede the nodes of a tree.
+const TREE_HEADER_LEN: usize = 32;
+
+/// Size in bytes of one tree node: four `u32` registers, the order id and the order.
+const TREE_NODE_LEN: usize = 64;
+
+/// Returns the orders of the red-black tree of `capacity` nodes at the start of `data`,
+/// or `None` if the tree is truncated or its links are corrupt.
+fn read_order_tree(data: &[u8], capacity: usize) -> Option<Vec<RestingOrder>> {
+    let nodes = data.get(TREE_HEADER_LEN..TREE_HEADER_LEN + capacity * TREE_NODE_LEN)?;
+    let node = |index: u32| {
+        nodes
+            .get((index as usize - 1) * TREE_NODE_LEN..)?
+            .get(..TREE_NODE_LEN)
+    };
+    let u64_at = |node: &[u8], offset: usize| -> u64 {
+        u64::from_le_bytes(node[offset..offset + 8].try_into().unwrap())
+    };
+
+    // Node indices are 1-based; 0 is the sentinel used for missing children.
+    let mut orders = Vec::new();
+    let mut stack = Vec::new();
+    let mut current = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    while current != 0 || !stack.is_empty() {
+        if current != 0 {
+            if stack.len() > capacity {
+                return None;
+            }
+            stack.push(current);
+            current = u32::from_le_bytes(node(current)?[0..4].try_into().ok()?);
+            continue;
+        }
+        let index = stack.pop()?;
+        let node = node(index)?;
+        orders.push(RestingOrder {
+            price_in_ticks: u64_at(node, 16),
+            order_sequence_number: u64_at(node, 24),
+            trader_index: u64_at(node, 32),
+            num_base_lots: u64_at(node, 40),
+            last_valid_slot: u64_at(node, 48),
+            last_valid_unix_timestamp_in_seconds: u64_at(node, 56),
+        });
+        if orders.len() > capacity {
+            return None;
+        }
+        current = u32::from_le_bytes(node[4..8].try_into().ok()?);
+    }
+    Some(orders)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+/// A Phoenix market account with the orders resting on its book.
+pub struct Market {
+    pub header: MarketHeader,
+    pub state: FifoMarketState,
+    /// Resting bids, in tree order.
+    pub bids: Vec<RestingOrder>,
+    /// Resting asks, in tree order.
+    pub asks: Vec<RestingOrder>,
+}
+
+impl Market {
+    /// Aggregates the resting orders into at most `levels` price levels per side.
+    ///
+    /// Prices are in quote atoms per base unit and sizes in base atoms. Orders past their
+    /// `last_valid_slot` or timestamp are still counted, since expiry depends on a clock
+    /// the account doesn't carry.
+    pub fn depth(&self, levels: usize) -> Depth {
+        let side = |orders: &[RestingOrder]| {
+            let mut by_price = BTreeMap::<u64, u64>::new();
+            for order in orders {
+                let lots = by_price.entry(order.price_in_ticks).or_default();
+                *lots = lots.saturating_add(order.num_base_lots);
+            }
+            by_price
+        };
+        let level = |(ticks, lots): (&u64, &u64)| PriceLevel {
+            price: ticks.saturating_mul(self.header.tick_size_in_quote_atoms_per_base_unit),
+            size: lots.saturating_mul(self.header.base_lot_size),
+        };
+        Depth {
+            bids: side(&self.bids)
+                .iter()
+                .rev()
+                .take(levels)
+                .map(level)
+                .collect(),
+            asks: side(&self.asks).iter().take(levels).map(level).collect(),
+        }
+    }
+}
+
+impl AccountDeserialize for Market {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < MarketHeader::LEN + FifoMarketState::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        if buf[..8] != MARKET_HEADER_DISCRIMINANT.to_le_bytes() {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        let header = MarketHeader::deserialize(buf)
+            .map_err(|_| anchor_lang::error::Error::from(ErrorCode::AccountDidNotDeserialize))?;
+        let state = FifoMarketState::deserialize(buf)
+            .map_err(|_| anchor_lang::error::Error::from(ErrorCode::AccountDidNotDeserialize))?;
+        let bids_size = header.market_size_params.bids_size as usize;
+        let asks_size = header.market_size_params.asks_size as usize;
+        let bids_len = TREE_HEADER_LEN + bids_size * TREE_NODE_LEN;
+        let (bids, asks) = buf
+            .get(bids_len..)
+            .and_then(|asks_data| {
+                Some((
+                    read_order_tree(buf, bids_size)?,
+                    read_order_tree(asks_data, asks_size)?,
+                ))
+            })
+            .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+        Ok(Market {
+            header,
+            state,
+            bids,
+            asks,
+        })
+    }
+}
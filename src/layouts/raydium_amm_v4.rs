@@ -0,0 +1,127 @@
+//! Raydium legacy AMM v4 (`675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8`).
+//!
+//! The layout mirrors `AmmInfo` from `raydium-amm/program/src/state.rs`. The program
+//! stores the struct as-is, without a discriminator, in a 752 byte account.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+/// Program id of the Raydium legacy AMM v4.
+pub const ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Fee parameters of an AMM v4 pool, each expressed as a numerator/denominator pair.
+pub struct Fees {
+    pub min_separate_numerator: u64,
+    pub min_separate_denominator: u64,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub pnl_numerator: u64,
+    pub pnl_denominator: u64,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Running PnL and swap statistics kept by an AMM v4 pool.
+pub struct StateData {
+    /// Coin amount owed to the pool as PnL that hasn't been taken yet.
+    pub need_take_pnl_coin: u64,
+    /// Pc amount owed to the pool as PnL that hasn't been taken yet.
+    pub need_take_pnl_pc: u64,
+    pub total_pnl_pc: u64,
+    pub total_pnl_coin: u64,
+    /// Unix timestamp from which swaps are allowed.
+    pub pool_open_time: u64,
+    pub padding: [u64; 2],
+    pub orderbook_to_init_time: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub swap_coin_in_amount: u128,
+    #[serde_as(as = "DisplayFromStr")]
+    pub swap_pc_out_amount: u128,
+    pub swap_acc_pc_fee: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub swap_pc_in_amount: u128,
+    #[serde_as(as = "DisplayFromStr")]
+    pub swap_coin_out_amount: u128,
+    pub swap_acc_coin_fee: u64,
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// State account of a Raydium AMM v4 pool.
+///
+/// "Coin" is the base token and "pc" (price currency) the quote token of the pair.
+pub struct AmmInfo {
+    pub status: u64,
+    pub nonce: u64,
+    pub order_num: u64,
+    pub depth: u64,
+    pub coin_decimals: u64,
+    pub pc_decimals: u64,
+    pub state: u64,
+    pub reset_flag: u64,
+    pub min_size: u64,
+    pub vol_max_cut_ratio: u64,
+    pub amount_wave: u64,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub min_price_multiplier: u64,
+    pub max_price_multiplier: u64,
+    pub sys_decimal_value: u64,
+    pub fees: Fees,
+    pub state_data: StateData,
+    /// Token account holding the pool's coin reserves.
+    #[serde_as(as = "DisplayFromStr")]
+    pub coin_vault: Pubkey,
+    /// Token account holding the pool's pc reserves.
+    #[serde_as(as = "DisplayFromStr")]
+    pub pc_vault: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub coin_vault_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pc_vault_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub lp_mint: Pubkey,
+    /// OpenBook open orders account owned by the pool.
+    #[serde_as(as = "DisplayFromStr")]
+    pub open_orders: Pubkey,
+    /// OpenBook market the pool places orders on.
+    #[serde_as(as = "DisplayFromStr")]
+    pub market: Pubkey,
+    /// OpenBook program id the market belongs to.
+    #[serde_as(as = "DisplayFromStr")]
+    pub market_program: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub target_orders: Pubkey,
+    pub padding1: [u64; 8],
+    #[serde_as(as = "DisplayFromStr")]
+    pub amm_owner: Pubkey,
+    pub lp_amount: u64,
+    pub client_order_id: u64,
+    pub recent_epoch: u64,
+    pub padding2: u64,
+}
+
+impl AmmInfo {
+    /// Size of an `AmmInfo` account in bytes.
+    pub const LEN: usize = 752;
+}
+
+impl AccountDeserialize for AmmInfo {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < Self::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
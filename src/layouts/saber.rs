@@ -0,0 +1,127 @@
+//! Saber stable-swap pools (`SSwpkEEcbUqx4vtoEByFjSkhKdCT862DNVb52nZg1UZ`).
+//!
+//! Follows the packed `SwapInfo` layout from `stable-swap-client`. Note that the packed
+//! order differs from the in-memory struct: reserves, mints and admin fee accounts of the
+//! two tokens are stored grouped by kind rather than per token.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+use crate::math::stable_swap;
+
+/// Program id of Saber's stable-swap program.
+pub const ID: Pubkey = pubkey!("SSwpkEEcbUqx4vtoEByFjSkhKdCT862DNVb52nZg1UZ");
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Trade and withdraw fees of a swap, each as a numerator/denominator pair.
+pub struct Fees {
+    pub admin_trade_fee_numerator: u64,
+    pub admin_trade_fee_denominator: u64,
+    pub admin_withdraw_fee_numerator: u64,
+    pub admin_withdraw_fee_denominator: u64,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub withdraw_fee_numerator: u64,
+    pub withdraw_fee_denominator: u64,
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// State account of a Saber stable-swap pool.
+pub struct SwapInfo {
+    pub is_initialized: bool,
+    pub is_paused: bool,
+    pub nonce: u8,
+    /// Amplification coefficient at the start of the current ramp.
+    pub initial_amp_factor: u64,
+    /// Amplification coefficient the ramp is heading towards.
+    pub target_amp_factor: u64,
+    pub start_ramp_ts: i64,
+    pub stop_ramp_ts: i64,
+    pub future_admin_deadline: i64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub future_admin_key: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub admin_key: Pubkey,
+    /// Token account holding the pool's token A reserves.
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_a_reserves: Pubkey,
+    /// Token account holding the pool's token B reserves.
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_b_reserves: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pool_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_a_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_b_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_a_admin_fees: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_b_admin_fees: Pubkey,
+    pub fees: Fees,
+}
+
+impl SwapInfo {
+    /// Size of a `SwapInfo` account in bytes.
+    pub const LEN: usize = 395;
+
+    /// Returns the amplification coefficient in effect at `current_ts`, interpolating
+    /// linearly while a ramp is in progress.
+    ///
+    /// Returns `None` if the ramp timestamps are inconsistent with `current_ts`.
+    pub fn amp_factor(&self, current_ts: i64) -> Option<u64> {
+        if current_ts >= self.stop_ramp_ts {
+            return Some(self.target_amp_factor);
+        }
+        let time_range = u128::try_from(self.stop_ramp_ts.checked_sub(self.start_ramp_ts)?).ok()?;
+        let time_delta = u128::try_from(current_ts.checked_sub(self.start_ramp_ts)?).ok()?;
+        let amp_range = self.target_amp_factor.abs_diff(self.initial_amp_factor) as u128;
+        let amp_delta =
+            u64::try_from(amp_range.checked_mul(time_delta)?.checked_div(time_range)?).ok()?;
+        if self.target_amp_factor >= self.initial_amp_factor {
+            self.initial_amp_factor.checked_add(amp_delta)
+        } else {
+            self.initial_amp_factor.checked_sub(amp_delta)
+        }
+    }
+
+    /// Returns the output of swapping `amount_in` into a pool holding `reserve_in` of the
+    /// input token and `reserve_out` of the output token at `current_ts`, net of the trade
+    /// fee.
+    ///
+    /// The reserves are the balances of `token_a_reserves` and `token_b_reserves`, which
+    /// the account itself doesn't store.
+    pub fn quote(
+        &self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        current_ts: i64,
+    ) -> Option<u64> {
+        let amp_factor = self.amp_factor(current_ts)?;
+        let balances = [u128::from(reserve_in), u128::from(reserve_out)];
+        let dy = stable_swap::get_dy(amp_factor, &balances, 0, 1, u128::from(amount_in))?;
+        let fee = dy
+            .checked_mul(u128::from(self.fees.trade_fee_numerator))?
+            .checked_div(u128::from(self.fees.trade_fee_denominator))?;
+        u64::try_from(dy.checked_sub(fee)?).ok()
+    }
+}
+
+impl AccountDeserialize for SwapInfo {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < Self::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
@@ -0,0 +1,126 @@
+//! Sanctum Infinity multi-LST pool (`5ocnV1qiCgaQR8Jb8xWnVbApfaygJ8tNoZfgPwsgx9kx`).
+//!
+//! The pool is split over two accounts: the `PoolState` and the `LstStateList`, a PDA
+//! holding one `LstState` per liquid staking token the pool accepts. Neither account has
+//! a discriminator; the list is simply a packed array of entries.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+/// Program id of Sanctum's S controller, which runs the Infinity pool.
+pub const ID: Pubkey = pubkey!("5ocnV1qiCgaQR8Jb8xWnVbApfaygJ8tNoZfgPwsgx9kx");
+
+/// Seed of the `LstStateList` PDA.
+pub const LST_STATE_LIST_SEED: &[u8] = b"lst-state-list";
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Global state of the Infinity pool.
+pub struct PoolState {
+    /// SOL value of every LST held by the pool, in lamports.
+    pub total_sol_value: u64,
+    pub trading_protocol_fee_bps: u16,
+    pub lp_protocol_fee_bps: u16,
+    pub version: u8,
+    pub is_disabled: u8,
+    pub is_rebalancing: u8,
+    pub padding: [u8; 1],
+    #[serde_as(as = "DisplayFromStr")]
+    pub admin: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub rebalance_authority: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub protocol_fee_beneficiary: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pricing_program: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub lp_token_mint: Pubkey,
+}
+
+impl PoolState {
+    /// Size of a `PoolState` account in bytes.
+    pub const LEN: usize = 176;
+}
+
+impl AccountDeserialize for PoolState {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < Self::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// One liquid staking token accepted by the pool.
+pub struct LstState {
+    pub is_input_disabled: u8,
+    pub pool_reserves_bump: u8,
+    pub protocol_fee_accumulator_bump: u8,
+    pub padding: [u8; 5],
+    /// SOL value of the pool's reserves of this LST, in lamports.
+    pub sol_value: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub mint: Pubkey,
+    /// Program that converts amounts of this LST into SOL.
+    #[serde_as(as = "DisplayFromStr")]
+    pub sol_value_calculator: Pubkey,
+}
+
+impl LstState {
+    /// Size of one entry of the list in bytes.
+    pub const LEN: usize = 80;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+/// Contents of the `LstStateList` account.
+pub struct LstStateList(pub Vec<LstState>);
+
+impl LstStateList {
+    /// Derives the address of the list for the given S controller program.
+    pub fn address(program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[LST_STATE_LIST_SEED], program_id).0
+    }
+
+    /// Returns the mints of every LST in the pool.
+    pub fn mints(&self) -> Vec<Pubkey> {
+        self.0.iter().map(|lst| lst.mint).collect()
+    }
+}
+
+impl AccountDeserialize for LstStateList {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if !buf.len().is_multiple_of(LstState::LEN) {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        let mut entries = Vec::with_capacity(buf.len() / LstState::LEN);
+        for mut chunk in buf.chunks_exact(LstState::LEN) {
+            let entry = LstState::deserialize(&mut chunk).map_err(|_| {
+                anchor_lang::error::Error::from(ErrorCode::AccountDidNotDeserialize)
+            })?;
+            entries.push(entry);
+        }
+        *buf = &buf[entries.len() * LstState::LEN..];
+        Ok(LstStateList(entries))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+/// The Infinity pool state together with its list of LSTs.
+pub struct InfinityPool {
+    pub state: PoolState,
+    pub lst_states: LstStateList,
+}
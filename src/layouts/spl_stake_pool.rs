@@ -0,0 +1,126 @@
+//! Stake pools of the SPL stake-pool program.
+//!
+//! Most liquid staking tokens other than mSOL (jitoSOL, bSOL, ...) are pools of this
+//! program. The `StakePool` account is the borsh `StakePool` from
+//! `spl-stake-pool/src/state.rs`; it has no fixed size because of its optional keys, and
+//! it starts with an `AccountType` tag instead of a discriminator.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+/// Program id of the SPL stake-pool program.
+pub const ID: Pubkey = pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+/// Type tag at the start of every account of the program.
+pub enum AccountType {
+    Uninitialized,
+    StakePool,
+    ValidatorList,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+/// A fee expressed as a ratio. Unlike most programs, the denominator comes first.
+pub struct Fee {
+    pub denominator: u64,
+    pub numerator: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+/// A value that takes effect after one or two epoch boundaries.
+pub enum FutureEpoch<T> {
+    None,
+    One(T),
+    Two(T),
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Lockup every stake account of the pool must have.
+pub struct Lockup {
+    pub unix_timestamp: i64,
+    pub epoch: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub custodian: Pubkey,
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// State account of a stake pool.
+pub struct StakePool {
+    pub account_type: AccountType,
+    #[serde_as(as = "DisplayFromStr")]
+    pub manager: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub staker: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub stake_deposit_authority: Pubkey,
+    pub stake_withdraw_bump_seed: u8,
+    #[serde_as(as = "DisplayFromStr")]
+    pub validator_list: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub reserve_stake: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pool_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub manager_fee_account: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_program_id: Pubkey,
+    /// Total stake under management, as of `last_update_epoch`.
+    pub total_lamports: u64,
+    pub pool_token_supply: u64,
+    pub last_update_epoch: u64,
+    pub lockup: Lockup,
+    /// Fee taken as a proportion of rewards each epoch.
+    pub epoch_fee: Fee,
+    pub next_epoch_fee: FutureEpoch<Fee>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub preferred_deposit_validator_vote_address: Option<Pubkey>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub preferred_withdraw_validator_vote_address: Option<Pubkey>,
+    pub stake_deposit_fee: Fee,
+    pub stake_withdrawal_fee: Fee,
+    pub next_stake_withdrawal_fee: FutureEpoch<Fee>,
+    /// Percentage (0-100) of stake deposit fees paid out to referrers.
+    pub stake_referral_fee: u8,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub sol_deposit_authority: Option<Pubkey>,
+    pub sol_deposit_fee: Fee,
+    /// Percentage (0-100) of SOL deposit fees paid out to referrers.
+    pub sol_referral_fee: u8,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub sol_withdraw_authority: Option<Pubkey>,
+    pub sol_withdrawal_fee: Fee,
+    pub next_sol_withdrawal_fee: FutureEpoch<Fee>,
+    pub last_epoch_pool_token_supply: u64,
+    pub last_epoch_total_lamports: u64,
+}
+
+impl StakePool {
+    /// Returns the lamports backing `pool_tokens` pool tokens, before withdrawal fees.
+    pub fn lamports_for_pool_tokens(&self, pool_tokens: u64) -> Option<u64> {
+        if self.pool_token_supply == 0 {
+            return Some(0);
+        }
+        let lamports = u128::from(pool_tokens).checked_mul(u128::from(self.total_lamports))?
+            / u128::from(self.pool_token_supply);
+        u64::try_from(lamports).ok()
+    }
+}
+
+impl AccountDeserialize for StakePool {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.first() != Some(&(AccountType::StakePool as u8)) {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
@@ -0,0 +1,102 @@
+//! Mint and token accounts of the SPL Token program.
+//!
+//! These are not Anchor accounts: the states are the packed `Mint` and `Account` from
+//! `spl-token/src/state.rs`, where each optional field is a `COption` stored as a four
+//! byte tag followed by the value. Token-2022 accounts share the same base layouts and
+//! append their extensions after them.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+/// Program id of the SPL Token program.
+pub const ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// State of a token mint.
+pub struct Mint {
+    pub mint_authority_tag: u32,
+    #[serde_as(as = "DisplayFromStr")]
+    pub mint_authority_key: Pubkey,
+    /// Total supply of the token, in base units.
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority_tag: u32,
+    #[serde_as(as = "DisplayFromStr")]
+    pub freeze_authority_key: Pubkey,
+}
+
+impl Mint {
+    /// Size of the base mint layout in bytes.
+    pub const LEN: usize = 82;
+
+    /// Returns the mint authority, if the mint still has one.
+    pub fn mint_authority(&self) -> Option<Pubkey> {
+        (self.mint_authority_tag != 0).then_some(self.mint_authority_key)
+    }
+
+    /// Returns the freeze authority, if the mint has one.
+    pub fn freeze_authority(&self) -> Option<Pubkey> {
+        (self.freeze_authority_tag != 0).then_some(self.freeze_authority_key)
+    }
+}
+
+impl AccountDeserialize for Mint {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < Self::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// State of a token account, such as a pool vault.
+pub struct TokenAccount {
+    #[serde_as(as = "DisplayFromStr")]
+    pub mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub owner: Pubkey,
+    /// Balance of the account, in base units of `mint`.
+    pub amount: u64,
+    pub delegate_tag: u32,
+    #[serde_as(as = "DisplayFromStr")]
+    pub delegate_key: Pubkey,
+    /// 0 = uninitialized, 1 = initialized, 2 = frozen.
+    pub state: u8,
+    pub is_native_tag: u32,
+    /// Rent-exempt reserve of a wrapped SOL account, which isn't part of `amount`.
+    pub is_native_reserve: u64,
+    pub delegated_amount: u64,
+    pub close_authority_tag: u32,
+    #[serde_as(as = "DisplayFromStr")]
+    pub close_authority_key: Pubkey,
+}
+
+impl TokenAccount {
+    /// Size of the base token account layout in bytes.
+    pub const LEN: usize = 165;
+}
+
+impl AccountDeserialize for TokenAccount {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < Self::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
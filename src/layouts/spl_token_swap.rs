@@ -0,0 +1,107 @@
+//! Pools of the SPL token-swap program and its forks.
+//!
+//! The account starts with a one byte `SwapVersion` tag followed by the packed `SwapV1`
+//! state from `spl-token-swap/src/state.rs`. Orca's legacy constant-product pools run
+//! deployments of the same program, and FluxBeam runs a fork that keeps the layout but
+//! accepts Token-2022 mints.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+/// Program id of the canonical SPL token-swap program.
+pub const ID: Pubkey = pubkey!("SwaPpA9LAaLfeLi3a68M4DjnLqgtticKg6CnyNwgAC8");
+
+/// Program id of Orca's first token-swap deployment.
+pub const ORCA_V1_ID: Pubkey = pubkey!("DjVE6JNiYqPL2QXyCUUh8rNjHrbz9hXHNYt99MQ59qw1");
+
+/// Program id of Orca's second token-swap deployment, used by most legacy Orca pools.
+pub const ORCA_V2_ID: Pubkey = pubkey!("9W959DqEETiGZocYWCQPaJ6sBmUzgfxXfqGeTEdp3aQP");
+
+/// Program id of FluxBeam's token-swap fork.
+pub const FLUXBEAM_ID: Pubkey = pubkey!("FLUXubRmkEi2q6K3Y9kBPg9248ggaZVsoSFhtJHSrm1X");
+
+/// Program id of the Token-2022 program, as stored in `TokenSwap::token_program_id`.
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// The only `SwapVersion` tag the program has ever written.
+pub const SWAP_V1: u8 = 1;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Fees of a swap, each expressed as a numerator/denominator pair.
+pub struct Fees {
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub owner_trade_fee_numerator: u64,
+    pub owner_trade_fee_denominator: u64,
+    pub owner_withdraw_fee_numerator: u64,
+    pub owner_withdraw_fee_denominator: u64,
+    pub host_fee_numerator: u64,
+    pub host_fee_denominator: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Curve used to price swaps.
+pub struct SwapCurve {
+    /// 0 = constant product, 1 = constant price, 2 = stable, 3 = offset.
+    pub curve_type: u8,
+    /// Packed parameters of the curve; their meaning depends on `curve_type`.
+    pub calculator: [u8; 32],
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// State account of a token-swap pool.
+pub struct TokenSwap {
+    pub version: u8,
+    pub is_initialized: bool,
+    pub bump_seed: u8,
+    /// Token program of the pool's mints, either SPL Token or Token-2022.
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_program_id: Pubkey,
+    /// Token account holding the pool's token A reserves.
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_a: Pubkey,
+    /// Token account holding the pool's token B reserves.
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_b: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pool_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_a_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_b_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pool_fee_account: Pubkey,
+    pub fees: Fees,
+    pub swap_curve: SwapCurve,
+}
+
+impl TokenSwap {
+    /// Size of a token-swap account in bytes, including the version tag.
+    pub const LEN: usize = 324;
+
+    /// Returns `true` if the pool's mints belong to the Token-2022 program.
+    pub fn is_token_2022(&self) -> bool {
+        self.token_program_id == TOKEN_2022_PROGRAM_ID
+    }
+}
+
+impl AccountDeserialize for TokenSwap {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < Self::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        if buf[0] != SWAP_V1 {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
@@ -0,0 +1,159 @@
+//! Mints of the Token-2022 program and the extensions that change how their tokens move.
+//!
+//! A Token-2022 mint starts with the SPL Token `Mint`, zero-padded to the size of a token
+//! account so both kinds can be told apart by the account type byte that follows. The
+//! extensions come after it as a TLV list of `u16` type, `u16` length and value. Only the
+//! extensions that affect quotes and balances are parsed; the others are skipped.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Pubkey, borsh, pubkey,
+};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+use crate::layouts::spl_token::{Mint, TokenAccount};
+
+/// Program id of the Token-2022 program.
+pub const ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Account type byte of a mint with extensions.
+pub const ACCOUNT_TYPE_MINT: u8 = 1;
+
+/// TLV type of `TransferFeeConfig`.
+pub const TRANSFER_FEE_CONFIG: u16 = 1;
+
+/// TLV type of `InterestBearingConfig`.
+pub const INTEREST_BEARING_CONFIG: u16 = 10;
+
+/// TLV type of `TransferHook`.
+pub const TRANSFER_HOOK: u16 = 14;
+
+/// Denominator of `TransferFee::transfer_fee_basis_points`.
+pub const BASIS_POINTS_DENOMINATOR: u128 = 10_000;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// A transfer fee rate, in effect from `epoch` on.
+pub struct TransferFee {
+    pub epoch: u64,
+    /// Cap on the fee of a single transfer, in atoms.
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    /// Returns the fee withheld from a transfer of `amount` atoms, rounded up like the
+    /// program does.
+    pub fn fee(&self, amount: u64) -> u64 {
+        let bps = u128::from(self.transfer_fee_basis_points);
+        let fee = (u128::from(amount) * bps).div_ceil(BASIS_POINTS_DENOMINATOR);
+        u64::try_from(fee).unwrap_or(u64::MAX).min(self.maximum_fee)
+    }
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Fee withheld from every transfer of the token.
+pub struct TransferFeeConfig {
+    /// Authority that can change the fee, or the default key if there is none.
+    #[serde_as(as = "DisplayFromStr")]
+    pub transfer_fee_config_authority: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub withdraw_withheld_authority: Pubkey,
+    /// Fees withheld in the mint itself, in atoms.
+    pub withheld_amount: u64,
+    pub older_transfer_fee: TransferFee,
+    /// Fee that replaces `older_transfer_fee` once its epoch is reached.
+    pub newer_transfer_fee: TransferFee,
+}
+
+impl TransferFeeConfig {
+    /// Returns the fee rate in effect during `epoch`.
+    pub fn epoch_fee(&self, epoch: u64) -> &TransferFee {
+        if epoch >= self.newer_transfer_fee.epoch {
+            &self.newer_transfer_fee
+        } else {
+            &self.older_transfer_fee
+        }
+    }
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Interest accrued continuously on every balance; only the displayed amount changes.
+pub struct InterestBearingConfig {
+    #[serde_as(as = "DisplayFromStr")]
+    pub rate_authority: Pubkey,
+    pub initialization_timestamp: i64,
+    pub pre_update_average_rate: i16,
+    pub last_update_timestamp: i64,
+    /// Current rate, in basis points per year.
+    pub current_rate: i16,
+}
+
+#[serde_as]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Serialize)]
+/// Program invoked on every transfer of the token, which may reject it.
+pub struct TransferHook {
+    #[serde_as(as = "DisplayFromStr")]
+    pub authority: Pubkey,
+    /// Hook program, or the default key if the hook is disabled.
+    #[serde_as(as = "DisplayFromStr")]
+    pub program_id: Pubkey,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize)]
+/// The extensions of a mint that this crate parses.
+pub struct MintExtensions {
+    pub transfer_fee_config: Option<TransferFeeConfig>,
+    pub interest_bearing_config: Option<InterestBearingConfig>,
+    pub transfer_hook: Option<TransferHook>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+/// A mint of either token program, with its extensions if it has any.
+pub struct MintWithExtensions {
+    pub mint: Mint,
+    pub extensions: MintExtensions,
+}
+
+impl AccountDeserialize for MintWithExtensions {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.len() < Mint::LEN {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        if buf.len() > Mint::LEN && buf.get(TokenAccount::LEN) != Some(&ACCOUNT_TYPE_MINT) {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        let data = *buf;
+        let mint = Mint::deserialize(buf)
+            .map_err(|_| anchor_lang::error::Error::from(ErrorCode::AccountDidNotDeserialize))?;
+        let mut extensions = MintExtensions::default();
+        let mut tlv = data.get(TokenAccount::LEN + 1..).unwrap_or_default();
+        while let [t0, t1, l0, l1, rest @ ..] = tlv {
+            let length = u16::from_le_bytes([*l0, *l1]) as usize;
+            let Some(mut value) = rest.get(..length) else {
+                return Err(ErrorCode::AccountDidNotDeserialize.into());
+            };
+            let parsed = match u16::from_le_bytes([*t0, *t1]) {
+                TRANSFER_FEE_CONFIG => TransferFeeConfig::deserialize(&mut value)
+                    .map(|config| extensions.transfer_fee_config = Some(config)),
+                INTEREST_BEARING_CONFIG => InterestBearingConfig::deserialize(&mut value)
+                    .map(|config| extensions.interest_bearing_config = Some(config)),
+                TRANSFER_HOOK => TransferHook::deserialize(&mut value)
+                    .map(|hook| extensions.transfer_hook = Some(hook)),
+                _ => Ok(()),
+            };
+            parsed.map_err(|_| {
+                anchor_lang::error::Error::from(ErrorCode::AccountDidNotDeserialize)
+            })?;
+            tlv = &rest[length..];
+        }
+        *buf = &data[data.len()..];
+        Ok(MintWithExtensions { mint, extensions })
+    }
+}
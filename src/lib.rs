@@ -1,2 +1,51 @@
+pub mod account_bytes;
+pub mod batch;
+pub mod blocking;
+pub mod cache;
+pub mod catalog;
+pub mod coalesce;
+pub mod consistency;
+pub mod detect;
+pub mod diff;
+pub mod discovery;
+pub mod dump;
+pub mod dynamic;
+#[cfg(feature = "evm")]
+pub mod evm;
+pub mod failover;
+pub mod fetcher;
+pub mod fields;
+pub mod hot_fields;
+pub mod http;
+pub mod json;
+pub mod layout_version;
+pub mod layouts;
+pub mod math;
+pub mod metadata;
+pub mod meteora_dlmm;
+pub mod mints;
+pub mod mock;
+pub mod normalized;
+pub mod orca_whirlpool;
+pub mod order_book;
 pub mod parsing;
-pub use parsing::get_info_struct;
+pub mod pool_access;
+pub mod pool_name;
+pub mod prelude;
+pub mod program_ids;
+pub mod pump_amm;
+pub mod rate_limit;
+pub mod raydium_amm_v4;
+pub mod raydium_clmm;
+pub mod raydium_cpmm;
+pub mod reserves;
+pub mod retry;
+#[cfg(feature = "schemars")]
+pub mod schema;
+pub mod serialization;
+pub mod summary;
+pub mod universal;
+pub use parsing::{
+    ParseMode, ParsedPool, Result, get_info_struct, get_info_struct_on, parse_from_bytes,
+    parse_from_bytes_with_mode,
+};
@@ -0,0 +1,1108 @@
+//! Price math shared by the concentrated liquidity pools.
+//!
+//! Raydium CLMM (`sqrt_price_x64`) and Orca Whirlpools (`sqrt_price`) both store the
+//! square root of the price of token 0 (A) in token 1 (B) as an unsigned Q64.64 number,
+//! in atoms rather than whole tokens. These helpers convert it to and from a decimal
+//! price in whole tokens.
+//!
+//! Both programs also share the Uniswap V3 tick grid, where tick `i` is the price
+//! `1.0001^i`, but compute sqrt prices at ticks with fixed-point routines of their own,
+//! whose constants differ in the last bits. Both routines are reproduced here bit for bit,
+//! selected with `ClmmProgram`, so ticks derived off-chain match the on-chain ones of the
+//! program they're for. Liquidity is converted to token amounts with the formulas of
+//! each program, on 256-bit intermediates, rounding down, and so are the fees a position
+//! has accrued from the pools' fee growth accumulators.
+
+pub mod stable_swap;
+
+use std::collections::BTreeMap;
+
+/// `2^64` as an `f64`, the scale of a Q64.64 number.
+pub(crate) const Q64: f64 = 18_446_744_073_709_551_616.0;
+
+/// Lowest tick of the Raydium CLMM and Whirlpool tick grids.
+pub const MIN_TICK: i32 = -443636;
+
+/// Highest tick of the Raydium CLMM and Whirlpool tick grids.
+pub const MAX_TICK: i32 = 443636;
+
+/// Q64.64 sqrt price at `MIN_TICK`, the same for both programs.
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+
+/// Q64.64 sqrt price at `MAX_TICK` in Raydium CLMM.
+pub const RAYDIUM_CLMM_MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+
+/// Q64.64 sqrt price at `MAX_TICK` in Orca Whirlpools.
+pub const WHIRLPOOL_MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579055;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The concentrated liquidity program whose fixed-point math to reproduce.
+pub enum ClmmProgram {
+    RaydiumClmm,
+    Whirlpool,
+}
+
+impl ClmmProgram {
+    /// Returns the Q64.64 sqrt price at `MAX_TICK` in the program.
+    pub fn max_sqrt_price_x64(self) -> u128 {
+        match self {
+            ClmmProgram::RaydiumClmm => RAYDIUM_CLMM_MAX_SQRT_PRICE_X64,
+            ClmmProgram::Whirlpool => WHIRLPOOL_MAX_SQRT_PRICE_X64,
+        }
+    }
+
+    /// Returns whether `sqrt_price_x64` is a sqrt price the program converts to a tick.
+    ///
+    /// Raydium CLMM excludes the sqrt price at `MAX_TICK`, which swaps never reach;
+    /// Whirlpools include it.
+    fn converts_to_tick(self, sqrt_price_x64: u128) -> bool {
+        let max = self.max_sqrt_price_x64();
+        match self {
+            ClmmProgram::RaydiumClmm => (MIN_SQRT_PRICE_X64..max).contains(&sqrt_price_x64),
+            ClmmProgram::Whirlpool => (MIN_SQRT_PRICE_X64..=max).contains(&sqrt_price_x64),
+        }
+    }
+}
+
+/// Converts a Q64.64 square root price into the price of one whole token 0 in token 1.
+///
+/// The square root is scaled down before squaring, so the result keeps the full `f64`
+/// precision across the whole `u128` range instead of overflowing or truncating.
+pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128, decimals_0: u8, decimals_1: u8) -> f64 {
+    let sqrt_price = sqrt_price_x64 as f64 / Q64;
+    sqrt_price * sqrt_price * decimals_factor(decimals_0, decimals_1)
+}
+
+/// Converts the price of one whole token 0 in token 1 into a Q64.64 square root price.
+///
+/// The result is rounded down. Returns `None` if the price is negative, not finite, or out
+/// of the `u128` range.
+pub fn price_to_sqrt_price_x64(price: f64, decimals_0: u8, decimals_1: u8) -> Option<u128> {
+    if !price.is_finite() || price < 0.0 {
+        return None;
+    }
+    let sqrt_price_x64 = (price / decimals_factor(decimals_0, decimals_1)).sqrt() * Q64;
+    if sqrt_price_x64 >= u128::MAX as f64 {
+        return None;
+    }
+    Some(sqrt_price_x64 as u128)
+}
+
+/// Returns `10^(decimals_0 - decimals_1)`, the factor from an atom price to a whole token
+/// price.
+fn decimals_factor(decimals_0: u8, decimals_1: u8) -> f64 {
+    10f64.powi(i32::from(decimals_0) - i32::from(decimals_1))
+}
+
+/// Returns the Q64.64 sqrt price at `tick`, exactly as `program` computes it, or `None`
+/// if the tick is outside `MIN_TICK..=MAX_TICK`.
+pub fn tick_to_sqrt_price_x64(program: ClmmProgram, tick: i32) -> Option<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+    Some(match program {
+        ClmmProgram::RaydiumClmm => raydium_sqrt_price(tick),
+        ClmmProgram::Whirlpool if tick >= 0 => whirlpool_sqrt_price_positive_tick(tick),
+        ClmmProgram::Whirlpool => whirlpool_sqrt_price_negative_tick(tick),
+    })
+}
+
+/// Returns the highest tick whose sqrt price in `program` is at most `sqrt_price_x64`, or
+/// `None` if the sqrt price is below `MIN_SQRT_PRICE_X64` or above the program's maximum.
+pub fn sqrt_price_x64_to_tick(program: ClmmProgram, sqrt_price_x64: u128) -> Option<i32> {
+    if !program.converts_to_tick(sqrt_price_x64) {
+        return None;
+    }
+    let fraction_bits = match program {
+        ClmmProgram::RaydiumClmm => RAYDIUM_LOG2_FRACTION_BITS,
+        ClmmProgram::Whirlpool => WHIRLPOOL_LOG2_FRACTION_BITS,
+    };
+    // log2 of the sqrt price, as a Q32.32: the integer part is the position of the most
+    // significant bit, and the program's bits of the fraction come from repeated squaring.
+    let msb = 127 - sqrt_price_x64.leading_zeros();
+    let log2_integer_x32 = (i128::from(msb) - 64) << 32;
+    let mut r = if msb >= 64 {
+        sqrt_price_x64 >> (msb - 63)
+    } else {
+        sqrt_price_x64 << (63 - msb)
+    };
+    let mut bit: i128 = 1 << 63;
+    let mut log2_fraction_x64: i128 = 0;
+    for _ in 0..fraction_bits {
+        r *= r;
+        let is_more_than_two = r >> 127;
+        r >>= 63 + is_more_than_two;
+        log2_fraction_x64 += bit * is_more_than_two as i128;
+        bit >>= 1;
+    }
+    let log2_x32 = log2_integer_x32 + (log2_fraction_x64 >> 32);
+
+    // Change to base sqrt(1.0001), then settle the two candidates the error margins leave.
+    let log_x64 = log2_x32 * LOG_SQRT_10001_2_X32;
+    let tick_low = ((log_x64 - LOG_ERR_MARGIN_LOWER_X64) >> 64) as i32;
+    let tick_high = ((log_x64 + LOG_ERR_MARGIN_UPPER_X64) >> 64) as i32;
+    if tick_low == tick_high {
+        return Some(tick_low);
+    }
+    match tick_to_sqrt_price_x64(program, tick_high) {
+        Some(high) if high <= sqrt_price_x64 => Some(tick_high),
+        _ => Some(tick_low),
+    }
+}
+
+/// Returns the price of one whole token 0 in token 1 at `tick` in `program`, or `None` if
+/// the tick is out of range.
+pub fn tick_to_price(
+    program: ClmmProgram,
+    tick: i32,
+    decimals_0: u8,
+    decimals_1: u8,
+) -> Option<f64> {
+    let sqrt_price_x64 = tick_to_sqrt_price_x64(program, tick)?;
+    Some(sqrt_price_x64_to_price(
+        sqrt_price_x64,
+        decimals_0,
+        decimals_1,
+    ))
+}
+
+/// Returns the highest tick of `program` at or below the price of one whole token 0 in
+/// token 1, or `None` if the price is outside the tick grid.
+pub fn price_to_tick(
+    program: ClmmProgram,
+    price: f64,
+    decimals_0: u8,
+    decimals_1: u8,
+) -> Option<i32> {
+    let sqrt_price_x64 = price_to_sqrt_price_x64(price, decimals_0, decimals_1)?;
+    sqrt_price_x64_to_tick(program, sqrt_price_x64)
+}
+
+/// Returns the token 0 and token 1 amounts, in atoms, that `liquidity` spread over
+/// `tick_lower..tick_upper` of `program` amounts to at the sqrt price `sqrt_price_x64`.
+///
+/// Below the range the liquidity is entirely token 0, above it entirely token 1. The
+/// amounts are rounded down, as the programs round what withdrawing the liquidity pays
+/// out. Returns `None` if the range is empty or out of bounds, or an amount doesn't fit in
+/// a `u64`.
+pub fn amounts_for_liquidity(
+    program: ClmmProgram,
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Option<(u64, u64)> {
+    let (lower, upper) = sqrt_price_range(program, tick_lower, tick_upper)?;
+    let (amount_0, amount_1) = if sqrt_price_x64 <= lower {
+        (amount_0_delta(program, lower, upper, liquidity, false)?, 0)
+    } else if sqrt_price_x64 >= upper {
+        (0, amount_1_delta(lower, upper, liquidity, false)?)
+    } else {
+        (
+            amount_0_delta(program, sqrt_price_x64, upper, liquidity, false)?,
+            amount_1_delta(lower, sqrt_price_x64, liquidity, false)?,
+        )
+    };
+    Some((amount_0.try_into().ok()?, amount_1.try_into().ok()?))
+}
+
+/// Returns the largest liquidity over `tick_lower..tick_upper` of `program` that
+/// `amount_0` of token 0 and `amount_1` of token 1 can provide at the sqrt price
+/// `sqrt_price_x64`.
+///
+/// In range, the side that runs out first bounds the liquidity and the other amount is
+/// only partly used. Returns `None` if the range is empty or out of bounds.
+pub fn liquidity_for_amounts(
+    program: ClmmProgram,
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    amount_0: u64,
+    amount_1: u64,
+) -> Option<u128> {
+    let (lower, upper) = sqrt_price_range(program, tick_lower, tick_upper)?;
+    let liquidity_0 = |from: u128| liquidity_0_delta(program, from, upper, amount_0);
+    let liquidity_1 = |to: u128| mul_div(u128::from(amount_1), 1 << 64, to - lower);
+    if sqrt_price_x64 <= lower {
+        liquidity_0(lower)
+    } else if sqrt_price_x64 >= upper {
+        liquidity_1(upper)
+    } else {
+        Some(liquidity_0(sqrt_price_x64)?.min(liquidity_1(sqrt_price_x64)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The liquidity of a CLMM pool over one bucket of ticks, and the tokens it amounts to.
+pub struct LiquidityBucket {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    /// Liquidity active while the price is in the bucket.
+    pub liquidity: u128,
+    /// Token 0 held by the bucket at the current price, in atoms.
+    pub amount_0: u64,
+    /// Token 1 held by the bucket at the current price, in atoms.
+    pub amount_1: u64,
+}
+
+/// Splits `tick_lower..tick_upper` into buckets of `tick_spacing` ticks and returns the
+/// liquidity of each, reconstructed from the pool's in-range `liquidity` at
+/// `tick_current` and the `liquidity_net` of its initialized `ticks`, with the amounts
+/// computed as `program` does.
+///
+/// `ticks` only needs to cover the requested range, in any order; ticks missing from it,
+/// for instance because their tick array wasn't fetched, are treated as uninitialized.
+/// The bounds are rounded outwards to multiples of `tick_spacing` and clamped to the tick
+/// grid. Returns `None` if the range is empty, if a bucket's amounts overflow, or if
+/// crossing a tick takes the liquidity below zero or beyond `u128::MAX`, which only
+/// happens when `ticks` doesn't agree with `liquidity`.
+#[allow(clippy::too_many_arguments)]
+pub fn liquidity_profile(
+    program: ClmmProgram,
+    sqrt_price_x64: u128,
+    tick_current: i32,
+    liquidity: u128,
+    tick_spacing: u16,
+    ticks: &[(i32, i128)],
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Option<Vec<LiquidityBucket>> {
+    let spacing = i32::from(tick_spacing);
+    if spacing == 0 || tick_lower >= tick_upper {
+        return None;
+    }
+    let min = -(-MIN_TICK).div_euclid(spacing) * spacing;
+    let max = MAX_TICK.div_euclid(spacing) * spacing;
+    let lower = (tick_lower.div_euclid(spacing) * spacing).max(min);
+    let upper = (-(-tick_upper).div_euclid(spacing) * spacing).min(max);
+    let net: BTreeMap<i32, i128> = ticks.iter().copied().collect();
+    let net_at = |tick: i32| net.get(&tick).copied().unwrap_or(0);
+
+    // Walk outwards from the bucket holding the current tick, crossing initialized ticks
+    // the way a swap would.
+    let current = tick_current.div_euclid(spacing) * spacing;
+    let mut above = Vec::new();
+    let mut active = liquidity;
+    let mut start = current;
+    while start < upper {
+        if start >= lower {
+            above.push((start, active));
+        }
+        start += spacing;
+        if start < upper {
+            active = active.checked_add_signed(net_at(start))?;
+        }
+    }
+    let mut below = Vec::new();
+    let mut active = liquidity;
+    let mut start = current;
+    while start > lower {
+        active = active.checked_add_signed(net_at(start).checked_neg()?)?;
+        start -= spacing;
+        if start < upper {
+            below.push((start, active));
+        }
+    }
+
+    below
+        .into_iter()
+        .rev()
+        .chain(above)
+        .map(|(start, liquidity)| {
+            let (amount_0, amount_1) =
+                amounts_for_liquidity(program, sqrt_price_x64, start, start + spacing, liquidity)?;
+            Some(LiquidityBucket {
+                tick_lower: start,
+                tick_upper: start + spacing,
+                liquidity,
+                amount_0,
+                amount_1,
+            })
+        })
+        .collect()
+}
+
+/// Returns the fee growth per unit of liquidity accumulated inside
+/// `tick_lower..tick_upper`, as a Q64.64.
+///
+/// `fee_growth_outside_lower` and `fee_growth_outside_upper` are the fee growth outside
+/// accumulators of the two boundary ticks, which are zero for uninitialized ticks. The
+/// accumulators are allowed to wrap around, so the differences wrap as well, like on-chain.
+pub fn fee_growth_inside(
+    tick_current: i32,
+    tick_lower: i32,
+    tick_upper: i32,
+    fee_growth_global: u128,
+    fee_growth_outside_lower: u128,
+    fee_growth_outside_upper: u128,
+) -> u128 {
+    let below = if tick_current >= tick_lower {
+        fee_growth_outside_lower
+    } else {
+        fee_growth_global.wrapping_sub(fee_growth_outside_lower)
+    };
+    let above = if tick_current < tick_upper {
+        fee_growth_outside_upper
+    } else {
+        fee_growth_global.wrapping_sub(fee_growth_outside_upper)
+    };
+    fee_growth_global.wrapping_sub(below).wrapping_sub(above)
+}
+
+/// Returns the fees owed to a position of `liquidity`, in atoms: the `fees_owed` it has
+/// already been credited plus what accrued since its checkpoint `fee_growth_inside_last`.
+///
+/// Returns `None` if the total doesn't fit in a `u64`.
+pub fn fees_owed(
+    liquidity: u128,
+    fee_growth_inside: u128,
+    fee_growth_inside_last: u128,
+    fees_owed: u64,
+) -> Option<u64> {
+    let growth = fee_growth_inside.wrapping_sub(fee_growth_inside_last);
+    let (high, low) = full_mul(growth, liquidity);
+    if high >> 64 != 0 {
+        return None;
+    }
+    let accrued = u64::try_from((high << 64) | (low >> 64)).ok()?;
+    fees_owed.checked_add(accrued)
+}
+
+/// Returns the sqrt prices bounding `tick_lower..tick_upper` in `program`, if the range
+/// is valid.
+fn sqrt_price_range(
+    program: ClmmProgram,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Option<(u128, u128)> {
+    if tick_lower >= tick_upper {
+        return None;
+    }
+    Some((
+        tick_to_sqrt_price_x64(program, tick_lower)?,
+        tick_to_sqrt_price_x64(program, tick_upper)?,
+    ))
+}
+
+/// Token 0 held by `liquidity` between the sqrt prices `lower < upper`:
+/// `L * (upper - lower) / (lower * upper)`, with sqrt prices as Q64.64, rounded up or
+/// down as `program` rounds it.
+///
+/// Both programs shift `L * (upper - lower)` left by 64 bits, on 256 bits. Raydium CLMM
+/// then divides by `upper`, requiring the quotient to fit in a `u128`, and divides that by
+/// `lower`, rounding both divisions; Whirlpools divide by `lower * upper` at once. A
+/// shifted product past 256 bits makes both programs fail, and gives `None` here.
+fn amount_0_delta(
+    program: ClmmProgram,
+    lower: u128,
+    upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<u128> {
+    let (high, low) = full_mul(liquidity, upper - lower);
+    if high >> 64 != 0 {
+        return None;
+    }
+    let (high, low) = ((high << 64) | (low >> 64), low << 64);
+    match program {
+        ClmmProgram::RaydiumClmm => {
+            let (0, quotient, remainder) = div_rem(high, low, upper)? else {
+                return None;
+            };
+            let quotient = quotient.checked_add(u128::from(round_up && remainder != 0))?;
+            let remainder = quotient.checked_rem(lower)?;
+            Some(quotient / lower + u128::from(round_up && remainder != 0))
+        }
+        ClmmProgram::Whirlpool => {
+            // Dividing by `lower` and then by `upper` gives the quotient of the division by
+            // `lower * upper`, and leaves a remainder exactly when that division does.
+            let (high, low, remainder_lower) = div_rem(high, low, lower)?;
+            let (0, quotient, remainder_upper) = div_rem(high, low, upper)? else {
+                return None;
+            };
+            let inexact = remainder_lower != 0 || remainder_upper != 0;
+            quotient.checked_add(u128::from(round_up && inexact))
+        }
+    }
+}
+
+/// Token 1 held by `liquidity` between the sqrt prices `lower < upper`:
+/// `L * (upper - lower)`, with sqrt prices as Q64.64, which both programs compute and
+/// round alike.
+fn amount_1_delta(lower: u128, upper: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+    let (high, low) = full_mul(liquidity, upper - lower);
+    if high >> 64 != 0 {
+        return None;
+    }
+    let quotient = (high << 64) | (low >> 64);
+    quotient.checked_add(u128::from(round_up && low as u64 != 0))
+}
+
+/// Liquidity `amount_0` of token 0 provides between the sqrt prices `lower < upper`:
+/// `amount_0 * lower * upper / (upper - lower)`, rounded down as `program` computes it.
+///
+/// Raydium CLMM rounds `lower * upper` down to a Q64.64 before multiplying it by the
+/// amount; Whirlpools multiply on 256 bits and round once.
+fn liquidity_0_delta(
+    program: ClmmProgram,
+    lower: u128,
+    upper: u128,
+    amount_0: u64,
+) -> Option<u128> {
+    match program {
+        ClmmProgram::RaydiumClmm => {
+            let scaled = mul_div(lower, upper, 1 << 64)?;
+            mul_div(u128::from(amount_0), scaled, upper - lower)
+        }
+        ClmmProgram::Whirlpool => {
+            let (high, low) = full_mul(lower, upper);
+            let (carry, low) = full_mul(low, u128::from(amount_0));
+            let (overflow, high) = full_mul(high, u128::from(amount_0));
+            if overflow != 0 {
+                return None;
+            }
+            let high = high.checked_add(carry)?;
+            let (quotient_high, quotient_low, _) = div_rem(high, low, upper - lower)?;
+            if quotient_high >> 64 != 0 {
+                return None;
+            }
+            Some((quotient_high << 64) | (quotient_low >> 64))
+        }
+    }
+}
+
+/// Returns the 256-bit product of `a` and `b` as its high and low halves.
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const LOW: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & LOW);
+    let (b_high, b_low) = (b >> 64, b & LOW);
+    let (middle, middle_carry) = (a_low * b_high).overflowing_add(a_high * b_low);
+    let (low, low_carry) = (a_low * b_low).overflowing_add(middle << 64);
+    let high =
+        a_high * b_high + (middle >> 64) + (u128::from(middle_carry) << 64) + u128::from(low_carry);
+    (high, low)
+}
+
+/// Returns `a * b / denominator` rounded down, computing the product on 256 bits, or
+/// `None` if `denominator` is zero or the quotient doesn't fit in a `u128`.
+pub(crate) fn mul_div(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    mul_add_div(a, b, 0, denominator)
+}
+
+/// Returns `(a * b + c) / denominator` rounded down, computing the numerator on 256 bits,
+/// or `None` if `denominator` is zero or the quotient doesn't fit in a `u128`.
+pub(crate) fn mul_add_div(a: u128, b: u128, c: u128, denominator: u128) -> Option<u128> {
+    let (high, low) = full_mul(a, b);
+    let (low, carry) = low.overflowing_add(c);
+    let high = high.checked_add(u128::from(carry))?;
+    match div_rem(high, low, denominator)? {
+        (0, quotient, _) => Some(quotient),
+        _ => None,
+    }
+}
+
+/// Divides the 256-bit number with the halves `high` and `low` by `denominator`, returning
+/// the high and low halves of the quotient and the remainder, or `None` if `denominator`
+/// is zero.
+fn div_rem(high: u128, low: u128, denominator: u128) -> Option<(u128, u128, u128)> {
+    if denominator == 0 {
+        return None;
+    }
+    let (quotient_high, mut remainder) = (high / denominator, high % denominator);
+    if remainder == 0 {
+        return Some((quotient_high, low / denominator, low % denominator));
+    }
+    // Long division of the low half, with what's left of the high half as the initial
+    // remainder. The remainder stays below the denominator, so only the bit shifted out
+    // can overflow.
+    let mut quotient_low = 0;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient_low <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient_low |= 1;
+        }
+    }
+    Some((quotient_high, quotient_low, remainder))
+}
+
+/// Bits of the fractional part of the log2 Raydium CLMM computes in
+/// `sqrt_price_x64_to_tick`.
+const RAYDIUM_LOG2_FRACTION_BITS: u32 = 16;
+
+/// Bits of the fractional part of the log2 Whirlpools compute in `sqrt_price_x64_to_tick`.
+const WHIRLPOOL_LOG2_FRACTION_BITS: u32 = 14;
+
+/// `1 / log2(sqrt(1.0001))` as a Q32.32.
+const LOG_SQRT_10001_2_X32: i128 = 59543866431248;
+
+/// 0.01 as a Q64.64, the error margin below the estimated tick.
+const LOG_ERR_MARGIN_LOWER_X64: i128 = 184467440737095516;
+
+/// `2^-14 / log2(sqrt(1.0001)) + 0.01` as a Q64.64, the error margin above it.
+const LOG_ERR_MARGIN_UPPER_X64: i128 = 15793534762490258745;
+
+/// Returns `(a * b) >> 96`, computing the product on 256 bits.
+fn mul_shift_96(a: u128, b: u128) -> u128 {
+    const LOW: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & LOW);
+    let (b_high, b_low) = (b >> 64, b & LOW);
+    let (middle, middle_carry) = (a_low * b_high).overflowing_add(a_high * b_low);
+    let (low, low_carry) = (a_low * b_low).overflowing_add(middle << 64);
+    let high =
+        a_high * b_high + (middle >> 64) + (u128::from(middle_carry) << 64) + u128::from(low_carry);
+    (high << 32) | (low >> 96)
+}
+
+/// Raydium CLMM sqrt price at a tick, from the product of `sqrt(1.0001)^-(2^i)` as
+/// Q64.64 numbers over the bits of the tick's magnitude, inverted for positive ticks.
+fn raydium_sqrt_price(tick: i32) -> u128 {
+    const FACTORS: [u128; 18] = [
+        18444899583751176192,
+        18443055278223355904,
+        18439367220385607680,
+        18431993317065453568,
+        18417254355718170624,
+        18387811781193609216,
+        18329067761203558400,
+        18212142134806163456,
+        17980523815641700352,
+        17526086738831433728,
+        16651378430235570176,
+        15030750278694412288,
+        12247334978884435968,
+        8131365268886854656,
+        3584323654725218816,
+        696457651848324352,
+        26294789957507116,
+        37481735321082,
+    ];
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio: u128 = if abs_tick & 1 != 0 {
+        18445821805675395072
+    } else {
+        18446744073709551616
+    };
+    for (i, factor) in FACTORS.iter().enumerate() {
+        if abs_tick & (2 << i) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+    if tick > 0 { u128::MAX / ratio } else { ratio }
+}
+
+/// Whirlpool sqrt price at a non-negative tick, from the product of `sqrt(1.0001)^(2^i)`
+/// as Q32.96 numbers over the bits of the tick.
+fn whirlpool_sqrt_price_positive_tick(tick: i32) -> u128 {
+    const FACTORS: [u128; 18] = [
+        79236085330515764027303304731,
+        79244008939048815603706035061,
+        79259858533276714757314932305,
+        79291567232598584799939703904,
+        79355022692464371645785046466,
+        79482085999252804386437311141,
+        79736823300114093921829183326,
+        80248749790819932309965073892,
+        81282483887344747381513967011,
+        83390072131320151908154831281,
+        87770609709833776024991924138,
+        97234110755111693312479820773,
+        119332217159966728226237229890,
+        179736315981702064433883588727,
+        407748233172238350107850275304,
+        2098478828474011932436660412517,
+        55581415166113811149459800483533,
+        38992368544603139932233054999993551,
+    ];
+    let mut ratio: u128 = if tick & 1 != 0 {
+        79232123823359799118286999567
+    } else {
+        79228162514264337593543950336
+    };
+    for (i, factor) in FACTORS.iter().enumerate() {
+        if tick & (2 << i) != 0 {
+            ratio = mul_shift_96(ratio, *factor);
+        }
+    }
+    ratio >> 32
+}
+
+/// Whirlpool sqrt price at a negative tick, from the product of `sqrt(1.0001)^-(2^i)` as
+/// Q64.64 numbers over the bits of the tick's magnitude.
+fn whirlpool_sqrt_price_negative_tick(tick: i32) -> u128 {
+    const FACTORS: [u128; 18] = [
+        18444899583751176498,
+        18443055278223354162,
+        18439367220385604838,
+        18431993317065449817,
+        18417254355718160513,
+        18387811781193591352,
+        18329067761203520168,
+        18212142134806087854,
+        17980523815641551639,
+        17526086738831147013,
+        16651378430235024244,
+        15030750278693429944,
+        12247334978882834399,
+        8131365268884726200,
+        3584323654723342297,
+        696457651847595233,
+        26294789957452057,
+        37481735321082,
+    ];
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio: u128 = if abs_tick & 1 != 0 {
+        18445821805675392311
+    } else {
+        18446744073709551616
+    };
+    for (i, factor) in FACTORS.iter().enumerate() {
+        if abs_tick & (2 << i) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+    ratio
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sqrt prices at ticks as computed by Raydium CLMM (`tick_math::get_sqrt_price_at_tick`
+    /// in `raydium-amm-v3` 0.1.0) and Orca Whirlpools (`tick_index_to_sqrt_price` in
+    /// `orca_whirlpools_core` 2.1.1).
+    const SQRT_PRICES: [(i32, u128, u128); 9] = [
+        (MIN_TICK, 4295048016, 4295048016),
+        (-50000, 1514390236238525528, 1514390236237315695),
+        (-1000, 17547129613991882732, 17547129613991598777),
+        (-1, 18445821805675395072, 18445821805675392311),
+        (0, 18446744073709551616, 18446744073709551616),
+        (1, 18447666387855957090, 18447666387855959850),
+        (1000, 19392480388906522465, 19392480388906836277),
+        (50000, 224699260981858280811, 224699260982037790824),
+        (
+            MAX_TICK,
+            79226673521066979257578248091,
+            79226673515401279992447579055,
+        ),
+    ];
+
+    #[test]
+    fn tick_to_sqrt_price_matches_each_program() {
+        for (tick, raydium, whirlpool) in SQRT_PRICES {
+            assert_eq!(
+                tick_to_sqrt_price_x64(ClmmProgram::RaydiumClmm, tick),
+                Some(raydium),
+                "Raydium CLMM tick {tick}"
+            );
+            assert_eq!(
+                tick_to_sqrt_price_x64(ClmmProgram::Whirlpool, tick),
+                Some(whirlpool),
+                "Whirlpool tick {tick}"
+            );
+        }
+    }
+
+    #[test]
+    fn tick_to_sqrt_price_rejects_ticks_off_the_grid() {
+        for program in [ClmmProgram::RaydiumClmm, ClmmProgram::Whirlpool] {
+            assert_eq!(tick_to_sqrt_price_x64(program, MIN_TICK - 1), None);
+            assert_eq!(tick_to_sqrt_price_x64(program, MAX_TICK + 1), None);
+        }
+    }
+
+    #[test]
+    fn sqrt_price_to_tick_inverts_each_program() {
+        for (tick, raydium, whirlpool) in SQRT_PRICES {
+            if tick != MAX_TICK {
+                let program = ClmmProgram::RaydiumClmm;
+                assert_eq!(sqrt_price_x64_to_tick(program, raydium), Some(tick));
+                assert_eq!(sqrt_price_x64_to_tick(program, raydium + 1), Some(tick));
+            }
+            let program = ClmmProgram::Whirlpool;
+            assert_eq!(sqrt_price_x64_to_tick(program, whirlpool), Some(tick));
+            if tick != MIN_TICK {
+                assert_eq!(
+                    sqrt_price_x64_to_tick(program, whirlpool - 1),
+                    Some(tick - 1)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sqrt_price_to_tick_uses_each_programs_bounds() {
+        let raydium = ClmmProgram::RaydiumClmm;
+        assert_eq!(
+            sqrt_price_x64_to_tick(raydium, RAYDIUM_CLMM_MAX_SQRT_PRICE_X64 - 1),
+            Some(MAX_TICK - 1)
+        );
+        assert_eq!(
+            sqrt_price_x64_to_tick(raydium, RAYDIUM_CLMM_MAX_SQRT_PRICE_X64),
+            None
+        );
+        assert_eq!(
+            sqrt_price_x64_to_tick(raydium, WHIRLPOOL_MAX_SQRT_PRICE_X64),
+            Some(MAX_TICK - 1)
+        );
+
+        let whirlpool = ClmmProgram::Whirlpool;
+        assert_eq!(
+            sqrt_price_x64_to_tick(whirlpool, WHIRLPOOL_MAX_SQRT_PRICE_X64),
+            Some(MAX_TICK)
+        );
+        assert_eq!(
+            sqrt_price_x64_to_tick(whirlpool, WHIRLPOOL_MAX_SQRT_PRICE_X64 + 1),
+            None
+        );
+
+        for program in [raydium, whirlpool] {
+            assert_eq!(
+                sqrt_price_x64_to_tick(program, MIN_SQRT_PRICE_X64 - 1),
+                None
+            );
+        }
+    }
+
+    /// Token 0 amounts between two sqrt prices, rounded down and up, as computed by both
+    /// programs (`liquidity_math::get_delta_amount_0_unsigned` in `raydium-amm-v3` 0.1.0,
+    /// `try_get_amount_delta_a` in `orca_whirlpools_core` 2.1.1).
+    const AMOUNT_0_DELTAS: [(u128, u128, u128, u128, u128); 5] = [
+        (
+            MIN_SQRT_PRICE_X64,
+            1 << 64,
+            1_000_000,
+            4294886576517030,
+            4294886576517031,
+        ),
+        (
+            18445821805675395072,
+            18447666387855957090,
+            123_456_789_012_345_678,
+            12345061663544,
+            12345061663545,
+        ),
+        (
+            1514390236238525528,
+            224699260981858280811,
+            987_654_321_987,
+            11949507257932,
+            11949507257933,
+        ),
+        (
+            281725312256481227532,
+            292327441803581238286,
+            441205323880522272225,
+            1047751082964920481,
+            1047751082964920482,
+        ),
+        (
+            154857714385655320525,
+            160262724833927470111,
+            82868464994680080931,
+            332920230860779118,
+            332920230860779119,
+        ),
+    ];
+
+    #[test]
+    fn amount_0_delta_matches_each_program() {
+        for (lower, upper, liquidity, down, up) in AMOUNT_0_DELTAS {
+            for program in [ClmmProgram::RaydiumClmm, ClmmProgram::Whirlpool] {
+                assert_eq!(
+                    amount_0_delta(program, lower, upper, liquidity, false),
+                    Some(down),
+                    "{program:?} rounding down"
+                );
+                assert_eq!(
+                    amount_0_delta(program, lower, upper, liquidity, true),
+                    Some(up),
+                    "{program:?} rounding up"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn amount_0_delta_overflows_where_each_program_does() {
+        // Raydium CLMM's quotient by `upper` doesn't fit in a `u128`, while the single
+        // division of Whirlpools still gives an amount.
+        let (lower, upper, liquidity) = (
+            WHIRLPOOL_MAX_SQRT_PRICE_X64,
+            RAYDIUM_CLMM_MAX_SQRT_PRICE_X64,
+            u128::MAX,
+        );
+        assert_eq!(
+            amount_0_delta(ClmmProgram::RaydiumClmm, lower, upper, liquidity, false),
+            None
+        );
+        assert_eq!(
+            amount_0_delta(ClmmProgram::Whirlpool, lower, upper, liquidity, false),
+            Some(5665912230855848070)
+        );
+        assert_eq!(
+            amount_0_delta(ClmmProgram::Whirlpool, lower, upper, liquidity, true),
+            Some(5665912230855848071)
+        );
+    }
+
+    #[test]
+    fn amount_1_delta_rounds_like_both_programs() {
+        let deltas = [
+            (MIN_SQRT_PRICE_X64, 1 << 64, 1_000_000, 999999, 1000000),
+            (
+                18445821805675395072,
+                18447666387855957090,
+                123_456_789_012_345_678,
+                12345061663544,
+                12345061663545,
+            ),
+            (
+                1514390236238525528,
+                224699260981858280811,
+                987_654_321_987,
+                11949507257932,
+                11949507257933,
+            ),
+        ];
+        for (lower, upper, liquidity, down, up) in deltas {
+            assert_eq!(amount_1_delta(lower, upper, liquidity, false), Some(down));
+            assert_eq!(amount_1_delta(lower, upper, liquidity, true), Some(up));
+        }
+        assert_eq!(
+            amount_1_delta(
+                MIN_SQRT_PRICE_X64,
+                RAYDIUM_CLMM_MAX_SQRT_PRICE_X64,
+                u128::MAX,
+                false
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn liquidity_0_delta_matches_each_program() {
+        // `liquidity_math::get_liquidity_from_amount_0` in `raydium-amm-v3` 0.1.0 and
+        // `try_get_liquidity_from_a` in `orca_whirlpools_core` 2.1.1.
+        let deltas = [
+            (
+                18445821805675395072,
+                18447666387855957090,
+                123_456_789,
+                1234629616855,
+                1234629616855,
+            ),
+            (
+                1007777059616588149237,
+                1007777060457552474150,
+                4705193143269049554,
+                308041405938760947961650532504,
+                308041405938760947961654916859,
+            ),
+            (
+                115706368573366191638,
+                115706368605526291097,
+                15417145005318368487,
+                347921589426564294233567835013,
+                347921589426564294233677174787,
+            ),
+        ];
+        for (lower, upper, amount_0, raydium, whirlpool) in deltas {
+            assert_eq!(
+                liquidity_0_delta(ClmmProgram::RaydiumClmm, lower, upper, amount_0),
+                Some(raydium)
+            );
+            assert_eq!(
+                liquidity_0_delta(ClmmProgram::Whirlpool, lower, upper, amount_0),
+                Some(whirlpool)
+            );
+        }
+    }
+
+    /// Liquidity units large enough for every bucket to hold a few atoms.
+    const UNIT: u128 = 1_000_000_000;
+
+    /// Liquidity of 700 units over the whole range, 300 over `-20..20` and 500 over
+    /// `10..40`, with the price in `0..10`.
+    const TICKS: [(i32, i128); 4] = [
+        (-20, 300 * UNIT as i128),
+        (20, -300 * UNIT as i128),
+        (10, 500 * UNIT as i128),
+        (40, -500 * UNIT as i128),
+    ];
+
+    fn profile(
+        ticks: &[(i32, i128)],
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Option<Vec<LiquidityBucket>> {
+        let sqrt_price_x64 = tick_to_sqrt_price_x64(ClmmProgram::Whirlpool, 5)?;
+        liquidity_profile(
+            ClmmProgram::Whirlpool,
+            sqrt_price_x64,
+            5,
+            1_000 * UNIT,
+            10,
+            ticks,
+            tick_lower,
+            tick_upper,
+        )
+    }
+
+    #[test]
+    fn liquidity_profile_crosses_ticks_both_ways() {
+        let buckets = profile(&TICKS, -30, 50).unwrap();
+        let liquidity: Vec<_> = buckets
+            .iter()
+            .map(|bucket| {
+                (
+                    bucket.tick_lower,
+                    bucket.tick_upper,
+                    bucket.liquidity / UNIT,
+                )
+            })
+            .collect();
+        assert_eq!(
+            liquidity,
+            [
+                (-30, -20, 700),
+                (-20, -10, 1_000),
+                (-10, 0, 1_000),
+                (0, 10, 1_000),
+                (10, 20, 1_500),
+                (20, 30, 1_200),
+                (30, 40, 1_200),
+                (40, 50, 700),
+            ]
+        );
+        for bucket in &buckets {
+            if bucket.tick_upper <= 0 {
+                assert!(bucket.amount_0 == 0 && bucket.amount_1 > 0);
+            } else if bucket.tick_lower >= 10 {
+                assert!(bucket.amount_0 > 0 && bucket.amount_1 == 0);
+            } else {
+                assert!(bucket.amount_0 > 0 && bucket.amount_1 > 0);
+            }
+        }
+
+        // Ranges away from the price, rounded outwards, still cross every tick on the way.
+        let above = profile(&TICKS, 25, 45).unwrap();
+        assert_eq!(
+            above
+                .iter()
+                .map(|bucket| bucket.liquidity / UNIT)
+                .collect::<Vec<_>>(),
+            [1_200, 1_200, 700]
+        );
+        let below = profile(&TICKS, -35, -15).unwrap();
+        assert_eq!(
+            below
+                .iter()
+                .map(|bucket| bucket.liquidity / UNIT)
+                .collect::<Vec<_>>(),
+            [700, 700, 1_000]
+        );
+    }
+
+    #[test]
+    fn liquidity_profile_rejects_ticks_that_disagree_with_the_liquidity() {
+        // Crossing upwards at 10 would take more liquidity out than is active.
+        assert_eq!(profile(&[(10, -2_000 * UNIT as i128)], 0, 20), None);
+        // Crossing downwards at 0 would too.
+        assert_eq!(profile(&[(0, 2_000 * UNIT as i128)], -10, 10), None);
+        assert_eq!(profile(&[(0, i128::MIN)], -10, 10), None);
+        // Ticks beyond the range aren't crossed.
+        assert!(
+            profile(
+                &[(10, -2_000 * UNIT as i128), (0, 2_000 * UNIT as i128)],
+                0,
+                10
+            )
+            .is_some()
+        );
+    }
+
+    /// A fee growth accumulated `growth << 64` before `fee_growth_global` wrapped around.
+    const fn before_wrap(growth: u128) -> u128 {
+        u128::MAX - (growth << 64) + 1
+    }
+
+    /// Fee growth inside `10..20` as computed by `collect_fees_quote` in
+    /// `orca_whirlpools_core` 2.1.1 and `get_fee_growth_inside` in `raydium-amm-v3` 0.1.0:
+    /// the current tick, `fee_growth_global`, the fee growth outside of the lower and upper
+    /// ticks, then the fee growth inside.
+    const FEE_GROWTHS_INSIDE: [(i32, u128, u128, u128, u128); 10] = [
+        // In range, including at the lower tick.
+        (15, 1_000 << 64, 300 << 64, 200 << 64, 500 << 64),
+        (10, 1_000 << 64, 300 << 64, 200 << 64, 500 << 64),
+        // Below and above the range, including at the upper tick.
+        (5, 1_000 << 64, 300 << 64, 200 << 64, 100 << 64),
+        (25, 1_000 << 64, 200 << 64, 300 << 64, 100 << 64),
+        (20, 1_000 << 64, 200 << 64, 300 << 64, 100 << 64),
+        // `fee_growth_global` wrapped after the lower tick was crossed.
+        (15, 50 << 64, before_wrap(100), 20 << 64, 130 << 64),
+        (10, 50 << 64, before_wrap(100), 20 << 64, 130 << 64),
+        // Wrapped below and above the range.
+        (5, 50 << 64, 40 << 64, before_wrap(30), 70 << 64),
+        (25, 50 << 64, before_wrap(60), 10 << 64, 70 << 64),
+        (
+            15,
+            123_456_789_012_345_678_901,
+            9_876_543_210_987_654,
+            1_234_567_890_123_456,
+            123_445_677_901_244_567_791,
+        ),
+    ];
+
+    /// Fees owed as computed by `collect_fees_quote` in `orca_whirlpools_core` 2.1.1: the
+    /// position's liquidity, the fee growth inside, its checkpoint and the fees already
+    /// credited, then the fees owed.
+    const FEES_OWED: [(u128, u128, u128, u64, u64); 6] = [
+        (5_000_000, 500 << 64, 100 << 64, 7, 2_000_000_007),
+        (5_000_000, 100 << 64, 50 << 64, 7, 250_000_007),
+        (3_000_000, 130 << 64, 0, 0, 390_000_000),
+        // The checkpoint was taken before the fee growth inside wrapped.
+        (3_000_000, 130 << 64, before_wrap(10), 1, 420_000_001),
+        (3_000_000, 70 << 64, 0, 0, 210_000_000),
+        // Fractional growth, where the fees are rounded down.
+        (
+            987_654_321,
+            123_445_677_901_244_567_791,
+            0,
+            0,
+            6_609_386_279,
+        ),
+    ];
+
+    #[test]
+    fn fee_growth_inside_matches_both_programs() {
+        for (tick, global, lower, upper, inside) in FEE_GROWTHS_INSIDE {
+            assert_eq!(
+                fee_growth_inside(tick, 10, 20, global, lower, upper),
+                inside,
+                "tick {tick}, global {global}"
+            );
+        }
+    }
+
+    #[test]
+    fn fees_owed_matches_whirlpools() {
+        for (liquidity, inside, checkpoint, owed, fees) in FEES_OWED {
+            assert_eq!(
+                fees_owed(liquidity, inside, checkpoint, owed),
+                Some(fees),
+                "growth {inside} since {checkpoint}"
+            );
+        }
+    }
+
+    #[test]
+    fn fees_owed_past_u64_are_none() {
+        let liquidity = u128::from(u64::MAX);
+        assert_eq!(fees_owed(liquidity, 500 << 64, 100 << 64, 0), None);
+        assert_eq!(fees_owed(1, 1 << 64, 0, u64::MAX), None);
+        assert_eq!(fees_owed(1, 1 << 64, 0, u64::MAX - 1), Some(u64::MAX));
+    }
+}
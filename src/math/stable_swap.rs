@@ -0,0 +1,219 @@
+//! Curve-style stable-swap invariant, as used by Saber and Mercurial.
+//!
+//! For `n` balances `x_i` with sum `S`, the invariant `D` satisfies
+//! `A * n^n * S + D = A * n^n * D + D^(n+1) / (n^n * prod(x_i))`. Like Curve's reference
+//! contracts and Saber's on-chain program, the amplification coefficient enters as
+//! `Ann = A * n`, and both `D` and the output balance of a swap are found with Newton's
+//! method on 256-bit intermediates. Balances must all be expressed in the same precision.
+
+use crate::math::{mul_add_div, mul_div};
+
+/// Maximum number of Newton iterations before settling for the current estimate.
+const MAX_ITERATIONS: usize = 256;
+
+/// Returns the invariant `D` of `balances` under the amplification coefficient
+/// `amp_factor`, or `None` if there are fewer than two balances, a balance is zero while
+/// others aren't, or the computation overflows.
+pub fn compute_d(amp_factor: u64, balances: &[u128]) -> Option<u128> {
+    let n = u128::try_from(balances.len()).ok()?;
+    if n < 2 {
+        return None;
+    }
+    let sum = balances
+        .iter()
+        .try_fold(0u128, |sum, balance| sum.checked_add(*balance))?;
+    if sum == 0 {
+        return Some(0);
+    }
+    let ann = u128::from(amp_factor).checked_mul(n)?;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // D^(n+1) / (n^n * prod(x_i)), one balance at a time.
+        let mut d_prod = d;
+        for balance in balances {
+            d_prod = mul_div(d_prod, d, balance.checked_mul(n)?)?;
+        }
+        let previous = d;
+        // D = (Ann * S + D_P * n) * D / ((Ann - 1) * D + (n + 1) * D_P)
+        let numerator = ann.checked_mul(sum)?.checked_add(d_prod.checked_mul(n)?)?;
+        let denominator = ann
+            .checked_sub(1)?
+            .checked_mul(d)?
+            .checked_add(d_prod.checked_mul(n + 1)?)?;
+        d = mul_div(numerator, d, denominator)?;
+        if d.abs_diff(previous) <= 1 {
+            break;
+        }
+    }
+    Some(d)
+}
+
+/// Returns the balance of token `j` that keeps the invariant at `d` given the other
+/// `balances`, whose `j`th entry is ignored.
+pub fn compute_y(amp_factor: u64, balances: &[u128], j: usize, d: u128) -> Option<u128> {
+    let n = u128::try_from(balances.len()).ok()?;
+    if n < 2 || j >= balances.len() {
+        return None;
+    }
+    let ann = u128::from(amp_factor).checked_mul(n)?;
+    // c = D^(n+1) / (n^n * prod(x_k, k != j) * Ann * n), b = S' + D / Ann
+    let mut c = d;
+    let mut sum = 0u128;
+    for (k, balance) in balances.iter().enumerate() {
+        if k != j {
+            sum = sum.checked_add(*balance)?;
+            c = mul_div(c, d, balance.checked_mul(n)?)?;
+        }
+    }
+    c = mul_div(c, d, ann.checked_mul(n)?)?;
+    let b = sum.checked_add(d.checked_div(ann)?)?;
+
+    // Solve y^2 + (b - D) * y = c.
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        let denominator = y.checked_mul(2)?.checked_add(b)?.checked_sub(d)?;
+        y = mul_add_div(y, y, c, denominator)?;
+        if y.abs_diff(previous) <= 1 {
+            break;
+        }
+    }
+    Some(y)
+}
+
+/// Returns the amount of token `j` received for `dx` of token `i`, before fees.
+///
+/// One unit is withheld from the output to cover rounding, as Curve and Saber do.
+pub fn get_dy(amp_factor: u64, balances: &[u128], i: usize, j: usize, dx: u128) -> Option<u128> {
+    if i == j || i >= balances.len() || j >= balances.len() {
+        return None;
+    }
+    let d = compute_d(amp_factor, balances)?;
+    let mut new_balances = balances.to_vec();
+    new_balances[i] = new_balances[i].checked_add(dx)?;
+    let y = compute_y(amp_factor, &new_balances, j, d)?;
+    balances[j].checked_sub(y)?.checked_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bounds of the amplification coefficient in Saber's program.
+    const MIN_AMP: u64 = 1;
+    const MAX_AMP: u64 = 1_000_000;
+
+    /// Two-token swaps of token 0 into token 1 as quoted by `StableSwap::swap_to` in
+    /// `stable-swap-math` 1.8.1, before fees: amp, reserves, amount in and amount out.
+    const SABER_QUOTES: [(u64, [u128; 2], u128, u128); 10] = [
+        (
+            100,
+            [1_000_000_000_000, 1_000_000_000_000],
+            1_000_000_000,
+            999_990_099,
+        ),
+        (
+            100,
+            [1_000_000_000_000, 1_000_000_000_000],
+            100_000_000_000,
+            99_900_110_864,
+        ),
+        (
+            100,
+            [1_500_000_000_000, 500_000_000_000],
+            1_000_000_000,
+            982_728_134,
+        ),
+        (
+            100,
+            [500_000_000_000, 1_500_000_000_000],
+            1_000_000_000,
+            1_017_494_503,
+        ),
+        (
+            MIN_AMP,
+            [1_000_000_000_000, 1_000_000_000_000],
+            1_000_000_000,
+            999_500_249,
+        ),
+        (
+            MIN_AMP,
+            [1_500_000_000_000, 500_000_000_000],
+            1_000_000_000,
+            545_263_218,
+        ),
+        (
+            MAX_AMP,
+            [1_000_000_000_000, 1_000_000_000_000],
+            1_000_000_000,
+            999_999_999,
+        ),
+        (
+            MAX_AMP,
+            [1_500_000_000_000, 500_000_000_000],
+            1_000_000_000,
+            999_998_217,
+        ),
+        (
+            MAX_AMP,
+            [1_500_000_000_000, 500_000_000_000],
+            400_000_000_000,
+            399_996_070_383,
+        ),
+        (MIN_AMP, [10, 10], 1, 0),
+    ];
+
+    #[test]
+    fn get_dy_matches_saber() {
+        for (amp, balances, dx, dy) in SABER_QUOTES {
+            assert_eq!(
+                get_dy(amp, &balances, 0, 1, dx),
+                Some(dy),
+                "amp {amp}, balances {balances:?}, dx {dx}"
+            );
+        }
+    }
+
+    #[test]
+    fn compute_d_matches_saber() {
+        let equal = [1_000_000_000_000, 1_000_000_000_000];
+        let imbalanced = [1_500_000_000_000, 500_000_000_000];
+        for amp in [MIN_AMP, 100, MAX_AMP] {
+            assert_eq!(compute_d(amp, &equal), Some(2_000_000_000_000));
+        }
+        assert_eq!(compute_d(MIN_AMP, &imbalanced), Some(1_858_889_071_871));
+        assert_eq!(compute_d(100, &imbalanced), Some(1_996_715_821_544));
+        assert_eq!(compute_d(MAX_AMP, &imbalanced), Some(1_999_999_666_667));
+    }
+
+    #[test]
+    fn get_dy_matches_curve_for_more_tokens() {
+        // `get_dy` of Curve's base pool template with `Ann = A * n`.
+        let equal = [1_000_000_000_000; 3];
+        assert_eq!(get_dy(200, &equal, 0, 2, 1_000_000_000), Some(999_995_024));
+        let imbalanced = [2_000_000_000_000, 1_000_000_000_000, 500_000_000_000];
+        assert_eq!(
+            get_dy(200, &imbalanced, 0, 2, 10_000_000_000),
+            Some(9_861_186_736)
+        );
+        let four = [
+            1_000_000_000_000,
+            2_000_000_000_000,
+            3_000_000_000_000,
+            4_000_000_000_000,
+        ];
+        assert_eq!(
+            get_dy(MAX_AMP, &four, 3, 0, 100_000_000_000),
+            Some(99_999_658_160)
+        );
+    }
+
+    #[test]
+    fn rejects_degenerate_inputs() {
+        assert_eq!(compute_d(100, &[1_000]), None);
+        assert_eq!(compute_d(100, &[0, 0]), Some(0));
+        assert_eq!(get_dy(100, &[1_000, 1_000], 0, 0, 10), None);
+        assert_eq!(get_dy(100, &[1_000, 1_000], 0, 2, 10), None);
+        assert_eq!(compute_y(100, &[1_000, 1_000], 2, 2_000), None);
+    }
+}
@@ -0,0 +1,58 @@
+//! Names and symbols of a pool's tokens, from their Metaplex metadata accounts.
+//!
+//! Metadata lives in a PDA of the Metaplex program derived from the mint, so it can be
+//! fetched without reading the mint first. Not every mint has metadata; those that
+//! don't come back as `None`.
+
+use anchor_lang::prelude::{AccountDeserialize, Pubkey};
+
+use crate::fetcher::AccountFetcher;
+use crate::layouts::metaplex::Metadata;
+use crate::mints::mint_addresses;
+use crate::parsing::{AmmPool, PoolError};
+
+/// Fetches and parses the metadata of `mints` in one request, in the same order.
+pub async fn fetch_metadata<F: AccountFetcher>(
+    mints: &[Pubkey],
+    fetcher: &F,
+) -> std::result::Result<Vec<Option<Metadata>>, PoolError> {
+    let addresses: Vec<Pubkey> = mints.iter().map(Metadata::address).collect();
+    let accounts = fetcher.fetch_accounts(&addresses).await?.value;
+    let mut metadata = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        let parsed = match account {
+            Some(account) => match Metadata::try_deserialize(&mut &account.data[..]) {
+                Ok(parsed) => Some(parsed),
+                Err(e) => return Err(PoolError::DeserializeError(e)),
+            },
+            None => None,
+        };
+        metadata.push(parsed);
+    }
+    Ok(metadata)
+}
+
+/// Fetches the metadata of the mints of `pool`, in the order of `mints::mint_addresses`.
+pub async fn fetch_pool_metadata<F: AccountFetcher>(
+    pool: &AmmPool,
+    fetcher: &F,
+) -> std::result::Result<Vec<Option<Metadata>>, PoolError> {
+    let mints = mint_addresses(pool);
+    if mints.is_empty() {
+        return Ok(Vec::new());
+    }
+    fetch_metadata(&mints, fetcher).await
+}
+
+/// Joins the symbols of `metadata` into a pair name such as `WIF/SOL`, or returns `None`
+/// if any token has no metadata or an empty symbol.
+pub fn pair_symbol(metadata: &[Option<Metadata>]) -> Option<String> {
+    let symbols = metadata
+        .iter()
+        .map(|metadata| {
+            let symbol = metadata.as_ref()?.symbol().trim();
+            (!symbol.is_empty()).then_some(symbol)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!symbols.is_empty()).then(|| symbols.join("/"))
+}
@@ -0,0 +1,81 @@
+//! Bin arrays of a Meteora DLMM pair, which `get_info_struct` doesn't fetch.
+//!
+//! A DLMM `LbPair` only records the id of its active bin. The liquidity of every bin is
+//! held in `BinArray`s of 70 consecutive bins each, stored in PDAs keyed by the array's
+//! index, so quoting a swap or reading the depth around the price needs those as well.
+
+use anchor_client::solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use anchor_lang::prelude::{AccountDeserialize, Pubkey};
+
+use crate::fetcher::AccountFetcher;
+use crate::parsing::PoolError;
+use crate::parsing::meteora_dlmm::{
+    self,
+    accounts::{BinArray, LbPair},
+};
+
+/// Number of bins held by one bin array.
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+
+/// Seed of the bin array PDAs.
+pub const BIN_ARRAY_SEED: &[u8] = b"bin_array";
+
+/// Denominator of DLMM fee rates; a rate of 10,000,000 is 1%.
+pub const FEE_PRECISION: u64 = 1_000_000_000;
+
+/// Returns the base fee rate of `lb_pair`, over `FEE_PRECISION`, before the variable fee
+/// that grows with volatility.
+pub fn base_fee_rate(lb_pair: &LbPair) -> u128 {
+    let power = 10u128.pow(u32::from(lb_pair.parameters.base_fee_power_factor));
+    u128::from(lb_pair.parameters.base_factor) * u128::from(lb_pair.bin_step) * 10 * power
+}
+
+/// Returns the index of the bin array containing the bin `bin_id`.
+pub fn bin_id_to_bin_array_index(bin_id: i32) -> i32 {
+    bin_id.div_euclid(MAX_BIN_PER_ARRAY)
+}
+
+/// Derives the address of the bin array of `lb_pair_address` with index `index`.
+pub fn bin_array_address(lb_pair_address: &Pubkey, index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            BIN_ARRAY_SEED,
+            lb_pair_address.as_ref(),
+            &i64::from(index).to_le_bytes(),
+        ],
+        &meteora_dlmm::ID,
+    )
+    .0
+}
+
+/// Fetches the bin array containing the pair's active bin and up to `n` arrays on each
+/// side of it, ordered by index.
+///
+/// Bin arrays are only created once liquidity is added to one of their bins, so arrays
+/// that don't exist on-chain are skipped rather than reported as errors.
+///
+/// Arrays are fetched `MAX_MULTIPLE_ACCOUNTS` at a time, the most one request can ask
+/// for, so the arrays of wider ranges may be read at different slots.
+pub async fn fetch_bin_arrays_around_active<F: AccountFetcher>(
+    lb_pair_address: &Pubkey,
+    lb_pair: &LbPair,
+    n: u8,
+    fetcher: &F,
+) -> std::result::Result<Vec<BinArray>, PoolError> {
+    let active = bin_id_to_bin_array_index(lb_pair.active_id);
+    let side = i32::from(n);
+    let addresses: Vec<Pubkey> = (-side..=side)
+        .map(|offset| bin_array_address(lb_pair_address, active + offset))
+        .collect();
+    let mut bin_arrays = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = fetcher.fetch_accounts(chunk).await?.value;
+        for account in accounts.into_iter().flatten() {
+            match BinArray::try_deserialize(&mut &account.data[..]) {
+                Ok(bin_array) => bin_arrays.push(bin_array),
+                Err(e) => return Err(PoolError::DeserializeError(e)),
+            }
+        }
+    }
+    Ok(bin_arrays)
+}
@@ -0,0 +1,169 @@
+//! Mints of a pool's tokens, which pool accounts only reference by address.
+//!
+//! Mints can belong to either token program. For Token-2022 mints the extensions that
+//! change what a transfer delivers, or whether it succeeds at all, are parsed along with
+//! the base mint, since quotes and reserves are wrong without them.
+//!
+//! `get_info_struct_with_mints` returns a pool together with its mints, for callers that
+//! need decimals right after parsing.
+
+use anchor_lang::prelude::{AccountDeserialize, Pubkey};
+
+use crate::fetcher::AccountFetcher;
+use crate::layouts::spl_token::Mint;
+use crate::layouts::token_2022::{self, MintExtensions, MintWithExtensions};
+use crate::parsing::{
+    AldrinPool, AmmPool, BonkswapPool, PoolError, PoolType, StabblePool, get_info_struct,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A token mint of a pool.
+pub struct PoolMint {
+    pub address: Pubkey,
+    /// Token program owning the mint, SPL Token or Token-2022.
+    pub token_program: Pubkey,
+    pub mint: Mint,
+    /// Extensions of the mint; always empty for SPL Token mints.
+    pub extensions: MintExtensions,
+}
+
+impl PoolMint {
+    /// Returns `true` if the mint belongs to the Token-2022 program.
+    pub fn is_token_2022(&self) -> bool {
+        self.token_program == token_2022::ID
+    }
+
+    /// Returns the fee withheld from a transfer of `amount` atoms during `epoch`, zero
+    /// for mints without a transfer fee.
+    pub fn transfer_fee(&self, amount: u64, epoch: u64) -> u64 {
+        match &self.extensions.transfer_fee_config {
+            Some(config) => config.epoch_fee(epoch).fee(amount),
+            None => 0,
+        }
+    }
+
+    /// Returns `true` if transfers of the token invoke a hook program.
+    pub fn has_transfer_hook(&self) -> bool {
+        self.extensions
+            .transfer_hook
+            .as_ref()
+            .is_some_and(|hook| hook.program_id != Pubkey::default())
+    }
+}
+
+/// Fetches and parses the mints at `addresses` in one request, in the same order.
+pub async fn fetch_mints<F: AccountFetcher>(
+    addresses: &[Pubkey],
+    fetcher: &F,
+) -> std::result::Result<Vec<PoolMint>, PoolError> {
+    let accounts = fetcher.fetch_accounts(addresses).await?.value;
+    let mut mints = Vec::with_capacity(accounts.len());
+    for (address, account) in addresses.iter().zip(accounts) {
+        let Some(account) = account else {
+            return Err(PoolError::AccountNotFound { address: *address });
+        };
+        let parsed = match MintWithExtensions::try_deserialize(&mut &account.data[..]) {
+            Ok(parsed) => parsed,
+            Err(e) => return Err(PoolError::DeserializeError(e)),
+        };
+        mints.push(PoolMint {
+            address: *address,
+            token_program: account.owner,
+            mint: parsed.mint,
+            extensions: parsed.extensions,
+        });
+    }
+    Ok(mints)
+}
+
+/// Returns the mints of the tokens `pool` holds, in the order of
+/// `reserves::vault_addresses` where both are known.
+///
+/// Mercurial pools don't store their mints; read them from `reserves::fetch_reserves`
+/// instead. Meteora DBC pools only store their base mint.
+pub fn mint_addresses(pool: &AmmPool) -> Vec<Pubkey> {
+    match pool {
+        AmmPool::PumpFun(pool) => vec![pool.base_mint, pool.quote_mint],
+        AmmPool::PumpFunWithConfig(pool) => vec![pool.pool.base_mint, pool.pool.quote_mint],
+        AmmPool::RaydiumCpmmAmm(pool) => vec![pool.token0_mint, pool.token1_mint],
+        AmmPool::RaydiumCpmmAmmWithConfig(pool) => {
+            vec![pool.pool.token0_mint, pool.pool.token1_mint]
+        }
+        AmmPool::RaydiumCamm(pool) => vec![pool.token_mint0, pool.token_mint1],
+        AmmPool::RaydiumLegacyAmm(amm) => vec![amm.coin_vault_mint, amm.pc_vault_mint],
+        AmmPool::LifinityV2(amm) => vec![amm.token_a_mint, amm.token_b_mint],
+        AmmPool::Phoenix(market) => vec![
+            market.header.base_params.mint_key,
+            market.header.quote_params.mint_key,
+        ],
+        AmmPool::SaberStableSwap(swap) => vec![swap.token_a_mint, swap.token_b_mint],
+        AmmPool::Invariant(pool) => vec![pool.token_x, pool.token_y],
+        AmmPool::CremaClmm(pool) => vec![pool.token_a, pool.token_b],
+        AmmPool::Aldrin(AldrinPool::V1(pool)) => vec![pool.base_token_mint, pool.quote_token_mint],
+        AmmPool::Aldrin(AldrinPool::V2(pool)) => vec![pool.base_token_mint, pool.quote_token_mint],
+        AmmPool::FluxBeam(swap) | AmmPool::OrcaTokenSwap(swap) | AmmPool::SplTokenSwap(swap) => {
+            vec![swap.token_a_mint, swap.token_b_mint]
+        }
+        AmmPool::SanctumInfinity(pool) => pool.lst_states.mints(),
+        AmmPool::Stabble(StabblePool::Stable(pool)) => {
+            pool.tokens.iter().map(|token| token.mint).collect()
+        }
+        AmmPool::Stabble(StabblePool::Weighted(pool)) => {
+            pool.tokens.iter().map(|token| token.mint).collect()
+        }
+        AmmPool::Moonshot(curve) => vec![curve.mint],
+        AmmPool::RaydiumLaunchLab(pool) => vec![pool.base_mint, pool.quote_mint],
+        AmmPool::MeteoraDbc(pool) => vec![pool.base_mint],
+        AmmPool::MeteoraVault(vault) => vec![vault.vault.token_mint],
+        AmmPool::Bonkswap(BonkswapPool::V1(pool)) => vec![pool.token_x, pool.token_y],
+        AmmPool::Bonkswap(BonkswapPool::V2(pool)) => vec![pool.token_x, pool.token_y],
+        AmmPool::MeteoraDlmm(pair) => vec![pair.token_x_mint, pair.token_y_mint],
+        AmmPool::OrcaWhirlpool(pool) => vec![pool.token_mint_a, pool.token_mint_b],
+        #[cfg(feature = "evm")]
+        AmmPool::UniswapV2(_) | AmmPool::UniswapV3(_) => Vec::new(),
+        AmmPool::MercurialStableSwap(_)
+        | AmmPool::PumpFunBondingCurve(_)
+        | AmmPool::BonkswapFarm(_)
+        | AmmPool::Dynamic(_)
+        | AmmPool::SplStakePool(_)
+        | AmmPool::Marinade(_)
+        | AmmPool::RaydiumCammConfig(_)
+        | AmmPool::RaydiumCammObservation(_)
+        | AmmPool::RaydiumCpmmConfig(_)
+        | AmmPool::PumpFunGlobalConfig(_)
+        | AmmPool::OrcaWhirlpoolPosition(_) => Vec::new(),
+    }
+}
+
+/// Fetches and parses the mints of `pool` in one request, in the order of
+/// `mint_addresses`.
+pub async fn fetch_pool_mints<F: AccountFetcher>(
+    pool: &AmmPool,
+    fetcher: &F,
+) -> std::result::Result<Vec<PoolMint>, PoolError> {
+    let addresses = mint_addresses(pool);
+    if addresses.is_empty() {
+        return Ok(Vec::new());
+    }
+    fetch_mints(&addresses, fetcher).await
+}
+
+#[derive(Debug)]
+/// A parsed pool together with the mints of its tokens.
+pub struct PoolWithMints {
+    pub pool: AmmPool,
+    /// Mints of the pool's tokens, in the order of `mint_addresses`.
+    pub mints: Vec<PoolMint>,
+}
+
+/// Like `get_info_struct`, but also fetches the mints of the pool's tokens.
+///
+/// This costs one more request after the pool itself is fetched.
+pub async fn get_info_struct_with_mints<F: AccountFetcher>(
+    pool_type: PoolType,
+    fetcher: &F,
+) -> std::result::Result<PoolWithMints, PoolError> {
+    let pool = get_info_struct(pool_type, fetcher).await?.pool;
+    let mints = fetch_pool_mints(&pool, fetcher).await?;
+    Ok(PoolWithMints { pool, mints })
+}
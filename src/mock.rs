@@ -0,0 +1,491 @@
+//! An `AccountFetcher` serving fixtures from memory, for testing code built on this crate
+//! without an RPC node.
+//!
+//! Every function that fetches pools takes an `AccountFetcher`, so tests hand them a
+//! `MockFetcher` holding the accounts they need. Accounts can be built with
+//! `AmmPool::to_account_bytes` or read from `solana account --output json` dumps:
+//!
+//! ```no_run
+//! # use anchor_lang::prelude::Pubkey;
+//! # use universal_liquidity_pool_parser::dump::AccountDump;
+//! # use universal_liquidity_pool_parser::mock::MockFetcher;
+//! # use universal_liquidity_pool_parser::parsing::{PoolError, PoolType, get_info_struct_on};
+//! # use universal_liquidity_pool_parser::program_ids::Cluster;
+//! # async fn example() -> Result<(), PoolError> {
+//! let dump = AccountDump::read_json("fixtures/whirlpool.json")?;
+//! let pool_address = dump.address;
+//! let fetcher = MockFetcher::new().with_slot(330_000_000).with_dump(dump);
+//! let pool_type = PoolType::OrcaWhirlpool { pool_address };
+//! let parsed = get_info_struct_on(pool_type, &fetcher, &Cluster::Mainnet).await?;
+//! assert_eq!(parsed.slot, Some(330_000_000));
+//! assert_eq!(fetcher.request_count(), 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requests are answered as an RPC node at a fixed slot would: data slices and
+//! `getProgramAccounts` filters are applied, and requests needing a later slot than the
+//! fetcher's fail as they do on a node that hasn't reached it.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anchor_client::solana_client::client_error::ClientError;
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_custom_error::JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED;
+use anchor_client::solana_client::rpc_filter::RpcFilterType;
+use anchor_client::solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use anchor_client::solana_client::rpc_response::{Response, RpcResponseContext};
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::Pubkey;
+
+use crate::dump::AccountDump;
+use crate::fetcher::{self, AccountFetcher};
+use crate::parsing::PoolError;
+
+#[derive(Debug, Default)]
+/// An `AccountFetcher` answering every request with the accounts it holds, at one slot.
+pub struct MockFetcher {
+    accounts: HashMap<Pubkey, Account>,
+    slot: u64,
+    requests: AtomicUsize,
+}
+
+impl MockFetcher {
+    /// Creates a fetcher without accounts, at slot zero.
+    pub fn new() -> Self {
+        MockFetcher::default()
+    }
+
+    /// Sets the slot the accounts are read at.
+    pub fn with_slot(mut self, slot: u64) -> Self {
+        self.slot = slot;
+        self
+    }
+
+    pub fn with_account(mut self, address: Pubkey, account: Account) -> Self {
+        self.insert(address, account);
+        self
+    }
+
+    pub fn with_dump(self, dump: AccountDump) -> Self {
+        self.with_account(dump.address, dump.account)
+    }
+
+    /// Adds or replaces the account at `address`, returning the one it replaces.
+    pub fn insert(&mut self, address: Pubkey, account: Account) -> Option<Account> {
+        self.accounts.insert(address, account)
+    }
+
+    /// Removes the account at `address`, so it's fetched as one that doesn't exist.
+    pub fn remove(&mut self, address: &Pubkey) -> Option<Account> {
+        self.accounts.remove(address)
+    }
+
+    pub fn set_slot(&mut self, slot: u64) {
+        self.slot = slot;
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    pub fn accounts(&self) -> &HashMap<Pubkey, Account> {
+        &self.accounts
+    }
+
+    /// Returns the number of requests answered or failed so far.
+    pub fn request_count(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Counts a request and fails it if it needs a later slot than the fetcher's.
+    fn request(&self, config: &RpcAccountInfoConfig) -> Result<(), PoolError> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        match config.min_context_slot {
+            Some(min_context_slot) if min_context_slot > self.slot => Err(PoolError::from(
+                ClientError::from(RpcError::RpcResponseError {
+                    code: JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+                    message: "Minimum context slot has not been reached".to_string(),
+                    data: RpcResponseErrorData::Empty,
+                }),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn response<T>(&self, value: T) -> Response<T> {
+        Response {
+            context: RpcResponseContext::new(self.slot),
+            value,
+        }
+    }
+}
+
+impl AccountFetcher for MockFetcher {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        self.request(&config)?;
+        let accounts = addresses
+            .iter()
+            .map(|address| {
+                self.accounts
+                    .get(address)
+                    .map(|account| fetcher::sliced(account, &config))
+            })
+            .collect();
+        Ok(self.response(accounts))
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        self.request(&config.account_config)?;
+        let filters = config.filters.unwrap_or_default();
+        let accounts = self
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.owner == *program_id)
+            .filter(|(_, account)| filters.iter().all(|filter| allows(filter, account)))
+            .map(|(address, account)| (*address, fetcher::sliced(account, &config.account_config)))
+            .collect();
+        Ok(self.response(accounts))
+    }
+}
+
+/// Returns whether `account` passes `filter`. `TokenAccountState` passes initialized token
+/// accounts, those of Token-2022 with extensions included.
+fn allows(filter: &RpcFilterType, account: &Account) -> bool {
+    match filter {
+        RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
+        RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&account.data),
+        RpcFilterType::TokenAccountState => is_token_account(&account.data),
+    }
+}
+
+/// Size of the base token account layout, which Token-2022 accounts extend.
+const TOKEN_ACCOUNT_LEN: usize = 165;
+/// Offset of the state of a token account.
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+/// Account type Token-2022 writes after the base layout of extended token accounts.
+const ACCOUNT_TYPE_TOKEN_ACCOUNT: u8 = 2;
+
+/// Returns whether `data` is an initialized token account, as RPC nodes tell it for
+/// `TokenAccountState` filters: the base layout, or a longer account marked as a token
+/// account right after it.
+fn is_token_account(data: &[u8]) -> bool {
+    let is_token_account = match data.len() {
+        len if len == TOKEN_ACCOUNT_LEN => true,
+        len if len > TOKEN_ACCOUNT_LEN => data[TOKEN_ACCOUNT_LEN] == ACCOUNT_TYPE_TOKEN_ACCOUNT,
+        _ => false,
+    };
+    is_token_account && data[TOKEN_ACCOUNT_STATE_OFFSET] != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU64;
+    use std::time::Duration;
+
+    use anchor_lang::AnchorSerialize;
+
+    use super::*;
+    use crate::consistency::{self, Consistency};
+    use crate::detect;
+    use crate::fetcher::WithMinContextSlot;
+    use crate::layouts::saber::{self, Fees, SwapInfo};
+    use crate::layouts::spl_token::{self, TokenAccount};
+    use crate::layouts::token_2022;
+    use crate::parsing::{AmmPool, ParseMode, PoolType, get_info_struct_on};
+    use crate::program_ids::Cluster;
+    use crate::reserves::VaultReserve;
+    use crate::retry::{RetryPolicy, Retrying};
+
+    fn saber_pool() -> SwapInfo {
+        SwapInfo {
+            is_initialized: true,
+            is_paused: false,
+            nonce: 255,
+            initial_amp_factor: 100,
+            target_amp_factor: 100,
+            start_ramp_ts: 0,
+            stop_ramp_ts: 0,
+            future_admin_deadline: 0,
+            future_admin_key: Pubkey::default(),
+            admin_key: Pubkey::new_unique(),
+            token_a_reserves: Pubkey::new_unique(),
+            token_b_reserves: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            token_a_admin_fees: Pubkey::new_unique(),
+            token_b_admin_fees: Pubkey::new_unique(),
+            fees: Fees {
+                admin_trade_fee_numerator: 0,
+                admin_trade_fee_denominator: 100,
+                admin_withdraw_fee_numerator: 0,
+                admin_withdraw_fee_denominator: 100,
+                trade_fee_numerator: 4,
+                trade_fee_denominator: 10_000,
+                withdraw_fee_numerator: 0,
+                withdraw_fee_denominator: 10_000,
+            },
+        }
+    }
+
+    fn pool_account(pool: &SwapInfo) -> Account {
+        let data = AmmPool::SaberStableSwap(pool.clone())
+            .to_account_bytes()
+            .unwrap();
+        Account {
+            data,
+            owner: saber::ID,
+            ..Account::default()
+        }
+    }
+
+    fn vault_account(mint: Pubkey, amount: u64) -> Account {
+        let vault = TokenAccount {
+            mint,
+            owner: Pubkey::new_unique(),
+            amount,
+            delegate_tag: 0,
+            delegate_key: Pubkey::default(),
+            state: 1,
+            is_native_tag: 0,
+            is_native_reserve: 0,
+            delegated_amount: 0,
+            close_authority_tag: 0,
+            close_authority_key: Pubkey::default(),
+        };
+        Account {
+            data: vault.try_to_vec().unwrap(),
+            owner: spl_token::ID,
+            ..Account::default()
+        }
+    }
+
+    /// A fetcher holding a Saber pool at `address` and both of its vaults, at `slot`.
+    fn saber_fixture(address: Pubkey, slot: u64) -> (MockFetcher, SwapInfo) {
+        let pool = saber_pool();
+        let fetcher = MockFetcher::new()
+            .with_slot(slot)
+            .with_account(address, pool_account(&pool))
+            .with_account(
+                pool.token_a_reserves,
+                vault_account(pool.token_a_mint, 1_000),
+            )
+            .with_account(
+                pool.token_b_reserves,
+                vault_account(pool.token_b_mint, 2_000),
+            );
+        (fetcher, pool)
+    }
+
+    #[tokio::test]
+    async fn parses_a_fixture_pool() {
+        let address = Pubkey::new_unique();
+        let (fetcher, pool) = saber_fixture(address, 330_000_000);
+        let pool_type = PoolType::SaberStableSwap {
+            pool_address: address,
+        };
+        let parsed = get_info_struct_on(pool_type, &fetcher, &Cluster::Mainnet)
+            .await
+            .unwrap();
+        assert!(matches!(&parsed.pool, AmmPool::SaberStableSwap(parsed) if *parsed == pool));
+        assert_eq!(parsed.address, address);
+        assert_eq!(parsed.slot, Some(330_000_000));
+        assert_eq!(fetcher.request_count(), 1);
+
+        let detected = detect::detect_and_parse(&address, &fetcher).await.unwrap();
+        assert!(matches!(detected, AmmPool::SaberStableSwap(detected) if detected == pool));
+    }
+
+    #[tokio::test]
+    async fn missing_accounts_are_not_found() {
+        let address = Pubkey::new_unique();
+        let pool_type = PoolType::SaberStableSwap {
+            pool_address: address,
+        };
+        let result = get_info_struct_on(pool_type, &MockFetcher::new(), &Cluster::Mainnet).await;
+        assert!(
+            matches!(result, Err(PoolError::AccountNotFound { address: missing }) if missing == address)
+        );
+
+        let (mut fetcher, pool) = saber_fixture(address, 1);
+        fetcher.remove(&pool.token_b_reserves);
+        let pool_type = PoolType::SaberStableSwap {
+            pool_address: address,
+        };
+        let result = consistency::get_info_struct_with(
+            pool_type,
+            &fetcher,
+            &Cluster::Mainnet,
+            ParseMode::default(),
+            Consistency::Any,
+            Some(&[]),
+        )
+        .await;
+        assert!(
+            matches!(result, Err(PoolError::AccountNotFound { address: missing }) if missing == pool.token_b_reserves)
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_beyond_the_slot_fail() {
+        let (fetcher, _) = saber_fixture(Pubkey::new_unique(), 10);
+        let behind = WithMinContextSlot::new(&fetcher, 11);
+        let result = behind.fetch_accounts(&[Pubkey::new_unique()]).await;
+        assert!(result.is_err_and(|e| e.is_transient()));
+        let caught_up = WithMinContextSlot::new(&fetcher, 10);
+        let response = caught_up.fetch_accounts(&[Pubkey::new_unique()]).await;
+        assert_eq!(response.unwrap().context.slot, 10);
+    }
+
+    #[tokio::test]
+    async fn retrying_repeats_only_transient_failures() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            jitter: 0.0,
+        };
+        let address = Pubkey::new_unique();
+        let pool_type = PoolType::SaberStableSwap {
+            pool_address: address,
+        };
+
+        let (fetcher, _) = saber_fixture(address, 10);
+        let retrying = Retrying::new(WithMinContextSlot::new(&fetcher, 11), policy);
+        let result = get_info_struct_on(pool_type.clone(), &retrying, &Cluster::Mainnet).await;
+        assert!(result.is_err_and(|e| e.is_transient()));
+        assert_eq!(fetcher.request_count(), 3);
+
+        let fetcher = MockFetcher::new();
+        let retrying = Retrying::new(&fetcher, policy);
+        let result = get_info_struct_on(pool_type, &retrying, &Cluster::Mainnet).await;
+        assert!(matches!(result, Err(PoolError::AccountNotFound { .. })));
+        assert_eq!(fetcher.request_count(), 1);
+    }
+
+    /// Answers each request of `inner` as read one slot later than the one before.
+    struct Advancing {
+        inner: MockFetcher,
+        next_slot: AtomicU64,
+    }
+
+    impl AccountFetcher for Advancing {
+        async fn fetch_accounts_with_config(
+            &self,
+            addresses: &[Pubkey],
+            config: RpcAccountInfoConfig,
+        ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+            let mut response = self
+                .inner
+                .fetch_accounts_with_config(addresses, config)
+                .await?;
+            response.context.slot = self.next_slot.fetch_add(1, Ordering::Relaxed);
+            Ok(response)
+        }
+
+        async fn fetch_program_accounts(
+            &self,
+            program_id: &Pubkey,
+            config: RpcProgramAccountsConfig,
+        ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+            self.inner.fetch_program_accounts(program_id, config).await
+        }
+    }
+
+    #[tokio::test]
+    async fn same_slot_refetches_accounts_read_at_different_slots() {
+        let address = Pubkey::new_unique();
+        let (inner, pool) = saber_fixture(address, 1_000);
+        let fetcher = Advancing {
+            inner,
+            next_slot: AtomicU64::new(100),
+        };
+        let pool_type = PoolType::SaberStableSwap {
+            pool_address: address,
+        };
+        let parsed = consistency::get_info_struct_with(
+            pool_type,
+            &fetcher,
+            &Cluster::Mainnet,
+            ParseMode::default(),
+            Consistency::SameSlot,
+            Some(&[]),
+        )
+        .await
+        .unwrap();
+        // The pool at slot 100 and its vaults at 101, then all of them again at 102.
+        assert_eq!(parsed.slot, Some(102));
+        assert_eq!(fetcher.inner.request_count(), 3);
+        assert_eq!(
+            parsed.reserves,
+            Some(vec![
+                VaultReserve {
+                    vault: pool.token_a_reserves,
+                    mint: pool.token_a_mint,
+                    amount: 1_000,
+                },
+                VaultReserve {
+                    vault: pool.token_b_reserves,
+                    mint: pool.token_b_mint,
+                    amount: 2_000,
+                },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn token_account_state_filter_matches_token_2022_accounts() {
+        let base = vault_account(Pubkey::new_unique(), 1);
+        let mut extended = base.clone();
+        extended.owner = token_2022::ID;
+        extended.data.push(2);
+        extended.data.extend_from_slice(&[0; 12]);
+        let mut mint = extended.clone();
+        mint.data[165] = 1;
+        let mut uninitialized = extended.clone();
+        uninitialized.data[108] = 0;
+        let short = Account {
+            data: vec![1; 82],
+            ..extended.clone()
+        };
+        let [base_address, extended_address] = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let fetcher = MockFetcher::new()
+            .with_account(base_address, base)
+            .with_account(extended_address, extended)
+            .with_account(Pubkey::new_unique(), mint)
+            .with_account(Pubkey::new_unique(), uninitialized)
+            .with_account(Pubkey::new_unique(), short);
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::TokenAccountState]),
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        let found = |program_id| {
+            let config = config.clone();
+            let fetcher = &fetcher;
+            async move {
+                let accounts = fetcher
+                    .fetch_program_accounts(&program_id, config)
+                    .await
+                    .unwrap()
+                    .value;
+                accounts
+                    .into_iter()
+                    .map(|(address, _)| address)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(found(spl_token::ID).await, [base_address]);
+        assert_eq!(found(token_2022::ID).await, [extended_address]);
+    }
+}
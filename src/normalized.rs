@@ -0,0 +1,238 @@
+//! A protocol-independent view of two-token pools.
+//!
+//! `NormalizedPool` holds only what every pool has in common, for callers that don't want
+//! to match on every `AmmPool` variant. Fields a pool account doesn't record, such as
+//! decimals of pools that don't cache them or fees that live in a separate config
+//! account, are `None`; fetch the mints with `mints::fetch_pool_mints` for decimals, and
+//! the fee with `AmmPool::fetch_fee_bps`.
+//!
+//! Fees are converted to basis points from whatever the protocol stores: rates over a
+//! fixed denominator, numerator and denominator pairs, or basis points in a config.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::fetcher::AccountFetcher;
+use crate::mints::mint_addresses;
+use crate::parsing::{AmmPool, PoolError, StabblePool};
+use crate::pool_access::{PoolAccess, pump_amm_fee_bps, ratio_bps};
+use crate::reserves::vault_addresses;
+use crate::{pump_amm, raydium_clmm, raydium_cpmm};
+
+/// `curve_type` of stable-swap curves in SPL token-swap pools.
+const TOKEN_SWAP_CURVE_STABLE: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Pricing model of a pool.
+pub enum PoolKind {
+    /// `x * y = k` pools.
+    ConstantProduct,
+    /// Pools whose liquidity is concentrated in tick ranges.
+    ConcentratedLiquidity,
+    /// Pools whose liquidity sits in discrete price bins, like Meteora DLMM.
+    LiquidityBook,
+    /// Amplified stable-swap pools.
+    StableSwap,
+    /// Balancer-style weighted pools.
+    Weighted,
+    /// Pools pricing from an oracle rather than their reserves.
+    Oracle,
+    /// Central limit order books.
+    OrderBook,
+    /// Launch curves that sell a token until it graduates to an AMM.
+    BondingCurve,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The fields common to every two-token pool.
+pub struct NormalizedPool {
+    /// Address of the pool account.
+    pub address: Pubkey,
+    /// Name of the protocol, as returned by `PoolType::pool_name`.
+    pub protocol: &'static str,
+    pub kind: PoolKind,
+    /// Base token, or token A/X/0.
+    pub token_a_mint: Pubkey,
+    /// Quote token, or token B/Y/1.
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Option<Pubkey>,
+    pub token_b_vault: Option<Pubkey>,
+    pub token_a_decimals: Option<u8>,
+    pub token_b_decimals: Option<u8>,
+    /// Trade fee in basis points, fractional for pools with finer fee tiers. `None` for
+    /// pools whose fee lives in a config account; see `AmmPool::fetch_fee_bps`.
+    pub fee_bps: Option<f64>,
+}
+
+impl AmmPool {
+    /// Returns the pricing model of the pool, or `None` for accounts that aren't pools.
+    ///
+    /// Token-swap pools are classified by the curve in their account, unlike
+    /// `PoolType::kind`, which only knows the program.
+    pub fn kind(&self) -> Option<PoolKind> {
+        kind(self)
+    }
+
+    /// Returns the common fields of the pool at `address`, or `None` if the account isn't
+    /// a pool of exactly two tokens whose mints it records.
+    pub fn normalize(&self, address: Pubkey) -> Option<NormalizedPool> {
+        let [token_a_mint, token_b_mint] = mint_addresses(self)[..] else {
+            return None;
+        };
+        let (token_a_vault, token_b_vault) = match vault_addresses(self)[..] {
+            [a, b] => (Some(a), Some(b)),
+            _ => (None, None),
+        };
+        let (token_a_decimals, token_b_decimals) = decimals(self);
+        Some(NormalizedPool {
+            address,
+            protocol: protocol(self)?,
+            kind: kind(self)?,
+            token_a_mint,
+            token_b_mint,
+            token_a_vault,
+            token_b_vault,
+            token_a_decimals,
+            token_b_decimals,
+            fee_bps: self.fee_bps(),
+        })
+    }
+}
+
+impl AmmPool {
+    /// Returns the trade fee in basis points, fetching the config account that sets it for
+    /// pools that don't store it themselves.
+    ///
+    /// Pools with the fee in their own account, and pools parsed together with their
+    /// config, return `PoolAccess::fee_bps` without a request. Raydium CPMM and CLMM pools
+    /// fetch their `AmmConfig` and PumpSwap pools the `GlobalConfig`, including the coin
+    /// creator fee if the pool has a coin creator. Returns `None` for pools whose fee isn't
+    /// known either way.
+    pub async fn fetch_fee_bps<F: AccountFetcher>(
+        &self,
+        fetcher: &F,
+    ) -> Result<Option<f64>, PoolError> {
+        if let Some(fee_bps) = self.fee_bps() {
+            return Ok(Some(fee_bps));
+        }
+        Ok(match self {
+            AmmPool::RaydiumCpmmAmm(pool) => {
+                let config = raydium_cpmm::fetch_amm_config(pool, fetcher).await?;
+                ratio_bps(config.trade_fee_rate, raydium_cpmm::FEE_RATE_DENOMINATOR)
+            }
+            AmmPool::RaydiumCamm(pool) => {
+                let config = raydium_clmm::fetch_amm_config(pool, fetcher).await?;
+                ratio_bps(
+                    u64::from(config.trade_fee_rate),
+                    u64::from(raydium_clmm::FEE_RATE_DENOMINATOR),
+                )
+            }
+            AmmPool::PumpFun(pool) => {
+                let config = pump_amm::fetch_global_config(fetcher).await?;
+                Some(pump_amm_fee_bps(
+                    &config,
+                    pool.coin_creator != Pubkey::default(),
+                ))
+            }
+            _ => None,
+        })
+    }
+}
+
+pub(crate) fn protocol(pool: &AmmPool) -> Option<&'static str> {
+    Some(match pool {
+        AmmPool::PumpFun(_) | AmmPool::PumpFunWithConfig(_) => "PumpFun AMM",
+        AmmPool::RaydiumCpmmAmm(_)
+        | AmmPool::RaydiumCpmmAmmWithConfig(_)
+        | AmmPool::RaydiumCamm(_) => "Raydium AMM",
+        AmmPool::RaydiumLegacyAmm(_) => "Raydium Legacy AMM",
+        AmmPool::LifinityV2(_) => "Lifinity v2",
+        AmmPool::Phoenix(_) => "Phoenix",
+        AmmPool::SaberStableSwap(_) => "Saber Stable Swap",
+        AmmPool::MercurialStableSwap(_) => "Mercurial Stable Swap",
+        AmmPool::Invariant(_) => "Invariant CLMM",
+        AmmPool::CremaClmm(_) => "Crema CLMM",
+        AmmPool::Aldrin(_) => "Aldrin AMM",
+        AmmPool::FluxBeam(_) => "FluxBeam",
+        AmmPool::SanctumInfinity(_) => "Sanctum Infinity",
+        AmmPool::Stabble(_) => "Stabble",
+        AmmPool::PumpFunBondingCurve(_) => "PumpFun Bonding Curve",
+        AmmPool::Moonshot(_) => "Moonshot",
+        AmmPool::RaydiumLaunchLab(_) => "Raydium LaunchLab",
+        AmmPool::MeteoraDbc(_) => "Meteora DBC",
+        AmmPool::OrcaTokenSwap(_) => "Orca Token Swap",
+        AmmPool::SplTokenSwap(_) => "SPL Token Swap",
+        AmmPool::Bonkswap(_) => "Bonkswap",
+        AmmPool::MeteoraDlmm(_) => "Meteora DLMM",
+        AmmPool::OrcaWhirlpool(_) => "Orca Whirlpool",
+        _ => return None,
+    })
+}
+
+fn kind(pool: &AmmPool) -> Option<PoolKind> {
+    Some(match pool {
+        AmmPool::PumpFun(_)
+        | AmmPool::PumpFunWithConfig(_)
+        | AmmPool::RaydiumCpmmAmm(_)
+        | AmmPool::RaydiumCpmmAmmWithConfig(_)
+        | AmmPool::RaydiumLegacyAmm(_)
+        | AmmPool::Aldrin(_)
+        | AmmPool::Bonkswap(_) => PoolKind::ConstantProduct,
+        AmmPool::FluxBeam(swap) | AmmPool::OrcaTokenSwap(swap) | AmmPool::SplTokenSwap(swap) => {
+            if swap.swap_curve.curve_type == TOKEN_SWAP_CURVE_STABLE {
+                PoolKind::StableSwap
+            } else {
+                PoolKind::ConstantProduct
+            }
+        }
+        AmmPool::RaydiumCamm(_)
+        | AmmPool::Invariant(_)
+        | AmmPool::CremaClmm(_)
+        | AmmPool::OrcaWhirlpool(_) => PoolKind::ConcentratedLiquidity,
+        AmmPool::MeteoraDlmm(_) => PoolKind::LiquidityBook,
+        AmmPool::SaberStableSwap(_)
+        | AmmPool::MercurialStableSwap(_)
+        | AmmPool::Stabble(StabblePool::Stable(_)) => PoolKind::StableSwap,
+        AmmPool::Stabble(StabblePool::Weighted(_)) => PoolKind::Weighted,
+        AmmPool::LifinityV2(_) | AmmPool::SanctumInfinity(_) => PoolKind::Oracle,
+        AmmPool::Phoenix(_) => PoolKind::OrderBook,
+        AmmPool::PumpFunBondingCurve(_)
+        | AmmPool::Moonshot(_)
+        | AmmPool::RaydiumLaunchLab(_)
+        | AmmPool::MeteoraDbc(_) => PoolKind::BondingCurve,
+        #[cfg(feature = "evm")]
+        AmmPool::UniswapV2(_) => PoolKind::ConstantProduct,
+        #[cfg(feature = "evm")]
+        AmmPool::UniswapV3(_) => PoolKind::ConcentratedLiquidity,
+        _ => return None,
+    })
+}
+
+pub(crate) fn decimals(pool: &AmmPool) -> (Option<u8>, Option<u8>) {
+    match pool {
+        AmmPool::RaydiumCpmmAmm(pool) => (Some(pool.mint0_decimals), Some(pool.mint1_decimals)),
+        AmmPool::RaydiumCpmmAmmWithConfig(pool) => (
+            Some(pool.pool.mint0_decimals),
+            Some(pool.pool.mint1_decimals),
+        ),
+        AmmPool::RaydiumCamm(pool) => (Some(pool.mint_decimals0), Some(pool.mint_decimals1)),
+        AmmPool::RaydiumLegacyAmm(amm) => (
+            u8::try_from(amm.coin_decimals).ok(),
+            u8::try_from(amm.pc_decimals).ok(),
+        ),
+        AmmPool::LifinityV2(amm) => (Some(amm.base_decimals), None),
+        AmmPool::Phoenix(market) => (
+            u8::try_from(market.header.base_params.decimals).ok(),
+            u8::try_from(market.header.quote_params.decimals).ok(),
+        ),
+        AmmPool::RaydiumLaunchLab(pool) => (Some(pool.base_decimals), Some(pool.quote_decimals)),
+        AmmPool::Stabble(StabblePool::Stable(pool)) => match &pool.tokens[..] {
+            [a, b] => (Some(a.decimals), Some(b.decimals)),
+            _ => (None, None),
+        },
+        AmmPool::Stabble(StabblePool::Weighted(pool)) => match &pool.tokens[..] {
+            [a, b] => (Some(a.decimals), Some(b.decimals)),
+            _ => (None, None),
+        },
+        _ => (None, None),
+    }
+}
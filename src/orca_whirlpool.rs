@@ -0,0 +1,290 @@
+//! Accounts around an Orca Whirlpool that `get_info_struct` doesn't fetch.
+//!
+//! Like a Raydium CLMM pool, a `Whirlpool` only describes the current price and in-range
+//! liquidity; the liquidity of every initialized tick lives in tick arrays of 88 ticks,
+//! stored in PDAs keyed by the first tick index they cover. Arrays are either the
+//! original fixed-size `TickArray` or the newer, resizable `DynamicTickArray`, and a pool
+//! can hold both kinds.
+
+use anchor_client::solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use anchor_lang::Discriminator;
+use anchor_lang::prelude::{AccountDeserialize, Pubkey};
+
+use crate::fetcher::AccountFetcher;
+use crate::math::{self, ClmmProgram, LiquidityBucket};
+use crate::parsing::PoolError;
+use crate::parsing::orca_whirlpool::{
+    self,
+    accounts::{DynamicTickArray, Position, TickArray, Whirlpool},
+    types::DynamicTick,
+};
+
+/// Number of ticks held by one tick array.
+pub const TICK_ARRAY_SIZE: i32 = 88;
+
+/// Seed of the tick array PDAs.
+pub const TICK_ARRAY_SEED: &[u8] = b"tick_array";
+
+/// Seed of the position PDAs.
+pub const POSITION_SEED: &[u8] = b"position";
+
+/// Denominator of `Whirlpool::fee_rate`; a `fee_rate` of 3000 is 0.3%.
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+#[derive(Debug)]
+/// A tick array of either layout.
+pub enum WhirlpoolTickArray {
+    Fixed(TickArray),
+    Dynamic(DynamicTickArray),
+}
+
+impl WhirlpoolTickArray {
+    /// Returns the index of the first tick covered by the array.
+    pub fn start_tick_index(&self) -> i32 {
+        match self {
+            WhirlpoolTickArray::Fixed(array) => array.start_tick_index,
+            WhirlpoolTickArray::Dynamic(array) => array.start_tick_index,
+        }
+    }
+
+    /// Returns the index and `liquidity_net` of every initialized tick of the array, for a
+    /// pool with the given `tick_spacing`.
+    pub fn initialized_ticks(&self, tick_spacing: u16) -> Vec<(i32, i128)> {
+        let start = self.start_tick_index();
+        let index = |offset: usize| start + offset as i32 * i32::from(tick_spacing);
+        match self {
+            WhirlpoolTickArray::Fixed(array) => array
+                .ticks
+                .iter()
+                .enumerate()
+                .filter(|(_, tick)| tick.initialized)
+                .map(|(offset, tick)| (index(offset), tick.liquidity_net))
+                .collect(),
+            WhirlpoolTickArray::Dynamic(array) => array
+                .ticks
+                .iter()
+                .enumerate()
+                .filter_map(|(offset, tick)| match tick {
+                    DynamicTick::Initialized(data) => Some((index(offset), data.liquidity_net)),
+                    DynamicTick::Uninitialized => None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the token A and token B fee growth outside the tick at `tick_index`, zero
+    /// if it's uninitialized, or `None` if the tick isn't in this array.
+    pub fn fee_growth_outside(&self, tick_index: i32, tick_spacing: u16) -> Option<(u128, u128)> {
+        let offset = (tick_index - self.start_tick_index()) / i32::from(tick_spacing);
+        let offset = usize::try_from(offset).ok()?;
+        match self {
+            WhirlpoolTickArray::Fixed(array) => {
+                let tick = array.ticks.get(offset)?;
+                Some((tick.fee_growth_outside_a, tick.fee_growth_outside_b))
+            }
+            WhirlpoolTickArray::Dynamic(array) => match array.ticks.get(offset)? {
+                DynamicTick::Initialized(data) => {
+                    Some((data.fee_growth_outside_a, data.fee_growth_outside_b))
+                }
+                DynamicTick::Uninitialized => Some((0, 0)),
+            },
+        }
+    }
+}
+
+impl AccountDeserialize for WhirlpoolTickArray {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        if buf.starts_with(TickArray::DISCRIMINATOR) {
+            TickArray::try_deserialize(buf).map(WhirlpoolTickArray::Fixed)
+        } else {
+            DynamicTickArray::try_deserialize(buf).map(WhirlpoolTickArray::Dynamic)
+        }
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Self::try_deserialize(buf)
+    }
+}
+
+/// Returns the start index of the tick array containing `tick`.
+pub fn tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    let ticks_per_array = i32::from(tick_spacing) * TICK_ARRAY_SIZE;
+    tick.div_euclid(ticks_per_array) * ticks_per_array
+}
+
+/// Derives the address of the tick array of `whirlpool_address` starting at `start_index`.
+///
+/// Unlike Raydium, Whirlpool seeds the PDA with the decimal string of the index.
+pub fn tick_array_address(whirlpool_address: &Pubkey, start_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED,
+            whirlpool_address.as_ref(),
+            start_index.to_string().as_bytes(),
+        ],
+        &orca_whirlpool::ID,
+    )
+    .0
+}
+
+/// Derives the address of the `Position` of the position NFT `position_mint`.
+pub fn position_address(position_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[POSITION_SEED, position_mint.as_ref()],
+        &orca_whirlpool::ID,
+    )
+    .0
+}
+
+/// Fetches the tick array containing the pool's current tick and up to `arrays_per_side`
+/// arrays on each side of it, ordered by start index.
+///
+/// Tick arrays are only created once a position uses them, so arrays that don't exist
+/// on-chain are skipped rather than reported as errors.
+///
+/// Arrays are fetched `MAX_MULTIPLE_ACCOUNTS` at a time, the most one request can ask
+/// for, so the arrays of wider ranges may be read at different slots.
+pub async fn fetch_tick_arrays<F: AccountFetcher>(
+    whirlpool_address: &Pubkey,
+    whirlpool: &Whirlpool,
+    arrays_per_side: u8,
+    fetcher: &F,
+) -> std::result::Result<Vec<WhirlpoolTickArray>, PoolError> {
+    let ticks_per_array = i32::from(whirlpool.tick_spacing) * TICK_ARRAY_SIZE;
+    let current = tick_array_start_index(whirlpool.tick_current_index, whirlpool.tick_spacing);
+    let side = i32::from(arrays_per_side);
+    let addresses: Vec<Pubkey> = (-side..=side)
+        .map(|offset| tick_array_address(whirlpool_address, current + offset * ticks_per_array))
+        .collect();
+    let mut tick_arrays = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = fetcher.fetch_accounts(chunk).await?.value;
+        for account in accounts.into_iter().flatten() {
+            match WhirlpoolTickArray::try_deserialize(&mut &account.data[..]) {
+                Ok(tick_array) => tick_arrays.push(tick_array),
+                Err(e) => return Err(PoolError::DeserializeError(e)),
+            }
+        }
+    }
+    Ok(tick_arrays)
+}
+
+/// Returns the pool's liquidity over `tick_lower..tick_upper` in buckets of one tick
+/// spacing, from the initialized ticks of `tick_arrays`.
+///
+/// See `math::liquidity_profile`; the tick arrays should cover the whole range, e.g. as
+/// fetched by `fetch_tick_arrays`.
+pub fn liquidity_profile(
+    whirlpool: &Whirlpool,
+    tick_arrays: &[WhirlpoolTickArray],
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Option<Vec<LiquidityBucket>> {
+    let ticks: Vec<(i32, i128)> = tick_arrays
+        .iter()
+        .flat_map(|array| array.initialized_ticks(whirlpool.tick_spacing))
+        .collect();
+    math::liquidity_profile(
+        ClmmProgram::Whirlpool,
+        whirlpool.sqrt_price,
+        whirlpool.tick_current_index,
+        whirlpool.liquidity,
+        whirlpool.tick_spacing,
+        &ticks,
+        tick_lower,
+        tick_upper,
+    )
+}
+
+/// Returns the token A and token B amounts, in atoms, held by `position` at the pool's
+/// current sqrt price.
+pub fn position_amounts(whirlpool: &Whirlpool, position: &Position) -> Option<(u64, u64)> {
+    math::amounts_for_liquidity(
+        ClmmProgram::Whirlpool,
+        whirlpool.sqrt_price,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        position.liquidity,
+    )
+}
+
+/// Fetches the tick arrays holding the lower and upper ticks of `position`, which are
+/// the same array if both ticks fall into it.
+pub async fn fetch_position_tick_arrays<F: AccountFetcher>(
+    whirlpool: &Whirlpool,
+    position: &Position,
+    fetcher: &F,
+) -> std::result::Result<Vec<WhirlpoolTickArray>, PoolError> {
+    let lower = tick_array_start_index(position.tick_lower_index, whirlpool.tick_spacing);
+    let upper = tick_array_start_index(position.tick_upper_index, whirlpool.tick_spacing);
+    let mut addresses = vec![tick_array_address(&position.whirlpool, lower)];
+    if upper != lower {
+        addresses.push(tick_array_address(&position.whirlpool, upper));
+    }
+    let accounts = fetcher.fetch_accounts(&addresses).await?.value;
+    let mut tick_arrays = Vec::with_capacity(accounts.len());
+    for (address, account) in addresses.iter().zip(accounts) {
+        let Some(account) = account else {
+            return Err(PoolError::AccountNotFound { address: *address });
+        };
+        match WhirlpoolTickArray::try_deserialize(&mut &account.data[..]) {
+            Ok(tick_array) => tick_arrays.push(tick_array),
+            Err(e) => return Err(PoolError::DeserializeError(e)),
+        }
+    }
+    Ok(tick_arrays)
+}
+
+/// Returns the token A and token B fee growth outside the tick at `tick_index`, from
+/// whichever of `tick_arrays` holds it.
+fn fee_growth_outside(
+    tick_arrays: &[WhirlpoolTickArray],
+    tick_index: i32,
+    tick_spacing: u16,
+) -> Option<(u128, u128)> {
+    let start = tick_array_start_index(tick_index, tick_spacing);
+    tick_arrays
+        .iter()
+        .find(|array| array.start_tick_index() == start)?
+        .fee_growth_outside(tick_index, tick_spacing)
+}
+
+/// Returns the token A and token B fees, in atoms, that `position` can collect.
+///
+/// `tick_arrays` must hold the position's lower and upper ticks, e.g. as fetched by
+/// `fetch_position_tick_arrays`; returns `None` if they don't, or if the fees overflow.
+pub fn position_fees(
+    whirlpool: &Whirlpool,
+    position: &Position,
+    tick_arrays: &[WhirlpoolTickArray],
+) -> Option<(u64, u64)> {
+    let spacing = whirlpool.tick_spacing;
+    let (lower_a, lower_b) = fee_growth_outside(tick_arrays, position.tick_lower_index, spacing)?;
+    let (upper_a, upper_b) = fee_growth_outside(tick_arrays, position.tick_upper_index, spacing)?;
+    let inside = |global: u128, lower_outside: u128, upper_outside: u128| {
+        math::fee_growth_inside(
+            whirlpool.tick_current_index,
+            position.tick_lower_index,
+            position.tick_upper_index,
+            global,
+            lower_outside,
+            upper_outside,
+        )
+    };
+    let inside_a = inside(whirlpool.fee_growth_global_a, lower_a, upper_a);
+    let inside_b = inside(whirlpool.fee_growth_global_b, lower_b, upper_b);
+    Some((
+        math::fees_owed(
+            position.liquidity,
+            inside_a,
+            position.fee_growth_checkpoint_a,
+            position.fee_owed_a,
+        )?,
+        math::fees_owed(
+            position.liquidity,
+            inside_b,
+            position.fee_growth_checkpoint_b,
+            position.fee_owed_b,
+        )?,
+    ))
+}
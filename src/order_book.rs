@@ -0,0 +1,66 @@
+//! Order-book depth in a shape shared by every order-book venue.
+//!
+//! Venues quote prices and sizes in their own units, so `PriceLevel` keeps them raw and
+//! the helpers here only compare prices relative to each other, which makes questions
+//! like "how much can be bought within 1% of the best ask" independent of the venue.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// All resting size at one price.
+pub struct PriceLevel {
+    /// Price in the venue's units, e.g. quote atoms per base unit for Phoenix.
+    pub price: u64,
+    /// Total size resting at `price`, in base atoms.
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Aggregated price levels of both sides of a book, best price first.
+pub struct Depth {
+    /// Bid levels, from the highest price down.
+    pub bids: Vec<PriceLevel>,
+    /// Ask levels, from the lowest price up.
+    pub asks: Vec<PriceLevel>,
+}
+
+/// Denominator of the basis point arguments of `Depth`.
+const BPS_DENOMINATOR: u128 = 10_000;
+
+impl Depth {
+    /// Returns the highest bid, if any.
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.first().copied()
+    }
+
+    /// Returns the lowest ask, if any.
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.first().copied()
+    }
+
+    /// Returns the size that can be bought at no more than `bps` basis points above the
+    /// best ask.
+    pub fn ask_size_within_bps(&self, bps: u64) -> u64 {
+        let Some(best) = self.best_ask() else {
+            return 0;
+        };
+        let limit = u128::from(best.price) * (BPS_DENOMINATOR + u128::from(bps));
+        self.asks
+            .iter()
+            .take_while(|level| u128::from(level.price) * BPS_DENOMINATOR <= limit)
+            .map(|level| level.size)
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Returns the size that can be sold at no less than `bps` basis points below the best
+    /// bid.
+    pub fn bid_size_within_bps(&self, bps: u64) -> u64 {
+        let Some(best) = self.best_bid() else {
+            return 0;
+        };
+        let limit = u128::from(best.price) * BPS_DENOMINATOR.saturating_sub(u128::from(bps));
+        self.bids
+            .iter()
+            .take_while(|level| u128::from(level.price) * BPS_DENOMINATOR >= limit)
+            .map(|level| level.size)
+            .fold(0, u64::saturating_add)
+    }
+}
@@ -1,36 +1,346 @@
-use anchor_client::{self, solana_client::rpc_client::RpcClient};
-use anchor_lang::prelude::{AccountDeserialize, Pubkey, declare_program, error};
+use anchor_client::solana_client::client_error::ClientErrorKind;
+use anchor_client::solana_client::rpc_request::RpcError;
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::{AccountDeserialize, Pubkey, declare_program};
+use serde::Serialize;
 use std::fmt::Debug;
+use std::str::FromStr;
+use std::time::SystemTime;
 use thiserror::Error;
 
+use crate::dynamic::{DynamicError, DynamicIdl, DynamicPool};
+use crate::fetcher::{self, AccountFetcher};
+use crate::layouts::mercurial::SwapInfo as MercurialSwapInfo;
+use crate::layouts::phoenix::Market as PhoenixMarket;
+use crate::layouts::raydium_amm_v4::AmmInfo;
+use crate::layouts::saber::SwapInfo as SaberSwapInfo;
+use crate::layouts::sanctum::{self, InfinityPool, LstStateList, PoolState as SanctumPoolState};
+use crate::layouts::spl_stake_pool::StakePool;
+use crate::layouts::spl_token::Mint;
+use crate::layouts::spl_token_swap::TokenSwap;
+use crate::program_ids::{self, Cluster};
+use crate::pump_amm::{self, PumpAmmPool};
+use crate::raydium_cpmm::{self, RaydiumCpmmPool};
+use crate::reserves::VaultReserve;
+
+#[cfg(feature = "evm")]
+use crate::evm::{EvmAddress, UniswapV2Pair, UniswapV3Pool};
+
 declare_program!(pamm);
-use pamm::accounts::Pool;
+use pamm::accounts::{GlobalConfig as PumpAmmGlobalConfig, Pool};
+
+declare_program!(pump);
+use pump::accounts::BondingCurve;
 
 declare_program!(raydium_amm_cpmm_new);
-use raydium_amm_cpmm_new::accounts::PoolState;
+use raydium_amm_cpmm_new::accounts::{AmmConfig as RaydiumCpmmAmmConfig, PoolState};
+
+// The generated CPI helpers take one argument per instruction parameter.
+#[allow(clippy::too_many_arguments)]
+mod raydium_camm_program {
+    use anchor_lang::prelude::declare_program;
+    declare_program!(raydium_camm);
+}
+use raydium_camm::accounts::{
+    AmmConfig as RaydiumCammAmmConfig, ObservationState as RaydiumCammObservationState,
+    PoolState as RaydiumCammPoolState,
+};
+pub use raydium_camm_program::raydium_camm;
+
+declare_program!(lifinity_v2);
+use lifinity_v2::accounts::Amm as LifinityV2Amm;
+
+declare_program!(invariant);
+use invariant::accounts::Pool as InvariantPool;
+
+declare_program!(crema_clmm);
+use crema_clmm::accounts::ClmmPool as CremaClmmPool;
+
+declare_program!(aldrin_v1);
+declare_program!(aldrin_v2);
+
+declare_program!(stabble_stable_swap);
+declare_program!(stabble_weighted_swap);
+
+declare_program!(moonshot);
+use moonshot::accounts::CurveAccount as MoonshotCurveAccount;
 
-declare_program!(raydium_camm);
-use raydium_camm::accounts::PoolState as RaydiumCammPoolState;
+declare_program!(raydium_launchlab);
+use raydium_launchlab::accounts::PoolState as LaunchLabPoolState;
+
+declare_program!(meteora_dbc);
+use meteora_dbc::accounts::VirtualPool as MeteoraDbcVirtualPool;
+
+declare_program!(bonkswap);
+use bonkswap::accounts::Farm as BonkswapFarm;
+
+declare_program!(marinade);
+use marinade::accounts::State as MarinadeState;
+
+declare_program!(meteora_vault);
+use meteora_vault::accounts::Strategy as MeteoraVaultStrategy;
+pub(crate) use meteora_vault::accounts::Vault as MeteoraVaultState;
+
+declare_program!(meteora_dlmm);
+use meteora_dlmm::accounts::LbPair as MeteoraDlmmLbPair;
+
+declare_program!(orca_whirlpool);
+use orca_whirlpool::accounts::{Position as OrcaWhirlpoolPosition, Whirlpool};
 
 #[derive(Debug, Clone)]
 /// Enum representing different types of pools that can be passed to functions.
 ///
-/// Each variant contains a `program_id` which is a `Pubkey` identifying the program associated with the pool.
+/// Each Solana variant contains the `pool_address` of the account to fetch, which must be
+/// owned by the program of its protocol.
 pub enum PoolType {
     /// Represents a PumpFun AMM pool.
-    PumpFun { program_id: Pubkey },
+    PumpFun { pool_address: Pubkey },
     /// Represents a Raydium CPMM AMM pool.
-    RaydiumCpmmAmm { program_id: Pubkey },
+    RaydiumCpmmAmm { pool_address: Pubkey },
     /// Represents a Raydium CAMM pool.
-    RaydiumCamm { program_id: Pubkey },
+    RaydiumCamm { pool_address: Pubkey },
+    /// Represents a Raydium legacy AMM v4 pool.
+    RaydiumLegacyAmm { pool_address: Pubkey },
+    /// Represents a Lifinity v2 pool.
+    LifinityV2 { pool_address: Pubkey },
+    /// Represents a Phoenix order-book market.
+    Phoenix { pool_address: Pubkey },
+    /// Represents a Saber stable-swap pool.
+    SaberStableSwap { pool_address: Pubkey },
+    /// Represents a Mercurial multi-token stable-swap pool.
+    MercurialStableSwap { pool_address: Pubkey },
+    /// Represents an Invariant concentrated-liquidity pool.
+    Invariant { pool_address: Pubkey },
+    /// Represents a Crema Finance concentrated-liquidity pool.
+    CremaClmm { pool_address: Pubkey },
+    /// Represents an Aldrin AMM pool of the given layout version.
+    Aldrin {
+        pool_address: Pubkey,
+        version: AldrinVersion,
+    },
+    /// Represents a FluxBeam token-swap pool, which may hold Token-2022 mints.
+    FluxBeam { pool_address: Pubkey },
+    /// Represents the Sanctum Infinity multi-LST pool.
+    SanctumInfinity { pool_address: Pubkey },
+    /// Represents a Stabble pool of the given kind.
+    Stabble {
+        pool_address: Pubkey,
+        kind: StabblePoolKind,
+    },
+    /// Represents a pump.fun bonding curve, before the token graduates to an AMM.
+    PumpFunBondingCurve { pool_address: Pubkey },
+    /// Represents a Moonshot bonding curve.
+    Moonshot { pool_address: Pubkey },
+    /// Represents a Raydium LaunchLab launch pool.
+    RaydiumLaunchLab { pool_address: Pubkey },
+    /// Represents a Meteora Dynamic Bonding Curve virtual pool.
+    MeteoraDbc { pool_address: Pubkey },
+    /// Represents a legacy Orca constant-product pool.
+    OrcaTokenSwap { pool_address: Pubkey },
+    /// Represents a pool of the canonical SPL token-swap program or any deployment sharing its layout.
+    SplTokenSwap { pool_address: Pubkey },
+    /// Represents a Meteora dynamic vault together with its LP supply and strategies.
+    MeteoraVault { pool_address: Pubkey },
+    /// Represents a Bonkswap pool in either of its layout versions.
+    Bonkswap { pool_address: Pubkey },
+    /// Represents a Bonkswap farm, which streams up to three reward tokens to a pool's LPs.
+    BonkswapFarm { pool_address: Pubkey },
+
+    /// Represents a Uniswap V2 pair on an EVM chain, fetched from the JSON-RPC endpoint at
+    /// `rpc_url` instead of the Solana RPC client.
+    #[cfg(feature = "evm")]
+    UniswapV2 {
+        pair_address: EvmAddress,
+        rpc_url: String,
+    },
+    /// Represents a Uniswap V3 pool on an EVM chain, fetched from the JSON-RPC endpoint at
+    /// `rpc_url` instead of the Solana RPC client.
+    #[cfg(feature = "evm")]
+    UniswapV3 {
+        pool_address: EvmAddress,
+        rpc_url: String,
+    },
+    /// Represents an account of any Anchor program, decoded with an IDL loaded at runtime.
+    Dynamic {
+        pool_address: Pubkey,
+        idl: DynamicIdl,
+    },
+    /// Represents a stake pool of the SPL stake-pool program.
+    SplStakePool { pool_address: Pubkey },
+    /// Represents Marinade's liquid staking state.
+    Marinade { pool_address: Pubkey },
+    /// Represents a Raydium CAMM `AmmConfig`, which sets the fees of the pools referencing it.
+    RaydiumCammConfig { pool_address: Pubkey },
+    /// Represents the oracle observations of a Raydium CAMM pool.
+    RaydiumCammObservation { pool_address: Pubkey },
+    /// Represents a Raydium CPMM `AmmConfig`, which sets the fees of the pools referencing it.
+    RaydiumCpmmConfig { pool_address: Pubkey },
+    /// Represents a Raydium CPMM AMM pool fetched together with its `AmmConfig`.
+    RaydiumCpmmAmmWithConfig { pool_address: Pubkey },
+    /// Represents the PumpFun AMM `GlobalConfig`, which sets the fees of every pool.
+    PumpFunGlobalConfig { pool_address: Pubkey },
+    /// Represents a PumpFun AMM pool fetched together with the `GlobalConfig` and its coin
+    /// creator fee vault.
+    PumpFunWithConfig { pool_address: Pubkey },
+    /// Represents a Meteora DLMM liquidity book pair.
+    MeteoraDlmm { pool_address: Pubkey },
+    /// Represents an Orca Whirlpool concentrated liquidity pool.
+    OrcaWhirlpool { pool_address: Pubkey },
+    /// Represents a liquidity position in an Orca Whirlpool.
+    OrcaWhirlpoolPosition { pool_address: Pubkey },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How strictly account data is checked against the layout of its pool type.
+///
+/// Both modes reject accounts with the wrong discriminator, and fetched accounts owned by
+/// a program other than the pool type's.
+pub enum ParseMode {
+    /// Ignores data past the end of the layout, such as fields added by a later version
+    /// of the program, so pools keep parsing after an upgrade.
+    #[default]
+    Lenient,
+    /// Rejects data past the end of the layout with `PoolError::TrailingBytes`, so a
+    /// layout that has fallen behind the program is noticed rather than misread.
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Layout version of an Aldrin AMM pool.
+pub enum AldrinVersion {
+    /// Pools of the original constant-product program.
+    V1,
+    /// Pools of the v2 program, which adds a choice of curve.
+    V2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Kind of a Stabble pool, each backed by its own program.
+pub enum StabblePoolKind {
+    /// Amplified stable-swap pools.
+    Stable,
+    /// Balancer-style weighted pools.
+    Weighted,
 }
 
 impl PoolType {
-    pub fn program_id(&self) -> Pubkey {
+    /// Returns the address of the account to fetch.
+    pub fn pool_address(&self) -> Pubkey {
+        match self {
+            PoolType::PumpFun { pool_address } => *pool_address,
+            PoolType::RaydiumCpmmAmm { pool_address } => *pool_address,
+            PoolType::RaydiumCamm { pool_address } => *pool_address,
+            PoolType::RaydiumLegacyAmm { pool_address } => *pool_address,
+            PoolType::LifinityV2 { pool_address } => *pool_address,
+            PoolType::Phoenix { pool_address } => *pool_address,
+            PoolType::SaberStableSwap { pool_address } => *pool_address,
+            PoolType::MercurialStableSwap { pool_address } => *pool_address,
+            PoolType::Invariant { pool_address } => *pool_address,
+            PoolType::CremaClmm { pool_address } => *pool_address,
+            PoolType::Aldrin { pool_address, .. } => *pool_address,
+            PoolType::Stabble { pool_address, .. } => *pool_address,
+            PoolType::FluxBeam { pool_address } => *pool_address,
+            PoolType::SanctumInfinity { pool_address } => *pool_address,
+            PoolType::PumpFunBondingCurve { pool_address } => *pool_address,
+            PoolType::Moonshot { pool_address } => *pool_address,
+            PoolType::RaydiumLaunchLab { pool_address } => *pool_address,
+            PoolType::MeteoraDbc { pool_address } => *pool_address,
+            PoolType::OrcaTokenSwap { pool_address } => *pool_address,
+            PoolType::SplTokenSwap { pool_address } => *pool_address,
+            PoolType::MeteoraVault { pool_address } => *pool_address,
+            PoolType::Bonkswap { pool_address } => *pool_address,
+            PoolType::BonkswapFarm { pool_address } => *pool_address,
+            #[cfg(feature = "evm")]
+            PoolType::UniswapV2 { pair_address, .. } => pair_address.to_pubkey(),
+            #[cfg(feature = "evm")]
+            PoolType::UniswapV3 { pool_address, .. } => pool_address.to_pubkey(),
+            PoolType::Dynamic { pool_address, .. } => *pool_address,
+            PoolType::SplStakePool { pool_address } => *pool_address,
+            PoolType::Marinade { pool_address } => *pool_address,
+            PoolType::RaydiumCammConfig { pool_address } => *pool_address,
+            PoolType::RaydiumCammObservation { pool_address } => *pool_address,
+            PoolType::RaydiumCpmmConfig { pool_address } => *pool_address,
+            PoolType::RaydiumCpmmAmmWithConfig { pool_address } => *pool_address,
+            PoolType::PumpFunGlobalConfig { pool_address } => *pool_address,
+            PoolType::PumpFunWithConfig { pool_address } => *pool_address,
+            PoolType::MeteoraDlmm { pool_address } => *pool_address,
+            PoolType::OrcaWhirlpool { pool_address } => *pool_address,
+            PoolType::OrcaWhirlpoolPosition { pool_address } => *pool_address,
+        }
+    }
+
+    /// Returns the mainnet program owning accounts of this pool type, or `None` if the
+    /// layout is shared by several deployments.
+    ///
+    /// SPL token-swap pools can belong to any deployment of the program, and dynamic pools
+    /// to whichever program their IDL declares, if it declares a valid address. Legacy Orca
+    /// pools return the v2 program, though v1 pools are still accepted by `is_owned_by`.
+    pub fn expected_program_id(&self) -> Option<Pubkey> {
+        self.expected_program_id_on(&Cluster::Mainnet)
+    }
+
+    /// Like `expected_program_id`, but for pools deployed on `cluster`.
+    pub fn expected_program_id_on(&self, cluster: &Cluster) -> Option<Pubkey> {
+        let mainnet_id = match self {
+            PoolType::PumpFun { .. }
+            | PoolType::PumpFunGlobalConfig { .. }
+            | PoolType::PumpFunWithConfig { .. } => program_ids::PUMP_AMM,
+            PoolType::RaydiumCpmmAmm { .. }
+            | PoolType::RaydiumCpmmConfig { .. }
+            | PoolType::RaydiumCpmmAmmWithConfig { .. } => program_ids::RAYDIUM_CPMM,
+            PoolType::RaydiumCamm { .. }
+            | PoolType::RaydiumCammConfig { .. }
+            | PoolType::RaydiumCammObservation { .. } => program_ids::RAYDIUM_CLMM,
+            PoolType::RaydiumLegacyAmm { .. } => program_ids::RAYDIUM_AMM_V4,
+            PoolType::LifinityV2 { .. } => program_ids::LIFINITY_V2,
+            PoolType::Phoenix { .. } => program_ids::PHOENIX,
+            PoolType::SaberStableSwap { .. } => program_ids::SABER,
+            PoolType::MercurialStableSwap { .. } => program_ids::MERCURIAL,
+            PoolType::Invariant { .. } => program_ids::INVARIANT,
+            PoolType::CremaClmm { .. } => program_ids::CREMA_CLMM,
+            PoolType::Aldrin { version, .. } => match version {
+                AldrinVersion::V1 => program_ids::ALDRIN_V1,
+                AldrinVersion::V2 => program_ids::ALDRIN_V2,
+            },
+            PoolType::FluxBeam { .. } => program_ids::FLUXBEAM,
+            PoolType::SanctumInfinity { .. } => program_ids::SANCTUM_INFINITY,
+            PoolType::Stabble { kind, .. } => match kind {
+                StabblePoolKind::Stable => program_ids::STABBLE_STABLE_SWAP,
+                StabblePoolKind::Weighted => program_ids::STABBLE_WEIGHTED_SWAP,
+            },
+            PoolType::PumpFunBondingCurve { .. } => program_ids::PUMP_BONDING_CURVE,
+            PoolType::Moonshot { .. } => program_ids::MOONSHOT,
+            PoolType::RaydiumLaunchLab { .. } => program_ids::RAYDIUM_LAUNCHLAB,
+            PoolType::MeteoraDbc { .. } => program_ids::METEORA_DBC,
+            PoolType::OrcaTokenSwap { .. } => program_ids::ORCA_TOKEN_SWAP_V2,
+            PoolType::SplTokenSwap { .. } => return None,
+            PoolType::MeteoraVault { .. } => program_ids::METEORA_VAULT,
+            PoolType::Bonkswap { .. } | PoolType::BonkswapFarm { .. } => program_ids::BONKSWAP,
+            // EVM pools aren't Solana accounts.
+            #[cfg(feature = "evm")]
+            PoolType::UniswapV2 { .. } | PoolType::UniswapV3 { .. } => return None,
+            PoolType::Dynamic { idl, .. } => Pubkey::from_str(idl.address()).ok()?,
+            PoolType::SplStakePool { .. } => program_ids::SPL_STAKE_POOL,
+            PoolType::Marinade { .. } => program_ids::MARINADE,
+            PoolType::MeteoraDlmm { .. } => program_ids::METEORA_DLMM,
+            PoolType::OrcaWhirlpool { .. } | PoolType::OrcaWhirlpoolPosition { .. } => {
+                program_ids::ORCA_WHIRLPOOL
+            }
+        };
+        Some(cluster.program_id(mainnet_id))
+    }
+
+    /// Returns whether an account on `cluster` owned by `owner` can be an account of this
+    /// pool type, which is any owner for pool types without an `expected_program_id`.
+    pub fn is_owned_by(&self, owner: &Pubkey, cluster: &Cluster) -> bool {
         match self {
-            PoolType::PumpFun { program_id } => *program_id,
-            PoolType::RaydiumCpmmAmm { program_id } => *program_id,
-            PoolType::RaydiumCamm { program_id } => *program_id,
+            PoolType::OrcaTokenSwap { .. } => {
+                *owner == cluster.program_id(program_ids::ORCA_TOKEN_SWAP_V1)
+                    || *owner == cluster.program_id(program_ids::ORCA_TOKEN_SWAP_V2)
+            }
+            _ => self
+                .expected_program_id_on(cluster)
+                .is_none_or(|program_id| *owner == program_id),
         }
     }
 
@@ -39,6 +349,42 @@ impl PoolType {
             PoolType::PumpFun { .. } => "PumpFun AMM",
             PoolType::RaydiumCpmmAmm { .. } => "Raydium AMM",
             PoolType::RaydiumCamm { .. } => "Raydium AMM",
+            PoolType::RaydiumLegacyAmm { .. } => "Raydium Legacy AMM",
+            PoolType::LifinityV2 { .. } => "Lifinity v2",
+            PoolType::Phoenix { .. } => "Phoenix",
+            PoolType::SaberStableSwap { .. } => "Saber Stable Swap",
+            PoolType::MercurialStableSwap { .. } => "Mercurial Stable Swap",
+            PoolType::Invariant { .. } => "Invariant CLMM",
+            PoolType::CremaClmm { .. } => "Crema CLMM",
+            PoolType::Aldrin { .. } => "Aldrin AMM",
+            PoolType::FluxBeam { .. } => "FluxBeam",
+            PoolType::SanctumInfinity { .. } => "Sanctum Infinity",
+            PoolType::Stabble { .. } => "Stabble",
+            PoolType::PumpFunBondingCurve { .. } => "PumpFun Bonding Curve",
+            PoolType::Moonshot { .. } => "Moonshot",
+            PoolType::RaydiumLaunchLab { .. } => "Raydium LaunchLab",
+            PoolType::MeteoraDbc { .. } => "Meteora DBC",
+            PoolType::OrcaTokenSwap { .. } => "Orca Token Swap",
+            PoolType::SplTokenSwap { .. } => "SPL Token Swap",
+            PoolType::MeteoraVault { .. } => "Meteora Vault",
+            PoolType::Bonkswap { .. } => "Bonkswap",
+            PoolType::BonkswapFarm { .. } => "Bonkswap Farm",
+            #[cfg(feature = "evm")]
+            PoolType::UniswapV2 { .. } => "Uniswap V2",
+            #[cfg(feature = "evm")]
+            PoolType::UniswapV3 { .. } => "Uniswap V3",
+            PoolType::Dynamic { .. } => "Dynamic",
+            PoolType::SplStakePool { .. } => "SPL Stake Pool",
+            PoolType::Marinade { .. } => "Marinade",
+            PoolType::RaydiumCammConfig { .. } => "Raydium CAMM Config",
+            PoolType::RaydiumCammObservation { .. } => "Raydium CAMM Observation",
+            PoolType::RaydiumCpmmConfig { .. } => "Raydium CPMM Config",
+            PoolType::RaydiumCpmmAmmWithConfig { .. } => "Raydium AMM",
+            PoolType::PumpFunGlobalConfig { .. } => "PumpFun Global Config",
+            PoolType::PumpFunWithConfig { .. } => "PumpFun AMM",
+            PoolType::MeteoraDlmm { .. } => "Meteora DLMM",
+            PoolType::OrcaWhirlpool { .. } => "Orca Whirlpool",
+            PoolType::OrcaWhirlpoolPosition { .. } => "Orca Whirlpool Position",
         }
     }
 }
@@ -48,62 +394,240 @@ impl PoolType {
 pub enum PoolError {
     /// Error that occurs when there is an issue with the RPC client.
     ///
-    /// This error wraps the underlying `ClientError` from the `anchor_client` crate.
+    /// This error wraps the underlying `ClientError` from the `anchor_client` crate. It is
+    /// boxed because `ClientError` is large enough to bloat every `Result` it's returned in.
     #[error("RPC client error: {0}")]
-    RpcError(#[from] anchor_client::solana_client::client_error::ClientError),
+    RpcError(#[source] Box<anchor_client::solana_client::client_error::ClientError>),
 
     /// Error that occurs when deserialization of pool data fails.
     ///
     /// This error wraps the underlying `Error` from the `anchor_lang` crate.
     #[error("Deserialization error: {0}")]
     DeserializeError(#[from] anchor_lang::error::Error),
+
+    /// Error that occurs when decoding an account with a runtime IDL.
+    #[error("Dynamic IDL error: {0}")]
+    DynamicError(#[from] DynamicError),
+
+    /// Error that occurs when an account doesn't exist, because it was never created or
+    /// has been closed, as pools are after migrating to another program. Unlike RPC
+    /// errors, it isn't transient.
+    #[error("Account {address} not found")]
+    AccountNotFound { address: Pubkey },
+
+    /// Error that occurs when an account is owned by a program this crate can't parse.
+    #[error("Unsupported program: {0}")]
+    UnsupportedProgram(Pubkey),
+
+    /// Error that occurs when the fetched account isn't owned by the program of the
+    /// requested pool type, so its data would be parsed with the wrong layout.
+    #[error("{0} account is owned by unexpected program {1}")]
+    UnexpectedOwner(&'static str, Pubkey),
+
+    /// Error that occurs when a pool type needs accounts besides its own to be parsed.
+    #[error("{0} can't be parsed from its account data alone")]
+    IncompleteData(&'static str),
+
+    /// Error that occurs in strict mode when account data extends past the layout of its
+    /// pool type by the given number of bytes.
+    #[error("{0} account has {1} bytes past the end of its layout")]
+    TrailingBytes(&'static str, usize),
+
+    /// Error that occurs when a pool type isn't enabled in a `UniversalParser`.
+    #[error("{0} is not enabled")]
+    ProtocolDisabled(&'static str),
+
+    /// Error that occurs when a `UniversalParser` is configured inconsistently.
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// Error that occurs when a pool can't be written back to the data of its account.
+    #[error("{0} can't be written back to account data")]
+    NotSerializable(&'static str),
+
+    /// Error that occurs when hot fields are requested for a pool type without them.
+    #[error("{0} has no hot fields")]
+    NoHotFields(&'static str),
+
+    /// Error that occurs when pools of a pool type can't be told apart from the other
+    /// accounts of its program, or need more than their own account to be parsed in bulk.
+    #[error("{0} pools can't be discovered")]
+    NotDiscoverable(&'static str),
+
+    /// Error that occurs when reading an account dump from disk.
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// Error that occurs when an account dump isn't in the format it's read as.
+    #[error("Invalid account dump: {0}")]
+    InvalidDump(String),
+
+    /// Error that occurs when the accounts of a pool were read at the given earliest and
+    /// latest slots, and couldn't be read again at one slot, as `Consistency::SameSlot`
+    /// requires. Parsing the pool again may succeed.
+    #[error("Accounts read at slots {0} to {1} rather than at one slot")]
+    InconsistentSlots(u64, u64),
+
+    /// Error that occurs when a request, or a fetch as a whole, takes longer than allowed.
+    /// Timeouts are transient, so the request may be retried.
+    #[error("Timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
+    /// Error that occurs when fetching an EVM pool over Ethereum JSON-RPC.
+    #[cfg(feature = "evm")]
+    #[error("EVM error: {0}")]
+    EvmError(#[from] crate::evm::EvmError),
 }
 
-fn handle_pump_amm_deserialize(
-    program_id: Pubkey,
-    con: &RpcClient,
-) -> std::result::Result<Pool, PoolError> {
-    let data = match con.get_account_data(&program_id) {
-        Ok(data) => data,
-        Err(e) => return Err(PoolError::RpcError(e)),
-    };
-    let pool = match Pool::try_deserialize(&mut &data[..]) {
-        Ok(pool) => pool,
-        Err(e) => return Err(PoolError::DeserializeError(e)),
-    };
-    Ok(pool)
+/// Result of the fallible operations of this crate.
+pub type Result<T> = std::result::Result<T, PoolError>;
+
+impl From<anchor_client::solana_client::client_error::ClientError> for PoolError {
+    /// Converts the error `RpcClient::get_account` and `get_account_data` return for
+    /// missing accounts to `PoolError::AccountNotFound`. The client reports other errors
+    /// of `getAccountInfo` with the same prefix followed by the error, which stay
+    /// `PoolError::RpcError`s.
+    fn from(e: anchor_client::solana_client::client_error::ClientError) -> Self {
+        if let ClientErrorKind::RpcError(RpcError::ForUser(message)) = e.kind() {
+            let address = message.strip_prefix("AccountNotFound: pubkey=");
+            if let Some(Ok(address)) = address.map(Pubkey::from_str) {
+                return PoolError::AccountNotFound { address };
+            }
+        }
+        PoolError::RpcError(Box::new(e))
+    }
 }
 
-fn handle_raydium_cpmm_amm_deserialize(
-    program_id: Pubkey,
-    con: &RpcClient,
-) -> std::result::Result<raydium_amm_cpmm_new::accounts::PoolState, PoolError> {
-    let data = match con.get_account_data(&program_id) {
-        Ok(data) => data,
-        Err(e) => return Err(PoolError::RpcError(e)),
-    };
-    let pool = match PoolState::try_deserialize(&mut &data[..]) {
-        Ok(pool_state) => pool_state,
+/// Fetches the LST state list that completes an already parsed Infinity pool `state`.
+pub(crate) async fn sanctum_infinity_with_lst_states<F: AccountFetcher>(
+    state: SanctumPoolState,
+    fetcher: &F,
+) -> std::result::Result<InfinityPool, PoolError> {
+    let address = LstStateList::address(&sanctum::ID);
+    let data = fetcher::fetch_account_data(fetcher, &address).await?;
+    sanctum_infinity_from_lst_states(state, &data)
+}
+
+fn sanctum_infinity_from_lst_states(
+    state: SanctumPoolState,
+    data: &[u8],
+) -> std::result::Result<InfinityPool, PoolError> {
+    let lst_states = match LstStateList::try_deserialize(&mut &data[..]) {
+        Ok(lst_states) => lst_states,
         Err(e) => return Err(PoolError::DeserializeError(e)),
     };
-    Ok(pool)
+    Ok(InfinityPool { state, lst_states })
 }
 
-fn handle_raydium_camm_deserialize(
-    program_id: Pubkey,
-    con: &RpcClient,
-) -> std::result::Result<RaydiumCammPoolState, PoolError> {
-    let data = match con.get_account_data(&program_id) {
-        Ok(data) => data,
-        Err(e) => return Err(PoolError::RpcError(e)),
-    };
-    let pool = match RaydiumCammPoolState::try_deserialize(&mut &data[..]) {
-        Ok(pool_state) => pool_state,
+/// Fetches the LP supply and strategies that complete an already parsed Meteora `vault`.
+pub(crate) async fn meteora_vault_with_strategies<F: AccountFetcher>(
+    vault: MeteoraVaultState,
+    fetcher: &F,
+) -> std::result::Result<MeteoraVault, PoolError> {
+    let data = fetcher::fetch_account_data(fetcher, &vault.lp_mint).await?;
+    let accounts = fetcher.fetch_accounts(&strategy_keys(&vault)).await?.value;
+    meteora_vault_from_accounts(vault, &data, accounts)
+}
+
+/// Returns the strategies in use by `vault`; unused strategy slots are left as the
+/// default key.
+fn strategy_keys(vault: &MeteoraVaultState) -> Vec<Pubkey> {
+    vault
+        .strategies
+        .iter()
+        .filter(|key| **key != Pubkey::default())
+        .copied()
+        .collect()
+}
+
+fn meteora_vault_from_accounts(
+    vault: MeteoraVaultState,
+    lp_mint_data: &[u8],
+    strategy_accounts: Vec<Option<Account>>,
+) -> std::result::Result<MeteoraVault, PoolError> {
+    let lp_supply = match Mint::try_deserialize(&mut &lp_mint_data[..]) {
+        Ok(mint) => mint.supply,
         Err(e) => return Err(PoolError::DeserializeError(e)),
     };
-    Ok(pool)
+    let mut strategies = Vec::with_capacity(strategy_accounts.len());
+    for account in strategy_accounts.into_iter().flatten() {
+        match MeteoraVaultStrategy::try_deserialize(&mut &account.data[..]) {
+            Ok(strategy) => strategies.push(strategy),
+            Err(e) => return Err(PoolError::DeserializeError(e)),
+        }
+    }
+    Ok(MeteoraVault {
+        vault,
+        lp_supply,
+        strategies,
+    })
+}
+
+#[derive(Debug, Serialize)]
+/// An Aldrin AMM pool in either of its layout versions.
+pub enum AldrinPool {
+    V1(aldrin_v1::accounts::Pool),
+    V2(aldrin_v2::accounts::Pool),
+}
+
+#[derive(Debug, Serialize)]
+/// A Bonkswap pool in either of its layout versions.
+pub enum BonkswapPool {
+    V1(bonkswap::accounts::Pool),
+    /// Pools created after the upgrade that added a dedicated LP mint.
+    V2(bonkswap::accounts::PoolV2),
+}
+
+#[derive(Debug, Serialize)]
+/// A Stabble pool of either kind.
+pub enum StabblePool {
+    Stable(stabble_stable_swap::accounts::Pool),
+    Weighted(stabble_weighted_swap::accounts::Pool),
+}
+
+/// Denominator of `LockedProfitTracker::locked_profit_degradation`.
+const LOCKED_PROFIT_DEGRADATION_DENOMINATOR: u128 = 1_000_000_000_000;
+
+#[derive(Debug, Serialize)]
+/// A Meteora dynamic vault with the LP supply and strategies needed to value its shares.
+pub struct MeteoraVault {
+    pub vault: MeteoraVaultState,
+    /// Supply of the vault's LP mint.
+    pub lp_supply: u64,
+    /// Strategies the vault lends its liquidity to, in the order of `vault.strategies`.
+    pub strategies: Vec<MeteoraVaultStrategy>,
+}
+
+impl MeteoraVault {
+    /// Returns the vault's total amount minus the profit that is still being dripped
+    /// in, as of the unix timestamp `current_time`.
+    pub fn unlocked_amount(&self, current_time: u64) -> Option<u64> {
+        let tracker = &self.vault.locked_profit_tracker;
+        let duration = u128::from(current_time.checked_sub(tracker.last_report)?);
+        let ratio = duration.checked_mul(u128::from(tracker.locked_profit_degradation))?;
+        let locked_profit = if ratio > LOCKED_PROFIT_DEGRADATION_DENOMINATOR {
+            0
+        } else {
+            let locked = u128::from(tracker.last_updated_locked_profit)
+                .checked_mul(LOCKED_PROFIT_DEGRADATION_DENOMINATOR - ratio)?
+                / LOCKED_PROFIT_DEGRADATION_DENOMINATOR;
+            u64::try_from(locked).ok()?
+        };
+        self.vault.total_amount.checked_sub(locked_profit)
+    }
+
+    /// Returns the amount of the underlying token withdrawable for `share` LP tokens.
+    pub fn amount_by_share(&self, current_time: u64, share: u64) -> Option<u64> {
+        let total_amount = self.unlocked_amount(current_time)?;
+        let amount = u128::from(share)
+            .checked_mul(u128::from(total_amount))?
+            .checked_div(u128::from(self.lp_supply))?;
+        u64::try_from(amount).ok()
+    }
 }
 
+#[derive(Serialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum AmmPool {
     /// Represents a PumpFun AMM pool.
     PumpFun(Pool),
@@ -111,6 +635,77 @@ pub enum AmmPool {
     RaydiumCpmmAmm(PoolState),
     /// Represents a Raydium CAMM pool.
     RaydiumCamm(RaydiumCammPoolState),
+    /// Represents a Raydium legacy AMM v4 pool.
+    RaydiumLegacyAmm(AmmInfo),
+    /// Represents a Lifinity v2 pool.
+    LifinityV2(LifinityV2Amm),
+    /// Represents a Phoenix order-book market.
+    Phoenix(PhoenixMarket),
+    /// Represents a Saber stable-swap pool.
+    SaberStableSwap(SaberSwapInfo),
+    /// Represents a Mercurial multi-token stable-swap pool.
+    MercurialStableSwap(MercurialSwapInfo),
+    /// Represents an Invariant concentrated-liquidity pool.
+    Invariant(InvariantPool),
+    /// Represents a Crema Finance concentrated-liquidity pool.
+    CremaClmm(CremaClmmPool),
+    /// Represents an Aldrin AMM pool.
+    Aldrin(AldrinPool),
+    /// Represents a FluxBeam token-swap pool, which may hold Token-2022 mints.
+    FluxBeam(TokenSwap),
+    /// Represents the Sanctum Infinity multi-LST pool.
+    SanctumInfinity(InfinityPool),
+    /// Represents a Stabble pool.
+    Stabble(StabblePool),
+    /// Represents a pump.fun bonding curve, before the token graduates to an AMM.
+    PumpFunBondingCurve(BondingCurve),
+    /// Represents a Moonshot bonding curve.
+    Moonshot(MoonshotCurveAccount),
+    /// Represents a Raydium LaunchLab launch pool.
+    RaydiumLaunchLab(LaunchLabPoolState),
+    /// Represents a Meteora Dynamic Bonding Curve virtual pool.
+    MeteoraDbc(MeteoraDbcVirtualPool),
+    /// Represents a legacy Orca constant-product pool.
+    OrcaTokenSwap(TokenSwap),
+    /// Represents a pool of the canonical SPL token-swap program or any deployment sharing its layout.
+    SplTokenSwap(TokenSwap),
+    /// Represents a Meteora dynamic vault together with its LP supply and strategies.
+    MeteoraVault(MeteoraVault),
+    /// Represents a Bonkswap pool in either of its layout versions.
+    Bonkswap(BonkswapPool),
+    /// Represents a Bonkswap farm, which streams up to three reward tokens to a pool's LPs.
+    BonkswapFarm(BonkswapFarm),
+    /// Represents a Uniswap V2 pair on an EVM chain.
+    #[cfg(feature = "evm")]
+    UniswapV2(UniswapV2Pair),
+    /// Represents a Uniswap V3 pool on an EVM chain.
+    #[cfg(feature = "evm")]
+    UniswapV3(UniswapV3Pool),
+    /// Represents an account of any Anchor program, decoded with an IDL loaded at runtime.
+    Dynamic(DynamicPool),
+    /// Represents a stake pool of the SPL stake-pool program.
+    SplStakePool(StakePool),
+    /// Represents Marinade's liquid staking state.
+    Marinade(MarinadeState),
+    /// Represents a Raydium CAMM `AmmConfig`, which sets the fees of the pools referencing it.
+    RaydiumCammConfig(RaydiumCammAmmConfig),
+    /// Represents the oracle observations of a Raydium CAMM pool.
+    RaydiumCammObservation(RaydiumCammObservationState),
+    /// Represents a Raydium CPMM `AmmConfig`, which sets the fees of the pools referencing it.
+    RaydiumCpmmConfig(RaydiumCpmmAmmConfig),
+    /// Represents a Raydium CPMM AMM pool fetched together with its `AmmConfig`.
+    RaydiumCpmmAmmWithConfig(RaydiumCpmmPool),
+    /// Represents the PumpFun AMM `GlobalConfig`, which sets the fees of every pool.
+    PumpFunGlobalConfig(PumpAmmGlobalConfig),
+    /// Represents a PumpFun AMM pool fetched together with the `GlobalConfig` and its coin
+    /// creator fee vault.
+    PumpFunWithConfig(PumpAmmPool),
+    /// Represents a Meteora DLMM liquidity book pair.
+    MeteoraDlmm(MeteoraDlmmLbPair),
+    /// Represents an Orca Whirlpool concentrated liquidity pool.
+    OrcaWhirlpool(Whirlpool),
+    /// Represents a liquidity position in an Orca Whirlpool.
+    OrcaWhirlpoolPosition(OrcaWhirlpoolPosition),
 }
 
 impl Debug for AmmPool {
@@ -121,49 +716,424 @@ impl Debug for AmmPool {
                 f.debug_tuple("Raydium").field(pool_state).finish()
             }
             AmmPool::RaydiumCamm(pool_state) => f.debug_tuple("Raydium").field(pool_state).finish(),
+            AmmPool::RaydiumLegacyAmm(amm_info) => {
+                f.debug_tuple("RaydiumLegacy").field(amm_info).finish()
+            }
+            AmmPool::LifinityV2(pool) => f.debug_tuple("LifinityV2").field(pool).finish(),
+            AmmPool::Phoenix(pool) => f.debug_tuple("Phoenix").field(pool).finish(),
+            AmmPool::SaberStableSwap(pool) => f.debug_tuple("SaberStableSwap").field(pool).finish(),
+            AmmPool::MercurialStableSwap(pool) => {
+                f.debug_tuple("MercurialStableSwap").field(pool).finish()
+            }
+            AmmPool::Invariant(pool) => f.debug_tuple("Invariant").field(pool).finish(),
+            AmmPool::CremaClmm(pool) => f.debug_tuple("CremaClmm").field(pool).finish(),
+            AmmPool::Aldrin(pool) => f.debug_tuple("Aldrin").field(pool).finish(),
+            AmmPool::FluxBeam(pool) => f.debug_tuple("FluxBeam").field(pool).finish(),
+            AmmPool::SanctumInfinity(pool) => f.debug_tuple("SanctumInfinity").field(pool).finish(),
+            AmmPool::Stabble(pool) => f.debug_tuple("Stabble").field(pool).finish(),
+            AmmPool::PumpFunBondingCurve(pool) => {
+                f.debug_tuple("PumpFunBondingCurve").field(pool).finish()
+            }
+            AmmPool::Moonshot(pool) => f.debug_tuple("Moonshot").field(pool).finish(),
+            AmmPool::RaydiumLaunchLab(pool) => {
+                f.debug_tuple("RaydiumLaunchLab").field(pool).finish()
+            }
+            AmmPool::MeteoraDbc(pool) => f.debug_tuple("MeteoraDbc").field(pool).finish(),
+            AmmPool::OrcaTokenSwap(pool) => f.debug_tuple("OrcaTokenSwap").field(pool).finish(),
+            AmmPool::SplTokenSwap(pool) => f.debug_tuple("SplTokenSwap").field(pool).finish(),
+            AmmPool::MeteoraVault(pool) => f.debug_tuple("MeteoraVault").field(pool).finish(),
+            AmmPool::Bonkswap(pool) => f.debug_tuple("Bonkswap").field(pool).finish(),
+            AmmPool::BonkswapFarm(pool) => f.debug_tuple("BonkswapFarm").field(pool).finish(),
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV2(pool) => f.debug_tuple("UniswapV2").field(pool).finish(),
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV3(pool) => f.debug_tuple("UniswapV3").field(pool).finish(),
+            AmmPool::Dynamic(pool) => f.debug_tuple("Dynamic").field(pool).finish(),
+            AmmPool::SplStakePool(pool) => f.debug_tuple("SplStakePool").field(pool).finish(),
+            AmmPool::Marinade(pool) => f.debug_tuple("Marinade").field(pool).finish(),
+            AmmPool::RaydiumCammConfig(pool) => {
+                f.debug_tuple("RaydiumCammConfig").field(pool).finish()
+            }
+            AmmPool::RaydiumCammObservation(pool) => {
+                f.debug_tuple("RaydiumCammObservation").field(pool).finish()
+            }
+            AmmPool::RaydiumCpmmConfig(pool) => {
+                f.debug_tuple("RaydiumCpmmConfig").field(pool).finish()
+            }
+            AmmPool::RaydiumCpmmAmmWithConfig(pool) => f
+                .debug_tuple("RaydiumCpmmAmmWithConfig")
+                .field(pool)
+                .finish(),
+            AmmPool::PumpFunGlobalConfig(pool) => {
+                f.debug_tuple("PumpFunGlobalConfig").field(pool).finish()
+            }
+            AmmPool::PumpFunWithConfig(pool) => {
+                f.debug_tuple("PumpFunWithConfig").field(pool).finish()
+            }
+            AmmPool::MeteoraDlmm(pool) => f.debug_tuple("MeteoraDlmm").field(pool).finish(),
+            AmmPool::OrcaWhirlpool(pool) => f.debug_tuple("OrcaWhirlpool").field(pool).finish(),
+            AmmPool::OrcaWhirlpoolPosition(pool) => {
+                f.debug_tuple("OrcaWhirlpoolPosition").field(pool).finish()
+            }
         }
     }
 }
 
-/// Retrieves the information structure for a given pool type and RPC URL.
+#[derive(Debug)]
+/// A pool returned by `get_info_struct`, with the context it was fetched in.
+pub struct ParsedPool {
+    pub pool: AmmPool,
+    /// Address of the pool account, or of the EVM pool widened to a `Pubkey`.
+    pub address: Pubkey,
+    /// Slot the RPC node had reached when it returned the account, or `None` for EVM
+    /// pools.
+    pub slot: Option<u64>,
+    /// When the account was returned by the RPC node.
+    pub fetched_at: SystemTime,
+    /// Data of the pool account, or `None` for EVM pools.
+    pub raw: Option<Vec<u8>>,
+    /// Balances of the vaults of the pool, in the order of `reserves::vault_addresses`,
+    /// if they were fetched with it.
+    pub reserves: Option<Vec<VaultReserve>>,
+}
+
+/// Fetches the account at the address of `pool_type` and parses it as that pool type.
+///
+/// Pool types whose parsed form includes other accounts, such as the `GlobalConfig` of a
+/// PumpFun AMM pool or the Sanctum LST state list, fetch those as well.
 ///
 /// # Arguments
 ///
-/// * `pool_type` - An enum representing the type of pool (PumpFun or Raydium) and its associated program ID.
-/// * `rpc_client` - A borrowed instance of the `RpcClient`.
+/// * `pool_type` - The kind of pool and the address of its account.
+/// * `rpc_client` - The `AccountFetcher` to read the account through, usually the
+///   nonblocking `RpcClient`. The blocking `RpcClient` is also accepted, but blocks the
+///   calling thread on each request.
 ///
 /// # Returns
 ///
-/// This function returns a `Result` containing an `AmmPool` enum, which can be either a `PumpFun` pool or a `Raydium` pool, or an error if the operation fails.
+/// A `ParsedPool`, which holds the parsed `AmmPool` along with the address, slot and data
+/// of its account.
 ///
 /// # Errors
 ///
-/// This function will return an error if there is an issue with deserializing the pool data or if the RPC client encounters an error.
+/// Returns an error if the request fails or the account data doesn't parse, and
+/// `PoolError::UnexpectedOwner` if the account isn't owned by the program of `pool_type`.
 ///
 /// # Examples
 ///
+/// ```no_run
+/// # use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+/// # use anchor_lang::prelude::Pubkey;
+/// # use std::str::FromStr;
+/// # use universal_liquidity_pool_parser::{get_info_struct, parsing::PoolType};
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let rpc_client = RpcClient::new(std::env::var("ULP_RPC_URL")?);
+/// let pool_address = Pubkey::from_str("POOL_ADDRESS_HERE")?;
+/// let pool_type = PoolType::RaydiumCpmmAmm { pool_address };
+/// let parsed = get_info_struct(pool_type, &rpc_client).await?;
+/// println!("{:?} at slot {:?}", parsed.pool, parsed.slot);
+/// # Ok(())
+/// # }
 /// ```
-/// let rpc_client = RpcClient::new("https://mainnet.helius-rpc.com/?api-key=...");
-/// let program_id = Pubkey::from_str("PROGRAM_ID_HERE")?;
-/// let pool_type = PoolType::Raydium { program_id };
-/// let pool_info = get_info_struct(pool_type, &rpc_client).await?;
-/// println!("{:?}", pool_info);
-/// ```
-pub async fn get_info_struct(
+pub async fn get_info_struct<F: AccountFetcher>(
     pool_type: PoolType,
-    rpc_client: &RpcClient,
-) -> std::result::Result<AmmPool, PoolError> {
+    rpc_client: &F,
+) -> std::result::Result<ParsedPool, PoolError> {
+    get_info_struct_on(pool_type, rpc_client, &Cluster::Mainnet).await
+}
+
+/// Like `get_info_struct`, but for a pool deployed on `cluster`, whose program ids decide
+/// the owners the account is accepted from.
+///
+/// Companion accounts derived from a program id, such as the PumpFun AMM `GlobalConfig`
+/// and the Sanctum LST state list, are still derived from the mainnet programs.
+pub async fn get_info_struct_on<F: AccountFetcher>(
+    pool_type: PoolType,
+    rpc_client: &F,
+    cluster: &Cluster,
+) -> std::result::Result<ParsedPool, PoolError> {
+    get_info_struct_with(pool_type, rpc_client, cluster, ParseMode::Lenient).await
+}
+
+/// Like `get_info_struct_on`, but checks the account data as `mode` requires.
+pub(crate) async fn get_info_struct_with<F: AccountFetcher>(
+    pool_type: PoolType,
+    rpc_client: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+) -> std::result::Result<ParsedPool, PoolError> {
+    let address = pool_type.pool_address();
+
+    #[cfg(feature = "evm")]
+    {
+        let pool = match &pool_type {
+            PoolType::UniswapV2 {
+                pair_address,
+                rpc_url,
+            } => Some(AmmPool::UniswapV2(
+                UniswapV2Pair::fetch(rpc_url, *pair_address).await?,
+            )),
+            PoolType::UniswapV3 {
+                pool_address,
+                rpc_url,
+            } => Some(AmmPool::UniswapV3(
+                UniswapV3Pool::fetch(rpc_url, *pool_address).await?,
+            )),
+            _ => None,
+        };
+        if let Some(pool) = pool {
+            return Ok(ParsedPool {
+                pool,
+                address,
+                slot: None,
+                fetched_at: SystemTime::now(),
+                raw: None,
+                reserves: None,
+            });
+        }
+    }
+
+    let response = rpc_client.fetch_account(&address).await?;
+    let fetched_at = SystemTime::now();
+    let Some(account) = response.value else {
+        return Err(PoolError::AccountNotFound { address });
+    };
+    let slot = response.context.slot;
+    parse_account_with(
+        pool_type, account, slot, fetched_at, rpc_client, cluster, mode,
+    )
+    .await
+}
+
+/// Parses `account`, fetched at `slot`, as the account of `pool_type`, fetching the other
+/// accounts some pool types are completed with.
+pub(crate) async fn parse_account_with<F: AccountFetcher>(
+    pool_type: PoolType,
+    account: Account,
+    slot: u64,
+    fetched_at: SystemTime,
+    rpc_client: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+) -> std::result::Result<ParsedPool, PoolError> {
+    let address = pool_type.pool_address();
+    if !pool_type.is_owned_by(&account.owner, cluster) {
+        return Err(PoolError::UnexpectedOwner(
+            pool_type.pool_name(),
+            account.owner,
+        ));
+    }
+    let data = &account.data[..];
+
+    // Pool types completed with other accounts parse their own account here and fetch the
+    // rest; every other type is parsed from its data alone.
     let pool = match pool_type {
-        PoolType::PumpFun { program_id } => {
-            AmmPool::PumpFun(handle_pump_amm_deserialize(program_id, &rpc_client)?)
+        PoolType::SanctumInfinity { .. } => {
+            let state = SanctumPoolState::try_deserialize(&mut &data[..])?;
+            AmmPool::SanctumInfinity(sanctum_infinity_with_lst_states(state, rpc_client).await?)
         }
-        PoolType::RaydiumCpmmAmm { program_id } => AmmPool::RaydiumCpmmAmm(
-            handle_raydium_cpmm_amm_deserialize(program_id, &rpc_client)?,
-        ),
-        PoolType::RaydiumCamm { program_id } => {
-            AmmPool::RaydiumCamm(handle_raydium_camm_deserialize(program_id, &rpc_client)?)
+        PoolType::MeteoraVault { .. } => {
+            let vault = MeteoraVaultState::try_deserialize(&mut &data[..])?;
+            let vault = meteora_vault_with_strategies(vault, rpc_client).await?;
+            AmmPool::MeteoraVault(vault)
         }
+        PoolType::RaydiumCpmmAmmWithConfig { .. } => {
+            let pool = PoolState::try_deserialize(&mut &data[..])?;
+            let amm_config = raydium_cpmm::fetch_amm_config(&pool, rpc_client).await?;
+            AmmPool::RaydiumCpmmAmmWithConfig(RaydiumCpmmPool { pool, amm_config })
+        }
+        PoolType::PumpFunWithConfig { .. } => {
+            let pool = Pool::try_deserialize(&mut &data[..])?;
+            let global_config = pump_amm::fetch_global_config(rpc_client).await?;
+            let creator_vault = pump_amm::fetch_creator_vault(&pool, rpc_client).await?;
+            AmmPool::PumpFunWithConfig(PumpAmmPool {
+                pool,
+                global_config,
+                creator_vault,
+            })
+        }
+        _ => parse_from_bytes(&pool_type, data)?,
     };
+    check_layout_len(&pool_type, &pool, data.len(), mode)?;
 
+    Ok(ParsedPool {
+        pool,
+        address,
+        slot: Some(slot),
+        fetched_at,
+        raw: Some(account.data),
+        reserves: None,
+    })
+}
+
+/// Like `parse_from_bytes`, but checks the data as `mode` requires.
+pub fn parse_from_bytes_with_mode(
+    pool_type: &PoolType,
+    data: &[u8],
+    mode: ParseMode,
+) -> std::result::Result<AmmPool, PoolError> {
+    let pool = parse_from_bytes(pool_type, data)?;
+    check_layout_len(pool_type, &pool, data.len(), mode)?;
     Ok(pool)
 }
+
+/// Fails in strict mode if `pool` was parsed from `len` bytes of data that extend past its
+/// layout.
+///
+/// Phoenix markets and dynamic pools aren't checked, since neither is written back to
+/// measure its layout, and nor are SPL stake pools, which are allocated with room to
+/// spare.
+pub(crate) fn check_layout_len(
+    pool_type: &PoolType,
+    pool: &AmmPool,
+    len: usize,
+    mode: ParseMode,
+) -> std::result::Result<(), PoolError> {
+    if mode == ParseMode::Lenient
+        || matches!(
+            pool,
+            AmmPool::Phoenix(_) | AmmPool::SplStakePool(_) | AmmPool::Dynamic(_)
+        )
+    {
+        return Ok(());
+    }
+    let layout_len = pool.to_account_bytes()?.len();
+    if len > layout_len {
+        return Err(PoolError::TrailingBytes(
+            pool_type.pool_name(),
+            len - layout_len,
+        ));
+    }
+    Ok(())
+}
+
+/// Parses account data obtained elsewhere, such as from Geyser, a snapshot or a test
+/// fixture, as the account of `pool_type`, without an RPC client.
+///
+/// The address in `pool_type` is ignored. Pool types that `get_info_struct` completes
+/// with other accounts, such as the Sanctum LST state list or a PumpFun AMM pool's
+/// `GlobalConfig`, return `PoolError::IncompleteData`; parse their accounts separately.
+pub fn parse_from_bytes(
+    pool_type: &PoolType,
+    data: &[u8],
+) -> std::result::Result<AmmPool, PoolError> {
+    let mut data = data;
+    let pool = match pool_type {
+        PoolType::PumpFun { .. } => Pool::try_deserialize(&mut data).map(AmmPool::PumpFun),
+        PoolType::RaydiumCpmmAmm { .. } => {
+            PoolState::try_deserialize(&mut data).map(AmmPool::RaydiumCpmmAmm)
+        }
+        PoolType::RaydiumCamm { .. } => {
+            RaydiumCammPoolState::try_deserialize(&mut data).map(AmmPool::RaydiumCamm)
+        }
+        PoolType::RaydiumLegacyAmm { .. } => {
+            AmmInfo::try_deserialize(&mut data).map(AmmPool::RaydiumLegacyAmm)
+        }
+        PoolType::LifinityV2 { .. } => {
+            LifinityV2Amm::try_deserialize(&mut data).map(AmmPool::LifinityV2)
+        }
+        PoolType::Phoenix { .. } => PhoenixMarket::try_deserialize(&mut data).map(AmmPool::Phoenix),
+        PoolType::SaberStableSwap { .. } => {
+            SaberSwapInfo::try_deserialize(&mut data).map(AmmPool::SaberStableSwap)
+        }
+        PoolType::MercurialStableSwap { .. } => {
+            MercurialSwapInfo::try_deserialize(&mut data).map(AmmPool::MercurialStableSwap)
+        }
+        PoolType::Invariant { .. } => {
+            InvariantPool::try_deserialize(&mut data).map(AmmPool::Invariant)
+        }
+        PoolType::CremaClmm { .. } => {
+            CremaClmmPool::try_deserialize(&mut data).map(AmmPool::CremaClmm)
+        }
+        PoolType::Aldrin { version, .. } => match version {
+            AldrinVersion::V1 => aldrin_v1::accounts::Pool::try_deserialize(&mut data)
+                .map(|pool| AmmPool::Aldrin(AldrinPool::V1(pool))),
+            AldrinVersion::V2 => aldrin_v2::accounts::Pool::try_deserialize(&mut data)
+                .map(|pool| AmmPool::Aldrin(AldrinPool::V2(pool))),
+        },
+        PoolType::FluxBeam { .. } => TokenSwap::try_deserialize(&mut data).map(AmmPool::FluxBeam),
+        PoolType::Stabble { kind, .. } => match kind {
+            StabblePoolKind::Stable => {
+                stabble_stable_swap::accounts::Pool::try_deserialize(&mut data)
+                    .map(|pool| AmmPool::Stabble(StabblePool::Stable(pool)))
+            }
+            StabblePoolKind::Weighted => {
+                stabble_weighted_swap::accounts::Pool::try_deserialize(&mut data)
+                    .map(|pool| AmmPool::Stabble(StabblePool::Weighted(pool)))
+            }
+        },
+        PoolType::PumpFunBondingCurve { .. } => {
+            BondingCurve::try_deserialize(&mut data).map(AmmPool::PumpFunBondingCurve)
+        }
+        PoolType::Moonshot { .. } => {
+            MoonshotCurveAccount::try_deserialize(&mut data).map(AmmPool::Moonshot)
+        }
+        PoolType::RaydiumLaunchLab { .. } => {
+            LaunchLabPoolState::try_deserialize(&mut data).map(AmmPool::RaydiumLaunchLab)
+        }
+        PoolType::MeteoraDbc { .. } => {
+            MeteoraDbcVirtualPool::try_deserialize(&mut data).map(AmmPool::MeteoraDbc)
+        }
+        PoolType::OrcaTokenSwap { .. } => {
+            TokenSwap::try_deserialize(&mut data).map(AmmPool::OrcaTokenSwap)
+        }
+        PoolType::SplTokenSwap { .. } => {
+            TokenSwap::try_deserialize(&mut data).map(AmmPool::SplTokenSwap)
+        }
+        PoolType::Bonkswap { .. } => bonkswap::accounts::PoolV2::try_deserialize(&mut data)
+            .map(BonkswapPool::V2)
+            .or_else(|_| bonkswap::accounts::Pool::try_deserialize(&mut data).map(BonkswapPool::V1))
+            .map(AmmPool::Bonkswap),
+        PoolType::BonkswapFarm { .. } => {
+            BonkswapFarm::try_deserialize(&mut data).map(AmmPool::BonkswapFarm)
+        }
+        PoolType::Dynamic { idl, .. } => {
+            return match idl.parse_account(data) {
+                Ok(pool) => Ok(AmmPool::Dynamic(pool)),
+                Err(e) => Err(PoolError::DynamicError(e)),
+            };
+        }
+        PoolType::SplStakePool { .. } => {
+            StakePool::try_deserialize(&mut data).map(AmmPool::SplStakePool)
+        }
+        PoolType::Marinade { .. } => {
+            MarinadeState::try_deserialize(&mut data).map(AmmPool::Marinade)
+        }
+        PoolType::RaydiumCammConfig { .. } => {
+            RaydiumCammAmmConfig::try_deserialize(&mut data).map(AmmPool::RaydiumCammConfig)
+        }
+        PoolType::RaydiumCammObservation { .. } => {
+            RaydiumCammObservationState::try_deserialize(&mut data)
+                .map(AmmPool::RaydiumCammObservation)
+        }
+        PoolType::RaydiumCpmmConfig { .. } => {
+            RaydiumCpmmAmmConfig::try_deserialize(&mut data).map(AmmPool::RaydiumCpmmConfig)
+        }
+        PoolType::PumpFunGlobalConfig { .. } => {
+            PumpAmmGlobalConfig::try_deserialize(&mut data).map(AmmPool::PumpFunGlobalConfig)
+        }
+        PoolType::MeteoraDlmm { .. } => {
+            MeteoraDlmmLbPair::try_deserialize(&mut data).map(AmmPool::MeteoraDlmm)
+        }
+        PoolType::OrcaWhirlpool { .. } => {
+            Whirlpool::try_deserialize(&mut data).map(AmmPool::OrcaWhirlpool)
+        }
+        PoolType::OrcaWhirlpoolPosition { .. } => {
+            OrcaWhirlpoolPosition::try_deserialize(&mut data).map(AmmPool::OrcaWhirlpoolPosition)
+        }
+        PoolType::SanctumInfinity { .. }
+        | PoolType::MeteoraVault { .. }
+        | PoolType::RaydiumCpmmAmmWithConfig { .. }
+        | PoolType::PumpFunWithConfig { .. } => {
+            return Err(PoolError::IncompleteData(pool_type.pool_name()));
+        }
+        #[cfg(feature = "evm")]
+        PoolType::UniswapV2 { .. } | PoolType::UniswapV3 { .. } => {
+            return Err(PoolError::IncompleteData(pool_type.pool_name()));
+        }
+    };
+    match pool {
+        Ok(pool) => Ok(pool),
+        Err(e) => Err(PoolError::DeserializeError(e)),
+    }
+}
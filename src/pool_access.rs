@@ -0,0 +1,699 @@
+//! Accessors shared by the pool accounts of every protocol.
+//!
+//! `PoolAccess` is implemented by each protocol's pool struct and by `AmmPool`, which
+//! delegates to the pool it holds, so generic code can read the common fields without
+//! matching on every variant. Accounts that aren't pools, such as configs and positions,
+//! don't implement it, and `AmmPool` returns `None` from every accessor for them.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::layouts::phoenix::{self, Market as PhoenixMarket};
+use crate::layouts::raydium_amm_v4::{self, AmmInfo};
+use crate::layouts::sanctum::{self, InfinityPool};
+use crate::layouts::spl_token_swap::{self, TokenSwap};
+use crate::layouts::{mercurial, saber};
+use crate::meteora_dlmm;
+use crate::parsing::meteora_dlmm::accounts::LbPair;
+use crate::parsing::{
+    AldrinPool, AmmPool, BonkswapPool, MeteoraVault, StabblePool, aldrin_v1, aldrin_v2, bonkswap,
+    crema_clmm, invariant, lifinity_v2, meteora_dbc, meteora_vault, moonshot, orca_whirlpool, pamm,
+    pump, raydium_amm_cpmm_new, raydium_camm, raydium_launchlab, stabble_stable_swap,
+    stabble_weighted_swap,
+};
+use crate::pump_amm::PumpAmmPool;
+use crate::raydium_amm_v4::RaydiumAmmV4Pool;
+use crate::raydium_cpmm::{self, RaydiumCpmmPool};
+
+/// Common fields of a pool account.
+pub trait PoolAccess {
+    /// Returns the program owning the pool, or `None` if the layout is shared by several
+    /// deployments and the account doesn't say which one it belongs to.
+    fn program_id(&self) -> Option<Pubkey>;
+
+    /// Returns the mint of the base token, or token A/X/0.
+    fn token_mint_a(&self) -> Option<Pubkey>;
+
+    /// Returns the mint of the quote token, or token B/Y/1.
+    fn token_mint_b(&self) -> Option<Pubkey>;
+
+    /// Returns the mint of the pool's LP token, for pools that issue one.
+    fn lp_mint(&self) -> Option<Pubkey> {
+        None
+    }
+
+    /// Returns the trade fee in basis points, for pools that store it in their own
+    /// account.
+    fn fee_bps(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Returns `numerator / denominator` in basis points, or `None` for a zero denominator.
+pub(crate) fn ratio_bps(numerator: u64, denominator: u64) -> Option<f64> {
+    (denominator != 0).then(|| numerator as f64 * 10_000.0 / denominator as f64)
+}
+
+/// Returns the trade fee plus the owner's trade fee of a token-swap style fee schedule,
+/// in basis points.
+fn trade_and_owner_fee_bps(
+    trade_numerator: u64,
+    trade_denominator: u64,
+    owner_numerator: u64,
+    owner_denominator: u64,
+) -> Option<f64> {
+    let owner = ratio_bps(owner_numerator, owner_denominator).unwrap_or(0.0);
+    Some(ratio_bps(trade_numerator, trade_denominator)? + owner)
+}
+
+/// Returns the fee `config` charges on PumpSwap trades, in basis points.
+pub(crate) fn pump_amm_fee_bps(
+    config: &pamm::accounts::GlobalConfig,
+    has_coin_creator: bool,
+) -> f64 {
+    let mut fee = config.lp_fee_basis_points + config.protocol_fee_basis_points;
+    if has_coin_creator {
+        fee += config.coin_creator_fee_basis_points;
+    }
+    fee as f64
+}
+
+impl PoolAccess for pamm::accounts::Pool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(pamm::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.base_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.quote_mint)
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.lp_mint)
+    }
+}
+
+impl PoolAccess for PumpAmmPool {
+    fn program_id(&self) -> Option<Pubkey> {
+        self.pool.program_id()
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        self.pool.token_mint_a()
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        self.pool.token_mint_b()
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        self.pool.lp_mint()
+    }
+
+    /// Includes the coin creator fee if the pool has a coin creator.
+    fn fee_bps(&self) -> Option<f64> {
+        Some(pump_amm_fee_bps(
+            &self.global_config,
+            self.creator_vault.is_some(),
+        ))
+    }
+}
+
+impl PoolAccess for raydium_amm_cpmm_new::accounts::PoolState {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(raydium_amm_cpmm_new::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token0_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token1_mint)
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.lp_mint)
+    }
+}
+
+impl PoolAccess for RaydiumCpmmPool {
+    fn program_id(&self) -> Option<Pubkey> {
+        self.pool.program_id()
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        self.pool.token_mint_a()
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        self.pool.token_mint_b()
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        self.pool.lp_mint()
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        ratio_bps(
+            self.amm_config.trade_fee_rate,
+            raydium_cpmm::FEE_RATE_DENOMINATOR,
+        )
+    }
+}
+
+impl PoolAccess for raydium_camm::accounts::PoolState {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(raydium_camm::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token_mint0)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token_mint1)
+    }
+}
+
+impl PoolAccess for AmmInfo {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(raydium_amm_v4::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.coin_vault_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.pc_vault_mint)
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.lp_mint)
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        ratio_bps(self.fees.swap_fee_numerator, self.fees.swap_fee_denominator)
+    }
+}
+
+impl PoolAccess for RaydiumAmmV4Pool {
+    fn program_id(&self) -> Option<Pubkey> {
+        self.amm.program_id()
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        self.amm.token_mint_a()
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        self.amm.token_mint_b()
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        self.amm.lp_mint()
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        self.amm.fee_bps()
+    }
+}
+
+impl PoolAccess for lifinity_v2::accounts::Amm {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(lifinity_v2::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token_a_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token_b_mint)
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.pool_mint)
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        trade_and_owner_fee_bps(
+            self.fees.trade_fee_numerator,
+            self.fees.trade_fee_denominator,
+            self.fees.owner_trade_fee_numerator,
+            self.fees.owner_trade_fee_denominator,
+        )
+    }
+}
+
+impl PoolAccess for PhoenixMarket {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(phoenix::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.header.base_params.mint_key)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.header.quote_params.mint_key)
+    }
+
+    /// Returns the taker fee.
+    fn fee_bps(&self) -> Option<f64> {
+        Some(self.state.taker_fee_bps as f64)
+    }
+}
+
+impl PoolAccess for saber::SwapInfo {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(saber::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token_a_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token_b_mint)
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.pool_mint)
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        ratio_bps(
+            self.fees.trade_fee_numerator,
+            self.fees.trade_fee_denominator,
+        )
+    }
+}
+
+/// Mercurial pools don't store their mints, only their token accounts.
+impl PoolAccess for mercurial::SwapInfo {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(mercurial::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        None
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        None
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.pool_mint)
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        ratio_bps(self.fee_numerator, mercurial::FEE_DENOMINATOR)
+    }
+}
+
+impl PoolAccess for invariant::accounts::Pool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(invariant::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token_x)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token_y)
+    }
+
+    /// Invariant stores fees as decimals with 12 fractional digits.
+    fn fee_bps(&self) -> Option<f64> {
+        Some(self.fee.v as f64 / 1e8)
+    }
+}
+
+impl PoolAccess for crema_clmm::accounts::ClmmPool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(crema_clmm::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token_a)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token_b)
+    }
+
+    /// Crema fee rates are in millionths, like those of Whirlpools.
+    fn fee_bps(&self) -> Option<f64> {
+        ratio_bps(u64::from(self.fee_rate), 1_000_000)
+    }
+}
+
+impl PoolAccess for AldrinPool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(match self {
+            AldrinPool::V1(_) => aldrin_v1::ID,
+            AldrinPool::V2(_) => aldrin_v2::ID,
+        })
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(match self {
+            AldrinPool::V1(pool) => pool.base_token_mint,
+            AldrinPool::V2(pool) => pool.base_token_mint,
+        })
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(match self {
+            AldrinPool::V1(pool) => pool.quote_token_mint,
+            AldrinPool::V2(pool) => pool.quote_token_mint,
+        })
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(match self {
+            AldrinPool::V1(pool) => pool.pool_mint,
+            AldrinPool::V2(pool) => pool.pool_mint,
+        })
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        match self {
+            AldrinPool::V1(pool) => trade_and_owner_fee_bps(
+                pool.fees.trade_fee_numerator,
+                pool.fees.trade_fee_denominator,
+                pool.fees.owner_trade_fee_numerator,
+                pool.fees.owner_trade_fee_denominator,
+            ),
+            AldrinPool::V2(pool) => trade_and_owner_fee_bps(
+                pool.fees.trade_fee_numerator,
+                pool.fees.trade_fee_denominator,
+                pool.fees.owner_trade_fee_numerator,
+                pool.fees.owner_trade_fee_denominator,
+            ),
+        }
+    }
+}
+
+/// The token-swap layout is shared by several deployments, so its `program_id` is
+/// unknown; `AmmPool` fills it in where the variant names the deployment.
+impl PoolAccess for TokenSwap {
+    fn program_id(&self) -> Option<Pubkey> {
+        None
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token_a_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token_b_mint)
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.pool_mint)
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        trade_and_owner_fee_bps(
+            self.fees.trade_fee_numerator,
+            self.fees.trade_fee_denominator,
+            self.fees.owner_trade_fee_numerator,
+            self.fees.owner_trade_fee_denominator,
+        )
+    }
+}
+
+/// Infinity holds any number of LSTs, so it has no token A or B.
+impl PoolAccess for InfinityPool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(sanctum::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        None
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        None
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.state.lp_token_mint)
+    }
+}
+
+impl PoolAccess for StabblePool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(match self {
+            StabblePool::Stable(_) => stabble_stable_swap::ID,
+            StabblePool::Weighted(_) => stabble_weighted_swap::ID,
+        })
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        match self {
+            StabblePool::Stable(pool) => pool.tokens.first().map(|token| token.mint),
+            StabblePool::Weighted(pool) => pool.tokens.first().map(|token| token.mint),
+        }
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        match self {
+            StabblePool::Stable(pool) => pool.tokens.get(1).map(|token| token.mint),
+            StabblePool::Weighted(pool) => pool.tokens.get(1).map(|token| token.mint),
+        }
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(match self {
+            StabblePool::Stable(pool) => pool.mint,
+            StabblePool::Weighted(pool) => pool.mint,
+        })
+    }
+}
+
+/// Bonding curves don't store the mint they sell; it is part of the curve's PDA seeds.
+impl PoolAccess for pump::accounts::BondingCurve {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(pump::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        None
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        None
+    }
+}
+
+impl PoolAccess for moonshot::accounts::CurveAccount {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(moonshot::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.mint)
+    }
+
+    /// The collateral is a currency rather than a mint.
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        None
+    }
+}
+
+impl PoolAccess for raydium_launchlab::accounts::PoolState {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(raydium_launchlab::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.base_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.quote_mint)
+    }
+}
+
+/// The quote mint of a virtual pool is stored in its config.
+impl PoolAccess for meteora_dbc::accounts::VirtualPool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(meteora_dbc::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.base_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        None
+    }
+}
+
+/// A vault holds a single token, which is returned as token A.
+impl PoolAccess for MeteoraVault {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(meteora_vault::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.vault.token_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        None
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        Some(self.vault.lp_mint)
+    }
+}
+
+impl PoolAccess for BonkswapPool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(bonkswap::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(match self {
+            BonkswapPool::V1(pool) => pool.token_x,
+            BonkswapPool::V2(pool) => pool.token_x,
+        })
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(match self {
+            BonkswapPool::V1(pool) => pool.token_y,
+            BonkswapPool::V2(pool) => pool.token_y,
+        })
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        match self {
+            BonkswapPool::V1(_) => None,
+            BonkswapPool::V2(pool) => Some(pool.lp_token),
+        }
+    }
+}
+
+impl PoolAccess for LbPair {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(crate::parsing::meteora_dlmm::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token_x_mint)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token_y_mint)
+    }
+
+    /// Returns the base fee, without the variable fee that grows with volatility.
+    fn fee_bps(&self) -> Option<f64> {
+        Some(
+            meteora_dlmm::base_fee_rate(self) as f64 * 10_000.0
+                / meteora_dlmm::FEE_PRECISION as f64,
+        )
+    }
+}
+
+impl PoolAccess for orca_whirlpool::accounts::Whirlpool {
+    fn program_id(&self) -> Option<Pubkey> {
+        Some(orca_whirlpool::ID)
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        Some(self.token_mint_a)
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        Some(self.token_mint_b)
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        ratio_bps(
+            u64::from(self.fee_rate),
+            u64::from(crate::orca_whirlpool::FEE_RATE_DENOMINATOR),
+        )
+    }
+}
+
+impl AmmPool {
+    /// Returns the pool held by this variant, or `None` for accounts that aren't pools.
+    pub fn as_pool_access(&self) -> Option<&dyn PoolAccess> {
+        Some(match self {
+            AmmPool::PumpFun(pool) => pool,
+            AmmPool::PumpFunWithConfig(pool) => pool,
+            AmmPool::RaydiumCpmmAmm(pool) => pool,
+            AmmPool::RaydiumCpmmAmmWithConfig(pool) => pool,
+            AmmPool::RaydiumCamm(pool) => pool,
+            AmmPool::RaydiumLegacyAmm(amm) => amm,
+            AmmPool::LifinityV2(amm) => amm,
+            AmmPool::Phoenix(market) => market,
+            AmmPool::SaberStableSwap(swap) => swap,
+            AmmPool::MercurialStableSwap(swap) => swap,
+            AmmPool::Invariant(pool) => pool,
+            AmmPool::CremaClmm(pool) => pool,
+            AmmPool::Aldrin(pool) => pool,
+            AmmPool::FluxBeam(swap)
+            | AmmPool::OrcaTokenSwap(swap)
+            | AmmPool::SplTokenSwap(swap) => swap,
+            AmmPool::SanctumInfinity(pool) => pool,
+            AmmPool::Stabble(pool) => pool,
+            AmmPool::PumpFunBondingCurve(curve) => curve,
+            AmmPool::Moonshot(curve) => curve,
+            AmmPool::RaydiumLaunchLab(pool) => pool,
+            AmmPool::MeteoraDbc(pool) => pool,
+            AmmPool::MeteoraVault(vault) => vault,
+            AmmPool::Bonkswap(pool) => pool,
+            AmmPool::MeteoraDlmm(pair) => pair,
+            AmmPool::OrcaWhirlpool(pool) => pool,
+            #[cfg(feature = "evm")]
+            AmmPool::UniswapV2(_) | AmmPool::UniswapV3(_) => return None,
+            AmmPool::BonkswapFarm(_)
+            | AmmPool::Dynamic(_)
+            | AmmPool::SplStakePool(_)
+            | AmmPool::Marinade(_)
+            | AmmPool::RaydiumCammConfig(_)
+            | AmmPool::RaydiumCammObservation(_)
+            | AmmPool::RaydiumCpmmConfig(_)
+            | AmmPool::PumpFunGlobalConfig(_)
+            | AmmPool::OrcaWhirlpoolPosition(_) => return None,
+        })
+    }
+}
+
+impl PoolAccess for AmmPool {
+    /// Fills in the program id of FluxBeam pools, whose layout doesn't identify it.
+    fn program_id(&self) -> Option<Pubkey> {
+        match self {
+            AmmPool::FluxBeam(_) => Some(spl_token_swap::FLUXBEAM_ID),
+            _ => self.as_pool_access()?.program_id(),
+        }
+    }
+
+    fn token_mint_a(&self) -> Option<Pubkey> {
+        self.as_pool_access()?.token_mint_a()
+    }
+
+    fn token_mint_b(&self) -> Option<Pubkey> {
+        self.as_pool_access()?.token_mint_b()
+    }
+
+    fn lp_mint(&self) -> Option<Pubkey> {
+        self.as_pool_access()?.lp_mint()
+    }
+
+    fn fee_bps(&self) -> Option<f64> {
+        self.as_pool_access()?.fee_bps()
+    }
+}
@@ -0,0 +1,217 @@
+//! Names of pool types, for CLI tools and config files.
+//!
+//! Every pool type has a kebab-case name, such as `raydium-cpmm` or `pumpfun-amm`, and
+//! parses from a string of its name and the pool address separated by a colon:
+//!
+//! ```text
+//! raydium-cpmm:<base58 address of the pool account>
+//! ```
+//!
+//! Pool types configured with more than an address, the EVM pools and dynamic pools, have
+//! names but can't be parsed from one.
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use thiserror::Error;
+
+use crate::parsing::{AldrinVersion, PoolType, StabblePoolKind};
+
+/// Names of the pool types `PoolType::from_name` constructs, in the order of the variants.
+pub(crate) const NAMES: &[&str] = &[
+    "pumpfun-amm",
+    "raydium-cpmm",
+    "raydium-clmm",
+    "raydium-amm-v4",
+    "lifinity-v2",
+    "phoenix",
+    "saber",
+    "mercurial",
+    "invariant",
+    "crema-clmm",
+    "aldrin-v1",
+    "aldrin-v2",
+    "fluxbeam",
+    "sanctum-infinity",
+    "stabble-stable-swap",
+    "stabble-weighted-swap",
+    "pumpfun-bonding-curve",
+    "moonshot",
+    "raydium-launchlab",
+    "meteora-dbc",
+    "orca-token-swap",
+    "spl-token-swap",
+    "meteora-vault",
+    "bonkswap",
+    "bonkswap-farm",
+    "spl-stake-pool",
+    "marinade",
+    "raydium-clmm-config",
+    "raydium-clmm-observation",
+    "raydium-cpmm-config",
+    "raydium-cpmm-with-config",
+    "pumpfun-amm-global-config",
+    "pumpfun-amm-with-config",
+    "meteora-dlmm",
+    "orca-whirlpool",
+    "orca-whirlpool-position",
+];
+
+/// Returns the name `PoolType::name` returns for the pool type called `name`, which
+/// `from_name` matches case-insensitively and with aliases.
+pub(crate) fn canonical_name(name: &str) -> Option<&'static str> {
+    let name = name.to_ascii_lowercase();
+    let name = if name == "raydium-camm" {
+        "raydium-clmm"
+    } else {
+        &name
+    };
+    NAMES
+        .iter()
+        .copied()
+        .chain(["uniswap-v2", "uniswap-v3", "dynamic"])
+        .find(|known| *known == name)
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+/// Error returned when a string doesn't name a pool type.
+pub enum ParsePoolTypeError {
+    #[error("unknown pool type {0:?}")]
+    UnknownName(String),
+    #[error("pool type {0:?} needs more than an address to be constructed")]
+    NotConstructible(String),
+    #[error("expected `<pool type>:<address>`, got {0:?}")]
+    MissingAddress(String),
+    #[error("invalid pool address {0:?}")]
+    InvalidAddress(String),
+}
+
+impl PoolType {
+    /// Returns the name of the pool type, which `from_name` accepts for every pool type
+    /// constructible from an address.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PoolType::PumpFun { .. } => "pumpfun-amm",
+            PoolType::RaydiumCpmmAmm { .. } => "raydium-cpmm",
+            PoolType::RaydiumCamm { .. } => "raydium-clmm",
+            PoolType::RaydiumLegacyAmm { .. } => "raydium-amm-v4",
+            PoolType::LifinityV2 { .. } => "lifinity-v2",
+            PoolType::Phoenix { .. } => "phoenix",
+            PoolType::SaberStableSwap { .. } => "saber",
+            PoolType::MercurialStableSwap { .. } => "mercurial",
+            PoolType::Invariant { .. } => "invariant",
+            PoolType::CremaClmm { .. } => "crema-clmm",
+            PoolType::Aldrin { version, .. } => match version {
+                AldrinVersion::V1 => "aldrin-v1",
+                AldrinVersion::V2 => "aldrin-v2",
+            },
+            PoolType::FluxBeam { .. } => "fluxbeam",
+            PoolType::SanctumInfinity { .. } => "sanctum-infinity",
+            PoolType::Stabble { kind, .. } => match kind {
+                StabblePoolKind::Stable => "stabble-stable-swap",
+                StabblePoolKind::Weighted => "stabble-weighted-swap",
+            },
+            PoolType::PumpFunBondingCurve { .. } => "pumpfun-bonding-curve",
+            PoolType::Moonshot { .. } => "moonshot",
+            PoolType::RaydiumLaunchLab { .. } => "raydium-launchlab",
+            PoolType::MeteoraDbc { .. } => "meteora-dbc",
+            PoolType::OrcaTokenSwap { .. } => "orca-token-swap",
+            PoolType::SplTokenSwap { .. } => "spl-token-swap",
+            PoolType::MeteoraVault { .. } => "meteora-vault",
+            PoolType::Bonkswap { .. } => "bonkswap",
+            PoolType::BonkswapFarm { .. } => "bonkswap-farm",
+            #[cfg(feature = "evm")]
+            PoolType::UniswapV2 { .. } => "uniswap-v2",
+            #[cfg(feature = "evm")]
+            PoolType::UniswapV3 { .. } => "uniswap-v3",
+            PoolType::Dynamic { .. } => "dynamic",
+            PoolType::SplStakePool { .. } => "spl-stake-pool",
+            PoolType::Marinade { .. } => "marinade",
+            PoolType::RaydiumCammConfig { .. } => "raydium-clmm-config",
+            PoolType::RaydiumCammObservation { .. } => "raydium-clmm-observation",
+            PoolType::RaydiumCpmmConfig { .. } => "raydium-cpmm-config",
+            PoolType::RaydiumCpmmAmmWithConfig { .. } => "raydium-cpmm-with-config",
+            PoolType::PumpFunGlobalConfig { .. } => "pumpfun-amm-global-config",
+            PoolType::PumpFunWithConfig { .. } => "pumpfun-amm-with-config",
+            PoolType::MeteoraDlmm { .. } => "meteora-dlmm",
+            PoolType::OrcaWhirlpool { .. } => "orca-whirlpool",
+            PoolType::OrcaWhirlpoolPosition { .. } => "orca-whirlpool-position",
+        }
+    }
+
+    /// Constructs the pool type called `name` for the account at `pool_address`.
+    ///
+    /// Names are matched case-insensitively, and `raydium-camm` is accepted for
+    /// `raydium-clmm` to match the variant names.
+    pub fn from_name(name: &str, pool_address: Pubkey) -> Result<Self, ParsePoolTypeError> {
+        let pool_type = match name.to_ascii_lowercase().as_str() {
+            "pumpfun-amm" => PoolType::PumpFun { pool_address },
+            "raydium-cpmm" => PoolType::RaydiumCpmmAmm { pool_address },
+            "raydium-clmm" | "raydium-camm" => PoolType::RaydiumCamm { pool_address },
+            "raydium-amm-v4" => PoolType::RaydiumLegacyAmm { pool_address },
+            "lifinity-v2" => PoolType::LifinityV2 { pool_address },
+            "phoenix" => PoolType::Phoenix { pool_address },
+            "saber" => PoolType::SaberStableSwap { pool_address },
+            "mercurial" => PoolType::MercurialStableSwap { pool_address },
+            "invariant" => PoolType::Invariant { pool_address },
+            "crema-clmm" => PoolType::CremaClmm { pool_address },
+            "aldrin-v1" => PoolType::Aldrin {
+                pool_address,
+                version: AldrinVersion::V1,
+            },
+            "aldrin-v2" => PoolType::Aldrin {
+                pool_address,
+                version: AldrinVersion::V2,
+            },
+            "fluxbeam" => PoolType::FluxBeam { pool_address },
+            "sanctum-infinity" => PoolType::SanctumInfinity { pool_address },
+            "stabble-stable-swap" => PoolType::Stabble {
+                pool_address,
+                kind: StabblePoolKind::Stable,
+            },
+            "stabble-weighted-swap" => PoolType::Stabble {
+                pool_address,
+                kind: StabblePoolKind::Weighted,
+            },
+            "pumpfun-bonding-curve" => PoolType::PumpFunBondingCurve { pool_address },
+            "moonshot" => PoolType::Moonshot { pool_address },
+            "raydium-launchlab" => PoolType::RaydiumLaunchLab { pool_address },
+            "meteora-dbc" => PoolType::MeteoraDbc { pool_address },
+            "orca-token-swap" => PoolType::OrcaTokenSwap { pool_address },
+            "spl-token-swap" => PoolType::SplTokenSwap { pool_address },
+            "meteora-vault" => PoolType::MeteoraVault { pool_address },
+            "bonkswap" => PoolType::Bonkswap { pool_address },
+            "bonkswap-farm" => PoolType::BonkswapFarm { pool_address },
+            "spl-stake-pool" => PoolType::SplStakePool { pool_address },
+            "marinade" => PoolType::Marinade { pool_address },
+            "raydium-clmm-config" => PoolType::RaydiumCammConfig { pool_address },
+            "raydium-clmm-observation" => PoolType::RaydiumCammObservation { pool_address },
+            "raydium-cpmm-config" => PoolType::RaydiumCpmmConfig { pool_address },
+            "raydium-cpmm-with-config" => PoolType::RaydiumCpmmAmmWithConfig { pool_address },
+            "pumpfun-amm-global-config" => PoolType::PumpFunGlobalConfig { pool_address },
+            "pumpfun-amm-with-config" => PoolType::PumpFunWithConfig { pool_address },
+            "meteora-dlmm" => PoolType::MeteoraDlmm { pool_address },
+            "orca-whirlpool" => PoolType::OrcaWhirlpool { pool_address },
+            "orca-whirlpool-position" => PoolType::OrcaWhirlpoolPosition { pool_address },
+            "uniswap-v2" | "uniswap-v3" | "dynamic" => {
+                return Err(ParsePoolTypeError::NotConstructible(name.to_string()));
+            }
+            _ => return Err(ParsePoolTypeError::UnknownName(name.to_string())),
+        };
+        Ok(pool_type)
+    }
+}
+
+impl FromStr for PoolType {
+    type Err = ParsePoolTypeError;
+
+    /// Parses `<name>:<address>`, as described in the module documentation.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, address) = s
+            .split_once(':')
+            .ok_or_else(|| ParsePoolTypeError::MissingAddress(s.to_string()))?;
+        let pool_address = Pubkey::from_str(address.trim())
+            .map_err(|_| ParsePoolTypeError::InvalidAddress(address.to_string()))?;
+        PoolType::from_name(name.trim(), pool_address)
+    }
+}
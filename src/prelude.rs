@@ -0,0 +1,20 @@
+//! The types needed to fetch and inspect pools, for glob imports:
+//!
+//! ```
+//! use universal_liquidity_pool_parser::prelude::*;
+//! ```
+
+pub use anchor_client::solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+pub use anchor_client::solana_client::rpc_client::RpcClient;
+pub use anchor_lang::prelude::Pubkey;
+
+pub use crate::detect::{detect_and_parse, detect_and_parse_with};
+pub use crate::fetcher::AccountFetcher;
+pub use crate::normalized::{NormalizedPool, PoolKind};
+pub use crate::parsing::{
+    AmmPool, ParseMode, ParsedPool, PoolError, PoolType, Result, get_info_struct,
+    get_info_struct_on, parse_from_bytes, parse_from_bytes_with_mode,
+};
+pub use crate::pool_access::PoolAccess;
+pub use crate::program_ids::Cluster;
+pub use crate::universal::{PoolFetcher, UniversalParser};
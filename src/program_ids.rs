@@ -0,0 +1,146 @@
+//! Mainnet program ids of the supported protocols.
+//!
+//! The ids come from the IDLs and layouts the crate parses with, so they always match
+//! the programs `get_info_struct` and `detect_and_parse` accept. `PoolType` maps each
+//! variant to its program with `PoolType::expected_program_id`.
+//!
+//! Most protocols deploy to devnet under their mainnet ids, if at all; those that don't
+//! are listed in `devnet`. `Cluster` resolves a mainnet id to the one of the cluster a
+//! pool is fetched from, including local validators and forks with their own
+//! deployments.
+
+use std::collections::BTreeMap;
+
+use anchor_lang::prelude::{Pubkey, pubkey};
+
+use crate::layouts::{
+    mercurial, phoenix, raydium_amm_v4, saber, sanctum, spl_stake_pool, spl_token_swap,
+};
+use crate::parsing::{
+    aldrin_v1, aldrin_v2, bonkswap, crema_clmm, invariant, lifinity_v2, marinade, meteora_dbc,
+    meteora_dlmm, meteora_vault, moonshot, orca_whirlpool, pamm, pump, raydium_amm_cpmm_new,
+    raydium_camm, raydium_launchlab, stabble_stable_swap, stabble_weighted_swap,
+};
+
+/// PumpSwap, the AMM pump.fun tokens graduate to.
+pub const PUMP_AMM: Pubkey = pamm::ID;
+/// pump.fun bonding curves.
+pub const PUMP_BONDING_CURVE: Pubkey = pump::ID;
+pub const RAYDIUM_CPMM: Pubkey = raydium_amm_cpmm_new::ID;
+pub const RAYDIUM_CLMM: Pubkey = raydium_camm::ID;
+pub const RAYDIUM_AMM_V4: Pubkey = raydium_amm_v4::ID;
+pub const RAYDIUM_LAUNCHLAB: Pubkey = raydium_launchlab::ID;
+pub const LIFINITY_V2: Pubkey = lifinity_v2::ID;
+pub const PHOENIX: Pubkey = phoenix::ID;
+pub const SABER: Pubkey = saber::ID;
+pub const MERCURIAL: Pubkey = mercurial::ID;
+pub const INVARIANT: Pubkey = invariant::ID;
+pub const CREMA_CLMM: Pubkey = crema_clmm::ID;
+pub const ALDRIN_V1: Pubkey = aldrin_v1::ID;
+pub const ALDRIN_V2: Pubkey = aldrin_v2::ID;
+pub const FLUXBEAM: Pubkey = spl_token_swap::FLUXBEAM_ID;
+pub const ORCA_TOKEN_SWAP_V1: Pubkey = spl_token_swap::ORCA_V1_ID;
+pub const ORCA_TOKEN_SWAP_V2: Pubkey = spl_token_swap::ORCA_V2_ID;
+/// The canonical deployment of the SPL token-swap program.
+pub const SPL_TOKEN_SWAP: Pubkey = spl_token_swap::ID;
+/// The S controller program, which owns the Infinity pool.
+pub const SANCTUM_INFINITY: Pubkey = sanctum::ID;
+pub const STABBLE_STABLE_SWAP: Pubkey = stabble_stable_swap::ID;
+pub const STABBLE_WEIGHTED_SWAP: Pubkey = stabble_weighted_swap::ID;
+pub const MOONSHOT: Pubkey = moonshot::ID;
+pub const METEORA_DBC: Pubkey = meteora_dbc::ID;
+pub const METEORA_VAULT: Pubkey = meteora_vault::ID;
+pub const METEORA_DLMM: Pubkey = meteora_dlmm::ID;
+pub const BONKSWAP: Pubkey = bonkswap::ID;
+pub const ORCA_WHIRLPOOL: Pubkey = orca_whirlpool::ID;
+pub const SPL_STAKE_POOL: Pubkey = spl_stake_pool::ID;
+pub const MARINADE: Pubkey = marinade::ID;
+
+/// Every program above, for matching the owner of an account against.
+pub const ALL: &[Pubkey] = &[
+    PUMP_AMM,
+    PUMP_BONDING_CURVE,
+    RAYDIUM_CPMM,
+    RAYDIUM_CLMM,
+    RAYDIUM_AMM_V4,
+    RAYDIUM_LAUNCHLAB,
+    LIFINITY_V2,
+    PHOENIX,
+    SABER,
+    MERCURIAL,
+    INVARIANT,
+    CREMA_CLMM,
+    ALDRIN_V1,
+    ALDRIN_V2,
+    FLUXBEAM,
+    ORCA_TOKEN_SWAP_V1,
+    ORCA_TOKEN_SWAP_V2,
+    SPL_TOKEN_SWAP,
+    SANCTUM_INFINITY,
+    STABBLE_STABLE_SWAP,
+    STABBLE_WEIGHTED_SWAP,
+    MOONSHOT,
+    METEORA_DBC,
+    METEORA_VAULT,
+    METEORA_DLMM,
+    BONKSWAP,
+    ORCA_WHIRLPOOL,
+    SPL_STAKE_POOL,
+    MARINADE,
+];
+
+/// Returns whether `program_id` is one of the programs above.
+pub fn is_supported(program_id: &Pubkey) -> bool {
+    ALL.contains(program_id)
+}
+
+/// Program ids of the devnet deployments that differ from mainnet.
+pub mod devnet {
+    use super::*;
+
+    pub const RAYDIUM_CPMM: Pubkey = pubkey!("CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW");
+    pub const RAYDIUM_CLMM: Pubkey = pubkey!("devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH");
+    pub const RAYDIUM_AMM_V4: Pubkey = pubkey!("HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8");
+
+    /// Pairs of a mainnet id and the devnet id replacing it.
+    pub(super) const REPLACEMENTS: &[(Pubkey, Pubkey)] = &[
+        (super::RAYDIUM_CPMM, RAYDIUM_CPMM),
+        (super::RAYDIUM_CLMM, RAYDIUM_CLMM),
+        (super::RAYDIUM_AMM_V4, RAYDIUM_AMM_V4),
+    ];
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The cluster pools are fetched from, which decides the program ids they're owned by.
+pub enum Cluster {
+    #[default]
+    Mainnet,
+    Devnet,
+    /// A cluster with its own deployments, given as the id replacing each mainnet id.
+    /// Programs without an entry keep their mainnet id.
+    Custom(BTreeMap<Pubkey, Pubkey>),
+}
+
+impl Cluster {
+    /// Returns the id on this cluster of the program deployed at `mainnet_id` on mainnet.
+    pub fn program_id(&self, mainnet_id: Pubkey) -> Pubkey {
+        let replacement = match self {
+            Cluster::Mainnet => None,
+            Cluster::Devnet => devnet::REPLACEMENTS
+                .iter()
+                .find(|(mainnet, _)| *mainnet == mainnet_id)
+                .map(|(_, devnet)| *devnet),
+            Cluster::Custom(ids) => ids.get(&mainnet_id).copied(),
+        };
+        replacement.unwrap_or(mainnet_id)
+    }
+
+    /// Returns the mainnet id of the program deployed at `program_id` on this cluster, or
+    /// `program_id` itself if it isn't a supported program there.
+    pub fn mainnet_id(&self, program_id: Pubkey) -> Pubkey {
+        ALL.iter()
+            .copied()
+            .find(|mainnet_id| self.program_id(*mainnet_id) == program_id)
+            .unwrap_or(program_id)
+    }
+}
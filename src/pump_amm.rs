@@ -0,0 +1,302 @@
+//! Accounts around a PumpSwap AMM pool that `get_info_struct` doesn't fetch by default.
+//!
+//! PumpSwap pools don't store their fees: every pool reads the LP, protocol and coin
+//! creator fees from the program's single `GlobalConfig` PDA, which the admin can update.
+//! Coin creator fees accrue in a token account of a PDA of the pool's `coin_creator`.
+//!
+//! Tokens launched on the pump.fun bonding curve migrate to a canonical PumpSwap pool once
+//! the curve completes. Both addresses are PDAs of the mint, so a token's stage can be
+//! told from the mint alone.
+//!
+//! A bonding curve prices its token from virtual reserves stored in the curve, while a
+//! pool only references its vaults, whose balances have to be fetched to get a price.
+
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::{AccountDeserialize, Pubkey, pubkey};
+use serde::Serialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+use crate::fetcher::{self, AccountFetcher};
+use crate::layouts::spl_token::{self, Mint, TokenAccount};
+use crate::parsing::PoolError;
+use crate::parsing::pamm::{
+    self,
+    accounts::{GlobalConfig, Pool},
+};
+use crate::parsing::pump::{self, accounts::BondingCurve};
+
+/// Seed of the `GlobalConfig` PDA.
+pub const GLOBAL_CONFIG_SEED: &[u8] = b"global_config";
+
+/// Seed of the PDA that owns a coin creator's fee token account.
+pub const CREATOR_VAULT_SEED: &[u8] = b"creator_vault";
+
+/// Program id of the associated token account program.
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Seed of the bonding curve PDAs of the pump.fun program.
+pub const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
+
+/// Seed of the pump.fun PDA that creates the canonical pool of a graduated mint.
+pub const POOL_AUTHORITY_SEED: &[u8] = b"pool-authority";
+
+/// Seed of the PumpSwap pool PDAs.
+pub const POOL_SEED: &[u8] = b"pool";
+
+/// Mint of wrapped SOL, the quote token of every canonical pool.
+pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+/// Decimals of every token minted by pump.fun.
+pub const PUMP_TOKEN_DECIMALS: u8 = 6;
+
+/// Decimals of SOL and wrapped SOL.
+pub const SOL_DECIMALS: u8 = 9;
+
+/// Derives the address of the program's `GlobalConfig`.
+pub fn global_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], &pamm::ID).0
+}
+
+#[derive(Debug, Serialize)]
+/// A PumpSwap pool together with the `GlobalConfig` that sets its fees.
+pub struct PumpAmmPool {
+    pub pool: Pool,
+    pub global_config: GlobalConfig,
+    /// Fee vault of the pool's coin creator, or `None` if the pool has no coin creator
+    /// and therefore charges no creator fee.
+    pub creator_vault: Option<CreatorVault>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// Accounts that collect the coin creator fee of a pool.
+pub struct CreatorVault {
+    /// PDA of the coin creator that owns `token_account`.
+    #[serde_as(as = "DisplayFromStr")]
+    pub authority: Pubkey,
+    /// Associated token account of `authority` for the pool's quote mint.
+    #[serde_as(as = "DisplayFromStr")]
+    pub token_account: Pubkey,
+    /// Fees accrued and not yet collected, in base units of the quote mint. Zero if the
+    /// token account hasn't been created yet.
+    pub balance: u64,
+}
+
+/// Fetches the program's `GlobalConfig`.
+pub async fn fetch_global_config<F: AccountFetcher>(
+    fetcher: &F,
+) -> std::result::Result<GlobalConfig, PoolError> {
+    let data = fetcher::fetch_account_data(fetcher, &global_config_address()).await?;
+    match GlobalConfig::try_deserialize(&mut &data[..]) {
+        Ok(config) => Ok(config),
+        Err(e) => Err(PoolError::DeserializeError(e)),
+    }
+}
+
+/// Derives the address of the pump.fun bonding curve of `mint`.
+pub fn bonding_curve_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[BONDING_CURVE_SEED, mint.as_ref()], &pump::ID).0
+}
+
+/// Derives the address of the canonical PumpSwap pool a pump.fun `mint` migrates to.
+pub fn canonical_pool_address(mint: &Pubkey) -> Pubkey {
+    let creator = Pubkey::find_program_address(&[POOL_AUTHORITY_SEED, mint.as_ref()], &pump::ID).0;
+    let index: u16 = 0;
+    Pubkey::find_program_address(
+        &[
+            POOL_SEED,
+            &index.to_le_bytes(),
+            creator.as_ref(),
+            mint.as_ref(),
+            WSOL_MINT.as_ref(),
+        ],
+        &pamm::ID,
+    )
+    .0
+}
+
+#[derive(Debug, Serialize)]
+/// Stage of a pump.fun token's lifecycle.
+pub enum PumpTokenStatus {
+    /// The token still trades on its bonding curve.
+    BondingCurve {
+        address: Pubkey,
+        bonding_curve: BondingCurve,
+    },
+    /// The curve is complete but the canonical pool hasn't been created yet.
+    AwaitingMigration {
+        address: Pubkey,
+        bonding_curve: BondingCurve,
+    },
+    /// The token has migrated to its canonical PumpSwap pool.
+    Graduated { pool_address: Pubkey },
+    /// The mint has neither a bonding curve nor a canonical pool, so it wasn't launched
+    /// on pump.fun.
+    NotPumpFun,
+}
+
+/// Determines whether `mint` is still on its bonding curve or has graduated to PumpSwap,
+/// fetching both candidate accounts in one request.
+pub async fn token_status<F: AccountFetcher>(
+    mint: &Pubkey,
+    fetcher: &F,
+) -> std::result::Result<PumpTokenStatus, PoolError> {
+    let curve_address = bonding_curve_address(mint);
+    let pool_address = canonical_pool_address(mint);
+    let accounts = fetcher
+        .fetch_accounts(&[curve_address, pool_address])
+        .await?
+        .value;
+    if accounts.get(1).is_some_and(Option::is_some) {
+        return Ok(PumpTokenStatus::Graduated { pool_address });
+    }
+    let Some(Some(curve)) = accounts.into_iter().next() else {
+        return Ok(PumpTokenStatus::NotPumpFun);
+    };
+    let bonding_curve = match BondingCurve::try_deserialize(&mut &curve.data[..]) {
+        Ok(bonding_curve) => bonding_curve,
+        Err(e) => return Err(PoolError::DeserializeError(e)),
+    };
+    if bonding_curve.complete {
+        Ok(PumpTokenStatus::AwaitingMigration {
+            address: curve_address,
+            bonding_curve,
+        })
+    } else {
+        Ok(PumpTokenStatus::BondingCurve {
+            address: curve_address,
+            bonding_curve,
+        })
+    }
+}
+
+/// Derives the PDA that owns the coin creator fee account of `coin_creator`.
+pub fn creator_vault_authority(coin_creator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[CREATOR_VAULT_SEED, coin_creator.as_ref()], &pamm::ID).0
+}
+
+/// Derives the associated token account of `owner` for `mint` under the SPL Token program.
+fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), spl_token::ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Derives and fetches the coin creator fee vault of `pool`.
+///
+/// Returns `None` for pools created before creator fees existed, whose `coin_creator` is
+/// the default key.
+pub async fn fetch_creator_vault<F: AccountFetcher>(
+    pool: &Pool,
+    fetcher: &F,
+) -> std::result::Result<Option<CreatorVault>, PoolError> {
+    let Some((authority, token_account)) = creator_vault_keys(pool) else {
+        return Ok(None);
+    };
+    let account = fetcher.fetch_account(&token_account).await?.value;
+    CreatorVault::from_account(authority, token_account, account.as_ref()).map(Some)
+}
+
+/// Derives the authority and token account of the coin creator fee vault of `pool`, or
+/// `None` if it has no coin creator.
+fn creator_vault_keys(pool: &Pool) -> Option<(Pubkey, Pubkey)> {
+    if pool.coin_creator == Pubkey::default() {
+        return None;
+    }
+    let authority = creator_vault_authority(&pool.coin_creator);
+    let token_account = associated_token_address(&authority, &pool.quote_mint);
+    Some((authority, token_account))
+}
+
+impl CreatorVault {
+    /// Reads the balance of the vault from its token `account`, if it has been created.
+    fn from_account(
+        authority: Pubkey,
+        token_account: Pubkey,
+        account: Option<&Account>,
+    ) -> std::result::Result<Self, PoolError> {
+        let balance = match account {
+            Some(account) => TokenAccount::try_deserialize(&mut &account.data[..])?.amount,
+            None => 0,
+        };
+        Ok(CreatorVault {
+            authority,
+            token_account,
+            balance,
+        })
+    }
+}
+
+/// Returns the price of one whole token at `reserve_base` base atoms against
+/// `reserve_quote` quote atoms, in whole quote tokens, or `None` if there is no base.
+fn reserves_price(
+    reserve_base: u64,
+    reserve_quote: u64,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Option<f64> {
+    if reserve_base == 0 {
+        return None;
+    }
+    let decimals = 10f64.powi(i32::from(base_decimals) - i32::from(quote_decimals));
+    Some(reserve_quote as f64 / reserve_base as f64 * decimals)
+}
+
+/// Returns the price of one whole token on `curve` in SOL, from its virtual reserves.
+///
+/// The real reserves only track what can still be bought and sold, so they don't enter
+/// the price. Returns `None` if the curve has no virtual token reserves.
+pub fn bonding_curve_spot_price(curve: &BondingCurve) -> Option<f64> {
+    reserves_price(
+        curve.virtual_token_reserves,
+        curve.virtual_sol_reserves,
+        PUMP_TOKEN_DECIMALS,
+        SOL_DECIMALS,
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+/// Vault balances of a PumpSwap pool and the decimals of its mints.
+pub struct PumpAmmReserves {
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+}
+
+impl PumpAmmReserves {
+    /// Returns the price of one whole base token in the quote token, SOL for canonical
+    /// pools, or `None` if the pool holds no base tokens.
+    pub fn spot_price(&self) -> Option<f64> {
+        reserves_price(
+            self.base_reserve,
+            self.quote_reserve,
+            self.base_decimals,
+            self.quote_decimals,
+        )
+    }
+}
+
+/// Fetches the vault balances of `pool` and the decimals of its mints in one request.
+pub async fn fetch_reserves<F: AccountFetcher>(
+    pool: &Pool,
+    fetcher: &F,
+) -> std::result::Result<PumpAmmReserves, PoolError> {
+    let keys: [Pubkey; 4] = [
+        pool.pool_base_token_account,
+        pool.pool_quote_token_account,
+        pool.base_mint,
+        pool.quote_mint,
+    ];
+    let [base_vault, quote_vault, base_mint, quote_mint] =
+        fetcher::fetch_existing_accounts(fetcher, &keys).await?;
+    Ok(PumpAmmReserves {
+        base_reserve: TokenAccount::try_deserialize(&mut &base_vault.data[..])?.amount,
+        quote_reserve: TokenAccount::try_deserialize(&mut &quote_vault.data[..])?.amount,
+        base_decimals: Mint::try_deserialize(&mut &base_mint.data[..])?.decimals,
+        quote_decimals: Mint::try_deserialize(&mut &quote_mint.data[..])?.decimals,
+    })
+}
@@ -0,0 +1,135 @@
+//! Keeping the requests made to an RPC node under its rate limit.
+//!
+//! A `RateLimiter` is a token bucket that refills at a fixed number of requests per
+//! second, up to a burst of one second's worth. Every request takes a token, waiting for
+//! one if the bucket is empty. Clones of the `Arc` a limiter is shared through draw from
+//! the same bucket, so one limiter can cover every parser and fetcher talking to the same
+//! endpoint:
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+//! # use universal_liquidity_pool_parser::rate_limit::{RateLimited, RateLimiter};
+//! # use universal_liquidity_pool_parser::universal::UniversalParser;
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let limiter = Arc::new(RateLimiter::new(10.0));
+//! let parser = UniversalParser::builder()
+//!     .rpc_url("https://api.mainnet-beta.solana.com")
+//!     .rate_limiter(limiter.clone())
+//!     .build()?;
+//! let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+//! let fetcher = RateLimited::new(rpc_client, limiter);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_response::Response;
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::Pubkey;
+
+use crate::fetcher::AccountFetcher;
+use crate::parsing::PoolError;
+
+#[derive(Debug)]
+/// A token bucket that allows `requests_per_second` requests per second on average.
+pub struct RateLimiter {
+    requests_per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Tokens left, negative while requests are waiting for tokens not yet refilled.
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a limiter with a full bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests_per_second` isn't positive.
+    pub fn new(requests_per_second: f64) -> Self {
+        assert!(
+            requests_per_second > 0.0,
+            "requests_per_second must be positive"
+        );
+        RateLimiter {
+            requests_per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: burst(requests_per_second),
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    /// Takes a token, waiting until the bucket has one.
+    ///
+    /// Tokens are handed out in the order they're asked for: a caller that has to wait
+    /// reserves its token at once, so later callers wait behind it.
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let refilled = now.duration_since(bucket.refilled_at).as_secs_f64();
+            bucket.tokens = (bucket.tokens + refilled * self.requests_per_second)
+                .min(burst(self.requests_per_second));
+            bucket.refilled_at = now;
+            bucket.tokens -= 1.0;
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-bucket.tokens / self.requests_per_second)
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Returns the size of the bucket, one second's worth of requests and at least one.
+fn burst(requests_per_second: f64) -> f64 {
+    requests_per_second.max(1.0)
+}
+
+#[derive(Debug, Clone)]
+/// An `AccountFetcher` that takes a token from `limiter` before each request of `inner`.
+pub struct RateLimited<F> {
+    pub inner: F,
+    pub limiter: Arc<RateLimiter>,
+}
+
+impl<F> RateLimited<F> {
+    pub fn new(inner: F, limiter: Arc<RateLimiter>) -> Self {
+        RateLimited { inner, limiter }
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for RateLimited<F> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        self.limiter.acquire().await;
+        self.inner
+            .fetch_accounts_with_config(addresses, config)
+            .await
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        self.limiter.acquire().await;
+        self.inner.fetch_program_accounts(program_id, config).await
+    }
+}
@@ -0,0 +1,83 @@
+//! Accounts around a Raydium legacy AMM v4 pool that `get_info_struct` doesn't fetch by
+//! default.
+//!
+//! An AMM v4 pool doesn't keep all of its liquidity in its vaults: part of it rests on
+//! its OpenBook market, held by the pool's open orders account, and part of what the
+//! vaults hold is PnL owed to the protocol. The pool's own swap math counts
+//! `vault + open orders - need_take_pnl` as its reserves, and so does this module.
+
+use anchor_lang::prelude::{AccountDeserialize, Pubkey};
+use serde::Serialize;
+
+use crate::fetcher::{self, AccountFetcher};
+use crate::layouts::openbook::{MarketState, OpenOrders};
+use crate::layouts::raydium_amm_v4::AmmInfo;
+use crate::layouts::spl_token::TokenAccount;
+use crate::parsing::PoolError;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// An AMM v4 pool together with its OpenBook accounts and vault balances.
+pub struct RaydiumAmmV4Pool {
+    pub amm: AmmInfo,
+    pub open_orders: OpenOrders,
+    pub market: MarketState,
+    /// Balance of `amm.coin_vault`.
+    pub coin_vault_amount: u64,
+    /// Balance of `amm.pc_vault`.
+    pub pc_vault_amount: u64,
+}
+
+impl RaydiumAmmV4Pool {
+    /// Returns the coin reserves the pool swaps against.
+    pub fn coin_reserve(&self) -> u64 {
+        self.coin_vault_amount
+            .saturating_add(self.open_orders.native_coin_total)
+            .saturating_sub(self.amm.state_data.need_take_pnl_coin)
+    }
+
+    /// Returns the pc reserves the pool swaps against.
+    pub fn pc_reserve(&self) -> u64 {
+        self.pc_vault_amount
+            .saturating_add(self.open_orders.native_pc_total)
+            .saturating_sub(self.amm.state_data.need_take_pnl_pc)
+    }
+}
+
+/// Fetches the pool at `amm_address` along with its open orders, market and both vaults.
+///
+/// This takes two requests, since the other accounts are only known once the pool is
+/// read; callers that already hold the pool fetch the rest in one with
+/// `fetch_pool_accounts`.
+pub async fn fetch_pool<F: AccountFetcher>(
+    amm_address: &Pubkey,
+    fetcher: &F,
+) -> std::result::Result<RaydiumAmmV4Pool, PoolError> {
+    let data = fetcher::fetch_account_data(fetcher, amm_address).await?;
+    let amm = match AmmInfo::try_deserialize(&mut &data[..]) {
+        Ok(amm) => amm,
+        Err(e) => return Err(PoolError::DeserializeError(e)),
+    };
+    fetch_pool_accounts(amm, fetcher).await
+}
+
+/// Fetches the open orders, market and both vaults of an already parsed `amm` in one
+/// request.
+pub async fn fetch_pool_accounts<F: AccountFetcher>(
+    amm: AmmInfo,
+    fetcher: &F,
+) -> std::result::Result<RaydiumAmmV4Pool, PoolError> {
+    let keys: [Pubkey; 4] = [amm.open_orders, amm.market, amm.coin_vault, amm.pc_vault];
+    let [open_orders, market, coin_vault, pc_vault] =
+        fetcher::fetch_existing_accounts(fetcher, &keys).await?;
+    let open_orders = OpenOrders::try_deserialize(&mut &open_orders.data[..])?;
+    let market = MarketState::try_deserialize(&mut &market.data[..])?;
+    let coin_vault_amount = TokenAccount::try_deserialize(&mut &coin_vault.data[..])?.amount;
+    let pc_vault_amount = TokenAccount::try_deserialize(&mut &pc_vault.data[..])?.amount;
+    Ok(RaydiumAmmV4Pool {
+        amm,
+        open_orders,
+        market,
+        coin_vault_amount,
+        pc_vault_amount,
+    })
+}
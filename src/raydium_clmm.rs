@@ -0,0 +1,400 @@
+//! Accounts around a Raydium CLMM pool that `get_info_struct` doesn't fetch.
+//!
+//! A CLMM `PoolState` only describes the current price and in-range liquidity. Quoting a
+//! swap that crosses ticks also needs the pool's tick arrays, which live in their own
+//! PDAs keyed by the first tick index they cover, and its fees are set by the `AmmConfig`
+//! the pool references. The pool also records cumulative ticks in an `ObservationState`,
+//! from which time-weighted average prices can be computed.
+//!
+//! Up to three reward tokens can be emitted to in-range liquidity; their emission rates
+//! are stored in the pool's `reward_infos` as Q64.64 atoms per second.
+
+use anchor_client::solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use anchor_lang::prelude::{AccountDeserialize, Pubkey};
+
+use crate::fetcher::{self, AccountFetcher};
+use crate::math::{self, ClmmProgram, LiquidityBucket};
+use crate::parsing::PoolError;
+use crate::parsing::raydium_camm::{
+    self,
+    accounts::{AmmConfig, ObservationState, PersonalPositionState, PoolState, TickArrayState},
+    types::TickState,
+};
+
+/// Number of ticks held by one tick array.
+pub const TICK_ARRAY_SIZE: i32 = 60;
+
+/// Seed of the tick array PDAs.
+pub const TICK_ARRAY_SEED: &[u8] = b"tick_array";
+
+/// Denominator of the fee rates of an `AmmConfig`; a `trade_fee_rate` of 2500 is 0.25%.
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+/// Returns the start index of the tick array containing `tick`.
+pub fn tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    let ticks_per_array = i32::from(tick_spacing) * TICK_ARRAY_SIZE;
+    tick.div_euclid(ticks_per_array) * ticks_per_array
+}
+
+/// Derives the address of the tick array of `pool_address` starting at `start_index`.
+pub fn tick_array_address(pool_address: &Pubkey, start_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED,
+            pool_address.as_ref(),
+            &start_index.to_be_bytes(),
+        ],
+        &raydium_camm::ID,
+    )
+    .0
+}
+
+/// Fetches the tick array containing the pool's current tick and up to `arrays_per_side`
+/// arrays on each side of it, ordered by start index.
+///
+/// Tick arrays are only created once a position uses them, so arrays that don't exist
+/// on-chain are skipped rather than reported as errors.
+///
+/// Arrays are fetched `MAX_MULTIPLE_ACCOUNTS` at a time, the most one request can ask
+/// for, so the arrays of wider ranges may be read at different slots.
+pub async fn fetch_tick_arrays<F: AccountFetcher>(
+    pool_address: &Pubkey,
+    pool: &PoolState,
+    arrays_per_side: u8,
+    fetcher: &F,
+) -> std::result::Result<Vec<TickArrayState>, PoolError> {
+    let ticks_per_array = i32::from(pool.tick_spacing) * TICK_ARRAY_SIZE;
+    let current = tick_array_start_index(pool.tick_current, pool.tick_spacing);
+    let side = i32::from(arrays_per_side);
+    let addresses: Vec<Pubkey> = (-side..=side)
+        .map(|offset| tick_array_address(pool_address, current + offset * ticks_per_array))
+        .collect();
+    let mut tick_arrays = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = fetcher.fetch_accounts(chunk).await?.value;
+        for account in accounts.into_iter().flatten() {
+            match TickArrayState::try_deserialize(&mut &account.data[..]) {
+                Ok(tick_array) => tick_arrays.push(tick_array),
+                Err(e) => return Err(PoolError::DeserializeError(e)),
+            }
+        }
+    }
+    Ok(tick_arrays)
+}
+
+/// Fetches the `AmmConfig` referenced by `pool.amm_config`.
+pub async fn fetch_amm_config<F: AccountFetcher>(
+    pool: &PoolState,
+    fetcher: &F,
+) -> std::result::Result<AmmConfig, PoolError> {
+    let data = fetcher::fetch_account_data(fetcher, &pool.amm_config).await?;
+    match AmmConfig::try_deserialize(&mut &data[..]) {
+        Ok(config) => Ok(config),
+        Err(e) => Err(PoolError::DeserializeError(e)),
+    }
+}
+
+/// Fetches the `ObservationState` referenced by `pool.observation_key`.
+pub async fn fetch_observation_state<F: AccountFetcher>(
+    pool: &PoolState,
+    fetcher: &F,
+) -> std::result::Result<ObservationState, PoolError> {
+    let data = fetcher::fetch_account_data(fetcher, &pool.observation_key).await?;
+    match ObservationState::try_deserialize(&mut &data[..]) {
+        Ok(observations) => Ok(observations),
+        Err(e) => Err(PoolError::DeserializeError(e)),
+    }
+}
+
+/// Returns the price of one whole token 0 in token 1 at the pool's current sqrt price.
+pub fn price(pool: &PoolState) -> f64 {
+    math::sqrt_price_x64_to_price(
+        pool.sqrt_price_x64,
+        pool.mint_decimals0,
+        pool.mint_decimals1,
+    )
+}
+
+/// Returns the pool's liquidity over `tick_lower..tick_upper` in buckets of one tick
+/// spacing, from the initialized ticks of `tick_arrays`.
+///
+/// See `math::liquidity_profile`; the tick arrays should cover the whole range, e.g. as
+/// fetched by `fetch_tick_arrays`.
+pub fn liquidity_profile(
+    pool: &PoolState,
+    tick_arrays: &[TickArrayState],
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Option<Vec<LiquidityBucket>> {
+    let ticks: Vec<(i32, i128)> = tick_arrays
+        .iter()
+        .flat_map(|array| array.ticks.iter())
+        .filter(|tick| tick.liquidity_gross != 0)
+        .map(|tick| (tick.tick, tick.liquidity_net))
+        .collect();
+    math::liquidity_profile(
+        ClmmProgram::RaydiumClmm,
+        pool.sqrt_price_x64,
+        pool.tick_current,
+        pool.liquidity,
+        pool.tick_spacing,
+        &ticks,
+        tick_lower,
+        tick_upper,
+    )
+}
+
+/// Returns the token 0 and token 1 amounts, in atoms, held by `position` at the pool's
+/// current sqrt price.
+pub fn position_amounts(pool: &PoolState, position: &PersonalPositionState) -> Option<(u64, u64)> {
+    math::amounts_for_liquidity(
+        ClmmProgram::RaydiumClmm,
+        pool.sqrt_price_x64,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        position.liquidity,
+    )
+}
+
+/// Fetches the tick arrays holding the lower and upper ticks of `position`, which are
+/// the same array if both ticks fall into it.
+pub async fn fetch_position_tick_arrays<F: AccountFetcher>(
+    pool: &PoolState,
+    position: &PersonalPositionState,
+    fetcher: &F,
+) -> std::result::Result<Vec<TickArrayState>, PoolError> {
+    let lower = tick_array_start_index(position.tick_lower_index, pool.tick_spacing);
+    let upper = tick_array_start_index(position.tick_upper_index, pool.tick_spacing);
+    let mut addresses = vec![tick_array_address(&position.pool_id, lower)];
+    if upper != lower {
+        addresses.push(tick_array_address(&position.pool_id, upper));
+    }
+    let accounts = fetcher.fetch_accounts(&addresses).await?.value;
+    let mut tick_arrays = Vec::with_capacity(accounts.len());
+    for (address, account) in addresses.iter().zip(accounts) {
+        let Some(account) = account else {
+            return Err(PoolError::AccountNotFound { address: *address });
+        };
+        match TickArrayState::try_deserialize(&mut &account.data[..]) {
+            Ok(tick_array) => tick_arrays.push(tick_array),
+            Err(e) => return Err(PoolError::DeserializeError(e)),
+        }
+    }
+    Ok(tick_arrays)
+}
+
+/// Returns the tick at index `tick` from whichever of `tick_arrays` holds it.
+fn find_tick(tick_arrays: &[TickArrayState], tick: i32, tick_spacing: u16) -> Option<&TickState> {
+    let start = tick_array_start_index(tick, tick_spacing);
+    let array = tick_arrays
+        .iter()
+        .find(|array| array.start_tick_index == start)?;
+    let offset = (tick - start) / i32::from(tick_spacing);
+    array.ticks.get(usize::try_from(offset).ok()?)
+}
+
+/// Returns the token 0 and token 1 fees, in atoms, that `position` can collect.
+///
+/// `tick_arrays` must hold the position's lower and upper ticks, e.g. as fetched by
+/// `fetch_position_tick_arrays`; returns `None` if they don't, or if the fees overflow.
+pub fn position_fees(
+    pool: &PoolState,
+    position: &PersonalPositionState,
+    tick_arrays: &[TickArrayState],
+) -> Option<(u64, u64)> {
+    let lower = find_tick(tick_arrays, position.tick_lower_index, pool.tick_spacing)?;
+    let upper = find_tick(tick_arrays, position.tick_upper_index, pool.tick_spacing)?;
+    let inside = |global: u128, lower_outside: u128, upper_outside: u128| {
+        math::fee_growth_inside(
+            pool.tick_current,
+            position.tick_lower_index,
+            position.tick_upper_index,
+            global,
+            lower_outside,
+            upper_outside,
+        )
+    };
+    let inside0 = inside(
+        pool.fee_growth_global0_x64,
+        lower.fee_growth_outside0_x64,
+        upper.fee_growth_outside0_x64,
+    );
+    let inside1 = inside(
+        pool.fee_growth_global1_x64,
+        lower.fee_growth_outside1_x64,
+        upper.fee_growth_outside1_x64,
+    );
+    Some((
+        math::fees_owed(
+            position.liquidity,
+            inside0,
+            position.fee_growth_inside0_last_x64,
+            position.token_fees_owed0,
+        )?,
+        math::fees_owed(
+            position.liquidity,
+            inside1,
+            position.fee_growth_inside1_last_x64,
+            position.token_fees_owed1,
+        )?,
+    ))
+}
+
+/// Seconds in a non-leap year, the period of the APRs computed from emissions.
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Emission schedule of one reward token of a pool.
+pub struct RewardEmission {
+    pub mint: Pubkey,
+    /// Token account the rewards are paid from.
+    pub vault: Pubkey,
+    /// Reward atoms emitted per second to in-range liquidity, as a Q64.64.
+    pub emissions_per_second_x64: u128,
+    /// Unix timestamp the emissions start at.
+    pub open_time: u64,
+    /// Unix timestamp the emissions stop at.
+    pub end_time: u64,
+}
+
+impl RewardEmission {
+    /// Returns the reward atoms emitted per second.
+    pub fn emissions_per_second(&self) -> f64 {
+        self.emissions_per_second_x64 as f64 / math::Q64
+    }
+
+    /// Returns `true` if the reward is being emitted at `current_time`.
+    pub fn is_active(&self, current_time: u64) -> bool {
+        (self.open_time..self.end_time).contains(&current_time)
+    }
+
+    /// Returns the yearly value of the emissions over `tvl`, as a fraction (0.05 is 5%).
+    ///
+    /// `reward_price` is the price of one whole reward token and `tvl` the value of the
+    /// liquidity the rewards go to, both in the same currency. The rate is annualised as if
+    /// it held all year, regardless of `end_time`.
+    pub fn apr(&self, reward_price: f64, reward_decimals: u8, tvl: f64) -> f64 {
+        let yearly = self.emissions_per_second() * SECONDS_PER_YEAR as f64
+            / 10f64.powi(i32::from(reward_decimals));
+        yearly * reward_price / tvl
+    }
+}
+
+/// Returns the emission schedules of the pool's initialized rewards.
+pub fn reward_emissions(pool: &PoolState) -> Vec<RewardEmission> {
+    pool.reward_infos
+        .iter()
+        .filter(|reward| reward.token_mint != Pubkey::default())
+        .map(|reward| RewardEmission {
+            mint: reward.token_mint,
+            vault: reward.token_vault,
+            emissions_per_second_x64: reward.emissions_per_second_x64,
+            open_time: reward.open_time,
+            end_time: reward.end_time,
+        })
+        .collect()
+}
+
+/// Returns the time-weighted average tick over at least the last `window_secs` seconds
+/// of observations, rounded down like Uniswap's oracle.
+///
+/// The average spans from the newest observation back to the most recent one that is at
+/// least `window_secs` older, so the actual window can be slightly longer if the pool
+/// wasn't traded at the window's start. Returns `None` if the buffer doesn't reach back
+/// that far.
+pub fn twap_tick(observations: &ObservationState, window_secs: u32) -> Option<i32> {
+    let ring = &observations.observations;
+    let len = ring.len();
+    let index = usize::from(observations.observation_index);
+    let newest = ring.get(index)?;
+    let target = newest.block_timestamp.checked_sub(window_secs)?;
+    let oldest = (1..len)
+        .map(|step| &ring[(index + len - step) % len])
+        // Slots that were never written have a zero timestamp.
+        .take_while(|observation| observation.block_timestamp != 0)
+        .find(|observation| observation.block_timestamp <= target)?;
+    let elapsed = i64::from(newest.block_timestamp - oldest.block_timestamp);
+    if elapsed == 0 {
+        return None;
+    }
+    let ticks = newest.tick_cumulative.checked_sub(oldest.tick_cumulative)?;
+    i32::try_from(ticks.div_euclid(elapsed)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::raydium_camm::types::Observation;
+
+    const UNWRITTEN: Observation = Observation {
+        block_timestamp: 0,
+        tick_cumulative: 0,
+        padding: [0; 4],
+    };
+
+    /// Timestamps and cumulative ticks of five observations ten seconds apart, during
+    /// which the tick was 10, 20, 30 and then 40.
+    const HISTORY: [(u32, i64); 5] = [(100, 0), (110, 100), (120, 300), (130, 600), (140, 1_000)];
+
+    /// Returns an observation buffer holding `history`, oldest first, with the newest
+    /// observation in slot `newest`. Observations that don't fit are overwritten.
+    fn observations(history: &[(u32, i64)], newest: usize) -> ObservationState {
+        let mut ring = [UNWRITTEN; 100];
+        for (age, (block_timestamp, tick_cumulative)) in history.iter().rev().take(100).enumerate()
+        {
+            ring[(newest + ring.len() - age) % ring.len()] = Observation {
+                block_timestamp: *block_timestamp,
+                tick_cumulative: *tick_cumulative,
+                padding: [0; 4],
+            };
+        }
+        ObservationState {
+            initialized: true,
+            recent_epoch: 0,
+            observation_index: newest as u16,
+            pool_id: Pubkey::new_unique(),
+            observations: ring,
+            padding: [0; 4],
+        }
+    }
+
+    #[test]
+    fn twap_tick_averages_back_to_the_first_observation_of_the_window() {
+        // Slot 4 in a buffer that hasn't wrapped, and slot 1 in one that has.
+        for newest in [4, 1] {
+            let observations = observations(&HISTORY, newest);
+            assert_eq!(twap_tick(&observations, 10), Some(40));
+            // No observation at 125, so the average reaches back to 120.
+            assert_eq!(twap_tick(&observations, 15), Some(35));
+            assert_eq!(twap_tick(&observations, 20), Some(35));
+            assert_eq!(twap_tick(&observations, 30), Some(30));
+            assert_eq!(twap_tick(&observations, 40), Some(25));
+        }
+    }
+
+    #[test]
+    fn twap_tick_is_none_past_the_oldest_observation() {
+        for newest in [4, 1] {
+            let observations = observations(&HISTORY, newest);
+            assert_eq!(twap_tick(&observations, 41), None);
+            assert_eq!(twap_tick(&observations, 141), None);
+        }
+    }
+
+    #[test]
+    fn twap_tick_reaches_back_to_the_oldest_slot_of_a_full_buffer() {
+        // One observation a second at a tick of 7, overwriting the buffer from slot 37.
+        let history: Vec<(u32, i64)> = (0..150)
+            .map(|age| (1_000 + age, 7 * i64::from(age)))
+            .collect();
+        let observations = observations(&history, 37);
+        assert_eq!(twap_tick(&observations, 99), Some(7));
+        assert_eq!(twap_tick(&observations, 100), None);
+    }
+
+    #[test]
+    fn twap_tick_rounds_negative_averages_down() {
+        let observations = observations(&[(100, 0), (102, -5)], 0);
+        assert_eq!(twap_tick(&observations, 2), Some(-3));
+    }
+}
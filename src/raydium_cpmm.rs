@@ -0,0 +1,163 @@
+//! Accounts around a Raydium CPMM pool that `get_info_struct` doesn't fetch by default.
+//!
+//! A CPMM `PoolState` only references its `AmmConfig`, which holds the trade, protocol
+//! and fund fee rates shared by every pool created with that config, and its vaults and
+//! LP mint, whose balances are needed to value LP tokens.
+//!
+//! The program has added fields to `PoolState` and `AmmConfig` since the bundled IDL
+//! was published, carving them out of the trailing padding so the accounts kept their
+//! length. Every pool therefore parses with the IDL, and `PoolStateExtension` and
+//! `creator_fee_rate` read the newer fields back out of the padding.
+
+use anchor_lang::prelude::{AccountDeserialize, Pubkey};
+use serde::Serialize;
+
+use crate::fetcher::{self, AccountFetcher};
+use crate::layouts::spl_token::{Mint, TokenAccount};
+use crate::parsing::PoolError;
+use crate::parsing::raydium_amm_cpmm_new::accounts::{AmmConfig, PoolState};
+
+/// Denominator of the fee rates of an `AmmConfig`; a `trade_fee_rate` of 2500 is 0.25%.
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+#[derive(Debug, Serialize)]
+/// A CPMM pool together with the `AmmConfig` that sets its fees.
+pub struct RaydiumCpmmPool {
+    pub pool: PoolState,
+    pub amm_config: AmmConfig,
+}
+
+/// Fetches the `AmmConfig` referenced by `pool.amm_config`.
+pub async fn fetch_amm_config<F: AccountFetcher>(
+    pool: &PoolState,
+    fetcher: &F,
+) -> std::result::Result<AmmConfig, PoolError> {
+    let data = fetcher::fetch_account_data(fetcher, &pool.amm_config).await?;
+    match AmmConfig::try_deserialize(&mut &data[..]) {
+        Ok(config) => Ok(config),
+        Err(e) => Err(PoolError::DeserializeError(e)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+/// Revision of the `PoolState` layout.
+pub enum PoolStateVersion {
+    /// The layout of the bundled IDL.
+    V1,
+    /// Adds `recent_epoch`, the epoch the pool last swapped in.
+    V2,
+    /// Adds fees collected for the pool creator on top of the trade fee.
+    V3,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+/// Fields of `PoolState` newer than the bundled IDL, which are zero in pools of earlier
+/// revisions.
+pub struct PoolStateExtension {
+    pub recent_epoch: u64,
+    /// Which of the pool's tokens creator fees are collected in: both (0), token 0 (1)
+    /// or token 1 (2).
+    pub creator_fee_on: u8,
+    pub enable_creator_fee: bool,
+    pub creator_fees_token0: u64,
+    pub creator_fees_token1: u64,
+}
+
+impl PoolStateExtension {
+    /// Reads the newer fields of `pool` out of its padding.
+    pub fn from_pool(pool: &PoolState) -> Self {
+        let [
+            recent_epoch,
+            flags,
+            creator_fees_token0,
+            creator_fees_token1,
+            ..,
+        ] = pool.padding;
+        let flags = flags.to_le_bytes();
+        PoolStateExtension {
+            recent_epoch,
+            creator_fee_on: flags[0],
+            enable_creator_fee: flags[1] != 0,
+            creator_fees_token0,
+            creator_fees_token1,
+        }
+    }
+
+    /// Returns the earliest revision that has every field set in the pool.
+    ///
+    /// A pool of a later revision that hasn't used its new fields yet holds the same bytes
+    /// as one of an earlier revision, so both are reported as the earlier one.
+    pub fn version(&self) -> PoolStateVersion {
+        if self.creator_fee_on != 0
+            || self.enable_creator_fee
+            || self.creator_fees_token0 != 0
+            || self.creator_fees_token1 != 0
+        {
+            PoolStateVersion::V3
+        } else if self.recent_epoch != 0 {
+            PoolStateVersion::V2
+        } else {
+            PoolStateVersion::V1
+        }
+    }
+}
+
+/// Returns the creator fee rate of `config`, over `FEE_RATE_DENOMINATOR`, which is zero
+/// for configs created before creator fees.
+pub fn creator_fee_rate(config: &AmmConfig) -> u64 {
+    config.padding[0]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+/// Reserves of a CPMM pool and the LP supply they back.
+pub struct LpValuation {
+    /// Supply of the pool's LP mint.
+    pub lp_supply: u64,
+    /// Vault balance of token 0 net of the protocol, fund and creator fees it still holds.
+    pub reserve0: u64,
+    /// Vault balance of token 1 net of the protocol, fund and creator fees it still holds.
+    pub reserve1: u64,
+}
+
+impl LpValuation {
+    /// Returns the amounts of token 0 and token 1 redeemable for `lp_amount` LP tokens,
+    /// rounded down.
+    pub fn amounts_for_lp(&self, lp_amount: u64) -> Option<(u64, u64)> {
+        if self.lp_supply == 0 {
+            return None;
+        }
+        let share = |reserve: u64| {
+            let amount = u128::from(lp_amount).checked_mul(u128::from(reserve))?
+                / u128::from(self.lp_supply);
+            u64::try_from(amount).ok()
+        };
+        Some((share(self.reserve0)?, share(self.reserve1)?))
+    }
+}
+
+/// Fetches the LP mint and both vaults of `pool` in one request and returns the pool's
+/// reserves and LP supply.
+pub async fn fetch_lp_valuation<F: AccountFetcher>(
+    pool: &PoolState,
+    fetcher: &F,
+) -> std::result::Result<LpValuation, PoolError> {
+    let keys: [Pubkey; 3] = [pool.lp_mint, pool.token0_vault, pool.token1_vault];
+    let [lp_mint, vault0, vault1] = fetcher::fetch_existing_accounts(fetcher, &keys).await?;
+    let lp_supply = Mint::try_deserialize(&mut &lp_mint.data[..])?.supply;
+    let amount0 = TokenAccount::try_deserialize(&mut &vault0.data[..])?.amount;
+    let amount1 = TokenAccount::try_deserialize(&mut &vault1.data[..])?.amount;
+    let extension = PoolStateExtension::from_pool(pool);
+    Ok(LpValuation {
+        lp_supply,
+        reserve0: amount0.saturating_sub(
+            pool.protocol_fees_token0
+                .saturating_add(pool.fund_fees_token0)
+                .saturating_add(extension.creator_fees_token0),
+        ),
+        reserve1: amount1.saturating_sub(
+            pool.protocol_fees_token1
+                .saturating_add(pool.fund_fees_token1)
+                .saturating_add(extension.creator_fees_token1),
+        ),
+    })
+}
@@ -0,0 +1,271 @@
+//! Live vault balances of a parsed pool.
+//!
+//! Most pool state accounts only reference the token accounts that hold their
+//! liquidity, so the balances that quotes are based on have to be fetched separately.
+//! Pools that track their reserves in the state account itself, such as bonding curves
+//! and Stabble pools, and accounts that aren't pools have no vaults here.
+//!
+//! `get_info_struct_with_reserves` fetches the balances together with the pool, so that
+//! both are read at the same slot or close to it.
+//!
+//! `fetch_reserves_ui` also converts the balances to whole tokens by the decimals of their
+//! mints, which it fetches unless the pool account caches them.
+
+use std::iter;
+use std::time::SystemTime;
+
+use anchor_client::solana_client::rpc_config::RpcAccountInfoConfig;
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::{AccountDeserialize, Pubkey};
+
+use crate::fetcher::AccountFetcher;
+use crate::layouts::spl_token::TokenAccount;
+use crate::mints::fetch_mints;
+use crate::normalized;
+use crate::parsing::{
+    self, AldrinPool, AmmPool, BonkswapPool, ParseMode, ParsedPool, PoolError, PoolType,
+    StabblePool,
+};
+use crate::program_ids::Cluster;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The balance of one vault of a pool.
+pub struct VaultReserve {
+    pub vault: Pubkey,
+    /// Mint of the vault, as recorded in the token account.
+    pub mint: Pubkey,
+    /// Balance of the vault, in atoms of `mint`.
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A reserve of a pool in whole tokens.
+pub struct UiReserve {
+    pub mint: Pubkey,
+    /// Reserve in atoms of `mint`.
+    pub amount: u64,
+    pub decimals: u8,
+    /// Reserve in whole tokens, `amount / 10^decimals`.
+    pub ui_amount: f64,
+}
+
+impl UiReserve {
+    /// Converts `amount` atoms of a mint with `decimals` decimals.
+    pub fn new(mint: Pubkey, amount: u64, decimals: u8) -> Self {
+        UiReserve {
+            mint,
+            amount,
+            decimals,
+            ui_amount: amount as f64 / 10f64.powi(i32::from(decimals)),
+        }
+    }
+}
+
+/// Returns the token accounts holding the liquidity of `pool`, base or token A/X/0
+/// first.
+///
+/// For a Raydium legacy AMM v4 pool the vaults are only part of the liquidity; use
+/// `raydium_amm_v4::fetch_pool` for its actual reserves.
+pub fn vault_addresses(pool: &AmmPool) -> Vec<Pubkey> {
+    match pool {
+        AmmPool::PumpFun(pool) => vec![pool.pool_base_token_account, pool.pool_quote_token_account],
+        AmmPool::PumpFunWithConfig(pool) => vec![
+            pool.pool.pool_base_token_account,
+            pool.pool.pool_quote_token_account,
+        ],
+        AmmPool::RaydiumCpmmAmm(pool) => vec![pool.token0_vault, pool.token1_vault],
+        AmmPool::RaydiumCpmmAmmWithConfig(pool) => {
+            vec![pool.pool.token0_vault, pool.pool.token1_vault]
+        }
+        AmmPool::RaydiumCamm(pool) => vec![pool.token_vault0, pool.token_vault1],
+        AmmPool::RaydiumLegacyAmm(amm) => vec![amm.coin_vault, amm.pc_vault],
+        AmmPool::LifinityV2(amm) => vec![amm.token_a_account, amm.token_b_account],
+        AmmPool::Phoenix(market) => vec![
+            market.header.base_params.vault_key,
+            market.header.quote_params.vault_key,
+        ],
+        AmmPool::SaberStableSwap(swap) => vec![swap.token_a_reserves, swap.token_b_reserves],
+        AmmPool::MercurialStableSwap(swap) => swap.active_token_accounts().to_vec(),
+        AmmPool::Invariant(pool) => vec![pool.token_x_reserve, pool.token_y_reserve],
+        AmmPool::CremaClmm(pool) => vec![pool.token_a_vault, pool.token_b_vault],
+        AmmPool::Aldrin(AldrinPool::V1(pool)) => {
+            vec![pool.base_token_vault, pool.quote_token_vault]
+        }
+        AmmPool::Aldrin(AldrinPool::V2(pool)) => {
+            vec![pool.base_token_vault, pool.quote_token_vault]
+        }
+        AmmPool::FluxBeam(swap) | AmmPool::OrcaTokenSwap(swap) | AmmPool::SplTokenSwap(swap) => {
+            vec![swap.token_a, swap.token_b]
+        }
+        AmmPool::RaydiumLaunchLab(pool) => vec![pool.base_vault, pool.quote_vault],
+        AmmPool::MeteoraDbc(pool) => vec![pool.base_vault, pool.quote_vault],
+        AmmPool::MeteoraVault(vault) => vec![vault.vault.token_vault],
+        AmmPool::Bonkswap(BonkswapPool::V1(pool)) => vec![pool.pool_x_account, pool.pool_y_account],
+        AmmPool::Bonkswap(BonkswapPool::V2(pool)) => vec![pool.pool_x_account, pool.pool_y_account],
+        AmmPool::MeteoraDlmm(pair) => vec![pair.reserve_x, pair.reserve_y],
+        AmmPool::OrcaWhirlpool(pool) => vec![pool.token_vault_a, pool.token_vault_b],
+        #[cfg(feature = "evm")]
+        AmmPool::UniswapV2(_) | AmmPool::UniswapV3(_) => Vec::new(),
+        AmmPool::SanctumInfinity(_)
+        | AmmPool::Stabble(_)
+        | AmmPool::PumpFunBondingCurve(_)
+        | AmmPool::Moonshot(_)
+        | AmmPool::BonkswapFarm(_)
+        | AmmPool::Dynamic(_)
+        | AmmPool::SplStakePool(_)
+        | AmmPool::Marinade(_)
+        | AmmPool::RaydiumCammConfig(_)
+        | AmmPool::RaydiumCammObservation(_)
+        | AmmPool::RaydiumCpmmConfig(_)
+        | AmmPool::PumpFunGlobalConfig(_)
+        | AmmPool::OrcaWhirlpoolPosition(_) => Vec::new(),
+    }
+}
+
+/// Fetches the vaults of `pool` in one request and returns their balances, in the order
+/// of `vault_addresses`.
+pub async fn fetch_reserves<F: AccountFetcher>(
+    pool: &AmmPool,
+    fetcher: &F,
+) -> std::result::Result<Vec<VaultReserve>, PoolError> {
+    let vaults = vault_addresses(pool);
+    if vaults.is_empty() {
+        return Ok(Vec::new());
+    }
+    let accounts = fetcher.fetch_accounts(&vaults).await?.value;
+    reserves_from_accounts(vaults, accounts)
+}
+
+/// Decodes the token accounts fetched from `vaults` into their balances.
+fn reserves_from_accounts(
+    vaults: Vec<Pubkey>,
+    accounts: Vec<Option<Account>>,
+) -> std::result::Result<Vec<VaultReserve>, PoolError> {
+    let mut reserves = Vec::with_capacity(vaults.len());
+    for (vault, account) in vaults.into_iter().zip(accounts) {
+        let Some(account) = account else {
+            return Err(PoolError::AccountNotFound { address: vault });
+        };
+        let token_account = TokenAccount::try_deserialize(&mut &account.data[..])?;
+        reserves.push(VaultReserve {
+            vault,
+            mint: token_account.mint,
+            amount: token_account.amount,
+        });
+    }
+    Ok(reserves)
+}
+
+/// Fetches and parses the pool like `parsing::get_info_struct_on`, together with the
+/// balances of its vaults in `ParsedPool::reserves`.
+///
+/// The vaults are read once the pool account tells which they are, in a second request
+/// made at the slot of the first or later. A `UniversalParser` built with `reserves` remembers
+/// the vaults of each pool and reads them in the same request as the pool instead.
+pub async fn get_info_struct_with_reserves<F: AccountFetcher>(
+    pool_type: PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+) -> std::result::Result<ParsedPool, PoolError> {
+    get_info_struct_with_vaults(pool_type, fetcher, cluster, ParseMode::Lenient, &[]).await
+}
+
+/// Like `get_info_struct_with_reserves`, checking the account data as `mode` requires and
+/// fetching `vaults`, the vaults the pool is expected to have, in the same request as the
+/// pool account. Other vaults are fetched as `get_info_struct_with_reserves` does.
+pub(crate) async fn get_info_struct_with_vaults<F: AccountFetcher>(
+    pool_type: PoolType,
+    fetcher: &F,
+    cluster: &Cluster,
+    mode: ParseMode,
+    vaults: &[Pubkey],
+) -> std::result::Result<ParsedPool, PoolError> {
+    #[cfg(feature = "evm")]
+    if matches!(
+        pool_type,
+        PoolType::UniswapV2 { .. } | PoolType::UniswapV3 { .. }
+    ) {
+        return parsing::get_info_struct_with(pool_type, fetcher, cluster, mode).await;
+    }
+
+    let address = pool_type.pool_address();
+    let addresses: Vec<Pubkey> = iter::once(address).chain(vaults.iter().copied()).collect();
+    let response = fetcher.fetch_accounts(&addresses).await?;
+    let fetched_at = SystemTime::now();
+    let slot = response.context.slot;
+    let mut accounts = response.value.into_iter();
+    let Some(account) = accounts.next().flatten() else {
+        return Err(PoolError::AccountNotFound { address });
+    };
+    let mut parsed =
+        parsing::parse_account_with(pool_type, account, slot, fetched_at, fetcher, cluster, mode)
+            .await?;
+
+    let pool_vaults = vault_addresses(&parsed.pool);
+    let vault_accounts = if pool_vaults == vaults {
+        accounts.collect()
+    } else if pool_vaults.is_empty() {
+        Vec::new()
+    } else {
+        let config = RpcAccountInfoConfig {
+            min_context_slot: Some(slot),
+            ..RpcAccountInfoConfig::default()
+        };
+        fetcher
+            .fetch_accounts_with_config(&pool_vaults, config)
+            .await?
+            .value
+    };
+    parsed.reserves = Some(reserves_from_accounts(pool_vaults, vault_accounts)?);
+    Ok(parsed)
+}
+
+/// Returns the reserves of `pool` in whole tokens, in the order of `vault_addresses`.
+///
+/// Vault balances are fetched in one request, and the mints in a second one unless the
+/// pool caches the decimals of both tokens. Stabble pools track their balances and
+/// decimals themselves and return them in the order of their tokens without a request.
+/// Other pools that aren't backed by vaults have no reserves here.
+pub async fn fetch_reserves_ui<F: AccountFetcher>(
+    pool: &AmmPool,
+    fetcher: &F,
+) -> std::result::Result<Vec<UiReserve>, PoolError> {
+    match pool {
+        AmmPool::Stabble(StabblePool::Stable(pool)) => {
+            return Ok(pool
+                .tokens
+                .iter()
+                .map(|token| UiReserve::new(token.mint, token.balance, token.decimals))
+                .collect());
+        }
+        AmmPool::Stabble(StabblePool::Weighted(pool)) => {
+            return Ok(pool
+                .tokens
+                .iter()
+                .map(|token| UiReserve::new(token.mint, token.balance, token.decimals))
+                .collect());
+        }
+        _ => {}
+    }
+
+    let reserves = fetch_reserves(pool, fetcher).await?;
+    if reserves.is_empty() {
+        return Ok(Vec::new());
+    }
+    let decimals = match (&reserves[..], normalized::decimals(pool)) {
+        ([_, _], (Some(a), Some(b))) => vec![a, b],
+        _ => {
+            let mints: Vec<Pubkey> = reserves.iter().map(|reserve| reserve.mint).collect();
+            fetch_mints(&mints, fetcher)
+                .await?
+                .iter()
+                .map(|mint| mint.mint.decimals)
+                .collect()
+        }
+    };
+    Ok(reserves
+        .iter()
+        .zip(decimals)
+        .map(|(reserve, decimals)| UiReserve::new(reserve.mint, reserve.amount, decimals))
+        .collect())
+}
@@ -0,0 +1,294 @@
+//! Retrying RPC requests that fail for reasons that go away on their own.
+//!
+//! `Retrying` wraps an `AccountFetcher` and repeats a request that failed with a
+//! transient error, as told by `PoolError::is_transient`, after an exponentially growing
+//! delay. Other errors, such as a missing account or data that doesn't parse, are
+//! returned at once. `UniversalParser` retries with `RetryPolicy::default()` unless
+//! configured otherwise.
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use anchor_client::solana_client::client_error::ClientErrorKind;
+use anchor_client::solana_client::client_error::reqwest::StatusCode;
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+    JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+};
+use anchor_client::solana_client::rpc_request::RpcError;
+use anchor_client::solana_client::rpc_response::Response;
+use anchor_client::solana_sdk::account::Account;
+use anchor_lang::prelude::Pubkey;
+
+use crate::fetcher::AccountFetcher;
+use crate::parsing::PoolError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How often and how long to wait before a failed request is repeated.
+///
+/// The delay before the `n`th retry is `base_delay * 2^(n - 1)`, capped at `max_delay`,
+/// and then reduced by a random fraction of up to `jitter` so that clients failing
+/// together don't retry together.
+pub struct RetryPolicy {
+    /// Attempts made in total, including the first. One disables retries.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction of the delay, between 0 and 1, that may be randomly taken off it.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, waiting about 200 ms and then 400 ms.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Returns the delay before the retry following failed attempt number `attempt`,
+    /// counted from one.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0) * random_fraction();
+        delay.mul_f64(1.0 - jitter)
+    }
+
+    /// Runs `request` until it succeeds, fails with an error that isn't transient, or
+    /// all attempts are used up.
+    pub async fn run<T, Request, Fut>(&self, request: Request) -> Result<T, PoolError>
+    where
+        Request: Fn() -> Fut,
+        Fut: Future<Output = Result<T, PoolError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match request().await {
+                Err(e) if e.is_transient() && attempt < self.max_attempts => {
+                    tokio::time::sleep(self.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Returns a random number in `[0, 1)`, from the random keys the standard library seeds
+/// hash maps with.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl PoolError {
+    /// Returns whether the request that failed with this error may succeed if repeated:
+    /// it timed out or read accounts at diverging slots, or the RPC node rate limited it,
+    /// couldn't be reached or answered with a server error, or is unhealthy or behind the
+    /// slot the request needs.
+    pub fn is_transient(&self) -> bool {
+        let e = match self {
+            PoolError::RpcError(e) => e,
+            PoolError::Timeout(_) | PoolError::InconsistentSlots(..) => return true,
+            _ => return false,
+        };
+        match e.kind() {
+            ClientErrorKind::Io(_) => true,
+            ClientErrorKind::Reqwest(e) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || e.status().is_some_and(|status| {
+                        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+                    })
+            }
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => matches!(
+                *code,
+                JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                    | JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE
+                    | JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED
+            ),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An `AccountFetcher` that repeats the requests of `inner` as `policy` allows.
+pub struct Retrying<F> {
+    pub inner: F,
+    pub policy: RetryPolicy,
+}
+
+impl<F> Retrying<F> {
+    pub fn new(inner: F, policy: RetryPolicy) -> Self {
+        Retrying { inner, policy }
+    }
+}
+
+impl<F: AccountFetcher> AccountFetcher for Retrying<F> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        self.policy
+            .run(|| {
+                self.inner
+                    .fetch_accounts_with_config(addresses, config.clone())
+            })
+            .await
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        self.policy
+            .run(|| {
+                self.inner
+                    .fetch_program_accounts(program_id, config.clone())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use anchor_client::solana_client::client_error::ClientError;
+    use anchor_client::solana_client::rpc_request::RpcResponseErrorData;
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::fetcher::WithMinContextSlot;
+    use crate::mock::MockFetcher;
+
+    /// Five attempts, waiting 100, 200, 250 and 250 ms without jitter.
+    const POLICY: RetryPolicy = RetryPolicy {
+        max_attempts: 5,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(250),
+        jitter: 0.0,
+    };
+
+    fn rpc_error(code: i64) -> PoolError {
+        PoolError::from(ClientError::from(RpcError::RpcResponseError {
+            code,
+            message: String::new(),
+            data: RpcResponseErrorData::Empty,
+        }))
+    }
+
+    #[test]
+    fn delay_doubles_up_to_max_delay() {
+        let delays: Vec<_> = (1..=5).map(|attempt| POLICY.delay(attempt)).collect();
+        let millis = [100, 200, 250, 250, 250].map(Duration::from_millis);
+        assert_eq!(delays, millis);
+        assert_eq!(POLICY.delay(u32::MAX), POLICY.max_delay);
+    }
+
+    #[test]
+    fn jitter_only_shortens_the_delay() {
+        let policy = RetryPolicy {
+            jitter: 0.5,
+            ..POLICY
+        };
+        for _ in 0..100 {
+            let delay = policy.delay(2);
+            assert!(delay > Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn timeouts_and_unavailable_nodes_are_transient() {
+        assert!(PoolError::Timeout(Duration::from_secs(1)).is_transient());
+        assert!(PoolError::InconsistentSlots(1, 2).is_transient());
+        assert!(rpc_error(JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY).is_transient());
+        assert!(rpc_error(JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED).is_transient());
+        // Invalid params.
+        assert!(!rpc_error(-32602).is_transient());
+        let address = Pubkey::new_unique();
+        assert!(!PoolError::AccountNotFound { address }.is_transient());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn transient_failures_are_retried_until_the_attempts_run_out() {
+        let fetcher = MockFetcher::new().with_slot(10);
+        let retrying = Retrying::new(WithMinContextSlot::new(&fetcher, 11), POLICY);
+        let start = Instant::now();
+        let result = retrying.fetch_accounts(&[Pubkey::new_unique()]).await;
+        assert!(result.is_err_and(|e| e.is_transient()));
+        assert_eq!(fetcher.request_count(), 5);
+        assert_eq!(
+            start.elapsed(),
+            Duration::from_millis(100 + 200 + 250 + 250)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_that_recover_return_their_response() {
+        let attempts = AtomicU32::new(0);
+        let start = Instant::now();
+        let result = POLICY
+            .run(|| async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(PoolError::Timeout(Duration::from_secs(1))),
+                    _ => Ok(7),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 7);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(start.elapsed(), Duration::from_millis(100 + 200));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn errors_that_arent_transient_are_returned_at_once() {
+        let attempts = AtomicU32::new(0);
+        let start = Instant::now();
+        let result: Result<(), _> = POLICY
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(rpc_error(-32602))
+            })
+            .await;
+        assert!(result.is_err_and(|e| !e.is_transient()));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn a_policy_of_none_makes_one_attempt() {
+        let fetcher = MockFetcher::new().with_slot(10);
+        let retrying = Retrying::new(WithMinContextSlot::new(&fetcher, 11), RetryPolicy::none());
+        assert!(
+            retrying
+                .fetch_accounts(&[Pubkey::new_unique()])
+                .await
+                .is_err()
+        );
+        assert_eq!(fetcher.request_count(), 1);
+    }
+}
@@ -0,0 +1,1168 @@
+//! JSON Schemas of the serialized pools, behind the `schemars` feature.
+//!
+//! Every pool type written by the `Serialize` impls of the crate implements
+//! `schemars::JsonSchema`, so `schemars::schema_for!(AmmPool)` describes the default serde
+//! output and `envelope_schema` the output of `AmmPool::to_json`. Accounts generated by
+//! `declare_program!` are described from their bundled IDL, so their schemas always match
+//! what is serialized; the hand-written layouts are listed field by field below.
+
+use std::borrow::Cow;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang_idl::types::{Idl, IdlArrayLen, IdlDefinedFields, IdlType, IdlTypeDefTy};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde_json::{Map, Value};
+
+use crate::dynamic::DynamicPool;
+use crate::json::JSON_SCHEMA_VERSION;
+use crate::layouts::sanctum::LstStateList;
+use crate::layouts::spl_stake_pool::{AccountType, FutureEpoch};
+use crate::parsing::{
+    AmmPool, MeteoraVault, aldrin_v1, aldrin_v2, bonkswap, crema_clmm, invariant, lifinity_v2,
+    marinade, meteora_dbc, meteora_dlmm, meteora_vault, moonshot, orca_whirlpool, pamm, pump,
+    raydium_amm_cpmm_new, raydium_camm, raydium_launchlab, stabble_stable_swap,
+    stabble_weighted_swap,
+};
+use crate::pump_amm::{CreatorVault, PumpAmmPool};
+use crate::raydium_cpmm::RaydiumCpmmPool;
+use crate::serialization::IdlAccount;
+
+#[cfg(feature = "evm")]
+use crate::evm::{EvmAddress, UniswapV2Pair, UniswapV3Pool};
+
+/// Schema of a value as it appears in a field of a serialized pool.
+///
+/// This differs from `JsonSchema` for types that are serialized as strings, such as
+/// pubkeys and 128-bit integers.
+pub(crate) trait FieldSchema {
+    fn field_schema(generator: &mut SchemaGenerator) -> Schema;
+}
+
+macro_rules! primitive_field_schema {
+    ($($ty:ty),+) => {
+        $(
+            impl FieldSchema for $ty {
+                fn field_schema(generator: &mut SchemaGenerator) -> Schema {
+                    generator.subschema_for::<$ty>()
+                }
+            }
+        )+
+    };
+}
+
+primitive_field_schema!(bool, u8, u16, u32, u64, i8, i16, i32, i64, f64, String);
+
+impl FieldSchema for u128 {
+    fn field_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({ "type": "string", "pattern": "^[0-9]+$" })
+    }
+}
+
+impl FieldSchema for i128 {
+    fn field_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({ "type": "string", "pattern": "^-?[0-9]+$" })
+    }
+}
+
+impl FieldSchema for Pubkey {
+    fn field_schema(_: &mut SchemaGenerator) -> Schema {
+        pubkey_schema()
+    }
+}
+
+#[cfg(feature = "evm")]
+impl FieldSchema for EvmAddress {
+    fn field_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({ "type": "string", "pattern": "^0x[0-9a-f]{40}$" })
+    }
+}
+
+impl<T: FieldSchema, const N: usize> FieldSchema for [T; N] {
+    fn field_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "array",
+            "items": T::field_schema(generator),
+            "minItems": N,
+            "maxItems": N,
+        })
+    }
+}
+
+impl<T: FieldSchema> FieldSchema for Vec<T> {
+    fn field_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({ "type": "array", "items": T::field_schema(generator) })
+    }
+}
+
+impl<T: FieldSchema> FieldSchema for Option<T> {
+    fn field_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({ "anyOf": [T::field_schema(generator), { "type": "null" }] })
+    }
+}
+
+impl<T: IdlAccount + JsonSchema> FieldSchema for T {
+    fn field_schema(generator: &mut SchemaGenerator) -> Schema {
+        generator.subschema_for::<T>()
+    }
+}
+
+impl FieldSchema for AccountType {
+    fn field_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({ "enum": ["Uninitialized", "StakePool", "ValidatorList"] })
+    }
+}
+
+impl<T: FieldSchema> FieldSchema for FutureEpoch<T> {
+    fn field_schema(generator: &mut SchemaGenerator) -> Schema {
+        let value = T::field_schema(generator);
+        json_schema!({
+            "oneOf": [
+                { "const": "None" },
+                tagged("One", value.clone()),
+                tagged("Two", value),
+            ]
+        })
+    }
+}
+
+impl FieldSchema for LstStateList {
+    fn field_schema(generator: &mut SchemaGenerator) -> Schema {
+        Vec::<crate::layouts::sanctum::LstState>::field_schema(generator)
+    }
+}
+
+/// Implements `JsonSchema` and `FieldSchema` for structs serialized field by field.
+macro_rules! struct_schema {
+    ($($ty:ty as $name:literal { $($field:ident: $field_ty:ty),* $(,)? })+) => {
+        $(
+            impl JsonSchema for $ty {
+                fn schema_name() -> Cow<'static, str> {
+                    $name.into()
+                }
+
+                fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+                    object_schema(vec![
+                        $((stringify!($field), <$field_ty as FieldSchema>::field_schema(generator)),)*
+                    ])
+                }
+            }
+
+            impl FieldSchema for $ty {
+                fn field_schema(generator: &mut SchemaGenerator) -> Schema {
+                    generator.subschema_for::<Self>()
+                }
+            }
+        )+
+    };
+}
+
+mod raydium_amm_v4_schemas {
+    use super::*;
+    use crate::layouts::raydium_amm_v4::{AmmInfo, Fees, StateData};
+
+    struct_schema! {
+        Fees as "raydium_amm_v4.Fees" {
+            min_separate_numerator: u64,
+            min_separate_denominator: u64,
+            trade_fee_numerator: u64,
+            trade_fee_denominator: u64,
+            pnl_numerator: u64,
+            pnl_denominator: u64,
+            swap_fee_numerator: u64,
+            swap_fee_denominator: u64,
+        }
+        StateData as "raydium_amm_v4.StateData" {
+            need_take_pnl_coin: u64,
+            need_take_pnl_pc: u64,
+            total_pnl_pc: u64,
+            total_pnl_coin: u64,
+            pool_open_time: u64,
+            padding: [u64; 2],
+            orderbook_to_init_time: u64,
+            swap_coin_in_amount: u128,
+            swap_pc_out_amount: u128,
+            swap_acc_pc_fee: u64,
+            swap_pc_in_amount: u128,
+            swap_coin_out_amount: u128,
+            swap_acc_coin_fee: u64,
+        }
+        AmmInfo as "raydium_amm_v4.AmmInfo" {
+            status: u64,
+            nonce: u64,
+            order_num: u64,
+            depth: u64,
+            coin_decimals: u64,
+            pc_decimals: u64,
+            state: u64,
+            reset_flag: u64,
+            min_size: u64,
+            vol_max_cut_ratio: u64,
+            amount_wave: u64,
+            coin_lot_size: u64,
+            pc_lot_size: u64,
+            min_price_multiplier: u64,
+            max_price_multiplier: u64,
+            sys_decimal_value: u64,
+            fees: Fees,
+            state_data: StateData,
+            coin_vault: Pubkey,
+            pc_vault: Pubkey,
+            coin_vault_mint: Pubkey,
+            pc_vault_mint: Pubkey,
+            lp_mint: Pubkey,
+            open_orders: Pubkey,
+            market: Pubkey,
+            market_program: Pubkey,
+            target_orders: Pubkey,
+            padding1: [u64; 8],
+            amm_owner: Pubkey,
+            lp_amount: u64,
+            client_order_id: u64,
+            recent_epoch: u64,
+            padding2: u64,
+        }
+    }
+}
+
+mod phoenix_schemas {
+    use super::*;
+    use crate::layouts::phoenix::{
+        FifoMarketState, Market, MarketHeader, MarketSizeParams, RestingOrder, TokenParams,
+    };
+
+    struct_schema! {
+        MarketSizeParams as "phoenix.MarketSizeParams" {
+            bids_size: u64,
+            asks_size: u64,
+            num_seats: u64,
+        }
+        TokenParams as "phoenix.TokenParams" {
+            decimals: u32,
+            vault_bump: u32,
+            mint_key: Pubkey,
+            vault_key: Pubkey,
+        }
+        MarketHeader as "phoenix.MarketHeader" {
+            discriminant: u64,
+            status: u64,
+            market_size_params: MarketSizeParams,
+            base_params: TokenParams,
+            base_lot_size: u64,
+            quote_params: TokenParams,
+            quote_lot_size: u64,
+            tick_size_in_quote_atoms_per_base_unit: u64,
+            authority: Pubkey,
+            fee_recipient: Pubkey,
+            market_sequence_number: u64,
+            successor: Pubkey,
+            raw_base_units_per_base_unit: u32,
+            padding1: u32,
+            padding2: [u64; 32],
+        }
+        FifoMarketState as "phoenix.FifoMarketState" {
+            padding: [u64; 32],
+            base_lots_per_base_unit: u64,
+            tick_size_in_quote_lots_per_base_unit: u64,
+            order_sequence_number: u64,
+            taker_fee_bps: u64,
+            collected_quote_lot_fees: u64,
+            unclaimed_quote_lot_fees: u64,
+        }
+        RestingOrder as "phoenix.RestingOrder" {
+            price_in_ticks: u64,
+            order_sequence_number: u64,
+            trader_index: u64,
+            num_base_lots: u64,
+            last_valid_slot: u64,
+            last_valid_unix_timestamp_in_seconds: u64,
+        }
+        Market as "phoenix.Market" {
+            header: MarketHeader,
+            state: FifoMarketState,
+            bids: Vec<RestingOrder>,
+            asks: Vec<RestingOrder>,
+        }
+    }
+}
+
+mod saber_schemas {
+    use super::*;
+    use crate::layouts::saber::{Fees, SwapInfo};
+
+    struct_schema! {
+        Fees as "saber.Fees" {
+            admin_trade_fee_numerator: u64,
+            admin_trade_fee_denominator: u64,
+            admin_withdraw_fee_numerator: u64,
+            admin_withdraw_fee_denominator: u64,
+            trade_fee_numerator: u64,
+            trade_fee_denominator: u64,
+            withdraw_fee_numerator: u64,
+            withdraw_fee_denominator: u64,
+        }
+        SwapInfo as "saber.SwapInfo" {
+            is_initialized: bool,
+            is_paused: bool,
+            nonce: u8,
+            initial_amp_factor: u64,
+            target_amp_factor: u64,
+            start_ramp_ts: i64,
+            stop_ramp_ts: i64,
+            future_admin_deadline: i64,
+            future_admin_key: Pubkey,
+            admin_key: Pubkey,
+            token_a_reserves: Pubkey,
+            token_b_reserves: Pubkey,
+            pool_mint: Pubkey,
+            token_a_mint: Pubkey,
+            token_b_mint: Pubkey,
+            token_a_admin_fees: Pubkey,
+            token_b_admin_fees: Pubkey,
+            fees: Fees,
+        }
+    }
+}
+
+mod mercurial_schemas {
+    use super::*;
+    use crate::layouts::mercurial::{MAX_TOKENS, SwapInfo};
+
+    struct_schema! {
+        SwapInfo as "mercurial.SwapInfo" {
+            version: u8,
+            is_initialized: bool,
+            nonce: u8,
+            amplification_coefficient: u64,
+            fee_numerator: u64,
+            admin_fee_numerator: u64,
+            token_accounts_length: u32,
+            precision_factor: u64,
+            precision_multipliers: [u64; MAX_TOKENS],
+            token_accounts: [Pubkey; MAX_TOKENS],
+            pool_mint: Pubkey,
+            admin_token_mint: Pubkey,
+        }
+    }
+}
+
+mod spl_token_swap_schemas {
+    use super::*;
+    use crate::layouts::spl_token_swap::{Fees, SwapCurve, TokenSwap};
+
+    struct_schema! {
+        Fees as "spl_token_swap.Fees" {
+            trade_fee_numerator: u64,
+            trade_fee_denominator: u64,
+            owner_trade_fee_numerator: u64,
+            owner_trade_fee_denominator: u64,
+            owner_withdraw_fee_numerator: u64,
+            owner_withdraw_fee_denominator: u64,
+            host_fee_numerator: u64,
+            host_fee_denominator: u64,
+        }
+        SwapCurve as "spl_token_swap.SwapCurve" {
+            curve_type: u8,
+            calculator: [u8; 32],
+        }
+        TokenSwap as "spl_token_swap.TokenSwap" {
+            version: u8,
+            is_initialized: bool,
+            bump_seed: u8,
+            token_program_id: Pubkey,
+            token_a: Pubkey,
+            token_b: Pubkey,
+            pool_mint: Pubkey,
+            token_a_mint: Pubkey,
+            token_b_mint: Pubkey,
+            pool_fee_account: Pubkey,
+            fees: Fees,
+            swap_curve: SwapCurve,
+        }
+    }
+}
+
+mod sanctum_schemas {
+    use super::*;
+    use crate::layouts::sanctum::{InfinityPool, LstState, PoolState};
+
+    struct_schema! {
+        PoolState as "sanctum.PoolState" {
+            total_sol_value: u64,
+            trading_protocol_fee_bps: u16,
+            lp_protocol_fee_bps: u16,
+            version: u8,
+            is_disabled: u8,
+            is_rebalancing: u8,
+            padding: [u8; 1],
+            admin: Pubkey,
+            rebalance_authority: Pubkey,
+            protocol_fee_beneficiary: Pubkey,
+            pricing_program: Pubkey,
+            lp_token_mint: Pubkey,
+        }
+        LstState as "sanctum.LstState" {
+            is_input_disabled: u8,
+            pool_reserves_bump: u8,
+            protocol_fee_accumulator_bump: u8,
+            padding: [u8; 5],
+            sol_value: u64,
+            mint: Pubkey,
+            sol_value_calculator: Pubkey,
+        }
+        InfinityPool as "sanctum.InfinityPool" {
+            state: PoolState,
+            lst_states: LstStateList,
+        }
+    }
+}
+
+mod spl_stake_pool_schemas {
+    use super::*;
+    use crate::layouts::spl_stake_pool::{Fee, Lockup, StakePool};
+
+    struct_schema! {
+        Fee as "spl_stake_pool.Fee" {
+            denominator: u64,
+            numerator: u64,
+        }
+        Lockup as "spl_stake_pool.Lockup" {
+            unix_timestamp: i64,
+            epoch: u64,
+            custodian: Pubkey,
+        }
+        StakePool as "spl_stake_pool.StakePool" {
+            account_type: AccountType,
+            manager: Pubkey,
+            staker: Pubkey,
+            stake_deposit_authority: Pubkey,
+            stake_withdraw_bump_seed: u8,
+            validator_list: Pubkey,
+            reserve_stake: Pubkey,
+            pool_mint: Pubkey,
+            manager_fee_account: Pubkey,
+            token_program_id: Pubkey,
+            total_lamports: u64,
+            pool_token_supply: u64,
+            last_update_epoch: u64,
+            lockup: Lockup,
+            epoch_fee: Fee,
+            next_epoch_fee: FutureEpoch<Fee>,
+            preferred_deposit_validator_vote_address: Option<Pubkey>,
+            preferred_withdraw_validator_vote_address: Option<Pubkey>,
+            stake_deposit_fee: Fee,
+            stake_withdrawal_fee: Fee,
+            next_stake_withdrawal_fee: FutureEpoch<Fee>,
+            stake_referral_fee: u8,
+            sol_deposit_authority: Option<Pubkey>,
+            sol_deposit_fee: Fee,
+            sol_referral_fee: u8,
+            sol_withdraw_authority: Option<Pubkey>,
+            sol_withdrawal_fee: Fee,
+            next_sol_withdrawal_fee: FutureEpoch<Fee>,
+            last_epoch_pool_token_supply: u64,
+            last_epoch_total_lamports: u64,
+        }
+    }
+}
+
+struct_schema! {
+    CreatorVault as "pump_amm.CreatorVault" {
+        authority: Pubkey,
+        token_account: Pubkey,
+        balance: u64,
+    }
+    PumpAmmPool as "pump_amm.PumpAmmPool" {
+        pool: pamm::accounts::Pool,
+        global_config: pamm::accounts::GlobalConfig,
+        creator_vault: Option<CreatorVault>,
+    }
+    RaydiumCpmmPool as "raydium_cpmm.RaydiumCpmmPool" {
+        pool: raydium_amm_cpmm_new::accounts::PoolState,
+        amm_config: raydium_amm_cpmm_new::accounts::AmmConfig,
+    }
+    MeteoraVault as "meteora_vault.MeteoraVault" {
+        vault: meteora_vault::accounts::Vault,
+        lp_supply: u64,
+        strategies: Vec<meteora_vault::accounts::Strategy>,
+    }
+    DynamicPool as "dynamic.DynamicPool" {
+        account_name: String,
+        value: AnyValue,
+    }
+}
+
+#[cfg(feature = "evm")]
+struct_schema! {
+    UniswapV2Pair as "evm.UniswapV2Pair" {
+        address: EvmAddress,
+        token0: EvmAddress,
+        token1: EvmAddress,
+        reserve0: u128,
+        reserve1: u128,
+        block_timestamp_last: u32,
+    }
+    UniswapV3Pool as "evm.UniswapV3Pool" {
+        address: EvmAddress,
+        token0: EvmAddress,
+        token1: EvmAddress,
+        fee: u32,
+        tick_spacing: i32,
+        liquidity: u128,
+        sqrt_price_x96: [u8; 20],
+        tick: i32,
+    }
+}
+
+/// A value decoded with a runtime IDL, which may have any shape.
+struct AnyValue;
+
+impl FieldSchema for AnyValue {
+    fn field_schema(_: &mut SchemaGenerator) -> Schema {
+        true.into()
+    }
+}
+
+/// Externally tagged, like serde's default for Rust enums.
+impl JsonSchema for AmmPool {
+    fn schema_name() -> Cow<'static, str> {
+        "AmmPool".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let variants: Vec<Value> = variants(generator)
+            .into_iter()
+            .map(|variant| tagged(variant.name, variant.schema).to_value())
+            .collect();
+        json_schema!({ "oneOf": variants })
+    }
+}
+
+/// The `AmmPool::to_json` envelope.
+struct Envelope;
+
+impl JsonSchema for Envelope {
+    fn schema_name() -> Cow<'static, str> {
+        "PoolEnvelope".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let mut envelopes = Vec::new();
+        for variant in variants(generator) {
+            for (tag, data) in variant.tags {
+                let address = if tag.starts_with("uniswap_") {
+                    json_schema!({ "type": "string", "pattern": "^0x[0-9a-f]{40}$" })
+                } else {
+                    pubkey_schema()
+                };
+                let envelope = object_schema(vec![
+                    (
+                        "schema_version",
+                        json_schema!({ "const": JSON_SCHEMA_VERSION }),
+                    ),
+                    ("protocol", json_schema!({ "const": tag })),
+                    ("address", address),
+                    ("data", data),
+                ]);
+                envelopes.push(envelope.to_value());
+            }
+        }
+        json_schema!({ "oneOf": envelopes })
+    }
+}
+
+/// Returns the root schema of the JSON written by `AmmPool::to_json`, with one branch per
+/// protocol tag.
+pub fn envelope_schema() -> Schema {
+    SchemaGenerator::default().into_root_schema_for::<Envelope>()
+}
+
+/// A variant of `AmmPool` with the schema of its serde output and the `to_json` tags it
+/// can be written under, with the schema of `data` for each.
+struct Variant {
+    name: &'static str,
+    schema: Schema,
+    tags: Vec<(&'static str, Schema)>,
+}
+
+fn variants(generator: &mut SchemaGenerator) -> Vec<Variant> {
+    /// A variant whose serde output is also its `data` under a single tag.
+    fn single<T: FieldSchema>(
+        generator: &mut SchemaGenerator,
+        name: &'static str,
+        tag: &'static str,
+    ) -> Variant {
+        let schema = T::field_schema(generator);
+        Variant {
+            name,
+            schema: schema.clone(),
+            tags: vec![(tag, schema)],
+        }
+    }
+
+    /// A variant over a two-version enum, whose `data` is the inner account.
+    fn versioned<A: FieldSchema, B: FieldSchema>(
+        generator: &mut SchemaGenerator,
+        name: &'static str,
+        (a_name, a_tag): (&'static str, &'static str),
+        (b_name, b_tag): (&'static str, &'static str),
+    ) -> Variant {
+        let a = A::field_schema(generator);
+        let b = B::field_schema(generator);
+        Variant {
+            name,
+            schema: json_schema!({
+                "oneOf": [tagged(a_name, a.clone()), tagged(b_name, b.clone())]
+            }),
+            tags: vec![(a_tag, a), (b_tag, b)],
+        }
+    }
+
+    // Keep the order of `AmmPool` so the schemas read like the enum.
+    let mut variants = vec![
+        single::<pamm::accounts::Pool>(generator, "PumpFun", "pump_fun_amm"),
+        single::<raydium_amm_cpmm_new::accounts::PoolState>(
+            generator,
+            "RaydiumCpmmAmm",
+            "raydium_cpmm",
+        ),
+        single::<raydium_camm::accounts::PoolState>(generator, "RaydiumCamm", "raydium_camm"),
+        single::<crate::layouts::raydium_amm_v4::AmmInfo>(
+            generator,
+            "RaydiumLegacyAmm",
+            "raydium_amm_v4",
+        ),
+        single::<lifinity_v2::accounts::Amm>(generator, "LifinityV2", "lifinity_v2"),
+        single::<crate::layouts::phoenix::Market>(generator, "Phoenix", "phoenix"),
+        single::<crate::layouts::saber::SwapInfo>(generator, "SaberStableSwap", "saber"),
+        single::<crate::layouts::mercurial::SwapInfo>(
+            generator,
+            "MercurialStableSwap",
+            "mercurial",
+        ),
+        single::<invariant::accounts::Pool>(generator, "Invariant", "invariant"),
+        single::<crema_clmm::accounts::ClmmPool>(generator, "CremaClmm", "crema_clmm"),
+        versioned::<aldrin_v1::accounts::Pool, aldrin_v2::accounts::Pool>(
+            generator,
+            "Aldrin",
+            ("V1", "aldrin_v1"),
+            ("V2", "aldrin_v2"),
+        ),
+        single::<crate::layouts::spl_token_swap::TokenSwap>(generator, "FluxBeam", "fluxbeam"),
+        single::<crate::layouts::sanctum::InfinityPool>(
+            generator,
+            "SanctumInfinity",
+            "sanctum_infinity",
+        ),
+        versioned::<stabble_stable_swap::accounts::Pool, stabble_weighted_swap::accounts::Pool>(
+            generator,
+            "Stabble",
+            ("Stable", "stabble_stable_swap"),
+            ("Weighted", "stabble_weighted_swap"),
+        ),
+        single::<pump::accounts::BondingCurve>(
+            generator,
+            "PumpFunBondingCurve",
+            "pump_fun_bonding_curve",
+        ),
+        single::<moonshot::accounts::CurveAccount>(generator, "Moonshot", "moonshot"),
+        single::<raydium_launchlab::accounts::PoolState>(
+            generator,
+            "RaydiumLaunchLab",
+            "raydium_launchlab",
+        ),
+        single::<meteora_dbc::accounts::VirtualPool>(generator, "MeteoraDbc", "meteora_dbc"),
+        single::<crate::layouts::spl_token_swap::TokenSwap>(
+            generator,
+            "OrcaTokenSwap",
+            "orca_token_swap",
+        ),
+        single::<crate::layouts::spl_token_swap::TokenSwap>(
+            generator,
+            "SplTokenSwap",
+            "spl_token_swap",
+        ),
+        single::<MeteoraVault>(generator, "MeteoraVault", "meteora_vault"),
+        versioned::<bonkswap::accounts::Pool, bonkswap::accounts::PoolV2>(
+            generator,
+            "Bonkswap",
+            ("V1", "bonkswap_v1"),
+            ("V2", "bonkswap_v2"),
+        ),
+        single::<bonkswap::accounts::Farm>(generator, "BonkswapFarm", "bonkswap_farm"),
+    ];
+    #[cfg(feature = "evm")]
+    variants.extend([
+        single::<UniswapV2Pair>(generator, "UniswapV2", "uniswap_v2"),
+        single::<UniswapV3Pool>(generator, "UniswapV3", "uniswap_v3"),
+    ]);
+    variants.extend([
+        single::<DynamicPool>(generator, "Dynamic", "dynamic"),
+        single::<crate::layouts::spl_stake_pool::StakePool>(
+            generator,
+            "SplStakePool",
+            "spl_stake_pool",
+        ),
+        single::<marinade::accounts::State>(generator, "Marinade", "marinade"),
+        single::<raydium_camm::accounts::AmmConfig>(
+            generator,
+            "RaydiumCammConfig",
+            "raydium_camm_config",
+        ),
+        single::<raydium_camm::accounts::ObservationState>(
+            generator,
+            "RaydiumCammObservation",
+            "raydium_camm_observation",
+        ),
+        single::<raydium_amm_cpmm_new::accounts::AmmConfig>(
+            generator,
+            "RaydiumCpmmConfig",
+            "raydium_cpmm_config",
+        ),
+        single::<RaydiumCpmmPool>(
+            generator,
+            "RaydiumCpmmAmmWithConfig",
+            "raydium_cpmm_with_config",
+        ),
+        single::<pamm::accounts::GlobalConfig>(
+            generator,
+            "PumpFunGlobalConfig",
+            "pump_fun_amm_global_config",
+        ),
+        single::<PumpAmmPool>(generator, "PumpFunWithConfig", "pump_fun_amm_with_config"),
+        single::<meteora_dlmm::accounts::LbPair>(generator, "MeteoraDlmm", "meteora_dlmm"),
+        single::<orca_whirlpool::accounts::Whirlpool>(generator, "OrcaWhirlpool", "orca_whirlpool"),
+        single::<orca_whirlpool::accounts::Position>(
+            generator,
+            "OrcaWhirlpoolPosition",
+            "orca_whirlpool_position",
+        ),
+    ]);
+    variants
+}
+
+/// Implements the schema half of `IdlAccount`: describes the account `T` from its IDL,
+/// registering the types it references as definitions named by `idl_type_name`.
+pub(crate) fn idl_account_schema<T: IdlAccount>(generator: &mut SchemaGenerator) -> Schema {
+    IdlSchema {
+        idl: T::idl().idl(),
+        program: T::PROGRAM,
+    }
+    .defined_body(generator, T::NAME)
+}
+
+/// Name under which the IDL type `name` of `program` is defined in generated schemas.
+pub(crate) fn idl_type_name(program: &str, name: &str) -> String {
+    format!("{program}.{name}")
+}
+
+struct IdlSchema<'a> {
+    idl: &'a Idl,
+    program: &'a str,
+}
+
+impl IdlSchema<'_> {
+    fn defined_body(&self, generator: &mut SchemaGenerator, name: &str) -> Schema {
+        let Some(def) = self.idl.types.iter().find(|def| def.name == name) else {
+            return true.into();
+        };
+        match &def.ty {
+            IdlTypeDefTy::Struct { fields } => self.fields(generator, fields.as_ref()),
+            IdlTypeDefTy::Enum { variants } => {
+                let variants: Vec<Value> = variants
+                    .iter()
+                    .map(|variant| match &variant.fields {
+                        None => json_schema!({ "const": variant.name }).to_value(),
+                        Some(fields) => {
+                            tagged(&variant.name, self.fields(generator, Some(fields))).to_value()
+                        }
+                    })
+                    .collect();
+                json_schema!({ "oneOf": variants })
+            }
+            IdlTypeDefTy::Type { alias } => self.ty(generator, alias),
+        }
+    }
+
+    /// Returns a reference to the definition of `name`, adding it on first use.
+    fn defined_ref(&self, generator: &mut SchemaGenerator, name: &str) -> Schema {
+        let key = idl_type_name(self.program, name);
+        if !generator.definitions().contains_key(&key) {
+            // Reserve the name before recursing, in case the type refers to itself.
+            generator
+                .definitions_mut()
+                .insert(key.clone(), Value::Bool(false));
+            let body = self.defined_body(generator, name);
+            generator
+                .definitions_mut()
+                .insert(key.clone(), body.to_value());
+        }
+        let path = generator.settings().definitions_path.as_ref();
+        let path = path.trim_start_matches('#').trim_end_matches('/');
+        Schema::new_ref(format!("#{path}/{key}"))
+    }
+
+    fn fields(&self, generator: &mut SchemaGenerator, fields: Option<&IdlDefinedFields>) -> Schema {
+        let fields = match fields {
+            Some(IdlDefinedFields::Named(fields)) => fields
+                .iter()
+                .map(|field| (field.name.clone(), self.ty(generator, &field.ty)))
+                .collect(),
+            Some(IdlDefinedFields::Tuple(types)) => types
+                .iter()
+                .enumerate()
+                .map(|(index, ty)| (index.to_string(), self.ty(generator, ty)))
+                .collect(),
+            None => Vec::new(),
+        };
+        object_schema(fields)
+    }
+
+    fn ty(&self, generator: &mut SchemaGenerator, ty: &IdlType) -> Schema {
+        match ty {
+            IdlType::Bool => bool::field_schema(generator),
+            IdlType::U8 => u8::field_schema(generator),
+            IdlType::I8 => i8::field_schema(generator),
+            IdlType::U16 => u16::field_schema(generator),
+            IdlType::I16 => i16::field_schema(generator),
+            IdlType::U32 => u32::field_schema(generator),
+            IdlType::I32 => i32::field_schema(generator),
+            IdlType::U64 => u64::field_schema(generator),
+            IdlType::I64 => i64::field_schema(generator),
+            IdlType::U128 => u128::field_schema(generator),
+            IdlType::I128 => i128::field_schema(generator),
+            IdlType::F32 => generator.subschema_for::<f32>(),
+            IdlType::F64 => f64::field_schema(generator),
+            IdlType::Bytes => Vec::<u8>::field_schema(generator),
+            IdlType::String => String::field_schema(generator),
+            IdlType::Pubkey => pubkey_schema(),
+            IdlType::Option(inner) => {
+                json_schema!({ "anyOf": [self.ty(generator, inner), { "type": "null" }] })
+            }
+            IdlType::Vec(inner) => {
+                json_schema!({ "type": "array", "items": self.ty(generator, inner) })
+            }
+            IdlType::Array(inner, IdlArrayLen::Value(len)) => json_schema!({
+                "type": "array",
+                "items": self.ty(generator, inner),
+                "minItems": len,
+                "maxItems": len,
+            }),
+            IdlType::Defined { name, generics } if generics.is_empty() => {
+                self.defined_ref(generator, name)
+            }
+            // Types the decoder in `dynamic` rejects are never serialized.
+            _ => true.into(),
+        }
+    }
+}
+
+fn pubkey_schema() -> Schema {
+    json_schema!({ "type": "string", "pattern": "^[1-9A-HJ-NP-Za-km-z]{32,44}$" })
+}
+
+/// An object with exactly `fields`, all required.
+fn object_schema<K: Into<String>>(fields: Vec<(K, Schema)>) -> Schema {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (name, schema) in fields {
+        let name = name.into();
+        required.push(Value::String(name.clone()));
+        properties.insert(name, schema.to_value());
+    }
+    json_schema!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// An object with the single key `name` holding `value`.
+fn tagged(name: &str, value: Schema) -> Schema {
+    object_schema(vec![(name, value)])
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, Discriminator};
+    use regex::Regex;
+
+    use super::*;
+    use crate::layouts::phoenix::{FifoMarketState, MARKET_HEADER_DISCRIMINANT, MarketHeader};
+    use crate::layouts::raydium_amm_v4::AmmInfo;
+    use crate::layouts::sanctum::{InfinityPool, LstState, PoolState};
+    use crate::layouts::{mercurial, saber, spl_token_swap};
+    use crate::parsing::{PoolType, parse_from_bytes};
+
+    /// Returns the errors of `value` against `schema`, whose `$ref`s point into `root`.
+    ///
+    /// Only the keywords that the schemas of this module use are checked; `format` is an
+    /// annotation.
+    fn validate(root: &Value, schema: &Value, value: &Value, path: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.strip_prefix("#/$defs/").unwrap();
+            return validate(root, &root["$defs"][name], value, path);
+        }
+        if let Some(ty) = schema.get("type").and_then(Value::as_str) {
+            let matches = match ty {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "integer" => value.is_i64() || value.is_u64(),
+                "boolean" => value.is_boolean(),
+                "null" => value.is_null(),
+                _ => panic!("unexpected type {ty}"),
+            };
+            if !matches {
+                return vec![format!("{path}: {value} isn't of type {ty}")];
+            }
+        }
+        if schema
+            .get("const")
+            .is_some_and(|constant| constant != value)
+        {
+            errors.push(format!("{path}: {value} isn't {}", schema["const"]));
+        }
+        if let Some(variants) = schema.get("enum").and_then(Value::as_array)
+            && !variants.contains(value)
+        {
+            errors.push(format!("{path}: {value} isn't one of {variants:?}"));
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str)
+            && !Regex::new(pattern)
+                .unwrap()
+                .is_match(value.as_str().unwrap())
+        {
+            errors.push(format!("{path}: {value} doesn't match {pattern}"));
+        }
+        let number = value.as_f64();
+        if let (Some(minimum), Some(number)) = (schema.get("minimum"), number)
+            && number < minimum.as_f64().unwrap()
+        {
+            errors.push(format!("{path}: {value} is below {minimum}"));
+        }
+        if let (Some(maximum), Some(number)) = (schema.get("maximum"), number)
+            && number > maximum.as_f64().unwrap()
+        {
+            errors.push(format!("{path}: {value} is above {maximum}"));
+        }
+        if let Some(object) = value.as_object() {
+            let required = schema.get("required").and_then(Value::as_array);
+            for key in required.into_iter().flatten() {
+                if !object.contains_key(key.as_str().unwrap()) {
+                    errors.push(format!("{path}: missing {key}"));
+                }
+            }
+            let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+            for (key, field) in object {
+                let field_path = format!("{path}.{key}");
+                match schema
+                    .get("properties")
+                    .and_then(|properties| properties.get(key))
+                {
+                    Some(property) => errors.extend(validate(root, property, field, &field_path)),
+                    None if closed => errors.push(format!("{path}: unexpected {key}")),
+                    None => {}
+                }
+            }
+        }
+        if let Some(items) = value.as_array() {
+            let len = items.len() as u64;
+            let min = schema.get("minItems").and_then(Value::as_u64);
+            let max = schema.get("maxItems").and_then(Value::as_u64);
+            if min.is_some_and(|min| len < min) || max.is_some_and(|max| len > max) {
+                errors.push(format!("{path}: unexpected length {len}"));
+            }
+            if let Some(item) = schema.get("items") {
+                for (index, value) in items.iter().enumerate() {
+                    errors.extend(validate(root, item, value, &format!("{path}[{index}]")));
+                }
+            }
+        }
+        let matching = |keyword| {
+            schema
+                .get(keyword)
+                .and_then(Value::as_array)
+                .map(|branches| {
+                    branches
+                        .iter()
+                        .filter(|branch| validate(root, branch, value, path).is_empty())
+                        .count()
+                })
+        };
+        if matching("anyOf") == Some(0) {
+            errors.push(format!("{path}: {value} matches no branch of anyOf"));
+        }
+        if let Some(count) = matching("oneOf").filter(|count| *count != 1) {
+            errors.push(format!("{path}: {value} matches {count} branches of oneOf"));
+        }
+        errors
+    }
+
+    /// Checks that the `to_json` output of `pool` validates against `envelope_schema`.
+    ///
+    /// The branch of the pool's protocol is validated first, so that a failure lists the
+    /// mismatched fields rather than just the number of matching branches.
+    fn assert_envelope_is_valid(pool: &AmmPool) {
+        let schema = envelope_schema().to_value();
+        let json = pool.to_json(&Pubkey::new_unique()).unwrap();
+        let protocol = pool.protocol_tag();
+        let branch = schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|branch| branch["properties"]["protocol"]["const"] == protocol)
+            .unwrap();
+        let errors = validate(&schema, branch, &json, "$");
+        assert!(errors.is_empty(), "{protocol}: {errors:#?}");
+        let errors = validate(&schema, &schema, &json, "$");
+        assert!(errors.is_empty(), "{protocol}: {errors:#?}");
+    }
+
+    /// Parses `len` bytes that differ from their neighbours, with `fix` applied, as
+    /// `pool_type`.
+    fn patterned(pool_type: PoolType, len: usize, fix: impl Fn(&mut [u8])) -> AmmPool {
+        let mut data: Vec<u8> = (0..len).map(|i| (i * 31 + 7) as u8).collect();
+        fix(&mut data);
+        parse_from_bytes(&pool_type, &data).unwrap()
+    }
+
+    fn pool_address() -> Pubkey {
+        Pubkey::new_unique()
+    }
+
+    #[test]
+    fn hand_written_layouts_match_the_envelope_schema() {
+        let amm_v4 = patterned(
+            PoolType::RaydiumLegacyAmm {
+                pool_address: pool_address(),
+            },
+            AmmInfo::LEN,
+            |_| {},
+        );
+        let saber = patterned(
+            PoolType::SaberStableSwap {
+                pool_address: pool_address(),
+            },
+            saber::SwapInfo::LEN,
+            |data| (data[0], data[1]) = (1, 0),
+        );
+        let mercurial = patterned(
+            PoolType::MercurialStableSwap {
+                pool_address: pool_address(),
+            },
+            mercurial::SwapInfo::LEN,
+            |data| data[1] = 1,
+        );
+        let token_swap = |pool_type| {
+            patterned(pool_type, spl_token_swap::TokenSwap::LEN, |data| {
+                (data[0], data[1]) = (spl_token_swap::SWAP_V1, 1)
+            })
+        };
+
+        let mut phoenix = vec![0; MarketHeader::LEN + FifoMarketState::LEN + 64];
+        phoenix[..8].copy_from_slice(&MARKET_HEADER_DISCRIMINANT.to_le_bytes());
+        let phoenix = parse_from_bytes(
+            &PoolType::Phoenix {
+                pool_address: pool_address(),
+            },
+            &phoenix,
+        )
+        .unwrap();
+
+        let mut stake_pool = vec![0; 1_000];
+        stake_pool[0] = 1;
+        let AmmPool::SplStakePool(mut stake_pool) = parse_from_bytes(
+            &PoolType::SplStakePool {
+                pool_address: pool_address(),
+            },
+            &stake_pool,
+        )
+        .unwrap() else {
+            unreachable!()
+        };
+        stake_pool.next_epoch_fee = FutureEpoch::One(stake_pool.epoch_fee);
+        stake_pool.next_sol_withdrawal_fee = FutureEpoch::Two(stake_pool.epoch_fee);
+        stake_pool.sol_deposit_authority = Some(Pubkey::new_unique());
+
+        let state: Vec<u8> = (0..PoolState::LEN).map(|i| (i * 31 + 7) as u8).collect();
+        let sanctum = AmmPool::SanctumInfinity(InfinityPool {
+            state: PoolState::try_deserialize(&mut state.as_slice()).unwrap(),
+            lst_states: LstStateList(vec![LstState {
+                is_input_disabled: 0,
+                pool_reserves_bump: 255,
+                protocol_fee_accumulator_bump: 254,
+                padding: [0; 5],
+                sol_value: 1_234_567_890,
+                mint: Pubkey::new_unique(),
+                sol_value_calculator: Pubkey::new_unique(),
+            }]),
+        });
+
+        for pool in [
+            amm_v4,
+            saber,
+            mercurial,
+            token_swap(PoolType::FluxBeam {
+                pool_address: pool_address(),
+            }),
+            token_swap(PoolType::OrcaTokenSwap {
+                pool_address: pool_address(),
+            }),
+            token_swap(PoolType::SplTokenSwap {
+                pool_address: pool_address(),
+            }),
+            phoenix,
+            AmmPool::SplStakePool(stake_pool),
+            sanctum,
+        ] {
+            assert_envelope_is_valid(&pool);
+        }
+    }
+
+    #[test]
+    fn idl_accounts_match_the_envelope_schema() {
+        use crate::parsing::orca_whirlpool::accounts::Whirlpool;
+        use crate::parsing::raydium_camm::accounts::PoolState as RaydiumClmmPoolState;
+
+        for (pool_type, discriminator) in [
+            (
+                PoolType::OrcaWhirlpool {
+                    pool_address: pool_address(),
+                },
+                Whirlpool::DISCRIMINATOR,
+            ),
+            (
+                PoolType::RaydiumCamm {
+                    pool_address: pool_address(),
+                },
+                RaydiumClmmPoolState::DISCRIMINATOR,
+            ),
+        ] {
+            let pool = patterned(pool_type, 2_000, |data| {
+                data[..8].copy_from_slice(discriminator)
+            });
+            assert_envelope_is_valid(&pool);
+        }
+    }
+
+    #[test]
+    fn envelopes_of_the_wrong_shape_are_invalid() {
+        let schema = envelope_schema().to_value();
+        let pool = patterned(
+            PoolType::RaydiumLegacyAmm {
+                pool_address: pool_address(),
+            },
+            AmmInfo::LEN,
+            |_| {},
+        );
+        let mut json = pool.to_json(&Pubkey::new_unique()).unwrap();
+        json["data"]["status"] = Value::String("1".to_string());
+        assert!(!validate(&schema, &schema, &json, "$").is_empty());
+
+        let mut json = pool.to_json(&Pubkey::new_unique()).unwrap();
+        json["protocol"] = Value::String("saber".to_string());
+        assert!(!validate(&schema, &schema, &json, "$").is_empty());
+    }
+}
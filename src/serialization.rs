@@ -0,0 +1,102 @@
+//! `serde::Serialize` for the accounts generated by `declare_program!`.
+//!
+//! The generated types only implement borsh, so they are serialized by writing them back
+//! to account data and decoding that with the bundled IDL, which yields the same field
+//! names as the Rust structs. Serialized pools follow the same conventions everywhere:
+//! pubkeys are base58 strings and 128-bit integers are decimal strings.
+
+use std::sync::OnceLock;
+
+use anchor_lang::AccountSerialize;
+use serde::ser::{Error, Serialize, Serializer};
+
+use crate::dynamic::DynamicIdl;
+
+/// An account generated by `declare_program!` from one of the IDLs in `idls/`.
+pub(crate) trait IdlAccount: AccountSerialize {
+    /// Name of the program, which is also the name of its IDL file.
+    #[cfg(feature = "schemars")]
+    const PROGRAM: &'static str;
+    /// Name of the account in the IDL.
+    #[cfg(feature = "schemars")]
+    const NAME: &'static str;
+
+    fn idl() -> &'static DynamicIdl;
+}
+
+/// Implements `IdlAccount` and `Serialize` for the given accounts of `$program`.
+macro_rules! serialize_with_idl {
+    ($program:ident: $($account:ident),+ $(,)?) => {
+        mod $program {
+            use super::*;
+
+            pub(super) fn idl() -> &'static DynamicIdl {
+                static IDL: OnceLock<DynamicIdl> = OnceLock::new();
+                IDL.get_or_init(|| {
+                    let json = include_bytes!(concat!("../idls/", stringify!($program), ".json"));
+                    DynamicIdl::from_json(json).expect("bundled IDL is valid")
+                })
+            }
+        }
+
+        $(
+            impl IdlAccount for crate::parsing::$program::accounts::$account {
+                #[cfg(feature = "schemars")]
+                const PROGRAM: &'static str = stringify!($program);
+                #[cfg(feature = "schemars")]
+                const NAME: &'static str = stringify!($account);
+
+                fn idl() -> &'static DynamicIdl {
+                    $program::idl()
+                }
+            }
+
+            impl Serialize for crate::parsing::$program::accounts::$account {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serialize_account(self, serializer)
+                }
+            }
+
+            #[cfg(feature = "schemars")]
+            impl schemars::JsonSchema for crate::parsing::$program::accounts::$account {
+                fn schema_name() -> std::borrow::Cow<'static, str> {
+                    crate::schema::idl_type_name(Self::PROGRAM, Self::NAME).into()
+                }
+
+                fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+                    crate::schema::idl_account_schema::<Self>(generator)
+                }
+            }
+        )+
+    };
+}
+
+serialize_with_idl!(pamm: Pool, GlobalConfig);
+serialize_with_idl!(pump: BondingCurve);
+serialize_with_idl!(raydium_amm_cpmm_new: PoolState, AmmConfig);
+serialize_with_idl!(raydium_camm: PoolState, AmmConfig, ObservationState);
+serialize_with_idl!(raydium_launchlab: PoolState);
+serialize_with_idl!(lifinity_v2: Amm);
+serialize_with_idl!(invariant: Pool);
+serialize_with_idl!(crema_clmm: ClmmPool);
+serialize_with_idl!(aldrin_v1: Pool);
+serialize_with_idl!(aldrin_v2: Pool);
+serialize_with_idl!(stabble_stable_swap: Pool);
+serialize_with_idl!(stabble_weighted_swap: Pool);
+serialize_with_idl!(moonshot: CurveAccount);
+serialize_with_idl!(meteora_dbc: VirtualPool);
+serialize_with_idl!(meteora_vault: Vault, Strategy);
+serialize_with_idl!(bonkswap: Pool, PoolV2, Farm);
+serialize_with_idl!(marinade: State);
+serialize_with_idl!(meteora_dlmm: LbPair);
+serialize_with_idl!(orca_whirlpool: Whirlpool, Position);
+
+fn serialize_account<T: IdlAccount, S: Serializer>(
+    account: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).map_err(S::Error::custom)?;
+    let decoded = T::idl().parse_account(&data).map_err(S::Error::custom)?;
+    decoded.value.serialize(serializer)
+}
@@ -0,0 +1,112 @@
+//! One-line summaries of parsed pools for logs.
+//!
+//! The `Debug` output of an `AmmPool` lists every field of the account, which runs to
+//! hundreds of lines for pools with tick or bin arrays inline. `AmmPool::summary` keeps
+//! only the protocol, the pair, and the reserves, price and fee where the account itself
+//! records them, and `AmmPool`'s `Display` prints that summary:
+//!
+//! ```text
+//! Stabble <mint A>/<mint B> reserves 1250000000/1249000000 fee 1 bps
+//! ```
+
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::normalized;
+use crate::parsing::{AmmPool, BonkswapPool, StabblePool};
+use crate::pool_access::PoolAccess;
+use crate::pump_amm::bonding_curve_spot_price;
+use crate::raydium_clmm;
+
+#[derive(Debug, Clone, PartialEq)]
+/// The fields of a pool worth a line in a log.
+pub struct PoolSummary {
+    /// Name of the protocol, or the `protocol_tag` of accounts that aren't pools.
+    pub protocol: &'static str,
+    /// Base token, or token A/X/0.
+    pub token_a_mint: Option<Pubkey>,
+    /// Quote token, or token B/Y/1.
+    pub token_b_mint: Option<Pubkey>,
+    /// Reserves of token A and token B in atoms, for pools that track them in their own
+    /// account.
+    pub reserves: Option<(u64, u64)>,
+    /// Price of one whole token A in token B, for pools whose account holds enough to
+    /// compute it.
+    pub price: Option<f64>,
+    /// Trade fee in basis points, as returned by `PoolAccess::fee_bps`.
+    pub fee_bps: Option<f64>,
+}
+
+impl AmmPool {
+    /// Returns the summary printed by `Display`.
+    pub fn summary(&self) -> PoolSummary {
+        PoolSummary {
+            protocol: normalized::protocol(self).unwrap_or_else(|| self.protocol_tag()),
+            token_a_mint: self.token_mint_a(),
+            token_b_mint: self.token_mint_b(),
+            reserves: reserves(self),
+            price: price(self),
+            fee_bps: self.fee_bps(),
+        }
+    }
+}
+
+fn reserves(pool: &AmmPool) -> Option<(u64, u64)> {
+    match pool {
+        AmmPool::PumpFunBondingCurve(curve) => {
+            Some((curve.real_token_reserves, curve.real_sol_reserves))
+        }
+        AmmPool::MeteoraDbc(pool) => Some((pool.base_reserve, pool.quote_reserve)),
+        AmmPool::Bonkswap(BonkswapPool::V1(pool)) => {
+            Some((pool.token_x_reserve.v, pool.token_y_reserve.v))
+        }
+        AmmPool::Bonkswap(BonkswapPool::V2(pool)) => {
+            Some((pool.token_x_reserve.v, pool.token_y_reserve.v))
+        }
+        AmmPool::Stabble(StabblePool::Stable(pool)) => match &pool.tokens[..] {
+            [a, b] => Some((a.balance, b.balance)),
+            _ => None,
+        },
+        AmmPool::Stabble(StabblePool::Weighted(pool)) => match &pool.tokens[..] {
+            [a, b] => Some((a.balance, b.balance)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn price(pool: &AmmPool) -> Option<f64> {
+    match pool {
+        AmmPool::RaydiumCamm(pool) => Some(raydium_clmm::price(pool)),
+        AmmPool::PumpFunBondingCurve(curve) => bonding_curve_spot_price(curve),
+        _ => None,
+    }
+}
+
+impl fmt::Display for PoolSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.protocol)?;
+        match (self.token_a_mint, self.token_b_mint) {
+            (Some(a), Some(b)) => write!(f, " {a}/{b}")?,
+            (Some(a), None) => write!(f, " {a}")?,
+            _ => {}
+        }
+        if let Some((a, b)) = self.reserves {
+            write!(f, " reserves {a}/{b}")?;
+        }
+        if let Some(price) = self.price {
+            write!(f, " price {price}")?;
+        }
+        if let Some(fee_bps) = self.fee_bps {
+            write!(f, " fee {fee_bps} bps")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AmmPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
+    }
+}
@@ -0,0 +1,1001 @@
+//! A configured entry point to the parser.
+//!
+//! `UniversalParser` holds what the free functions of the crate take as loose
+//! parameters, so services configure it once and pass it around:
+//!
+//! ```no_run
+//! # use universal_liquidity_pool_parser::universal::UniversalParser;
+//! # use universal_liquidity_pool_parser::parsing::PoolError;
+//! # fn example() -> Result<(), PoolError> {
+//! let parser = UniversalParser::builder()
+//!     .rpc_url("https://api.mainnet-beta.solana.com")
+//!     .protocols(["raydium-cpmm", "raydium-clmm", "orca-whirlpool"])
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Deployments that keep RPC URLs and keys out of their source configure it from the
+//! environment with `UniversalParser::from_env` instead:
+//!
+//! | Variable | Sets | Example |
+//! |---|---|---|
+//! | `ULP_RPC_URL` | `rpc_urls`, comma-separated; required | `https://rpc.example.com` |
+//! | `ULP_WS_URL` | `ws_url` | `wss://rpc.example.com` |
+//! | `ULP_COMMITMENT` | `commitment` | `confirmed` |
+//! | `ULP_RPS_LIMIT` | `rate_limit` | `10` |
+//! | `ULP_CLUSTER` | `cluster` | `devnet` |
+//! | `ULP_BEARER_TOKEN` | `bearer_auth` | |
+//! | `ULP_PROXY` | `proxy` | `http://proxy.internal:3128` |
+//!
+//! Variables that are unset or empty leave the builder default.
+
+use std::collections::{BTreeSet, HashMap};
+use std::env::{self, VarError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use anchor_client::solana_client::rpc_filter::RpcFilterType;
+use anchor_client::solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use anchor_client::solana_client::rpc_response::Response;
+use anchor_client::solana_sdk::account::Account;
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_lang::prelude::Pubkey;
+
+use crate::batch::{self, DEFAULT_MAX_IN_FLIGHT};
+use crate::blocking;
+use crate::cache::AccountCache;
+use crate::coalesce::InFlight;
+use crate::consistency::{self, Consistency};
+use crate::detect;
+use crate::discovery;
+use crate::failover::{DEFAULT_COOLDOWN, Failover};
+use crate::fetcher::{AccountFetcher, DEFAULT_REQUEST_TIMEOUT, WithMinContextSlot, WithTimeout};
+use crate::hot_fields::{self, HotFields};
+use crate::http::HttpOptions;
+use crate::parsing::{self, ParseMode, ParsedPool, PoolError, PoolType};
+use crate::pool_name::canonical_name;
+use crate::program_ids::Cluster;
+use crate::rate_limit::RateLimiter;
+use crate::reserves::vault_addresses;
+use crate::retry::{RetryPolicy, Retrying};
+
+/// Fetches and parses pools with a fixed configuration.
+pub struct UniversalParser {
+    endpoints: Failover<WithTimeout<Arc<RpcClient>>>,
+    cluster: Cluster,
+    mode: ParseMode,
+    /// Names of the enabled pool types, or `None` if every pool type is enabled.
+    protocols: Option<BTreeSet<&'static str>>,
+    max_in_flight: usize,
+    retry: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Commitment of every request, or `None` to use that of each client.
+    commitment: Option<CommitmentConfig>,
+    /// Longest a `parse` may take, retries included.
+    overall_timeout: Option<Duration>,
+    /// Cache answering requests before they're sent, if any.
+    cache: Option<Arc<AccountCache>>,
+    /// Requests in flight, or `None` if requests aren't coalesced.
+    in_flight: Option<InFlight>,
+    /// Whether `parse` also fetches the balances of the vaults of the pool.
+    reserves: bool,
+    /// Vaults of the pools parsed with their reserves, fetched with the pool next time.
+    vaults: Mutex<HashMap<Pubkey, Vec<Pubkey>>>,
+    consistency: Consistency,
+    ws_url: Option<String>,
+}
+
+/// The long-lived client services keep instead of passing RPC clients, limiters and
+/// clusters to the free functions: `UniversalParser` under the name of what it does for
+/// them. `fetch` detects the protocol of an address, `fetch_typed` trusts a given one and
+/// `fetch_many` fetches many addresses in as few requests as possible.
+///
+/// ```no_run
+/// # use anchor_lang::prelude::Pubkey;
+/// # use universal_liquidity_pool_parser::parsing::PoolError;
+/// # use universal_liquidity_pool_parser::universal::PoolFetcher;
+/// # async fn example(address: Pubkey, addresses: &[Pubkey]) -> Result<(), PoolError> {
+/// let fetcher = PoolFetcher::builder()
+///     .rpc_url("https://api.mainnet-beta.solana.com")
+///     .rate_limit(10.0)
+///     .build()?;
+/// let pool = fetcher.fetch(address).await?;
+/// let clmm_pool = fetcher.fetch_typed("raydium-clmm", address).await?;
+/// for (address, result) in fetcher.fetch_many(addresses).await? {
+///     println!("{address}: {:?}", result.map(|parsed| parsed.pool.summary()));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub type PoolFetcher = UniversalParser;
+
+#[derive(Default)]
+/// Configuration of a `UniversalParser`, created by `UniversalParser::builder`.
+pub struct UniversalParserBuilder {
+    rpc_urls: Vec<String>,
+    rpc_clients: Vec<Arc<RpcClient>>,
+    http: HttpOptions,
+    failover_cooldown: Option<Duration>,
+    request_timeout: Option<Duration>,
+    overall_timeout: Option<Duration>,
+    commitment: Option<CommitmentConfig>,
+    cluster: Cluster,
+    mode: ParseMode,
+    protocols: Option<Vec<String>>,
+    max_in_flight: Option<usize>,
+    retry: Option<RetryPolicy>,
+    rate_limit: Option<f64>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    cache_ttl: Option<Duration>,
+    cache_capacity: Option<usize>,
+    cache: Option<Arc<AccountCache>>,
+    coalesce: Option<bool>,
+    reserves: bool,
+    consistency: Consistency,
+    ws_url: Option<String>,
+}
+
+impl UniversalParser {
+    pub fn builder() -> UniversalParserBuilder {
+        UniversalParserBuilder::default()
+    }
+
+    /// Creates a parser configured from the `ULP_*` environment variables described in
+    /// the module documentation.
+    ///
+    /// Returns `PoolError::InvalidConfig` if `ULP_RPC_URL` is unset, if a variable can't
+    /// be parsed, or as `UniversalParserBuilder::build` does.
+    pub fn from_env() -> Result<UniversalParser, PoolError> {
+        UniversalParserBuilder::from_env()?.build()
+    }
+
+    /// Returns the nonblocking RPC client of the primary endpoint.
+    pub fn rpc_client(&self) -> &Arc<RpcClient> {
+        &self.endpoints.primary().inner
+    }
+
+    /// Returns the endpoints pools are fetched from, with their health.
+    pub fn endpoints(&self) -> &Failover<WithTimeout<Arc<RpcClient>>> {
+        &self.endpoints
+    }
+
+    pub fn cluster(&self) -> &Cluster {
+        &self.cluster
+    }
+
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    /// Returns the commitment every request is made at, or `None` if each client uses its
+    /// own.
+    pub fn commitment(&self) -> Option<CommitmentConfig> {
+        self.commitment
+    }
+
+    /// Returns the limiter every request of the parser takes a token from, if any.
+    pub fn rate_limiter(&self) -> Option<&Arc<RateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Returns the cache that answers the requests of the parser, if any, with its hit and
+    /// miss counts.
+    pub fn account_cache(&self) -> Option<&Arc<AccountCache>> {
+        self.cache.as_ref()
+    }
+
+    /// Returns whether `parse` also fetches the balances of the vaults of each pool.
+    pub fn fetches_reserves(&self) -> bool {
+        self.reserves
+    }
+
+    /// Returns which slots `parse` reads the accounts of a pool at.
+    pub fn consistency(&self) -> Consistency {
+        self.consistency
+    }
+
+    /// Returns the WebSocket URL of the RPC node set with `ws_url`, if any.
+    pub fn ws_url(&self) -> Option<&str> {
+        self.ws_url.as_deref()
+    }
+
+    /// Returns whether pools of `pool_type` may be parsed.
+    pub fn is_enabled(&self, pool_type: &PoolType) -> bool {
+        self.protocols
+            .as_ref()
+            .is_none_or(|protocols| protocols.contains(pool_type.name()))
+    }
+
+    /// Fetches and parses the pool, like `get_info_struct_on` with the configured client
+    /// and cluster, checking the data as the configured mode requires. Requests are
+    /// rate limited, timed out, failed over between endpoints and retried as configured.
+    ///
+    /// If the parser was built with `reserves`, the balances of the vaults of the pool are
+    /// returned in `ParsedPool::reserves`. The vaults of a pool parsed before are fetched
+    /// in the same request as the pool, at the same slot; those of a new pool are fetched
+    /// in a second request, as `reserves::get_info_struct_with_reserves` does.
+    ///
+    /// Returns `PoolError::ProtocolDisabled` for pool types that aren't enabled, and
+    /// `PoolError::Timeout` if the configured overall timeout passes first.
+    pub async fn parse(&self, pool_type: PoolType) -> Result<ParsedPool, PoolError> {
+        let fetcher = Retrying::new(self.requests(), self.retry);
+        self.parse_with(pool_type, &fetcher).await
+    }
+
+    /// Like `parse`, but only accepts the accounts of the pool read at `min_context_slot`
+    /// or later, as `fetcher::WithMinContextSlot` does.
+    ///
+    /// RPC nodes serve the current state of accounts, not that at a past slot: this
+    /// bounds how stale the pool may be, for instance when replaying transactions, rather
+    /// than reading it as it was at `min_context_slot`. Requests to nodes that haven't
+    /// reached the slot are retried as configured.
+    pub async fn parse_at_min_slot(
+        &self,
+        pool_type: PoolType,
+        min_context_slot: u64,
+    ) -> Result<ParsedPool, PoolError> {
+        let fetcher = Retrying::new(
+            WithMinContextSlot::new(self.requests(), min_context_slot),
+            self.retry,
+        );
+        self.parse_with(pool_type, &fetcher).await
+    }
+
+    /// Fetches and parses each of `pool_types` like `parse`, with at most the configured
+    /// number of pools fetched at once. Each result is paired with its `PoolType`, in
+    /// the order the pools complete.
+    pub async fn parse_many<I>(
+        &self,
+        pool_types: I,
+    ) -> Vec<(PoolType, Result<ParsedPool, PoolError>)>
+    where
+        I: IntoIterator<Item = PoolType>,
+    {
+        batch::fetch_many(pool_types, self.max_in_flight, |pool_type| {
+            self.parse(pool_type)
+        })
+        .await
+    }
+
+    /// Fetches the account at `address` and parses it with the protocol of the program
+    /// owning it, like `detect::detect_and_parse_with`. Owners are recognized by their
+    /// ids on the configured cluster, and requests are handled as in `parse`.
+    ///
+    /// Returns `PoolError::UnsupportedProgram` if the owner isn't a program this crate
+    /// parses and `PoolError::ProtocolDisabled` if its protocol isn't enabled.
+    pub async fn fetch(&self, address: Pubkey) -> Result<ParsedPool, PoolError> {
+        let fetched = async {
+            let fetcher = Retrying::new(self.requests(), self.retry);
+            let response = fetcher.fetch_account(&address).await?;
+            let fetched_at = SystemTime::now();
+            let Some(account) = response.value else {
+                return Err(PoolError::AccountNotFound { address });
+            };
+            self.parse_account(
+                address,
+                account,
+                response.context.slot,
+                fetched_at,
+                &fetcher,
+            )
+            .await
+        };
+        self.with_overall_timeout(fetched).await
+    }
+
+    /// Fetches and parses the account at `address` as the pool type called `name`, by the
+    /// names `PoolType::from_name` accepts, like `parse`.
+    ///
+    /// Returns `PoolError::InvalidConfig` if `name` isn't a pool type constructible from
+    /// an address.
+    pub async fn fetch_typed(&self, name: &str, address: Pubkey) -> Result<ParsedPool, PoolError> {
+        let pool_type = match PoolType::from_name(name, address) {
+            Ok(pool_type) => pool_type,
+            Err(e) => return Err(PoolError::InvalidConfig(e.to_string())),
+        };
+        self.parse(pool_type).await
+    }
+
+    /// Fetches the accounts at `addresses` and parses each like `fetch`, in the same
+    /// order. The accounts are fetched together, up to the 100 accounts a request may ask
+    /// for, and only Sanctum Infinity pools and Meteora vaults take requests of their own.
+    ///
+    /// Fails as a whole only if a request does; accounts that are missing or don't parse
+    /// fail on their own.
+    pub async fn fetch_many(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<(Pubkey, Result<ParsedPool, PoolError>)>, PoolError> {
+        let fetched = async {
+            let fetcher = Retrying::new(self.requests(), self.retry);
+            let mut parsed = Vec::with_capacity(addresses.len());
+            for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+                let response = fetcher.fetch_accounts(chunk).await?;
+                let fetched_at = SystemTime::now();
+                for (address, account) in chunk.iter().zip(response.value) {
+                    let result = match account {
+                        Some(account) => {
+                            let slot = response.context.slot;
+                            self.parse_account(*address, account, slot, fetched_at, &fetcher)
+                                .await
+                        }
+                        None => Err(PoolError::AccountNotFound { address: *address }),
+                    };
+                    parsed.push((*address, result));
+                }
+            }
+            Ok(parsed)
+        };
+        self.with_overall_timeout(fetched).await
+    }
+
+    /// Finds and parses every pool of `pool_type`, like `discovery::discover_pools` with
+    /// the configured cluster, checking the data as the configured mode requires.
+    /// Requests are handled as in `parse`, except for the overall timeout.
+    ///
+    /// Returns `PoolError::ProtocolDisabled` for pool types that aren't enabled.
+    pub async fn discover_pools(
+        &self,
+        pool_type: &PoolType,
+    ) -> Result<Vec<(Pubkey, Result<ParsedPool, PoolError>)>, PoolError> {
+        self.check_enabled(pool_type)?;
+        let fetcher = Retrying::new(self.requests(), self.retry);
+        discovery::discover_pools_with(pool_type, &fetcher, &self.cluster, self.mode).await
+    }
+
+    /// Finds the address of every pool of `pool_type`, like
+    /// `discovery::discover_pool_addresses` with the configured cluster.
+    ///
+    /// Returns `PoolError::ProtocolDisabled` for pool types that aren't enabled.
+    pub async fn discover_pool_addresses(
+        &self,
+        pool_type: &PoolType,
+    ) -> Result<Vec<Pubkey>, PoolError> {
+        self.check_enabled(pool_type)?;
+        let fetcher = Retrying::new(self.requests(), self.retry);
+        discovery::discover_pool_addresses(pool_type, &fetcher, &self.cluster).await
+    }
+
+    /// Finds every pool of an enabled pool type that trades `mint_a` against `mint_b`,
+    /// like `discovery::find_pools_for_pair` with the configured cluster, checking the
+    /// data as the configured mode requires. Requests are handled as in `parse`, except
+    /// for the overall timeout.
+    pub async fn find_pools_for_pair(
+        &self,
+        mint_a: &Pubkey,
+        mint_b: &Pubkey,
+    ) -> Result<Vec<(PoolType, Result<ParsedPool, PoolError>)>, PoolError> {
+        let mint_filters = discovery::pair_filters(*mint_a, *mint_b);
+        self.find_pools(mint_filters).await
+    }
+
+    /// Finds every pool of an enabled pool type that trades `mint`, like
+    /// `discovery::find_pools_for_mint` with the configured cluster, checking the data as
+    /// the configured mode requires. Requests are handled as in `parse`, except for the
+    /// overall timeout.
+    pub async fn find_pools_for_mint(
+        &self,
+        mint: &Pubkey,
+    ) -> Result<Vec<(PoolType, Result<ParsedPool, PoolError>)>, PoolError> {
+        let fetcher = Retrying::new(self.requests(), self.retry);
+        let pool_types = self.searched_pool_types();
+        discovery::find_pools_for_mint_with(pool_types, mint, &fetcher, &self.cluster, self.mode)
+            .await
+    }
+
+    /// Fetches the hot fields of each of `pool_types`, like `hot_fields::fetch_hot_fields`
+    /// with the configured cluster. Requests are handled as in `parse`, except for the
+    /// overall timeout.
+    ///
+    /// Returns `PoolError::ProtocolDisabled` if one of `pool_types` isn't enabled.
+    pub async fn fetch_hot_fields(
+        &self,
+        pool_types: &[PoolType],
+    ) -> Result<Vec<Option<HotFields>>, PoolError> {
+        for pool_type in pool_types {
+            self.check_enabled(pool_type)?;
+        }
+        let fetcher = Retrying::new(self.requests(), self.retry);
+        hot_fields::fetch_hot_fields(pool_types, &fetcher, &self.cluster).await
+    }
+
+    /// Parses account data obtained elsewhere, like `parse_from_bytes_with_mode` with the
+    /// configured mode.
+    ///
+    /// Returns `PoolError::ProtocolDisabled` for pool types that aren't enabled.
+    pub fn parse_bytes(
+        &self,
+        pool_type: &PoolType,
+        data: &[u8],
+    ) -> Result<parsing::AmmPool, PoolError> {
+        self.check_enabled(pool_type)?;
+        parsing::parse_from_bytes_with_mode(pool_type, data, self.mode)
+    }
+
+    /// Runs `discovery::find_pools_with` over the enabled pool types.
+    async fn find_pools(
+        &self,
+        mint_filters: impl Fn(&[usize]) -> Vec<Vec<RpcFilterType>>,
+    ) -> Result<Vec<(PoolType, Result<ParsedPool, PoolError>)>, PoolError> {
+        let pool_types = self.searched_pool_types();
+        let fetcher = Retrying::new(self.requests(), self.retry);
+        discovery::find_pools_with(pool_types, mint_filters, &fetcher, &self.cluster, self.mode)
+            .await
+    }
+
+    /// Parses the pool as `parse` does, with the requests of `fetcher`.
+    async fn parse_with<F: AccountFetcher>(
+        &self,
+        pool_type: PoolType,
+        fetcher: &F,
+    ) -> Result<ParsedPool, PoolError> {
+        self.check_enabled(&pool_type)?;
+        let address = pool_type.pool_address();
+        let vaults = self.reserves.then(|| self.known_vaults(&address));
+        let parsed = consistency::get_info_struct_with(
+            pool_type,
+            fetcher,
+            &self.cluster,
+            self.mode,
+            self.consistency,
+            vaults.as_deref(),
+        );
+        let parsed = self.with_overall_timeout(parsed).await?;
+        if let Some(vaults) = vaults {
+            let pool_vaults = vault_addresses(&parsed.pool);
+            if pool_vaults != vaults {
+                self.vaults
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(address, pool_vaults);
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Returns the vaults the pool at `address` had when it was last parsed, if any.
+    fn known_vaults(&self, address: &Pubkey) -> Vec<Pubkey> {
+        self.vaults
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(address)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the enabled pool types among those searched by mint.
+    fn searched_pool_types(&self) -> Vec<PoolType> {
+        discovery::searchable_pool_types()
+            .into_iter()
+            .filter(|pool_type| self.is_enabled(pool_type))
+            .collect()
+    }
+
+    /// Detects and completes the pool in `account`, fetched from `address` at `slot`,
+    /// checking it's enabled and, in strict mode, that its data fits its layout.
+    async fn parse_account<F: AccountFetcher>(
+        &self,
+        address: Pubkey,
+        account: Account,
+        slot: u64,
+        fetched_at: SystemTime,
+        fetcher: &F,
+    ) -> Result<ParsedPool, PoolError> {
+        let owner = self.cluster.mainnet_id(account.owner);
+        let detected = detect::detect(&owner, &account.data)?;
+        let pool_type = detected.pool_type(address);
+        if let Some(pool_type) = &pool_type {
+            self.check_enabled(pool_type)?;
+        }
+        let pool = detect::complete(detected, fetcher).await?;
+        if let Some(pool_type) = &pool_type {
+            parsing::check_layout_len(pool_type, &pool, account.data.len(), self.mode)?;
+        }
+        Ok(ParsedPool {
+            pool,
+            address,
+            slot: Some(slot),
+            fetched_at,
+            raw: Some(account.data),
+            reserves: None,
+        })
+    }
+
+    /// Runs `request`, failing with `PoolError::Timeout` if the configured overall timeout
+    /// passes first.
+    async fn with_overall_timeout<T>(
+        &self,
+        request: impl Future<Output = Result<T, PoolError>>,
+    ) -> Result<T, PoolError> {
+        match self.overall_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, request).await {
+                Ok(result) => result,
+                Err(_) => Err(PoolError::Timeout(timeout)),
+            },
+            None => request.await,
+        }
+    }
+
+    fn requests(&self) -> Requests<'_> {
+        Requests {
+            endpoints: &self.endpoints,
+            commitment: self.commitment,
+            rate_limiter: self.rate_limiter.as_deref(),
+            cache: self.cache.as_deref(),
+            in_flight: self.in_flight.as_ref(),
+        }
+    }
+
+    fn check_enabled(&self, pool_type: &PoolType) -> Result<(), PoolError> {
+        if self.is_enabled(pool_type) {
+            Ok(())
+        } else {
+            Err(PoolError::ProtocolDisabled(pool_type.name()))
+        }
+    }
+}
+
+impl UniversalParserBuilder {
+    /// Creates a builder configured from the `ULP_*` environment variables described in
+    /// the module documentation, to be configured further before it's built.
+    ///
+    /// Returns `PoolError::InvalidConfig` if a variable isn't valid Unicode or can't be
+    /// parsed. A missing `ULP_RPC_URL` is only reported by `build`.
+    pub fn from_env() -> Result<Self, PoolError> {
+        let mut builder = UniversalParserBuilder::default();
+        if let Some(urls) = env_var("ULP_RPC_URL")? {
+            let urls = urls.split(',').map(str::trim).filter(|url| !url.is_empty());
+            builder = builder.rpc_urls(urls);
+        }
+        if let Some(url) = env_var("ULP_WS_URL")? {
+            builder = builder.ws_url(url);
+        }
+        if let Some(commitment) = env_var("ULP_COMMITMENT")? {
+            let Ok(commitment) = commitment.parse::<CommitmentConfig>() else {
+                return Err(PoolError::InvalidConfig(format!(
+                    "ULP_COMMITMENT {commitment:?} isn't processed, confirmed or finalized"
+                )));
+            };
+            builder = builder.commitment(commitment);
+        }
+        if let Some(limit) = env_var("ULP_RPS_LIMIT")? {
+            let Ok(requests_per_second) = limit.parse::<f64>() else {
+                return Err(PoolError::InvalidConfig(format!(
+                    "ULP_RPS_LIMIT {limit:?} isn't a number"
+                )));
+            };
+            builder = builder.rate_limit(requests_per_second);
+        }
+        if let Some(cluster) = env_var("ULP_CLUSTER")? {
+            let cluster = match cluster.as_str() {
+                "mainnet" | "mainnet-beta" => Cluster::Mainnet,
+                "devnet" => Cluster::Devnet,
+                _ => {
+                    return Err(PoolError::InvalidConfig(format!(
+                        "ULP_CLUSTER {cluster:?} isn't mainnet or devnet"
+                    )));
+                }
+            };
+            builder = builder.cluster(cluster);
+        }
+        if let Some(token) = env_var("ULP_BEARER_TOKEN")? {
+            builder = builder.bearer_auth(token);
+        }
+        if let Some(url) = env_var("ULP_PROXY")? {
+            builder = builder.proxy(url);
+        }
+        Ok(builder)
+    }
+
+    /// Fetches pools from the RPC node at `url`.
+    pub fn rpc_url(mut self, url: impl Into<String>) -> Self {
+        self.rpc_urls = vec![url.into()];
+        self
+    }
+
+    /// Fetches pools from the RPC nodes at `urls`, failing over from each to the next as
+    /// described in `failover`.
+    pub fn rpc_urls<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rpc_urls = urls.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Fetches pools with an existing client, which takes precedence over `rpc_url`.
+    pub fn rpc_client(mut self, rpc_client: Arc<RpcClient>) -> Self {
+        self.rpc_clients = vec![rpc_client];
+        self
+    }
+
+    /// Fetches pools with existing clients that fail over from each to the next, which
+    /// take precedence over `rpc_urls`.
+    pub fn rpc_clients(mut self, rpc_clients: Vec<Arc<RpcClient>>) -> Self {
+        self.rpc_clients = rpc_clients;
+        self
+    }
+
+    /// Sends the header `name` with `value` with every request to the RPC nodes set with
+    /// `rpc_url` or `rpc_urls`, as `HttpOptions::header` does.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.http = self.http.header(name, value);
+        self
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every request to the RPC nodes set with
+    /// `rpc_url` or `rpc_urls`.
+    pub fn bearer_auth(mut self, token: impl AsRef<str>) -> Self {
+        self.http = self.http.bearer_auth(token);
+        self
+    }
+
+    /// Sends the requests to the RPC nodes set with `rpc_url` or `rpc_urls` through the
+    /// proxy at `url`, as `HttpOptions::proxy` does.
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.http = self.http.proxy(url);
+        self
+    }
+
+    /// Creates the clients of the RPC nodes set with `rpc_url` or `rpc_urls` with `http`,
+    /// replacing the headers and proxy set before.
+    pub fn http_options(mut self, http: HttpOptions) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Sets how long an endpoint that failed is skipped, `failover::DEFAULT_COOLDOWN` by
+    /// default.
+    pub fn failover_cooldown(mut self, cooldown: Duration) -> Self {
+        self.failover_cooldown = Some(cooldown);
+        self
+    }
+
+    /// Sets how long a request may take before it fails with `PoolError::Timeout` and
+    /// moves on to the next endpoint, `fetcher::DEFAULT_REQUEST_TIMEOUT` by default.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long `parse` may take as a whole, retries included, before it fails with
+    /// `PoolError::Timeout`. This bounds each pool of `parse_many` as well. There is no
+    /// overall timeout by default.
+    pub fn overall_timeout(mut self, timeout: Duration) -> Self {
+        self.overall_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the commitment every request is made at, such as `CommitmentConfig::processed()`
+    /// for the lowest latency or `CommitmentConfig::finalized()` for data that won't be
+    /// rolled back. Clients passed to `rpc_clients` are overridden too. Without it, clients
+    /// created from `rpc_urls` fetch finalized data and other clients keep their own
+    /// commitment.
+    pub fn commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = Some(commitment);
+        self
+    }
+
+    /// Sets the cluster whose program ids pools are checked against, mainnet by default.
+    pub fn cluster(mut self, cluster: Cluster) -> Self {
+        self.cluster = cluster;
+        self
+    }
+
+    /// Sets how strictly account data is checked, leniently by default.
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Enables only the pool types named, by the names `PoolType::from_name` accepts.
+    /// Every pool type is enabled by default.
+    pub fn protocols<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.protocols = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets how many pools `parse_many` fetches at once, `DEFAULT_MAX_IN_FLIGHT` by
+    /// default.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Sets how requests that fail with a transient error are retried,
+    /// `RetryPolicy::default()` by default. `RetryPolicy::none()` disables retries.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Limits the parser to `requests_per_second` requests per second on average, with a
+    /// limiter of its own. Requests aren't limited by default.
+    pub fn rate_limit(mut self, requests_per_second: f64) -> Self {
+        self.rate_limit = Some(requests_per_second);
+        self
+    }
+
+    /// Limits the parser with an existing limiter, which may be shared with other parsers
+    /// and takes precedence over `rate_limit`.
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Answers requests from a cache of their own, which keeps accounts for `ttl`. Requests
+    /// aren't cached by default.
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Bounds the cache set with `cache_ttl` to `capacity` accounts, evicting the least
+    /// recently used ones past it. The cache is unbounded by default.
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Answers requests from an existing cache, which may be shared with other parsers and
+    /// takes precedence over `cache_ttl`.
+    pub fn account_cache(mut self, cache: Arc<AccountCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets whether concurrent calls making the same request share it, as
+    /// `coalesce::Coalescing` does. Requests are coalesced by default.
+    pub fn coalesce(mut self, coalesce: bool) -> Self {
+        self.coalesce = Some(coalesce);
+        self
+    }
+
+    /// Makes `UniversalParser::parse` also fetch the balances of the vaults of each pool.
+    pub fn reserves(mut self, reserves: bool) -> Self {
+        self.reserves = reserves;
+        self
+    }
+
+    /// Sets which slots `parse`, `parse_at_min_slot` and `parse_many` read the accounts of
+    /// a pool at, `Consistency::Any` by default. `Consistency::SameSlot` reads the pool and
+    /// the accounts completing it, vaults included, at one slot.
+    pub fn consistency(mut self, consistency: Consistency) -> Self {
+        self.consistency = consistency;
+        self
+    }
+
+    /// Sets the WebSocket URL of the RPC node, kept for the subscriptions of the
+    /// application. The parser itself only sends HTTP requests.
+    pub fn ws_url(mut self, url: impl Into<String>) -> Self {
+        self.ws_url = Some(url.into());
+        self
+    }
+
+    /// Creates the parser, with methods that block until they're done, as
+    /// `blocking::UniversalParser`.
+    ///
+    /// Fails as `build` does.
+    pub fn build_blocking(self) -> Result<blocking::UniversalParser, PoolError> {
+        self.build().map(blocking::UniversalParser::new)
+    }
+
+    /// Creates the parser.
+    ///
+    /// Returns `PoolError::InvalidConfig` if no RPC URL or client was set, if headers or
+    /// the proxy are invalid or set together with clients, if `protocols` names an unknown pool
+    /// type, if `rate_limit` isn't positive, or if `cache_capacity` is zero or set
+    /// without `cache_ttl`.
+    pub fn build(self) -> Result<UniversalParser, PoolError> {
+        let rpc_clients = if !self.rpc_clients.is_empty() {
+            if self.http.header_names().next().is_some() || self.http.proxy_url().is_some() {
+                return Err(PoolError::InvalidConfig(
+                    "headers and proxies apply to clients created from RPC URLs, not to given clients"
+                        .to_string(),
+                ));
+            }
+            self.rpc_clients
+        } else if !self.rpc_urls.is_empty() {
+            let commitment = self.commitment.unwrap_or_default();
+            self.rpc_urls
+                .into_iter()
+                .map(|url| self.http.rpc_client(url, commitment).map(Arc::new))
+                .collect::<Result<_, _>>()?
+        } else {
+            return Err(PoolError::InvalidConfig(
+                "an RPC URL or client is required".to_string(),
+            ));
+        };
+        let request_timeout = self.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+        let endpoints = rpc_clients
+            .into_iter()
+            .map(|rpc_client| WithTimeout::new(rpc_client, request_timeout))
+            .collect();
+        let endpoints = Failover::new(endpoints)
+            .with_cooldown(self.failover_cooldown.unwrap_or(DEFAULT_COOLDOWN));
+        let protocols = match self.protocols {
+            Some(names) => Some(
+                names
+                    .iter()
+                    .map(|name| {
+                        canonical_name(name).ok_or_else(|| {
+                            PoolError::InvalidConfig(format!("unknown pool type {name:?}"))
+                        })
+                    })
+                    .collect::<Result<BTreeSet<_>, _>>()?,
+            ),
+            None => None,
+        };
+        let rate_limiter = match (self.rate_limiter, self.rate_limit) {
+            (Some(rate_limiter), _) => Some(rate_limiter),
+            (None, Some(requests_per_second)) if requests_per_second > 0.0 => {
+                Some(Arc::new(RateLimiter::new(requests_per_second)))
+            }
+            (None, Some(requests_per_second)) => {
+                return Err(PoolError::InvalidConfig(format!(
+                    "rate limit of {requests_per_second} requests per second isn't positive"
+                )));
+            }
+            (None, None) => None,
+        };
+        let cache = match (self.cache, self.cache_ttl, self.cache_capacity) {
+            (Some(cache), _, _) => Some(cache),
+            (None, _, Some(0)) => {
+                return Err(PoolError::InvalidConfig(
+                    "cache capacity isn't positive".to_string(),
+                ));
+            }
+            (None, Some(ttl), capacity) => {
+                let cache = AccountCache::new(ttl);
+                Some(Arc::new(match capacity {
+                    Some(capacity) => cache.with_capacity(capacity),
+                    None => cache,
+                }))
+            }
+            (None, None, Some(_)) => {
+                return Err(PoolError::InvalidConfig(
+                    "a cache capacity needs a cache TTL".to_string(),
+                ));
+            }
+            (None, None, None) => None,
+        };
+        Ok(UniversalParser {
+            endpoints,
+            cluster: self.cluster,
+            mode: self.mode,
+            protocols,
+            max_in_flight: self.max_in_flight.unwrap_or(DEFAULT_MAX_IN_FLIGHT),
+            retry: self.retry.unwrap_or_default(),
+            rate_limiter,
+            commitment: self.commitment,
+            overall_timeout: self.overall_timeout,
+            cache,
+            in_flight: self.coalesce.unwrap_or(true).then(InFlight::default),
+            reserves: self.reserves,
+            vaults: Mutex::new(HashMap::new()),
+            consistency: self.consistency,
+            ws_url: self.ws_url,
+        })
+    }
+}
+
+/// Returns the value of the environment variable `name`, or `None` if it's unset or empty.
+fn env_var(name: &str) -> Result<Option<String>, PoolError> {
+    match env::var(name) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(VarError::NotUnicode(_)) => Err(PoolError::InvalidConfig(format!(
+            "{name} isn't valid Unicode"
+        ))),
+    }
+}
+
+/// The requests of a parser, answered from its cache, shared with the same requests in
+/// flight, or made with its endpoints at its commitment once its limiter allows.
+struct Requests<'a> {
+    endpoints: &'a Failover<WithTimeout<Arc<RpcClient>>>,
+    commitment: Option<CommitmentConfig>,
+    rate_limiter: Option<&'a RateLimiter>,
+    cache: Option<&'a AccountCache>,
+    in_flight: Option<&'a InFlight>,
+}
+
+impl Requests<'_> {
+    /// Sends the request for the accounts at `addresses`, or waits for the same request
+    /// in flight.
+    async fn coalesce_accounts(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        match self.in_flight {
+            Some(in_flight) => {
+                let request = || self.send_accounts(addresses, config.clone());
+                in_flight.fetch_accounts(addresses, &config, request).await
+            }
+            None => self.send_accounts(addresses, config).await,
+        }
+    }
+
+    async fn send_accounts(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        if let Some(rate_limiter) = self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        let config = RpcAccountInfoConfig {
+            commitment: config.commitment.or(self.commitment),
+            ..config
+        };
+        self.endpoints
+            .fetch_accounts_with_config(addresses, config)
+            .await
+    }
+
+    async fn send_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        if let Some(rate_limiter) = self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        let config = RpcProgramAccountsConfig {
+            account_config: RpcAccountInfoConfig {
+                commitment: config.account_config.commitment.or(self.commitment),
+                ..config.account_config
+            },
+            ..config
+        };
+        self.endpoints
+            .fetch_program_accounts(program_id, config)
+            .await
+    }
+}
+
+impl AccountFetcher for Requests<'_> {
+    async fn fetch_accounts_with_config(
+        &self,
+        addresses: &[Pubkey],
+        config: RpcAccountInfoConfig,
+    ) -> Result<Response<Vec<Option<Account>>>, PoolError> {
+        match self.cache {
+            Some(cache) => {
+                let request = || self.coalesce_accounts(addresses, config.clone());
+                cache.fetch_with(addresses, &config, request).await
+            }
+            None => self.coalesce_accounts(addresses, config).await,
+        }
+    }
+
+    async fn fetch_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Response<Vec<(Pubkey, Account)>>, PoolError> {
+        match self.in_flight {
+            Some(in_flight) => {
+                let request = || self.send_program_accounts(program_id, config.clone());
+                in_flight
+                    .fetch_program_accounts(program_id, &config, request)
+                    .await
+            }
+            None => self.send_program_accounts(program_id, config).await,
+        }
+    }
+}